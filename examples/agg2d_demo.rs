@@ -0,0 +1,138 @@
+// Draws with the Agg2D facade and saves the result to a PNG file:
+//
+//   cargo run --example agg2d_demo [output.png]
+
+use agg::agg2d::{Agg2D, Direction, DrawPathFlag, ImageFilterKind};
+use agg::color_rgba::OrderBgra;
+use agg::pixfmt_rgba::CompOp;
+use agg::png::save_png;
+use agg::{ImageBuffer, LineJoin, PixBgra32, Rgba8};
+use std::f64::consts::PI;
+
+fn rgba(r: u32, g: u32, b: u32, a: u32) -> Rgba8 {
+    Rgba8::new_params(r, g, b, a)
+}
+
+fn main() {
+    let fname = std::env::args()
+        .nth(1)
+        .unwrap_or("agg2d_demo.png".to_string());
+
+    // A small pattern to draw as an image
+    let mut pattern = ImageBuffer::<PixBgra32>::new(64, 64);
+    {
+        let mut g = Agg2D::<OrderBgra>::new_image(&mut pattern);
+        g.clear_all(&rgba(255, 240, 200, 255));
+        g.no_line();
+        g.set_fill_color(&rgba(200, 40, 40, 255));
+        for i in 0..4 {
+            for j in 0..4 {
+                if (i + j) % 2 == 0 {
+                    g.rectangle(
+                        i as f64 * 16.,
+                        j as f64 * 16.,
+                        i as f64 * 16. + 16.,
+                        j as f64 * 16. + 16.,
+                    );
+                }
+            }
+        }
+        g.set_fill_color(&rgba(40, 40, 200, 200));
+        g.ellipse(32., 32., 20., 20.);
+    }
+
+    let mut img = ImageBuffer::<PixBgra32>::new(600, 500);
+    let mut g = Agg2D::<OrderBgra>::new_image(&mut img);
+    g.clear_all(&rgba(255, 255, 255, 255));
+
+    // Gradient filled rounded rectangle, rotated around its center
+    g.save_state();
+    g.translate(-150., -100.);
+    g.rotate(PI / 12.);
+    g.translate(150., 100.);
+    g.fill_linear_gradient(
+        40.,
+        40.,
+        260.,
+        160.,
+        &rgba(255, 200, 0, 255),
+        &rgba(200, 0, 100, 255),
+        1.0,
+    );
+    g.set_line_color(&rgba(60, 0, 40, 255));
+    g.set_line_width(4.0);
+    g.rounded_rect(40., 40., 260., 160., 20.);
+    g.restore_state();
+
+    // Radial gradients for the fill and the outline
+    g.fill_radial_gradient3(
+        430.,
+        100.,
+        80.,
+        &rgba(255, 255, 255, 255),
+        &rgba(80, 180, 255, 255),
+        &rgba(0, 40, 120, 255),
+    );
+    g.line_radial_gradient(
+        430.,
+        100.,
+        90.,
+        &rgba(0, 0, 0, 255),
+        &rgba(0, 160, 0, 255),
+        0.5,
+    );
+    g.set_line_width(10.0);
+    g.ellipse(430., 100., 80., 80.);
+
+    // Paths, stars and an arc in solid colors
+    g.set_fill_color(&rgba(255, 220, 0, 255));
+    g.set_line_color(&rgba(160, 80, 0, 255));
+    g.set_line_width(3.0);
+    g.set_line_join(LineJoin::Miter);
+    g.star(90., 300., 25., 60., -PI / 2., 5);
+    g.set_fill_even_odd(true);
+    g.reset_path();
+    g.move_to(180., 250.);
+    g.line_to(280., 250.);
+    g.line_to(280., 350.);
+    g.line_to(180., 350.);
+    g.close_polygon();
+    g.add_ellipse(230., 300., 30., 30., Direction::CCW);
+    g.set_fill_color(&rgba(0, 140, 80, 255));
+    g.draw_path(DrawPathFlag::FillAndStroke);
+    g.set_fill_even_odd(false);
+    g.set_line_color(&rgba(200, 0, 0, 255));
+    g.set_line_width(6.0);
+    g.arc(90., 300., 75., 75., 0., PI);
+
+    // The same pair of circles blended with different modes
+    let modes = [
+        CompOp::CompOpSrcOver,
+        CompOp::CompOpMultiply,
+        CompOp::CompOpScreen,
+        CompOp::CompOpDifference,
+        CompOp::CompOpXor,
+    ];
+    g.no_line();
+    for (i, m) in modes.iter().enumerate() {
+        let x = 60. + i as f64 * 110.;
+        g.set_blend_mode(CompOp::CompOpSrcOver);
+        g.set_fill_color(&rgba(230, 60, 30, 255));
+        g.ellipse(x, 440., 32., 32.);
+        g.set_blend_mode(*m);
+        g.set_fill_color(&rgba(30, 120, 230, 200));
+        g.ellipse(x + 24., 440., 32., 32.);
+    }
+    g.set_blend_mode(CompOp::CompOpSrcOver);
+
+    // Images: transformed with filtering, as a path fill and blended
+    g.set_image_filter(ImageFilterKind::Bicubic);
+    g.transform_image_parl(&pattern, None, &[340., 200., 440., 230., 410., 330.]);
+    g.reset_path();
+    g.add_ellipse(520., 270., 50., 50., Direction::CCW);
+    g.transform_image_path(&pattern, None, 470., 220., 570., 320.);
+    g.blend_image(&pattern, None, 300., 340., 128);
+
+    save_png(&*img.pixfmt(), &fname).unwrap();
+    println!("Saved {}", fname);
+}
@@ -0,0 +1,85 @@
+// Draws text in a color font, COLR/CPAL layers or sbix/CBDT bitmaps like
+// the ones of emoji fonts, with color glyphs off and on and in each
+// palette, and saves the result to a PNG file:
+//
+//   cargo run --example color_glyphs [font.ttf [text [output.png]]]
+
+use agg::font_freetype::FreetypeBase;
+use agg::{
+    render_scanlines_aa_solid, Canvas, ColorGlyph, FontCacheManager, FontEngine, GlyphDataType,
+    GlyphRender, PixBgr24, Rgba8,
+};
+
+type Fman<'a> = FontCacheManager<FreetypeBase<'a, i32>>;
+
+fn draw_text(canvas: &mut Canvas<PixBgr24>, fman: &mut Fman, text: &str, x: f64, y: f64) {
+    let (_, sl, mut ren) = canvas.parts_mut();
+    let c = Rgba8::new_params(0, 0, 0, 255);
+    let (mut x, mut y) = (x, y);
+    fman.reset_last_glyph();
+    for ch in text.chars() {
+        let glyph = fman.glyph(ch as u32);
+        if glyph.is_null() {
+            continue;
+        }
+        fman.add_kerning(&mut x, &mut y);
+        let gl = unsafe { &*glyph };
+        match gl.data_type {
+            GlyphDataType::Gray8 => {
+                let mut adp = fman.gray8_adaptor();
+                adp.init(gl.data.as_ptr(), gl.data_size as usize, x, y);
+                render_scanlines_aa_solid(&mut adp, sl, &mut ren, &c);
+            }
+            GlyphDataType::Color => {
+                ColorGlyph::new(&gl.data).render(&mut ren, sl, x, y, &c, 255);
+            }
+            _ => {}
+        }
+        x += gl.advance_x;
+        y += gl.advance_y;
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let font = args.get(1).map_or("NotoColorEmoji.ttf", |s| s.as_str());
+    let text = args
+        .get(2)
+        .map_or("AGG \u{1F600}\u{1F3A8}\u{2764}", |s| s.as_str());
+    let out = args.get(3).map_or("color_glyphs.png", |s| s.as_str());
+
+    let mut fman = FontCacheManager::new(FreetypeBase::<i32>::new(32), 32);
+    if !fman
+        .engine_mut()
+        .load_font(&format!("{}\0", font), 0, GlyphRender::AggGray8, &[], 0)
+    {
+        println!("Can't load '{}'", font);
+        return;
+    }
+    fman.engine_mut().set_flip_y(true);
+    println!("{}: {} palettes", font, fman.engine().num_palettes());
+
+    let mut canvas = Canvas::<PixBgr24>::new(800, 600);
+    canvas.clear(&Rgba8::new_params(255, 255, 240, 255));
+
+    let mut y = 60.;
+    for &h in [24., 48.].iter() {
+        fman.engine_mut().set_height(h);
+        fman.engine_mut().set_color_glyphs(false);
+        draw_text(&mut canvas, &mut fman, text, 20., y);
+        fman.engine_mut().set_color_glyphs(true);
+        for p in 0..fman.engine().num_palettes().max(1) {
+            fman.engine_mut().set_palette(p);
+            draw_text(&mut canvas, &mut fman, text, 20. + 260. * (p + 1) as f64, y);
+        }
+        y += h * 2.;
+    }
+
+    // Bitmaps are scaled from the closest strike
+    fman.engine_mut().set_height(64.);
+    fman.engine_mut().set_palette(0);
+    draw_text(&mut canvas, &mut fman, text, 20., y + 60.);
+
+    canvas.save_png(out).unwrap();
+    println!("saved {}", out);
+}
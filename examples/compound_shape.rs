@@ -0,0 +1,75 @@
+// Renders a Flash-style compound shape from a shapes.txt file in one call
+// and saves it to a PNG file. Most fill styles are solid colors, style 1
+// is a gradient and style 2 a checkerboard image:
+//
+//   cargo run --example compound_shape [shapes.txt [index [output.png]]]
+
+use agg::{
+    load_compound_shapes, render_compound_shape, Canvas, Color, GradientShape, ImageBuffer,
+    ImageFilterKind, LineStyle, PixBgr24Pre, PixBgra32, PixFmt, Rgba8, StyleTable, TransAffine,
+};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let fname = args
+        .get(1)
+        .map_or("examples/web/shapes.txt", |s| s.as_str());
+    let index = args.get(2).map_or(0, |s| s.parse::<usize>().unwrap());
+    let out = args.get(3).map_or("compound_shape.png", |s| s.as_str());
+
+    let mut shapes = load_compound_shapes(fname).unwrap();
+    let shape = &mut shapes[index];
+
+    let mut checker = ImageBuffer::<PixBgra32>::new(64, 64);
+    {
+        let mut pix = checker.pixfmt();
+        for y in 0..64 {
+            for x in 0..64 {
+                let v = if (x / 8 + y / 8) % 2 == 0 { 40 } else { 220 };
+                pix.copy_pixel(x, y, &Rgba8::new_params(v, v, 255 - v, 255));
+            }
+        }
+    }
+    let mut ramp = Vec::new();
+    for i in 0..256 {
+        let c1 = Rgba8::new_params(255, 0, 0, 180);
+        let c2 = Rgba8::new_params(0, 0, 255, 180);
+        let mut c = c1.gradient(&c2, i as f64 / 255.);
+        c.premultiply();
+        ramp.push(c);
+    }
+
+    let mut fills = StyleTable::<PixBgra32>::new();
+    let mut seed = 12345u32;
+    for i in 0..100 {
+        match i {
+            1 => {
+                let mtx = TransAffine::new_default();
+                fills.add_gradient(GradientShape::Linear, &mtx, 0., 655., &ramp);
+            }
+            2 => {
+                let mtx = TransAffine::trans_affine_scaling_eq(655. / 64.);
+                fills.add_image_buffer(checker.clone(), &mtx, ImageFilterKind::Bilinear);
+            }
+            _ => {
+                let mut c = [0u32; 3];
+                for v in c.iter_mut() {
+                    seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                    *v = (seed >> 16) & 0xFF;
+                }
+                let mut color = Rgba8::new_params(c[0], c[1], c[2], 230);
+                color.premultiply();
+                fills.add_solid(color);
+            }
+        }
+    }
+    let lines = [LineStyle::new(8., Rgba8::new_params(0, 0, 0, 128))];
+
+    let mut canvas = Canvas::<PixBgr24Pre>::new(655, 520);
+    canvas.clear(&Rgba8::new_params(255, 255, 240, 255));
+    let mtx = shape.viewport(655., 520.);
+    render_compound_shape(&mut canvas.ren(), shape, &mtx, &mut fills, &lines);
+
+    canvas.save_png(out).unwrap();
+    println!("{}: {} paths, saved {}", fname, shape.paths(), out);
+}
@@ -0,0 +1,70 @@
+// Inflates and deflates a shape with holes, concave corners and a tight
+// curve using different joins and saves the result to a PNG file:
+//
+//   cargo run --example conv_offset [output.png]
+
+use agg::math_stroke::LineJoin;
+use agg::{Canvas, ConvCurve, ConvOffset, Ellipse, PathStorage, PixBgr24, Rgba8};
+
+fn shape(x: f64, y: f64) -> PathStorage {
+    let mut p = PathStorage::new();
+    p.move_to(x + 20., y + 20.);
+    p.line_to(x + 140., y + 20.);
+    p.line_to(x + 140., y + 50.);
+    p.line_to(x + 60., y + 50.);
+    p.line_to(x + 60., y + 70.);
+    p.line_to(x + 120., y + 75.);
+    p.line_to(x + 60., y + 80.);
+    p.line_to(x + 60., y + 110.);
+    p.line_to(x + 140., y + 110.);
+    p.line_to(x + 140., y + 140.);
+    p.line_to(x + 20., y + 140.);
+    p.close_polygon(0);
+
+    let mut hole = Ellipse::new_ellipse(x + 40., y + 80., 8., 25., 32, true);
+    p.concat_path(&mut hole, 0);
+
+    p.move_to(x + 170., y + 30.);
+    p.curve3_ctrl(x + 260., y + 80., x + 170., y + 130.);
+    p.line_to(x + 180., y + 80.);
+    p.close_polygon(0);
+    p
+}
+
+fn main() {
+    let fname = std::env::args()
+        .nth(1)
+        .unwrap_or("conv_offset.png".to_string());
+
+    let mut canvas = Canvas::<PixBgr24>::new(4 * 280, 2 * 170);
+    canvas.clear(&Rgba8::new_params(255, 255, 255, 255));
+
+    let offsets = [
+        (6., LineJoin::Miter, 4.),
+        (6., LineJoin::Round, 4.),
+        (6., LineJoin::Miter, 1.),
+        (12., LineJoin::Round, 4.),
+        (-4., LineJoin::Miter, 4.),
+        (-4., LineJoin::Round, 4.),
+        (-8., LineJoin::Miter, 1.),
+        (-14., LineJoin::Round, 4.),
+    ];
+    for (i, &(d, join, limit)) in offsets.iter().enumerate() {
+        let (x, y) = ((i % 4) as f64 * 280., (i / 4) as f64 * 170.);
+
+        let curve: ConvCurve<_> = ConvCurve::new_owned(shape(x, y));
+        let mut offset = ConvOffset::new_owned(curve);
+        offset.set_offset(d);
+        offset.set_line_join(join);
+        offset.set_miter_limit(limit);
+        canvas.fill_path(&mut offset, 0, &Rgba8::new_params(200, 80, 40, 140));
+        canvas.set_line_width(0.7);
+        canvas.stroke_path(&mut offset, 0, &Rgba8::new_params(0, 0, 0, 255));
+
+        let mut outline: ConvCurve<_> = ConvCurve::new_owned(shape(x, y));
+        canvas.stroke_path(&mut outline, 0, &Rgba8::new_params(0, 0, 255, 255));
+    }
+
+    canvas.save_png(&fname).unwrap();
+    println!("saved {}", fname);
+}
@@ -0,0 +1,111 @@
+// Records a small scene with solid colors, a gradient, an image and text
+// into a DrawList, saves it as text, loads it back and replays it at two
+// sizes into an RGB and a gray image, the second time from the cache:
+//
+//   cargo run --example display_list [output_prefix]
+
+use agg::{
+    Canvas, DrawList, Ellipse, GradientShape, Gray8, ImageBuffer, Paint, PathStorage, PixBgr24,
+    PixGray8, RendererBase, Rgba8, TransAffine,
+};
+use std::io::BufReader;
+use std::time::Instant;
+
+fn record() -> DrawList {
+    let mut list = DrawList::new();
+
+    // A checkerboard image, premultiplied RGBA
+    let (iw, ih) = (64u32, 64u32);
+    let mut data = Vec::new();
+    for y in 0..ih {
+        for x in 0..iw {
+            let c = if (x / 8 + y / 8) % 2 == 0 {
+                [230, 180, 40, 255]
+            } else {
+                [40, 90, 200, 255]
+            };
+            data.extend_from_slice(&c);
+        }
+    }
+    let mut imtx = TransAffine::trans_affine_rotation(0.3);
+    imtx *= TransAffine::trans_affine_scaling_eq(2.5);
+    imtx *= TransAffine::trans_affine_translation(100., 60.);
+    let img = list.add_image(iw, ih, &data, &imtx).unwrap();
+
+    let stops = [
+        (0., Rgba8::new_params(255, 240, 120, 255)),
+        (0.5, Rgba8::new_params(230, 60, 60, 255)),
+        (1., Rgba8::new_params(60, 20, 120, 255)),
+    ];
+    let grad = list.add_gradient(
+        GradientShape::Linear,
+        &TransAffine::trans_affine_translation(20., 0.),
+        0.,
+        360.,
+        &stops,
+    );
+    let center = TransAffine::trans_affine_translation(300., 200.);
+    let rgrad = list.add_gradient(GradientShape::Radial, &center, 0., 90., &stops);
+
+    let mut rect = PathStorage::new();
+    rect.move_to(20., 20.);
+    rect.line_to(380., 20.);
+    rect.line_to(380., 280.);
+    rect.line_to(20., 280.);
+    rect.close_polygon(0);
+    list.fill_path(&mut rect, 0, Paint::Gradient(grad));
+
+    let mut e = Ellipse::new_ellipse(300., 200., 90., 60., 0, false);
+    list.fill_path(&mut e, 0, Paint::Gradient(rgrad));
+    list.set_line_width(3.);
+    list.stroke_path(&mut e, 0, &Rgba8::new_params(0, 0, 0, 255));
+
+    let mut tri = PathStorage::new();
+    tri.move_to(60., 260.);
+    tri.line_to(200., 60.);
+    tri.line_to(220., 250.);
+    tri.close_polygon(0);
+    list.fill_path(&mut tri, 0, Paint::Image(img));
+
+    list.set_text_flip(true);
+    list.set_line_width(1.5);
+    list.text(30., 50., 20., "DrawList", &Rgba8::new_params(0, 0, 0, 255));
+    list
+}
+
+fn main() {
+    let prefix = std::env::args()
+        .nth(1)
+        .unwrap_or("display_list".to_string());
+
+    let list = record();
+    let mut text = Vec::new();
+    list.save(&mut text).unwrap();
+    println!("{} commands, {} bytes as text", list.len(), text.len());
+    let mut list = DrawList::load(&mut BufReader::new(&text[..])).unwrap();
+    list.set_caching(true);
+
+    let white = Rgba8::new_params(255, 255, 255, 255);
+    for &(scale, kind) in [(1., "rgb"), (2., "gray"), (2., "rgb")].iter() {
+        let viewport = TransAffine::trans_affine_scaling_eq(scale);
+        let (w, h) = ((400. * scale) as u32, (300. * scale) as u32);
+        let fname = format!("{}_{}_{}.png", prefix, kind, scale);
+        let t = Instant::now();
+        if kind == "rgb" {
+            let mut img = ImageBuffer::<PixBgr24>::new(w, h);
+            img.clear(&white);
+            let mut pix = img.pixfmt();
+            list.render(&mut RendererBase::new_borrowed(&mut pix), &viewport);
+            drop(pix);
+            Canvas::new_from_image(img).save_png(&fname).unwrap();
+        } else {
+            let mut img = ImageBuffer::<PixGray8>::new(w, h);
+            img.clear(&Gray8::from(white));
+            let mut pix = img.pixfmt();
+            list.render(&mut RendererBase::new_borrowed(&mut pix), &viewport);
+            drop(pix);
+            Canvas::new_from_image(img).save_png(&fname).unwrap();
+        }
+        println!("{}: {:.2} ms", fname, t.elapsed().as_secs_f64() * 1000.);
+    }
+}
@@ -1,8 +1,7 @@
 use crate::platform::*;
 use agg::rendering_buffer::RenderBuf;
 use agg::{
-    Color, ConvCurve, Gamma, PathStorage, RasterScanLine, RasterStyle, RendererScanlineColor,
-    VertexSource,
+    Color, CompoundShape, ConvCurve, Gamma, RasterScanLine, RasterStyle, RendererScanlineColor,
 };
 
 mod ctrl;
@@ -12,255 +11,15 @@ use libc::*;
 use std::cell::RefCell;
 use std::f64::consts::PI;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::ops::Index;
+use std::io::BufReader;
 use std::rc::Rc;
 
 fn frand() -> i32 {
     unsafe { rand() }
 }
 
-trait ReadLine {
-    fn readline(&mut self, buf: &mut String) -> bool;
-}
-
-impl ReadLine for BufReader<File> {
-    fn readline(&mut self, buf: &mut String) -> bool {
-        if let Ok(i) = self.read_line(buf) {
-            if i == 0 {
-                return false;
-            }
-            return true;
-        }
-        false
-    }
-}
 const FLIP_Y: bool = false;
 
-#[derive(Debug)]
-struct PathStyle {
-    path_id: u32,
-    left_fill: i32,
-    right_fill: i32,
-    line: i32,
-}
-impl PathStyle {
-    fn new() -> Self {
-        Self {
-            path_id: 0,
-            left_fill: 0,
-            right_fill: 0,
-            line: 0,
-        }
-    }
-}
-struct CompoundShape<'a> {
-    trans: agg::ConvTransform<'a, ConvCurve<'a, PathStorage>, agg::TransAffine>,
-    styles: Vec<PathStyle>,
-    _x1: f64,
-    _y1: f64,
-    _x2: f64,
-    _y2: f64,
-    buf_rdr: Option<BufReader<File>>,
-}
-
-impl<'a> VertexSource for CompoundShape<'a> {
-    fn rewind(&mut self, path_id: u32) {
-        self.trans.rewind(path_id);
-    }
-
-    fn vertex(&mut self, x: &mut f64, y: &mut f64) -> u32 {
-        self.trans.vertex(x, y)
-    }
-}
-
-impl<'a> Index<usize> for CompoundShape<'a> {
-    type Output = u32;
-    fn index(&self, i: usize) -> &Self::Output {
-        &self.styles[i].path_id
-    }
-}
-impl<'a> CompoundShape<'a> {
-    fn new() -> Self {
-        let p = agg::PathStorage::new();
-        let mtx = agg::TransAffine::new_default();
-        let cc = agg::ConvCurve::new_owned(p);
-        let tr = agg::ConvTransform::new_owned(cc, mtx);
-        CompoundShape {
-            trans: tr,
-            styles: Vec::new(),
-            _x1: 0.0,
-            _y1: 0.0,
-            _x2: 0.0,
-            _y2: 0.0,
-            buf_rdr: None,
-        }
-    }
-
-    fn open(&mut self, fname: &str) -> bool {
-        if let Ok(fd) = std::fs::File::open(fname) {
-            self.buf_rdr = Some(BufReader::new(fd));
-            return true;
-        }
-        false
-    }
-
-    fn paths(&self) -> usize {
-        self.styles.len()
-    }
-
-    fn style(&self, i: usize) -> &PathStyle {
-        &self.styles[i]
-    }
-
-    #[allow(dead_code)]
-    fn scale(&self) -> f64 {
-        self.trans.trans().scale()
-    }
-
-    fn modify_vertex(&mut self, i: u32, x: f64, y: f64) {
-        let (mut x, mut y) = (x, y);
-        self.trans.trans_mut().inverse_transform(&mut x, &mut y);
-        self.trans.source_mut().source_mut().modify_vertex(i, x, y);
-    }
-
-    fn set_scale(&mut self, w: f64, h: f64) {
-        self.trans.trans_mut().reset();
-        let (mut x1, mut y1, mut x2, mut y2) = (0., 0., 0., 0.);
-        let mut tmp = vec![];
-        for i in 0..self.styles.len() {
-            tmp.push(self.styles[i].path_id);
-        }
-        agg::bounding_rect(
-            self.trans.source_mut().source_mut(),
-            tmp,
-            0,
-            self.styles.len() as u32,
-            &mut x1,
-            &mut y1,
-            &mut x2,
-            &mut y2,
-        );
-        if x1 < x2 && y1 < y2 {
-            let mut vp = agg::TransViewport::new();
-            vp.set_preserve_aspect_ratio(0.5, 0.5, agg::AspectRatio::Meet);
-            vp.set_world_viewport(x1, y1, x2, y2);
-            vp.set_device_viewport(0.0, 0.0, w, h);
-            *self.trans.trans_mut() = vp.to_affine();
-        }
-        let s = self.trans.trans_mut().scale();
-        self.trans.source_mut().set_approximation_scale(s);
-    }
-
-    fn set_approximation_scale(&mut self, s: f64) {
-        let sc = self.trans.trans_mut().scale();
-        self.trans.source_mut().set_approximation_scale(sc * s);
-    }
-
-    fn read_next(&mut self) -> bool {
-        self.trans.source_mut().source_mut().remove_all();
-        self.styles.clear();
-        //const SPACE: &str = " \t\n\r";
-        let mut ax: f64;
-        let mut ay: f64;
-        let mut cx: f64;
-        let mut cy: f64;
-
-        if let Some(rdr) = &mut self.buf_rdr {
-            let mut buf = String::new();
-            loop {
-                if !rdr.readline(&mut buf) {
-                    return false;
-                }
-                if buf.as_bytes()[0] == b'=' {
-                    break;
-                }
-            }
-            buf.clear();
-            while rdr.readline(&mut buf) {
-                if buf.as_bytes()[0] == b'!' {
-                    break;
-                }
-                if buf.as_bytes()[0] == b'P' {
-                    let mut style = PathStyle::new();
-                    let mut iter = buf.split_whitespace();
-
-                    style.path_id = self.trans.source_mut().source_mut().start_new_path();
-                    let mut ts = iter.next(); // Path;
-                    ts = iter.next(); // left_style
-                    style.left_fill = ts.unwrap().parse::<i32>().unwrap();
-                    ts = iter.next(); // right_style
-                    style.right_fill = ts.unwrap().parse::<i32>().unwrap();
-                    ts = iter.next();
-                    style.line = ts.unwrap().parse::<i32>().unwrap();
-                    ts = iter.next();
-                    ax = ts.unwrap().parse::<f64>().unwrap();
-                    ts = iter.next();
-                    ay = ts.unwrap().parse::<f64>().unwrap();
-                    self.trans.source_mut().source_mut().move_to(ax, ay);
-                    self.styles.push(style);
-                }
-
-                if buf.as_bytes()[0] == b'C' {
-                    let mut iter = buf.split_whitespace();
-                    let mut ts = iter.next(); // Curve;
-                    ts = iter.next();
-                    cx = ts.unwrap().parse::<f64>().unwrap(); // cx
-                    ts = iter.next();
-                    cy = ts.unwrap().parse::<f64>().unwrap(); // cy
-                    ts = iter.next();
-                    ax = ts.unwrap().parse::<f64>().unwrap();
-                    ts = iter.next();
-                    ay = ts.unwrap().parse::<f64>().unwrap();
-                    self.trans
-                        .source_mut()
-                        .source_mut()
-                        .curve3_ctrl(cx, cy, ax, ay);
-                }
-
-                if buf.as_bytes()[0] == b'L' {
-                    let mut iter = buf.split_whitespace();
-                    let mut ts = iter.next(); // Line;
-                    ts = iter.next();
-                    ax = ts.unwrap().parse::<f64>().unwrap();
-                    ts = iter.next();
-                    ay = ts.unwrap().parse::<f64>().unwrap();
-                    self.trans.source_mut().source_mut().line_to(ax, ay)
-                }
-
-                if buf.as_bytes()[0] == b'<' {
-                    // EndPath
-                }
-                buf.clear();
-            }
-            return true;
-        }
-        false
-    }
-
-    fn hit_test(&mut self, x: f64, y: f64, r: f64) -> i32 {
-        let mut x = x;
-        let mut y = y;
-        let mut r = r;
-        self.trans.trans_mut().inverse_transform(&mut x, &mut y);
-        r /= self.trans.trans_mut().scale();
-        for i in 0..self.trans.source_mut().source_mut().total_vertices() {
-            let (mut vx, mut vy) = (0., 0.);
-            let cmd = self
-                .trans
-                .source_mut()
-                .source_mut()
-                .vertex(&mut vx, &mut vy);
-            if agg::is_vertex(cmd) {
-                if agg::calc_distance(x, y, vx, vy) <= r {
-                    return i as i32;
-                }
-            }
-        }
-        -1
-    }
-}
-
 struct TestStyles<'a, C: Color> {
     solid_colors: &'a [C],
     gradient: &'a [C],
@@ -298,7 +57,9 @@ impl<'a, C: Color> RasterStyle<C> for TestStyles<'a, C> {
 }
 
 pub struct Application {
-    shape: CompoundShape<'static>,
+    shape: CompoundShape,
+    shapes: Option<BufReader<File>>,
+    view: agg::TransAffine,
     colors: [agg::Rgba8; 100],
     scale: agg::TransAffine,
     gamma: agg::GammaLut<u8>,
@@ -312,13 +73,20 @@ pub struct Application {
 
 impl Application {
     pub fn open(&mut self, fname: &str) -> bool {
-        self.shape.open(fname)
+        if let Ok(fd) = File::open(fname) {
+            self.shapes = Some(BufReader::new(fd));
+            return true;
+        }
+        false
     }
 
     pub fn read_next(&mut self) {
-        self.shape.read_next();
-        self.shape
-            .set_scale(self.util.borrow().width(), self.util.borrow().height());
+        if let Some(rdr) = &mut self.shapes {
+            if self.shape.read_next(rdr).unwrap_or(false) {
+                let (w, h) = (self.util.borrow().width(), self.util.borrow().height());
+                self.view = self.shape.viewport(w, h);
+            }
+        }
     }
 }
 
@@ -345,6 +113,8 @@ impl Interface for Application {
 
         Self {
             shape: CompoundShape::new(),
+            shapes: None,
+            view: agg::TransAffine::new_default(),
             colors,
             scale: agg::TransAffine::new_default(),
             gamma,
@@ -369,7 +139,9 @@ impl Interface for Application {
                 let mut xd = x as f64;
                 let mut yd = y as f64;
                 self.scale.inverse_transform(&mut xd, &mut yd);
-                self.shape.modify_vertex(self.point_idx as u32, xd, yd);
+                self.view.inverse_transform(&mut xd, &mut yd);
+                let path = self.shape.path_mut();
+                path.modify_vertex(self.point_idx as u32, xd, yd);
                 return Draw::Yes;
             }
         }
@@ -380,9 +152,13 @@ impl Interface for Application {
         if flags & 1 != 0 {
             let mut xd = x as f64;
             let mut yd = y as f64;
-            let r = 4.0 / self.scale.scale();
+            let r = 4.0 / self.scale.scale() / self.view.scale();
             self.scale.inverse_transform(&mut xd, &mut yd);
-            self.point_idx = self.shape.hit_test(xd, yd, r);
+            self.view.inverse_transform(&mut xd, &mut yd);
+            self.point_idx = match self.shape.hit_test_vertex(xd, yd, r) {
+                Some(i) => i as i32,
+                None => -1,
+            };
             return Draw::Yes;
         }
         if flags & 2 != 0 {
@@ -425,11 +201,13 @@ impl Interface for Application {
             agg::RasterizerCompoundAa::new();
         let mut sl = agg::ScanlineU8::new();
         let mut sl_bin = agg::ScanlineBin::new();
-        let lscale = self.scale;
-        let sc = self.scale.scale();
-        self.shape.set_approximation_scale(sc);
+        let mut mtx = self.view;
+        mtx *= self.scale;
+        let styles = self.shape.styles().to_vec();
+        let mut curve: ConvCurve<_> = ConvCurve::new_borrowed(self.shape.path_mut());
+        curve.set_approximation_scale(mtx.scale());
 
-        let mut shape = agg::ConvTransform::new_borrowed(&mut self.shape, lscale);
+        let mut shape = agg::ConvTransform::new_borrowed(&mut curve, mtx);
 
         // Fill shape
         //----------------------
@@ -437,15 +215,10 @@ impl Interface for Application {
         rasc_clip_dbl.reset();
         //rasc.filling_rule(agg::fill_even_odd);
         self.util.borrow_mut().start_timer();
-        let plen = shape.source().paths();
-        for i in 0..plen {
-            if shape.source().style(i).left_fill >= 0 || shape.source().style(i).right_fill >= 0 {
-                rasc_clip_dbl.set_styles(
-                    shape.source().style(i).left_fill,
-                    shape.source().style(i).right_fill,
-                );
-                let id = shape.source().style(i).path_id;
-                rasc_clip_dbl.add_path(&mut shape, id);
+        for s in styles.iter() {
+            if s.left_fill >= 0 || s.right_fill >= 0 {
+                rasc_clip_dbl.set_styles(s.left_fill, s.right_fill);
+                rasc_clip_dbl.add_path(&mut shape, s.path_id);
             }
         }
 
@@ -479,13 +252,10 @@ impl Interface for Application {
             stroke.set_width(f64::sqrt(self.scale.scale()));
             stroke.set_line_join(agg::LineJoin::Round);
             stroke.set_line_cap(agg::LineCap::Round);
-            let plen = stroke.source().source().paths();
-            for i in 0..plen {
+            for s in styles.iter() {
                 ras_clip_dbl.reset();
-                let l = stroke.source().source().style(i).line;
-                if l >= 0 {
-                    let id = stroke.source().source().style(i).path_id;
-                    ras_clip_dbl.add_path(&mut stroke, id);
+                if s.line >= 0 {
+                    ras_clip_dbl.add_path(&mut stroke, s.path_id);
                     ren.set_color(agg::Rgba8::new_params(0, 0, 0, 128));
                     agg::render_scanlines(&mut ras_clip_dbl, &mut sl, &mut ren);
                 }
@@ -528,9 +298,7 @@ impl Interface for Application {
     fn on_key(&mut self, _rb: &mut agg::RenderBuf, x: i32, y: i32, key: u32, _flags: u32) -> Draw {
         let (x, y) = (x as f64, y as f64);
         if key == ' ' as u32 {
-            self.shape.read_next();
-            self.shape
-                .set_scale(self.util.borrow().width(), self.util.borrow().height());
+            self.read_next();
             return Draw::Yes;
         }
 
@@ -0,0 +1,126 @@
+// Shows the variation axes and named instances of a variable font, draws
+// a line of text in every instance and along the weight axis, with GPOS
+// kerning where the font has it, and saves the result to a PNG file:
+//
+//   cargo run --example font_variations [font.ttf [output.png]]
+//
+// Fonts without variations get one line in the default instance.
+
+use agg::font_freetype::FreetypeBase;
+use agg::{
+    render_scanlines_aa_solid, Canvas, FontCacheManager, FontEngine, GlyphDataType, GlyphRender,
+    PixBgr24, Rgba8,
+};
+
+const TEXT: &str = "AVAWAY Typography To Te";
+
+type Fman<'a> = FontCacheManager<FreetypeBase<'a, i32>>;
+
+fn draw_text(canvas: &mut Canvas<PixBgr24>, fman: &mut Fman, text: &str, x: f64, y: f64) {
+    let (_, sl, mut ren) = canvas.parts_mut();
+    let black = Rgba8::new_params(0, 0, 0, 255);
+    let (mut x, mut y) = (x, y);
+    fman.reset_last_glyph();
+    for ch in text.chars() {
+        let glyph = fman.glyph(ch as u32);
+        if glyph.is_null() {
+            continue;
+        }
+        fman.add_kerning(&mut x, &mut y);
+        let gl = unsafe { &*glyph };
+        if gl.data_type == GlyphDataType::Gray8 {
+            let mut adp = fman.gray8_adaptor();
+            adp.init(gl.data.as_ptr(), gl.data_size as usize, x, y);
+            render_scanlines_aa_solid(&mut adp, sl, &mut ren, &black);
+        }
+        x += gl.advance_x;
+        y += gl.advance_y;
+    }
+}
+
+// The kerning between two characters in pixels
+fn pair_kerning(fman: &mut Fman, a: char, b: char) -> f64 {
+    let (mut x, mut y) = (0., 0.);
+    fman.reset_last_glyph();
+    fman.glyph(a as u32);
+    fman.glyph(b as u32);
+    fman.add_kerning(&mut x, &mut y);
+    x
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let font = args.get(1).map_or("timesi.ttf", |s| s.as_str());
+    let out = args.get(2).map_or("font_variations.png", |s| s.as_str());
+
+    let mut fman = FontCacheManager::new(FreetypeBase::<i32>::new(32), 32);
+    if !fman
+        .engine_mut()
+        .load_font(&format!("{}\0", font), 0, GlyphRender::AggGray8, &[], 0)
+    {
+        println!("Can't load '{}'", font);
+        return;
+    }
+    fman.engine_mut().set_height(28.);
+    fman.engine_mut().set_flip_y(true);
+    fman.engine_mut().set_hinting(false);
+
+    let axes = fman.engine().variation_axes();
+    let instances = fman.engine().num_named_instances();
+    println!(
+        "{}: {} axes, {} named instances, {} kerning",
+        font,
+        axes.len(),
+        instances,
+        if fman.engine().has_gpos_kerning() {
+            "GPOS"
+        } else {
+            "kern table"
+        }
+    );
+    for a in axes.iter() {
+        println!(
+            "  {} '{}': {} .. {} .. {}",
+            String::from_utf8_lossy(&a.tag),
+            a.name,
+            a.minimum,
+            a.default,
+            a.maximum
+        );
+    }
+
+    let mut canvas = Canvas::<PixBgr24>::new(800, 600);
+    canvas.clear(&Rgba8::new_params(255, 255, 255, 255));
+
+    // The default instance first, then the named ones
+    let mut y = 40.;
+    for i in 0..=instances {
+        fman.engine_mut().set_named_instance(i);
+        let wght = fman.engine().variation(b"wght");
+        println!(
+            "instance {}: wght {:?}, AV kerning {:.2}px",
+            i,
+            wght,
+            pair_kerning(&mut fman, 'A', 'V')
+        );
+        draw_text(&mut canvas, &mut fman, TEXT, 20., y);
+        y += 40.;
+    }
+
+    if let Some(a) = axes.iter().find(|a| &a.tag == b"wght") {
+        for i in 0..5 {
+            let w = a.minimum + (a.maximum - a.minimum) * i as f64 / 4.;
+            fman.engine_mut().set_variation(b"wght", w);
+            println!(
+                "wght {}: AV kerning {:.2}px",
+                w,
+                pair_kerning(&mut fman, 'A', 'V')
+            );
+            draw_text(&mut canvas, &mut fman, TEXT, 20., y);
+            y += 40.;
+        }
+    }
+
+    canvas.save_png(out).unwrap();
+    println!("saved {}", out);
+}
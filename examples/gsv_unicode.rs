@@ -0,0 +1,158 @@
+// Draws text in the built-in Unicode stroke font of GsvText: Latin-1,
+// Latin Extended-A, Greek and Cyrillic, a multi-line text with its
+// bounding box and base lines, and a font of a few symbols made in the
+// example, with the Unicode font as fallback. The Unicode font is also
+// written to a file and loaded back, and a bad font file is rejected.
+// The result is saved to a PNG file:
+//
+//   cargo run --example gsv_unicode [output.png]
+
+use agg::{Canvas, GsvFont, GsvText, LineCap, LineJoin, PathStorage, PixBgr24, Rgba8};
+
+const LINES: &[&str] = &[
+    "Latin-1: àáâãäå æ ç èéêë ìíîï ñ òóôõöø ùúûü ý ß ¿¡ «»",
+    "Latin Ext: ĀāĂăĄą ĆćČč Ďď Ěě Ğğ Łł Ńń Őő Œœ Řř Śś Šš Ťť Ůů Žž",
+    "Ελληνικά: ΑΒΓΔΕΖΗΘΙΚΛΜΝΞΟΠΡΣΤΥΦΧΨΩ αβγδεζηθικλμνξοπρστυφχψω",
+    "Кириллица: АБВГДЕЁЖЗИЙКЛМНОПРСТУФХЦЧШЩЪЫЬЭЮЯ абвгдеёжзий",
+    "Symbols: © ® ° ± × ÷ ½ ¼ ¾ ² ³ µ ¶ § £ ¥ ¢",
+];
+
+const MULTI: &str = "Multi-line text\nwith a bounding box,\nline heights and advances";
+
+fn rect(x1: f64, y1: f64, x2: f64, y2: f64) -> PathStorage {
+    let mut path = PathStorage::new();
+    path.move_to(x1, y1);
+    path.line_to(x2, y1);
+    path.line_to(x2, y2);
+    path.line_to(x1, y2);
+    path.close_polygon(0);
+    path
+}
+
+// Glyph data of strokes given as points in font units, with the advance
+fn glyph(strokes: &[&[(i32, i32)]], advance: i32) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut pen = (0, 0);
+    let mut step = |p: (i32, i32), mv: bool| {
+        data.push((p.0 - pen.0) as u8);
+        data.push(((p.1 - pen.1) as u8 & 0x7f) | if mv { 0x80 } else { 0 });
+        pen = p;
+    };
+    for s in strokes.iter() {
+        step(s[0], true);
+        for &p in s[1..].iter() {
+            step(p, false);
+        }
+    }
+    step((advance, 0), true);
+    data
+}
+
+fn symbol_font() -> GsvFont {
+    let left = glyph(&[&[(22, 9), (4, 9)], &[(10, 15), (4, 9), (10, 3)]], 26);
+    let right = glyph(&[&[(4, 9), (22, 9)], &[(16, 15), (22, 9), (16, 3)]], 26);
+    let up = glyph(&[&[(9, 0), (9, 18)], &[(3, 12), (9, 18), (15, 12)]], 18);
+    let down = glyph(&[&[(9, 18), (9, 0)], &[(3, 6), (9, 0), (15, 6)]], 18);
+    let check = glyph(&[&[(3, 8), (8, 1), (19, 19)]], 22);
+    let glyphs = vec![
+        (0x2190, left),
+        (0x2191, up),
+        (0x2192, right),
+        (0x2193, down),
+        (0x2713, check),
+    ];
+    GsvFont::from_glyphs(21, &glyphs).unwrap()
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let out = args.get(1).map_or("gsv_unicode.png", |s| s.as_str());
+
+    let mut canvas = Canvas::<PixBgr24>::new(820, 560);
+    canvas.clear(&Rgba8::new_params(255, 255, 255, 255));
+    canvas.set_line_join(LineJoin::Round);
+    canvas.set_line_cap(LineCap::Round);
+    let black = Rgba8::new_params(0, 0, 0, 255);
+    let blue = Rgba8::new_params(0, 80, 200, 255);
+    let red = Rgba8::new_params(200, 40, 0, 255);
+
+    let mut text = GsvText::new();
+    text.set_flip(true);
+    text.set_size(14., 0.);
+    text.set_space(1.);
+    canvas.set_line_width(1.2);
+    for (i, line) in LINES.iter().enumerate() {
+        text.set_start_point(10., 30. + i as f64 * 30.);
+        text.set_text(line);
+        canvas.stroke_path(&mut text, 0, &black);
+    }
+
+    // A multi-line text with its bounding box, the base line of each line
+    // and the advance of each line
+    text.set_size(20., 0.);
+    text.set_line_space(8.);
+    text.set_start_point(10., 210.);
+    text.set_text(MULTI);
+    canvas.set_line_width(1.6);
+    canvas.stroke_path(&mut text, 0, &black);
+    canvas.set_line_width(0.5);
+    if let Some((x1, y1, x2, y2)) = text.bounding_rect() {
+        canvas.stroke_path(&mut rect(x1, y1, x2, y2), 0, &red);
+    }
+    for (i, w) in text.line_advances().iter().enumerate() {
+        let y = 210. + i as f64 * text.line_height();
+        canvas.stroke_path(&mut rect(10., y, 10. + w, y), 0, &blue);
+    }
+    println!(
+        "{} lines, line height {}, text height {}, width {:.1}, last advance {:.1}",
+        text.num_lines(),
+        text.line_height(),
+        text.text_height(),
+        text.text_width(),
+        text.text_advance()
+    );
+
+    // A font of a few symbols as the current font, the characters it
+    // lacks come from the Unicode font
+    let symbols = text.add_font(symbol_font());
+    text.set_font(symbols);
+    text.set_size(16., 0.);
+    text.set_line_space(0.);
+    text.set_start_point(10., 360.);
+    text.set_text("Symbol font: ← → ↑ ↓ ✓ with fallback: Γειά σου, Привет");
+    canvas.set_line_width(1.2);
+    canvas.stroke_path(&mut text, 0, &black);
+
+    // The Unicode font written to a file, loaded back and drawn wide
+    let file = std::env::temp_dir().join("gsv_unicode.gsv");
+    let file = file.to_str().unwrap();
+    std::fs::write(file, GsvFont::new_unicode().data()).unwrap();
+    match text.load_font(file) {
+        Ok(idx) => {
+            let font = &text.fonts()[idx];
+            println!("loaded {} glyphs from {}", font.num_glyphs(), file);
+            text.set_size(24., 30.);
+            text.set_start_point(10., 420.);
+            text.set_text("Wide: Ωmega ĳ Ŋŋ Ħħ ŉ");
+            canvas.set_line_width(2.);
+            canvas.stroke_path(&mut text, 0, &blue);
+        }
+        Err(e) => println!("can't load {}: {}", file, e),
+    }
+
+    // A file that is no font
+    std::fs::write(file, b"GSVU\x02\x00\x15\x00\x00\x00\x00\x00").unwrap();
+    if let Err(e) = text.load_font(file) {
+        println!("bad font: {}", e);
+        text.set_font(0);
+        text.set_size(14., 0.);
+        text.set_start_point(10., 480.);
+        text.set_text(&format!("Error: {}", e));
+        canvas.set_line_width(1.2);
+        canvas.stroke_path(&mut text, 0, &red);
+    }
+    std::fs::remove_file(file).ok();
+
+    canvas.save_png(out).unwrap();
+    println!("saved {}", out);
+}
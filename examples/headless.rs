@@ -0,0 +1,51 @@
+// Renders without a window and saves the result to a PNG file:
+//
+//   cargo run --example headless [output.png]
+
+#[path = "misc/parse_lion.rs"]
+mod parse_lion;
+
+use crate::parse_lion::parse_lion;
+use agg::math_stroke::LineJoin;
+use agg::{
+    Canvas, Ellipse, PathStorage, PixBgr24, PixBgra32, Rgba8, RoundedRect, TransAffine,
+};
+
+fn main() {
+    let fname = std::env::args().nth(1).unwrap_or("headless.png".to_string());
+
+    let mut canvas = Canvas::<PixBgr24>::new(400, 420);
+    canvas.clear(&Rgba8::new_params(255, 255, 255, 255));
+
+    let mut path = PathStorage::new();
+    let mut colors = [Rgba8::new_params(0, 0, 0, 0); 100];
+    let mut path_idx = [0u32; 100];
+    let npaths = parse_lion(&mut path, &mut colors, &mut path_idx);
+
+    let mut mtx = TransAffine::new_default();
+    mtx.multiply(&TransAffine::trans_affine_translation(20., 20.));
+    canvas.set_transform(&mtx);
+    for i in 0..npaths as usize {
+        canvas.fill_path(&mut path, path_idx[i], &colors[i]);
+    }
+
+    // A translucent badge rendered into an image of its own and blended
+    // onto the lion
+    let mut badge = Canvas::<PixBgra32>::new(120, 120);
+    badge.clear(&Rgba8::new_params(0, 0, 0, 0));
+    let mut e = Ellipse::new_ellipse(60., 60., 55., 55., 0, false);
+    badge.fill_path(&mut e, 0, &Rgba8::new_params(40, 90, 200, 160));
+    badge.set_line_width(6.0);
+    badge.stroke_path(&mut e, 0, &Rgba8::new_params(20, 40, 120, 255));
+    canvas.draw_image(badge.pixfmt(), 260, 280, 255);
+
+    canvas.reset_transform();
+    canvas.set_line_width(4.0);
+    canvas.set_line_join(LineJoin::Round);
+    let mut r = RoundedRect::new(6., 6., 394., 414., 16.);
+    r.normalize_radius();
+    canvas.stroke_path(&mut r, 0, &Rgba8::new_params(90, 60, 30, 255));
+
+    canvas.save_png(&fname).unwrap();
+    println!("Saved {}", fname);
+}
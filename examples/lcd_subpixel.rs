@@ -0,0 +1,152 @@
+// Renders text and thin shapes with subpixel (LCD) rendering next to
+// plain gray anti-aliasing: the glyphs and paths are rasterized three
+// times as wide and drawn on a PixLcd, with the different filters and
+// subpixel orders, and a part is magnified to show the color fringes.
+// The result is saved to a PNG file:
+//
+//   cargo run --example lcd_subpixel [font.ttf [output.png]]
+//
+// timesi.ttf is in examples/web/timesi.zip.
+
+use agg::{
+    render_scanlines_aa_solid, Canvas, ConvStroke, ConvTransform, Ellipse, FontCacheManager,
+    FontEngine, GlyphDataType, GlyphRender, LcdFilter, LcdOrder, PathStorage, PixBgr24, PixFmt,
+    PixLcd, RasterScanLine, RasterizerScanlineAa, Renderer, RendererBase, Rgba8, ScanlineU8,
+    TransAffine, TrueTypeBase,
+};
+
+const TEXT: &str = "Anti-Grain Geometry: subpixel rendering";
+
+type Fman<'a> = FontCacheManager<TrueTypeBase<'a, i32>>;
+
+// Draws the text at (x, y) in pixels with the renderer, sub is 3 for
+// subpixels and 1 for pixels
+fn draw_text<Pix: PixFmt<C = Rgba8>>(
+    ren: &mut RendererBase<Pix>, fman: &mut Fman, text: &str, x: f64, y: f64, sub: f64,
+) {
+    let mut sl = ScanlineU8::new();
+    let black = Rgba8::new_params(0, 0, 0, 255);
+    let (mut x, mut y) = (x * sub, y);
+    fman.reset_last_glyph();
+    for ch in text.chars() {
+        let glyph = fman.glyph(ch as u32);
+        if glyph.is_null() {
+            continue;
+        }
+        fman.add_kerning(&mut x, &mut y);
+        let gl = unsafe { &*glyph };
+        if gl.data_type == GlyphDataType::Gray8 {
+            let mut adp = fman.gray8_adaptor();
+            adp.init(gl.data.as_ptr(), gl.data_size as usize, x, y);
+            render_scanlines_aa_solid(&mut adp, &mut sl, ren, &black);
+        }
+        x += gl.advance_x;
+        y += gl.advance_y;
+    }
+}
+
+// Thin lines fanning out and a circle, sub as for draw_text
+fn draw_shapes<Pix: PixFmt<C = Rgba8>>(
+    ras: &mut RasterizerScanlineAa, ren: &mut RendererBase<Pix>, x: f64, y: f64, sub: f64,
+) {
+    let mut sl = ScanlineU8::new();
+    let mtx = TransAffine::trans_affine_scaling(sub, 1.);
+    let mut path = PathStorage::new();
+    for i in 0..12 {
+        let a = i as f64 * 0.13;
+        path.move_to(x, y);
+        path.line_to(x + 150. * a.cos(), y + 150. * a.sin());
+    }
+    let mut ell = Ellipse::new_ellipse(x + 60., y + 60., 40., 40., 64, false);
+    path.concat_path(&mut ell, 0);
+    let mut stroke: ConvStroke<_> = ConvStroke::new_owned(path);
+    stroke.set_width(0.8);
+    let mut trans = ConvTransform::new_owned(stroke, mtx);
+    ras.reset();
+    ras.add_path(&mut trans, 0);
+    render_scanlines_aa_solid(ras, &mut sl, ren, &Rgba8::new_params(0, 0, 0, 255));
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let font = args.get(1).map_or("timesi.ttf", |s| s.as_str());
+    let out = args.get(2).map_or("lcd_subpixel.png", |s| s.as_str());
+
+    let mut fman = FontCacheManager::new(TrueTypeBase::<i32>::new(4), 32);
+    if !fman
+        .engine_mut()
+        .load_font(font, 0, GlyphRender::AggGray8, &[], 0)
+    {
+        println!("Can't load '{}'", font);
+        return;
+    }
+    fman.engine_mut().set_flip_y(true);
+    fman.engine_mut().set_hinting(false);
+
+    let mut canvas = Canvas::<PixBgr24>::new(800, 600);
+    canvas.clear(&Rgba8::new_params(255, 255, 255, 255));
+    let mut ras = RasterizerScanlineAa::new();
+
+    let filters = [
+        ("gray", None),
+        ("lcd rgb", Some((LcdOrder::Rgb, LcdFilter::new_default()))),
+        ("lcd bgr", Some((LcdOrder::Bgr, LcdFilter::new_default()))),
+        ("light", Some((LcdOrder::Rgb, LcdFilter::new_light()))),
+        ("none", Some((LcdOrder::Rgb, LcdFilter::new_none()))),
+    ];
+    let mut y = 20.;
+    for (name, lcd) in filters.iter() {
+        fman.engine_mut().transform(&TransAffine::new_default());
+        fman.engine_mut().set_height(10.);
+        let mut ren = canvas.ren();
+        draw_text(&mut ren, &mut fman, name, 5., y + 12., 1.);
+        for &h in [9., 11., 13.].iter() {
+            fman.engine_mut().set_height(h);
+            let pix = ren.ren_mut();
+            match lcd {
+                Some((order, filter)) => {
+                    fman.engine_mut()
+                        .transform(&PixLcd::<PixBgr24>::transform());
+                    let mut lcd = PixLcd::new(pix, *order);
+                    lcd.set_filter(filter.clone());
+                    let mut ren = RendererBase::new_borrowed(&mut lcd);
+                    draw_text(&mut ren, &mut fman, TEXT, 60., y, 3.);
+                }
+                None => {
+                    let mut ren = RendererBase::new_borrowed(pix);
+                    draw_text(&mut ren, &mut fman, TEXT, 60., y, 1.);
+                }
+            }
+            y += h + 4.;
+        }
+        y += 6.;
+    }
+
+    // Shapes, gray at the left, subpixels at the right
+    {
+        let mut ren = canvas.ren();
+        draw_shapes(&mut ras, &mut ren, 20., 290., 1.);
+        let mut lcd = PixLcd::new(ren.ren_mut(), LcdOrder::Rgb);
+        let mut ren = RendererBase::new_borrowed(&mut lcd);
+        draw_shapes(&mut ras, &mut ren, 200., 290., 3.);
+    }
+
+    // The start of the first gray and rgb lines magnified 8 times
+    let zoom = 8;
+    let (w, h) = (48, 16);
+    for (k, &sy) in [8, 59].iter().enumerate() {
+        for y in 0..h {
+            for x in 0..w {
+                let c = canvas.pixfmt().pixel(60 + x, sy + y);
+                let dx = 400 + x * zoom;
+                let dy = 290 + k as i32 * (h * zoom + 8) + y * zoom;
+                canvas
+                    .ren()
+                    .blend_bar(dx, dy, dx + zoom - 1, dy + zoom - 1, &c, 255);
+            }
+        }
+    }
+
+    canvas.save_png(out).unwrap();
+    println!("saved {}", out);
+}
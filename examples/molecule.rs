@@ -0,0 +1,56 @@
+// Loads the molecules of an SDF file, renders the first few of them in a
+// grid with their names and saves it to a PNG file:
+//
+//   cargo run --example molecule [file.sdf [first [output.png]]]
+
+use agg::{
+    load_sdf, render_molecule, Canvas, GsvText, MoleculeStyle, PixBgr24, Rgba8, TransAffine,
+};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let fname = args.get(1).map_or("examples/web/1.sdf", |s| s.as_str());
+    let first = args.get(2).map_or(0, |s| s.parse::<usize>().unwrap());
+    let out = args.get(3).map_or("molecule.png", |s| s.as_str());
+
+    let molecules = load_sdf(fname).unwrap();
+    println!("{}: {} molecules", fname, molecules.len());
+
+    let mut style = MoleculeStyle::new(Rgba8::new_params(0, 0, 0, 255));
+    style.set_element_color("N", Rgba8::new_params(0, 0, 160, 255));
+    style.set_element_color("O", Rgba8::new_params(200, 0, 0, 255));
+    style.set_element_color("S", Rgba8::new_params(150, 130, 0, 255));
+    style.set_element_color("P", Rgba8::new_params(200, 100, 0, 255));
+    for e in ["F", "Cl", "Br", "I"].iter() {
+        style.set_element_color(e, Rgba8::new_params(0, 150, 0, 255));
+    }
+
+    let (cols, rows, cw, ch) = (3, 2, 400., 320.);
+    let mut canvas = Canvas::<PixBgr24>::new((cols as f64 * cw) as u32, (rows as f64 * ch) as u32);
+    canvas.clear(&Rgba8::new_params(255, 255, 255, 255));
+    let black = Rgba8::new_params(0, 0, 0, 255);
+    for (i, mol) in molecules.iter().skip(first).take(cols * rows).enumerate() {
+        println!(
+            "{}: {} atoms, {} bonds, {} data items",
+            mol.name,
+            mol.atoms.len(),
+            mol.bonds.len(),
+            mol.data.len()
+        );
+        let (x, y) = ((i % cols) as f64 * cw, (i / cols) as f64 * ch);
+        let mut mtx = mol.viewport(cw, ch - 30.);
+        mtx *= TransAffine::trans_affine_translation(x, y);
+        render_molecule(&mut canvas.ren(), mol, &mtx, &style);
+
+        let mut name = GsvText::new();
+        name.set_size(12., 0.);
+        name.set_flip(true);
+        name.set_start_point(x + 10., y + ch - 10.);
+        name.set_text(&mol.name);
+        canvas.set_line_width(1.2);
+        canvas.stroke_path(&mut name, 0, &black);
+    }
+
+    canvas.save_png(out).unwrap();
+    println!("saved {}", out);
+}
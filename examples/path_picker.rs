@@ -0,0 +1,139 @@
+// Scatters filled and stroked shapes, picks the topmost one under a few
+// points and all of them in a rectangle and saves the result with the
+// picked shapes outlined to a PNG file:
+//
+//   cargo run --example path_picker [output.png]
+
+use agg::basics::FillingRule;
+use agg::{Canvas, ConvCurve, Ellipse, PathPicker, PathStorage, PixBgr24, Rgba8};
+
+enum Kind {
+    Ellipse,
+    Star,
+    Line,
+}
+
+fn main() {
+    let fname = std::env::args()
+        .nth(1)
+        .unwrap_or("path_picker.png".to_string());
+
+    let mut seed = 1234u32;
+    let mut rnd = |n: f64| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        ((seed >> 16) & 0x7FFF) as f64 / 32768. * n
+    };
+
+    let mut shapes = Vec::new();
+    for i in 0..150 {
+        let (x, y, r) = (rnd(760.) + 20., rnd(560.) + 20., rnd(30.) + 10.);
+        let mut p = PathStorage::new();
+        let kind = match i % 3 {
+            0 => {
+                let mut e = Ellipse::new_ellipse(x, y, r, r * 0.7, 0, false);
+                p.concat_path(&mut e, 0);
+                Kind::Ellipse
+            }
+            1 => {
+                // Five points joined crosswise, the center is a hole with
+                // the even-odd rule
+                for k in 0..5 {
+                    let a = (k * 2) as f64 * std::f64::consts::PI * 2. / 5.;
+                    let (px, py) = (x + r * a.sin(), y - r * a.cos());
+                    if k == 0 {
+                        p.move_to(px, py);
+                    } else {
+                        p.line_to(px, py);
+                    }
+                }
+                p.close_polygon(0);
+                Kind::Star
+            }
+            _ => {
+                p.move_to(x - r, y);
+                p.curve3_ctrl(x, y - 2. * r, x + r, y);
+                p.line_to(x + r, y + r);
+                Kind::Line
+            }
+        };
+        shapes.push((p, kind));
+    }
+
+    let mut picker = PathPicker::new();
+    for (i, (p, kind)) in shapes.iter_mut().enumerate() {
+        match kind {
+            Kind::Ellipse => picker.add_fill(p, 0, i as u32, FillingRule::FillNonZero),
+            Kind::Star => picker.add_fill(p, 0, i as u32, FillingRule::FillEvenOdd),
+            Kind::Line => picker.add_stroke(p, 0, i as u32, 6.),
+        }
+    }
+
+    let mut canvas = Canvas::<PixBgr24>::new(800, 600);
+    canvas.clear(&Rgba8::new_params(255, 255, 255, 255));
+    let draw = |canvas: &mut Canvas<PixBgr24>, p: &mut PathStorage, kind: &Kind, c: &Rgba8| {
+        let mut curve: ConvCurve<_> = ConvCurve::new_borrowed(p);
+        match kind {
+            Kind::Ellipse => canvas.fill_path(&mut curve, 0, c),
+            Kind::Star => {
+                canvas.set_filling_rule(FillingRule::FillEvenOdd);
+                canvas.fill_path(&mut curve, 0, c);
+                canvas.set_filling_rule(FillingRule::FillNonZero);
+            }
+            Kind::Line => {
+                canvas.set_line_width(6.);
+                canvas.stroke_path(&mut curve, 0, c);
+            }
+        }
+    };
+    for (i, (p, kind)) in shapes.iter_mut().enumerate() {
+        let c = Rgba8::new_params(
+            (i * 67 % 200) as u32 + 40,
+            (i * 131 % 200) as u32 + 40,
+            (i * 29 % 200) as u32 + 40,
+            200,
+        );
+        draw(&mut canvas, p, kind, &c);
+    }
+
+    let outline = |canvas: &mut Canvas<PixBgr24>, p: &mut PathStorage, c: &Rgba8| {
+        let mut curve: ConvCurve<_> = ConvCurve::new_borrowed(p);
+        canvas.set_line_width(2.5);
+        canvas.stroke_path(&mut curve, 0, c);
+    };
+
+    let red = Rgba8::new_params(255, 0, 0, 255);
+    for &(x, y) in [(200., 150.), (400., 300.), (610., 420.), (120., 480.)].iter() {
+        let top = picker.pick(x, y);
+        println!(
+            "({}, {}): top {:?}, all {:?}",
+            x,
+            y,
+            top,
+            picker.pick_all(x, y)
+        );
+        if let Some(id) = top {
+            outline(&mut canvas, &mut shapes[id as usize].0, &red);
+        }
+        let mut mark = Ellipse::new_ellipse(x, y, 3., 3., 16, false);
+        canvas.fill_path(&mut mark, 0, &Rgba8::new_params(0, 0, 0, 255));
+    }
+
+    let blue = Rgba8::new_params(0, 0, 255, 255);
+    let (x1, y1, x2, y2) = (480., 60., 680., 200.);
+    let ids = picker.pick_rect(x1, y1, x2, y2);
+    println!("rect ({}, {}, {}, {}): {:?}", x1, y1, x2, y2, ids);
+    for &id in ids.iter() {
+        outline(&mut canvas, &mut shapes[id as usize].0, &blue);
+    }
+    let mut rect = PathStorage::new();
+    rect.move_to(x1, y1);
+    rect.line_to(x2, y1);
+    rect.line_to(x2, y2);
+    rect.line_to(x1, y2);
+    rect.close_polygon(0);
+    canvas.set_line_width(1.);
+    canvas.stroke_path(&mut rect, 0, &blue);
+
+    canvas.save_png(&fname).unwrap();
+    println!("saved {}", fname);
+}
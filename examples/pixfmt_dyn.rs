@@ -0,0 +1,108 @@
+// Decodes PNG files of different color types in one process, draws on
+// each in the pixel format matching the file and saves the results:
+//
+//   cargo run --example pixfmt_dyn [input.png ...]
+//
+// Without arguments a gray and an RGBA image are generated first.
+
+use agg::pixfmt_rgba::PixRgba32;
+use agg::png::{decode_png, encode_png, save_png, PngColorType, PngImage};
+use agg::renderer_scanline::render_scanlines_aa_solid;
+use agg::ConvStroke;
+use agg::{
+    Ellipse, Gray8, ImageBuffer, PixFmt, PixFmtDyn, PixGray8, PixelFormat, RasterScanLine,
+    RasterizerScanlineAa, RenderBuf, RendererBase, Rgba8, ScanlineU8,
+};
+
+fn format_for(png: &PngImage) -> PixelFormat {
+    match (png.color_type(), png.bit_depth()) {
+        (PngColorType::Gray, 16) => PixelFormat::Gray16,
+        (PngColorType::Gray, _) => PixelFormat::Gray8,
+        (PngColorType::GrayAlpha, 16) | (PngColorType::Rgba, 16) => PixelFormat::Rgba64,
+        (PngColorType::GrayAlpha, _) | (PngColorType::Rgba, _) => PixelFormat::Rgba32,
+        (_, 16) => PixelFormat::Rgb48,
+        _ => PixelFormat::Rgb24,
+    }
+}
+
+fn sample_pngs() -> Vec<(String, Vec<u8>)> {
+    let mut gray = ImageBuffer::<PixGray8>::new(200, 150);
+    {
+        let mut pix = gray.pixfmt();
+        for y in 0..150 {
+            for x in 0..200 {
+                pix.copy_pixel(x, y, &Gray8::new_params((x + y) as u32 * 255 / 350, 255));
+            }
+        }
+    }
+    let mut rgba = ImageBuffer::<PixRgba32>::new(200, 150);
+    {
+        let mut pix = rgba.pixfmt();
+        for y in 0..150 {
+            for x in 0..200 {
+                let c = Rgba8::new_params(x as u32 + 55, 80, 255 - y as u32, 128 + y as u32 / 2);
+                pix.copy_pixel(x, y, &c);
+            }
+        }
+    }
+    vec![
+        ("gray.png".to_string(), encode_png(&*gray.pixfmt())),
+        ("rgba.png".to_string(), encode_png(&*rgba.pixfmt())),
+    ]
+}
+
+fn main() {
+    let inputs: Vec<(String, Vec<u8>)> = if std::env::args().len() > 1 {
+        std::env::args()
+            .skip(1)
+            .map(|f| {
+                let data = std::fs::read(&f).unwrap();
+                (f, data)
+            })
+            .collect()
+    } else {
+        sample_pngs()
+    };
+
+    let mut ras: RasterizerScanlineAa = RasterizerScanlineAa::new();
+    let mut sl = ScanlineU8::new();
+    for (i, (name, data)) in inputs.iter().enumerate() {
+        let png = decode_png(data).unwrap();
+        let format = format_for(&png);
+        let (w, h) = (png.width(), png.height());
+        let stride = w * format.pix_width();
+        let mut buf = vec![0u8; (stride * h) as usize];
+        let rbuf = RenderBuf::new(buf.as_mut_ptr(), w, h, stride as i32);
+
+        let mut pix = PixFmtDyn::<Rgba8>::new(format, rbuf);
+        png.copy_to(&mut pix, 0, 0);
+        let mut ren = RendererBase::new_owned(pix);
+
+        let (cx, cy) = (w as f64 / 2., h as f64 / 2.);
+        let r = cx.min(cy) * 0.7;
+        let mut e = Ellipse::new_ellipse(cx, cy, r, r, 0, false);
+        ras.reset();
+        ras.add_path(&mut e, 0);
+        render_scanlines_aa_solid(
+            &mut ras,
+            &mut sl,
+            &mut ren,
+            &Rgba8::new_params(220, 60, 20, 160),
+        );
+
+        let mut stroke: ConvStroke<_> = ConvStroke::new_owned(e);
+        stroke.set_width(4.0);
+        ras.reset();
+        ras.add_path(&mut stroke, 0);
+        render_scanlines_aa_solid(
+            &mut ras,
+            &mut sl,
+            &mut ren,
+            &Rgba8::new_params(0, 0, 0, 255),
+        );
+
+        let fname = format!("pixfmt_dyn{}.png", i);
+        save_png(ren.ren(), &fname).unwrap();
+        println!("{}: {:?}, saved {}", name, ren.ren().format(), fname);
+    }
+}
@@ -1,8 +1,8 @@
 use windows_sys::{Win32::Foundation::*, Win32::Graphics::Gdi::*};
 
 use core::ptr::*;
+use agg::image_io;
 use std::fs::File;
-use std::io::{Read, Write};
 use std::mem;
 use std::mem::*;
 use std::ptr;
@@ -444,43 +444,32 @@ impl PixelMap {
         self.blend_rect(h_dc, Some(&rect), None);
     }
 
-    pub fn load_from_bmp(&mut self, fd: &mut File)  -> bool {
-        let mut bmf: BITMAPFILEHEADER = unsafe { mem::zeroed() };
-        let bmi;
-        let bmp_size;
-
-        unsafe {
-            fd.read(std::slice::from_raw_parts_mut(
-                &mut bmf as *mut BITMAPFILEHEADER as *mut u8,
-                mem::size_of::<BITMAPFILEHEADER>(),
-            ))
-            .unwrap();
-            if bmf.bfType != 0x4D42 {
-                return false;
-            }
-
-            bmp_size = bmf.bfSize - mem::size_of::<BITMAPFILEHEADER>() as u32;
-
-            bmi = libc::malloc(bmp_size as usize) as *mut BITMAPINFO;
-
-            if let Ok(n) = fd.read(std::slice::from_raw_parts_mut(
-                bmi as *mut u8,
-                bmp_size as usize,
-            )) {
-                if n != bmp_size as usize {
-					libc::free(bmi as *mut libc::c_void);
-                    return false;
-                }
-            } else {
-				libc::free(bmi as *mut libc::c_void);
-                return false;
+    pub fn load_from_bmp(&mut self, fd: &mut File) -> bool {
+        let img = match image_io::read_bmp(fd) {
+            Ok(img) => img,
+            Err(_) => return false,
+        };
+        let (org, format) = match img.format() {
+            f if f.is_gray() => (Org::Mono8, agg::PixelFormat::Gray8),
+            f if f.has_alpha() => (Org::Color32, agg::PixelFormat::Bgra32),
+            _ => (Org::Color24, agg::PixelFormat::Bgr24),
+        };
+        let img = img.convert(format);
+        let h = img.height();
+        self.create(img.width(), h, org, 256);
+        let stride = self.stride() as usize;
+        // Pixel maps are stored bottom-up
+        for y in 0..h {
+            let row = img.row(y);
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    row.as_ptr(),
+                    self.buf.add((h - 1 - y) as usize * stride),
+                    row.len(),
+                );
             }
-            self.destroy();
-            self.bpp = (*bmi).bmiHeader.biBitCount as u32;
-            self.create_from_bmp(bmi);
-            self.is_internal = true;
         }
-        return true;
+        true
     }
 
     pub fn load_from_bmp_str(&mut self, filename: &str)  -> bool {
@@ -491,37 +480,30 @@ impl PixelMap {
         false
     }
 
-    pub fn save_as_bmp(&self, fd: &mut File)  -> bool {
+    pub fn save_as_bmp(&self, fd: &mut File) -> bool {
         if self.bmp.is_null() {
             return false;
         }
-
-        let mut bmf: BITMAPFILEHEADER = unsafe { mem::zeroed() };
-
-        bmf.bfType = 0x4D42;
-        bmf.bfOffBits =
-            Self::calc_header_size(self.bmp) + mem::size_of::<BITMAPFILEHEADER>() as u32;
-        bmf.bfSize = bmf.bfOffBits + self.img_size;
-        bmf.bfReserved1 = 0;
-        bmf.bfReserved2 = 0;
-
-        unsafe {
-            fd.write(std::slice::from_raw_parts(
-                &bmf as *const BITMAPFILEHEADER as *const u8,
-                mem::size_of::<BITMAPFILEHEADER>(),
-            ))
-            .unwrap();
-            fd.write(std::slice::from_raw_parts(
-                self.bmp as *const u8,
-                self.full_size as usize,
-            ))
-            .unwrap();
+        let format = match self.bpp {
+            8 => agg::PixelFormat::Gray8,
+            24 => agg::PixelFormat::Bgr24,
+            32 => agg::PixelFormat::Bgra32,
+            _ => return false,
+        };
+        let (w, h, stride) = (self.width(), self.height(), self.stride() as usize);
+        let mut img = image_io::RawImage::new(w, h, format);
+        let len = img.stride();
+        for y in 0..h {
+            let src = unsafe {
+                std::slice::from_raw_parts(self.buf.add((h - 1 - y) as usize * stride), len)
+            };
+            img.row_mut(y).copy_from_slice(src);
         }
-        return true;
+        image_io::write_bmp(&img, fd).is_ok()
     }
 
     pub fn save_as_bmp_str(&self, filename: &str)  -> bool {
-        let fd = File::open(filename);
+        let fd = File::create(filename);
         if let Ok(mut fd) = fd {
             return self.save_as_bmp(&mut fd);
         }
@@ -12,6 +12,7 @@ use std::{cell::RefCell, rc::Rc, slice, str};
 
 //use arr_macro::arr;
 //use core::mem::MaybeUninit;
+use agg::image_io::{self, ImageIoError};
 use agg::util::*;
 use agg::RenderBuf;
 use agg::RenderBuffer;
@@ -362,152 +363,59 @@ impl PlatUtil {
 
     pub fn load_img(&mut self, id: u32, file: &str) -> bool {
         if id < MAX_IMAGES {
-            let mut buf = [0u8; 1024];
             let mut f = String::from(file);
             let len = f.len();
             if len < 4 || &f[len - 4..] != ".ppm" {
                 f.push_str(".ppm");
             }
-            let mut fd = match File::open(f) {
-                Ok(f) => f,
-                Err(_) => return false,
-            };
-            let len = match fd.read(&mut buf[..1022]) {
-                Ok(l) => l,
-                Err(_) => return false,
-            };
-            buf[len] = 0;
-            let mut idx = 0;
-            if buf[0] != 'P' as u8 && buf[1] != '6' as u8 {
-                return false;
-            }
-            idx += 2;
-            while buf[idx] > 0 && !buf[idx].is_ascii_digit() {
-                idx += 1;
-            }
-
-            if buf[idx] == 0 {
-                return false;
-            }
-
-            fn getstr(buf: &[u8], idx: usize, len: usize) -> (&str, usize) {
-                let mut i = 0;
-                while idx + i < len && buf[idx + i].is_ascii_digit() {
-                    i += 1;
-                }
-                (unsafe { str::from_utf8_unchecked(&buf[idx..idx + i]) }, i)
-            }
-
-            let (s, i) = getstr(&buf, idx, len);
-            let st = s.parse::<u32>();
-            let width = match st {
-                Ok(w) => w,
-                Err(_) => return false,
-            };
-            if width == 0 || width > 4096 {
-                return false;
-            }
-            idx += i;
-            while buf[idx] > 0 && !buf[idx].is_ascii_digit() {
-                idx += 1;
-            }
-            if buf[idx] == 0 {
-                return false;
-            }
-            let (s, i) = getstr(&buf, idx, len);
-            let st = s.parse::<u32>();
-            let height = match st {
-                Ok(h) => h,
-                Err(_) => return false,
-            };
-            if height == 0 || height > 4096 {
-                return false;
-            }
-            idx += i;
-            while buf[idx] > 0 && !buf[idx].is_ascii_digit() {
-                idx += 1;
-            }
-            let (s, i) = getstr(&buf, idx, len);
-            let st = s.parse::<u32>();
-            if match st {
-                Ok(v) => v,
-                Err(_) => return false,
-            } != 255
+            let mut img = match File::open(f)
+                .map_err(ImageIoError::from)
+                .and_then(|mut fd| image_io::read_pnm(&mut fd))
             {
-                return false;
-            }
-            idx += i;
-            if buf[idx] == 0 {
-                return false;
-            }
-            idx += 1;
-            match fd.seek(SeekFrom::Start(idx as u64)) {
-                Ok(_) => (),
+                Ok(img) => img.convert(agg::PixelFormat::Rgb24),
                 Err(_) => return false,
             };
+            let (width, height) = (img.width(), img.height());
             self.create_img(id, width, height);
             let mut ret = true;
             let id = id as usize;
-            let tmp = unsafe {
-                std::slice::from_raw_parts_mut(
-                    self.specific.buf_img[id],
-                    (width * height * 3) as usize,
-                )
+            let rbuf_img = RenderBuf::new(
+                img.data_mut().as_mut_ptr(),
+                width,
+                height,
+                if self.specific.flip_y {
+                    -(width as i32 * 3)
+                } else {
+                    width as i32 * 3
+                },
+            );
+            match self.specific.format {
+                PixFormat::Rgb24 => {
+                    color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToRgb24::convert)
+                }
+                PixFormat::Rgb555 => {
+                    color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToRgb555::convert)
+                }
+                PixFormat::Rgb565 => {
+                    color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToRgb565::convert)
+                }
+                PixFormat::Bgr24 => {
+                    color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToBgr24::convert)
+                }
+                PixFormat::Rgba32 => {
+                    color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToRgba32::convert)
+                }
+                PixFormat::Argb32 => {
+                    color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToArgb32::convert)
+                }
+                PixFormat::Bgra32 => {
+                    color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToBgra32::convert)
+                }
+                PixFormat::Abgr32 => {
+                    color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToAbgr32::convert)
+                }
+                _ => ret = false,
             };
-            if self.specific.format == PixFormat::Rgb24 {
-                match fd.read(tmp) {
-                    Ok(_) => (),
-                    Err(_) => ret = false,
-                };
-            } else {
-                let buf_img = unsafe {
-                    std::alloc::alloc(
-                        std::alloc::Layout::from_size_align((width * height * 3) as usize, 1)
-                            .unwrap(),
-                    )
-                } as *mut u8;
-                let rbuf_img = RenderBuf::new(
-                    unsafe { &mut *buf_img },
-                    width,
-                    height,
-                    if self.specific.flip_y {
-                        -(width as i32 * 3)
-                    } else {
-                        width as i32 * 3
-                    },
-                );
-                let tmp = unsafe {
-                    std::slice::from_raw_parts_mut(buf_img, (width * height * 3) as usize)
-                };
-                match fd.read(tmp) {
-                    Ok(_) => (),
-                    Err(_) => ret = false,
-                };
-                match self.specific.format {
-                    PixFormat::Rgb555 => {
-                        color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToRgb555::convert)
-                    }
-                    PixFormat::Rgb565 => {
-                        color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToRgb565::convert)
-                    }
-                    PixFormat::Bgr24 => {
-                        color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToBgr24::convert)
-                    }
-                    PixFormat::Rgba32 => {
-                        color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToRgba32::convert)
-                    }
-                    PixFormat::Argb32 => {
-                        color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToArgb32::convert)
-                    }
-                    PixFormat::Bgra32 => {
-                        color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToBgra32::convert)
-                    }
-                    PixFormat::Abgr32 => {
-                        color_conv(&mut self.rbuf_img[id], &rbuf_img, Rgb24ToAbgr32::convert)
-                    }
-                    _ => ret = false,
-                };
-            }
             return ret;
         }
         return false;
@@ -521,62 +429,55 @@ impl PlatUtil {
                 buf.push_str(".ppm");
             }
 
-            let fd = File::create(buf);
-            if fd.is_err() {
-                return false;
-            }
-
             let w = self.rbuf_img(idx).width();
-            let h = self.rbuf_img(idx).height() as i32;
-
-            let mut f = fd.unwrap();
-            f.write_fmt(format_args!("P6\n{} {}\n255\n", w, h)).unwrap();
-
-            let tmp_buf = unsafe {
-                std::alloc::alloc(std::alloc::Layout::from_size_align((w * 3) as usize, 1).unwrap())
-            } as *mut u8;
-            let tmp_buf_ref = unsafe { std::slice::from_raw_parts_mut(tmp_buf, (w * 3) as usize) };
-            for y in 0..self.rbuf_img(idx).height() as i32 {
-                let src = self
-                    .rbuf_img(idx)
-                    .row(if self.specific.flip_y { h - 1 - y } else { y });
+            let h = self.rbuf_img(idx).height();
+            let mut img = image_io::RawImage::new(w, h, agg::PixelFormat::Rgb24);
+            for y in 0..h {
+                let src = self.rbuf_img(idx).row(if self.specific.flip_y {
+                    (h - 1 - y) as i32
+                } else {
+                    y as i32
+                });
+                let dst = img.row_mut(y);
                 match self.specific.format {
                     PixFormat::Rgb555 => {
-                        color_conv_row(tmp_buf_ref, src, w, Rgb555ToRgb24::convert);
+                        color_conv_row(dst, src, w, Rgb555ToRgb24::convert);
                     }
 
                     PixFormat::Rgb565 => {
-                        color_conv_row(tmp_buf_ref, src, w, Rgb565ToRgb24::convert);
+                        color_conv_row(dst, src, w, Rgb565ToRgb24::convert);
                     }
 
                     PixFormat::Bgr24 => {
-                        color_conv_row(tmp_buf_ref, src, w, Bgr24ToRgb24::convert);
+                        color_conv_row(dst, src, w, Bgr24ToRgb24::convert);
                     }
 
                     PixFormat::Rgb24 => {
-                        color_conv_row(tmp_buf_ref, src, w, Rgb24ToRgb24::convert);
+                        color_conv_row(dst, src, w, Rgb24ToRgb24::convert);
                     }
 
                     PixFormat::Rgba32 => {
-                        color_conv_row(tmp_buf_ref, src, w, Rgba32ToRgb24::convert);
+                        color_conv_row(dst, src, w, Rgba32ToRgb24::convert);
                     }
 
                     PixFormat::Argb32 => {
-                        color_conv_row(tmp_buf_ref, src, w, Argb32ToRgb24::convert);
+                        color_conv_row(dst, src, w, Argb32ToRgb24::convert);
                     }
 
                     PixFormat::Bgra32 => {
-                        color_conv_row(tmp_buf_ref, src, w, Bgra32ToRgb24::convert);
+                        color_conv_row(dst, src, w, Bgra32ToRgb24::convert);
                     }
 
                     PixFormat::Abgr32 => {
-                        color_conv_row(tmp_buf_ref, src, w, Abgr32ToRgb24::convert);
+                        color_conv_row(dst, src, w, Abgr32ToRgb24::convert);
                     }
                     _ => {}
                 }
-                f.write(tmp_buf_ref).unwrap();
             }
-            true
+            match File::create(buf) {
+                Ok(mut fd) => image_io::write_pnm(&img, &mut fd).is_ok(),
+                Err(_) => false,
+            }
         } else {
             false
         }
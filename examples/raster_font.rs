@@ -0,0 +1,181 @@
+// Draws text with bitmap fonts through GlyphRasterBin: a font of the
+// embedded tables, a small BDF font of a few Unicode symbols made in the
+// example, and a BDF or PCF font file given on the command line, e.g.
+// one of the X11 fonts in /usr/share/fonts/X11/misc. Characters a font
+// lacks are left out. The result is saved to a PNG file:
+//
+//   cargo run --example raster_font [font.bdf|font.pcf[.gz] [output.png]]
+
+use agg::embedded_raster_fonts::{GSE8X16, VERDANA16_BOLD};
+use agg::glyph_raster_bin::GlyphRasterBin;
+use agg::{Canvas, PixBgr24, RasterFont, RendererRasterHtextSolid, Rgba8};
+
+const SYMBOLS_BDF: &str = "STARTFONT 2.1
+FONT -example-symbols-medium-r-normal--8-80-75-75-c-80-iso10646-1
+SIZE 8 75 75
+FONTBOUNDINGBOX 8 8 0 -1
+STARTPROPERTIES 2
+FONT_ASCENT 7
+FONT_DESCENT 1
+ENDPROPERTIES
+CHARS 6
+STARTCHAR uni2190
+ENCODING 8592
+DWIDTH 8 0
+BBX 7 5 0 1
+BITMAP
+20
+40
+FE
+40
+20
+ENDCHAR
+STARTCHAR uni2192
+ENCODING 8594
+DWIDTH 8 0
+BBX 7 5 0 1
+BITMAP
+08
+04
+FE
+04
+08
+ENDCHAR
+STARTCHAR uni2605
+ENCODING 9733
+DWIDTH 8 0
+BBX 7 7 0 0
+BITMAP
+10
+10
+FE
+7C
+38
+6C
+44
+ENDCHAR
+STARTCHAR uni2665
+ENCODING 9829
+DWIDTH 8 0
+BBX 7 6 0 0
+BITMAP
+6C
+FE
+FE
+7C
+38
+10
+ENDCHAR
+STARTCHAR uni03A9
+ENCODING 937
+DWIDTH 8 0
+BBX 7 7 0 0
+BITMAP
+38
+44
+82
+82
+44
+28
+EE
+ENDCHAR
+STARTCHAR unencoded
+ENCODING -1
+DWIDTH 8 0
+BBX 8 8 0 -1
+BITMAP
+FF
+FF
+FF
+FF
+FF
+FF
+FF
+FF
+ENDCHAR
+ENDFONT
+";
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let file = args.get(1).map(|s| s.as_str());
+    let out = args.get(2).map_or("raster_font.png", |s| s.as_str());
+
+    let mut canvas = Canvas::<PixBgr24>::new(640, 400);
+    canvas.clear(&Rgba8::new_params(255, 255, 255, 255));
+    let black = Rgba8::new_params(0, 0, 0, 255);
+    let blue = Rgba8::new_params(0, 80, 200, 255);
+    let red = Rgba8::new_params(200, 40, 0, 255);
+
+    let mut fonts = vec![
+        ("gse8x16", RasterFont::from_embedded(&GSE8X16).unwrap()),
+        (
+            "verdana16_bold",
+            RasterFont::from_embedded(&VERDANA16_BOLD).unwrap(),
+        ),
+    ];
+    let symbols = RasterFont::read_bdf(&mut SYMBOLS_BDF.as_bytes()).unwrap();
+    println!(
+        "symbols: {} glyphs, codes {:x?}",
+        symbols.num_glyphs(),
+        symbols.codes().collect::<Vec<_>>()
+    );
+    fonts.push(("symbols", symbols));
+    let mut error = None;
+    if let Some(file) = file {
+        match RasterFont::load(file) {
+            Ok(f) => {
+                println!(
+                    "{}: {} glyphs, height {}, base line {}",
+                    file,
+                    f.num_glyphs(),
+                    f.height(),
+                    f.base_line()
+                );
+                fonts.push((file, f));
+            }
+            Err(e) => error = Some(format!("{}: {}", file, e)),
+        }
+    }
+
+    // The names and widths are drawn in the first font, the lines of text
+    // in each font with the characters it lacks left out
+    let label = fonts[0].1.clone();
+    let mut glyph = GlyphRasterBin::new_raster_font(None);
+    let mut ren = canvas.ren();
+    let mut rt = RendererRasterHtextSolid::new(&mut ren, &mut glyph);
+    let mut y = 0.;
+    for (name, font) in fonts {
+        rt.glyph_gen_mut().set_raster_font(label.clone());
+        y += label.height() as f64 - label.base_line() as f64 + 4.;
+        rt.set_color(blue);
+        rt.render_text(10., y, name, true);
+        for text in [
+            "A quick brown fox jumps over the lazy dog 0123456789",
+            "àéîõü ñ ß Ωμέγα Привет ← → ★ ♥",
+        ] {
+            rt.glyph_gen_mut().set_raster_font(font.clone());
+            y += font.height().max(label.height()) as f64 + 2.;
+            rt.set_color(black);
+            rt.render_text(10., y, text, true);
+            let w = rt.glyph_gen().width(text);
+            rt.glyph_gen_mut().set_raster_font(label.clone());
+            rt.set_color(red);
+            rt.render_text(20. + w, y, format!("{}px", w), true);
+        }
+        y += 6.;
+    }
+
+    // A file that is no font
+    let bad = RasterFont::read_bdf(&mut &b"STARTFONT 2.1\nFONTBOUNDINGBOX 8 x\n"[..]).err();
+    for e in error.into_iter().chain(bad.map(|e| e.to_string())) {
+        println!("error: {}", e);
+        rt.glyph_gen_mut().set_font(GSE8X16.as_ptr());
+        rt.set_color(red);
+        y += rt.glyph_gen().height() + 4.;
+        rt.render_text(10., y, format!("Error: {}", e), true);
+    }
+
+    canvas.save_png(out).unwrap();
+    println!("saved {}", out);
+}
@@ -0,0 +1,126 @@
+// Turns glyph outlines into single and multi-channel signed distance
+// fields of 48x48 pixels and draws them with SpanSdf at growing scales,
+// rotated, and with outlines and glows, then saves the result to a PNG
+// file:
+//
+//   cargo run --example sdf_glyphs --no-default-features [font.ttf [text [output.png]]]
+//
+// timesi.ttf is in examples/web/timesi.zip.
+
+use agg::{
+    render_scanlines_aa, Canvas, FontCacheManager, FontEngine, GlyphDataType, GlyphRender,
+    ImageBuffer, PathStorage, PixBgr24, PixFmt, PixGray8, RasterScanLine, Rgba8, SdfGenerator,
+    SpanIpLinear, SpanSdf, TransAffine, Transformer, TrueTypeBase, VecSpan,
+};
+
+const SIZE: u32 = 48;
+const RANGE: f64 = 4.;
+
+// Draws the field with mtx, its transformation from field pixels to
+// the canvas, and the style
+fn draw_field<Pix: PixFmt<T = u8>>(
+    canvas: &mut Canvas<PixBgr24>, field: &Pix, mtx: &TransAffine, style: u32,
+) {
+    let (ras, sl, mut ren) = canvas.parts_mut();
+    let mut inv = *mtx;
+    inv.invert();
+    let mut ip: SpanIpLinear<TransAffine> = SpanIpLinear::new(inv);
+    let mut sg = SpanSdf::new(field, &mut ip, RANGE, Rgba8::new_params(0, 0, 0, 255));
+    sg.set_scale(mtx.scale());
+    match style {
+        1 => sg.set_outline(Rgba8::new_params(220, 40, 0, 255), mtx.scale() * 1.5),
+        2 => {
+            sg.set_color(Rgba8::new_params(255, 255, 255, 255));
+            sg.set_glow(Rgba8::new_params(0, 90, 255, 255), mtx.scale() * 3.);
+        }
+        _ => {}
+    }
+
+    // The quad the field covers
+    let mut quad = PathStorage::new();
+    for (i, &(x, y)) in [(0., 0.), (1., 0.), (1., 1.), (0., 1.)].iter().enumerate() {
+        let (mut x, mut y) = (x * SIZE as f64, y * SIZE as f64);
+        mtx.transform(&mut x, &mut y);
+        if i == 0 {
+            quad.move_to(x, y);
+        } else {
+            quad.line_to(x, y);
+        }
+    }
+    quad.close_polygon(0);
+    ras.reset();
+    ras.add_path(&mut quad, 0);
+    let mut sa = VecSpan::<Rgba8>::new();
+    render_scanlines_aa(ras, sl, &mut ren, &mut sa, &mut sg);
+}
+
+// Scaling by s, then moving to (x, y)
+fn place(s: f64, x: f64, y: f64) -> TransAffine {
+    let mut mtx = TransAffine::trans_affine_scaling_eq(s);
+    mtx *= TransAffine::trans_affine_translation(x, y);
+    mtx
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let font = args.get(1).map_or("timesi.ttf", |s| s.as_str());
+    let text = args.get(2).map_or("AGg&", |s| s.as_str());
+    let out = args.get(3).map_or("sdf_glyphs.png", |s| s.as_str());
+
+    let mut fman = FontCacheManager::new(TrueTypeBase::<i32>::new(4), 32);
+    let engine = fman.engine_mut();
+    if !engine.load_font(font, 0, GlyphRender::Outline, &[], 0) {
+        println!("Can't load '{}': {}", font, engine.last_error().unwrap());
+        return;
+    }
+    engine.set_height(64.);
+    engine.set_flip_y(true);
+    engine.set_hinting(false);
+
+    let mut canvas = Canvas::<PixBgr24>::new(800, 640);
+    canvas.clear(&Rgba8::new_params(255, 255, 240, 255));
+
+    let mut x = 2.;
+    for ch in text.chars().take(4) {
+        let glyph = fman.glyph(ch as u32);
+        if glyph.is_null() {
+            continue;
+        }
+        let gl = unsafe { &*glyph };
+        if gl.data_type != GlyphDataType::Outline {
+            continue;
+        }
+        let mut sdf = SdfGenerator::new();
+        let mut path = fman.path_adaptor();
+        path.init(gl.data.as_ptr(), gl.data_size as usize, 0., 0., 1.);
+        sdf.add_path(&mut path, 0);
+        let fit = sdf.fit(SIZE, SIZE, RANGE);
+
+        let mut single = ImageBuffer::<PixGray8>::new(SIZE, SIZE);
+        let mut multi = ImageBuffer::<PixBgr24>::new(SIZE, SIZE);
+        sdf.generate_sdf(&mut single.pixfmt(), &fit, RANGE);
+        sdf.generate_msdf(&mut multi.pixfmt(), &fit, RANGE);
+        let single = single.pixfmt();
+        let multi = multi.pixfmt();
+
+        // Single and multi-channel at 1x, multi-channel at 2x, both at
+        // 4x, then rotated with an outline and with a glow
+        let s = SIZE as f64;
+        draw_field(&mut canvas, &*single, &place(1., x, 10.), 0);
+        draw_field(&mut canvas, &*multi, &place(1., x + s + 4., 10.), 0);
+        draw_field(&mut canvas, &*multi, &place(2., x + s * 2. + 8., 10.), 0);
+        draw_field(&mut canvas, &*single, &place(4., x, 112.), 0);
+        draw_field(&mut canvas, &*multi, &place(4., x, 310.), 0);
+        let mut mtx = TransAffine::trans_affine_translation(-s / 2., -s / 2.);
+        mtx *= TransAffine::trans_affine_rotation(0.3);
+        mtx *= TransAffine::trans_affine_scaling_eq(1.6);
+        mtx *= TransAffine::trans_affine_translation(x + 48., 560.);
+        draw_field(&mut canvas, &*multi, &mtx, 1);
+        mtx *= TransAffine::trans_affine_translation(96., 0.);
+        draw_field(&mut canvas, &*multi, &mtx, 2);
+        x += 200.;
+    }
+
+    canvas.save_png(out).unwrap();
+    println!("saved {}", out);
+}
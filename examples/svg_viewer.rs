@@ -0,0 +1,193 @@
+mod ctrl;
+mod platform;
+
+use crate::ctrl::slider::*;
+use crate::platform::*;
+use agg::svg::{Parser, PathRenderer};
+use agg::{RasterizerScanlineAa, RenderBuf, ScanlineP8, TransAffine};
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+type Ptr<T> = Rc<RefCell<T>>;
+fn ctrl_ptr<T>(t: T) -> Ptr<T> {
+    Rc::new(RefCell::new(t))
+}
+
+const FLIP_Y: bool = false;
+
+struct Application {
+    expand: Ptr<Slider<'static, agg::Rgba8>>,
+    rotate: Ptr<Slider<'static, agg::Rgba8>>,
+    rasterizer: RasterizerScanlineAa,
+    scanline: ScanlineP8,
+    path: PathRenderer,
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    dx: f64,
+    dy: f64,
+    drag_x: f64,
+    drag_y: f64,
+    drag_flag: bool,
+    ctrls: CtrlContainer,
+    util: Rc<RefCell<PlatUtil>>,
+}
+
+impl Application {
+    fn open(&mut self, fname: &str) -> Result<String, agg::svg::SvgError> {
+        self.path.remove_all();
+        let title = {
+            let mut p = Parser::new(&mut self.path);
+            p.parse(fname)?;
+            p.title().to_string()
+        };
+        self.path.arrange_orientations();
+        self.path
+            .bounding_rect(&mut self.x1, &mut self.y1, &mut self.x2, &mut self.y2);
+        Ok(title)
+    }
+}
+
+impl Interface for Application {
+    fn on_ctrls(&mut self) -> &mut CtrlContainer {
+        &mut self.ctrls
+    }
+
+    fn on_draw(&mut self, rbuf: &mut RenderBuf) {
+        let width = self.util.borrow().width();
+        let height = self.util.borrow().height();
+
+        let mut pix = agg::PixBgr24::new_borrowed(rbuf);
+        let mut rb = agg::RendererBase::<agg::PixBgr24>::new_borrowed(&mut pix);
+        rb.clear(&agg::Rgba8::new_params(255, 255, 255, 255));
+
+        let scl = ((width - 20.0) / (self.x2 - self.x1)).min((height - 60.0) / (self.y2 - self.y1))
+            * self.expand.borrow().value();
+
+        let mut mtx = TransAffine::new_default();
+        mtx.multiply(&TransAffine::trans_affine_translation(
+            -(self.x1 + self.x2) / 2.0,
+            -(self.y1 + self.y2) / 2.0,
+        ));
+        mtx.multiply(&TransAffine::trans_affine_scaling(scl, scl));
+        mtx.multiply(&TransAffine::trans_affine_rotation(agg::basics::deg2rad(
+            self.rotate.borrow().value(),
+        )));
+        mtx.multiply(&TransAffine::trans_affine_translation(
+            width / 2.0 + self.dx,
+            (height + 40.0) / 2.0 + self.dy,
+        ));
+
+        self.path
+            .render(&mut self.rasterizer, &mut self.scanline, &mut rb, &mtx, 1.0);
+
+        ctrl::render_ctrl(
+            &mut self.rasterizer,
+            &mut self.scanline,
+            &mut rb,
+            &mut *self.expand.borrow_mut(),
+        );
+        ctrl::render_ctrl(
+            &mut self.rasterizer,
+            &mut self.scanline,
+            &mut rb,
+            &mut *self.rotate.borrow_mut(),
+        );
+    }
+
+    fn on_mouse_button_down(&mut self, _rb: &mut RenderBuf, x: i32, y: i32, flags: u32) -> Draw {
+        if flags & InputFlag::MouseLeft as u32 != 0 {
+            self.drag_flag = true;
+            self.drag_x = x as f64 - self.dx;
+            self.drag_y = y as f64 - self.dy;
+        }
+        Draw::No
+    }
+
+    fn on_mouse_move(&mut self, _rb: &mut RenderBuf, x: i32, y: i32, flags: u32) -> Draw {
+        if flags & InputFlag::MouseLeft as u32 == 0 {
+            self.drag_flag = false;
+        }
+        if self.drag_flag {
+            self.dx = x as f64 - self.drag_x;
+            self.dy = y as f64 - self.drag_y;
+            return Draw::Yes;
+        }
+        Draw::No
+    }
+
+    fn on_mouse_button_up(&mut self, _rb: &mut RenderBuf, _x: i32, _y: i32, _flags: u32) -> Draw {
+        self.drag_flag = false;
+        Draw::No
+    }
+
+    fn new(_format: PixFormat, flip_y: bool, util: Ptr<PlatUtil>) -> Application {
+        let expand = ctrl_ptr(Slider::new(5., 5., 256. - 5., 11., !flip_y));
+        let rotate = ctrl_ptr(Slider::new(256. + 5., 5., 512. - 5., 11., !flip_y));
+
+        expand.borrow_mut().no_transform();
+        expand.borrow_mut().set_range(0.1, 4.0);
+        expand.borrow_mut().set_value(1.0);
+        expand.borrow_mut().set_label("Scale=%3.2f");
+
+        rotate.borrow_mut().no_transform();
+        rotate.borrow_mut().set_range(-180.0, 180.0);
+        rotate.borrow_mut().set_value(0.0);
+        rotate.borrow_mut().set_label("Rotate=%3.2f");
+
+        Application {
+            expand: expand.clone(),
+            rotate: rotate.clone(),
+            rasterizer: RasterizerScanlineAa::new(),
+            scanline: ScanlineP8::new(),
+            path: PathRenderer::new(),
+            x1: 0.0,
+            y1: 0.0,
+            x2: 0.0,
+            y2: 0.0,
+            dx: 0.0,
+            dy: 0.0,
+            drag_x: 0.0,
+            drag_y: 0.0,
+            drag_flag: false,
+            ctrls: CtrlContainer {
+                ctrl: vec![expand, rotate],
+                cur_ctrl: -1,
+                num_ctrl: 2,
+            },
+            util: util,
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let mut fname = "tiger.svg";
+    if args.len() > 1 {
+        fname = &args[1];
+    }
+
+    let mut plat = PlatSupport::<Application>::create_plat(PixFormat::Bgr24, FLIP_Y);
+
+    match plat.app_mut().open(fname) {
+        Ok(title) => {
+            let caption = if title.is_empty() {
+                format!("AGG Example - SVG Viewer: {}", fname)
+            } else {
+                format!("AGG Example - SVG Viewer: {}", title)
+            };
+            plat.set_caption(&caption);
+        }
+        Err(e) => {
+            let buf = format!("Cannot load {}: {}", fname, e);
+            plat.app_mut().util.borrow_mut().message(&buf);
+            plat.set_caption("AGG Example - SVG Viewer");
+        }
+    }
+
+    if plat.init(512, 600, WindowFlag::Resize as u32) {
+        plat.run();
+    }
+}
@@ -0,0 +1,110 @@
+// Lays out a paragraph with TextLayout in four boxes, left aligned,
+// centered, right aligned and justified, renders the glyphs as gray8,
+// gray8, mono and outline data and saves the result to a PNG file:
+//
+//   cargo run --example text_layout [font.ttf [output.png]]
+//
+// timesi.ttf is in examples/web/timesi.zip.
+
+use agg::font_freetype::FreetypeBase;
+use agg::{
+    render_scanlines_aa_solid, render_scanlines_bin_solid, Canvas, ConvCurve, FontCacheManager,
+    FontEngine, GlyphDataType, GlyphRender, PathStorage, PixBgr24, RasterScanLine, Rgba8,
+    TextAlign, TextLayout,
+};
+
+const TEXT: &str = "Anti-Grain Geometry is an Open Source, free of charge graphic library, \
+written in industrially standard C++. The terms and conditions of use AGG are described on \
+The License page.\nAGG doesn't depend on any graphic API or technology.";
+
+type Fman<'a> = FontCacheManager<FreetypeBase<'a, i32>>;
+
+fn draw_glyphs(canvas: &mut Canvas<PixBgr24>, fman: &mut Fman, layout: &TextLayout, c: &Rgba8) {
+    let (ras, sl, mut ren) = canvas.parts_mut();
+    for g in layout.glyphs() {
+        let glyph = fman.glyph(g.code);
+        if glyph.is_null() {
+            continue;
+        }
+        let gl = unsafe { &*glyph };
+        match gl.data_type {
+            GlyphDataType::Mono => {
+                let mut adp = fman.mono_adaptor();
+                adp.init(gl.data.as_ptr(), gl.data_size as usize, g.x, g.y);
+                render_scanlines_bin_solid(&mut adp, &mut fman.mono_scanline(), &mut ren, c);
+            }
+            GlyphDataType::Gray8 => {
+                let mut adp = fman.gray8_adaptor();
+                adp.init(gl.data.as_ptr(), gl.data_size as usize, g.x, g.y);
+                render_scanlines_aa_solid(&mut adp, sl, &mut ren, c);
+            }
+            GlyphDataType::Outline => {
+                let mut path = fman.path_adaptor();
+                path.init(gl.data.as_ptr(), gl.data_size as usize, g.x, g.y, 1.);
+                let mut curve: ConvCurve<_> = ConvCurve::new_owned(path);
+                ras.reset();
+                ras.add_path(&mut curve, 0);
+                render_scanlines_aa_solid(ras, sl, &mut ren, c);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let font = args.get(1).map_or("timesi.ttf", |s| s.as_str());
+    let out = args.get(2).map_or("text_layout.png", |s| s.as_str());
+
+    let mut fman = FontCacheManager::new(FreetypeBase::<i32>::new(32), 32);
+    let mut canvas = Canvas::<PixBgr24>::new(800, 600);
+    canvas.clear(&Rgba8::new_params(255, 255, 255, 255));
+    let black = Rgba8::new_params(0, 0, 0, 255);
+    let frame = Rgba8::new_params(160, 160, 255, 255);
+
+    let boxes = [
+        (TextAlign::Left, GlyphRender::AggGray8, 20., 20.),
+        (TextAlign::Center, GlyphRender::AggGray8, 420., 20.),
+        (TextAlign::Right, GlyphRender::AggMono, 20., 310.),
+        (TextAlign::Justify, GlyphRender::Outline, 420., 310.),
+    ];
+    for &(align, ren_type, x, y) in boxes.iter() {
+        if !fman
+            .engine_mut()
+            .load_font(&format!("{}\0", font), 0, ren_type, &[], 0)
+        {
+            println!("Can't load '{}'", font);
+            return;
+        }
+        fman.engine_mut().set_height(18.);
+        fman.engine_mut().set_flip_y(true);
+
+        let mut layout = TextLayout::new();
+        layout.set_width(360.);
+        layout.set_align(align);
+        layout.set_line_spacing(1.1);
+        layout.layout(&mut fman, TEXT, x, y);
+        println!(
+            "{:?}: {} lines, {} glyphs, {:.1} x {:.1}",
+            align,
+            layout.lines().len(),
+            layout.glyphs().len(),
+            layout.text_width(),
+            layout.text_height()
+        );
+
+        let mut rect = PathStorage::new();
+        rect.move_to(x, y);
+        rect.line_to(x + layout.width(), y);
+        rect.line_to(x + layout.width(), y + layout.text_height());
+        rect.line_to(x, y + layout.text_height());
+        rect.close_polygon(0);
+        canvas.set_line_width(1.);
+        canvas.stroke_path(&mut rect, 0, &frame);
+
+        draw_glyphs(&mut canvas, &mut fman, &layout, &black);
+    }
+
+    canvas.save_png(out).unwrap();
+    println!("saved {}", out);
+}
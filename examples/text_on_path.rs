@@ -0,0 +1,95 @@
+// Puts text along curves with TextOnPath: FreeType glyphs bent along the
+// path, rotated to its tangent, upright and justified, and GsvText
+// strokes, and saves the result to a PNG file:
+//
+//   cargo run --example text_on_path [font.ttf [output.png]]
+//
+// timesi.ttf is in examples/web/timesi.zip.
+
+use agg::font_freetype::FreetypeBase;
+use agg::{
+    Canvas, ConvCurve, ConvStroke, FontCacheManager, FontEngine, GlyphOrientation, GlyphRender,
+    GsvText, PathStorage, PixBgr24, Rgba8, TextAlign, TextOnPath,
+};
+
+const TEXT: &str = "Anti-Grain Geometry - text along a path";
+
+fn wave(y: f64) -> PathStorage {
+    let mut p = PathStorage::new();
+    p.move_to(40., y);
+    p.curve4_ctrl(200., y - 120., 400., y + 120., 560., y);
+    p.curve4(720., y - 60., 760., y - 20.);
+    p
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let font = args.get(1).map_or("timesi.ttf", |s| s.as_str());
+    let out = args.get(2).map_or("text_on_path.png", |s| s.as_str());
+
+    let mut fman = FontCacheManager::new(FreetypeBase::<i32>::new(32), 32);
+    if !fman
+        .engine_mut()
+        .load_font(&format!("{}\0", font), 0, GlyphRender::Outline, &[], 0)
+    {
+        println!("Can't load '{}'", font);
+        return;
+    }
+    fman.engine_mut().set_hinting(false);
+    fman.engine_mut().set_height(28.);
+    fman.engine_mut().set_flip_y(true);
+
+    let mut canvas = Canvas::<PixBgr24>::new(800, 720);
+    canvas.clear(&Rgba8::new_params(255, 255, 255, 255));
+    let black = Rgba8::new_params(0, 0, 0, 255);
+    let guide = Rgba8::new_params(170, 50, 20, 100);
+
+    let rows = [
+        (GlyphOrientation::Bend, TextAlign::Left, 0., -4.),
+        (GlyphOrientation::Tangent, TextAlign::Center, 0., -4.),
+        (GlyphOrientation::Upright, TextAlign::Right, 0., 8.),
+        (GlyphOrientation::Bend, TextAlign::Justify, 20., 20.),
+    ];
+    for (i, &(orientation, align, start, offset)) in rows.iter().enumerate() {
+        let curve = wave(110. + i as f64 * 150.);
+        let mut curve: ConvCurve<_> = ConvCurve::new_owned(curve);
+        let mut top = TextOnPath::new();
+        top.set_path(&mut curve, 0);
+        top.set_start(start);
+        top.set_align(align);
+        top.set_baseline_offset(offset);
+        top.set_orientation(orientation);
+        top.set_letter_spacing(1.);
+        top.layout(&mut fman, TEXT);
+        println!(
+            "{:?} {:?}: {} of {} glyphs on a {:.1} long path",
+            orientation,
+            align,
+            top.glyphs().len(),
+            TEXT.chars().count(),
+            top.path_length()
+        );
+        canvas.fill_path(&mut top, 0, &black);
+        canvas.set_line_width(2.);
+        canvas.stroke_path(&mut curve, 0, &guide);
+    }
+
+    // GsvText below the last curve, scrolled in from before its start
+    let curve = wave(110. + 3. * 150.);
+    let mut curve: ConvCurve<_> = ConvCurve::new_owned(curve);
+    let mut gsv = GsvText::new();
+    gsv.set_size(16., 0.);
+    gsv.set_flip(true);
+    let mut top = TextOnPath::new();
+    top.set_path(&mut curve, 0);
+    top.set_start(-40.);
+    top.set_baseline_offset(-8.);
+    top.set_orientation(GlyphOrientation::Tangent);
+    top.layout_gsv(&mut gsv, "GsvText rotated to the tangent of the path");
+    let mut stroke: ConvStroke<_> = ConvStroke::new_owned(top);
+    stroke.set_width(1.5);
+    canvas.fill_path(&mut stroke, 0, &Rgba8::new_params(0, 0, 160, 255));
+
+    canvas.save_png(out).unwrap();
+    println!("saved {}", out);
+}
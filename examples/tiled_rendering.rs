@@ -0,0 +1,122 @@
+// Records a scene of a few thousand fills and strokes, renders it once on
+// one thread and once in bands on all cores, checks that both images are
+// the same and saves the result to a PNG file:
+//
+//   cargo run --release --example tiled_rendering [output.png [threads]]
+
+use agg::math_stroke::{LineCap, LineJoin};
+use agg::{
+    Canvas, DrawList, Ellipse, GradientShape, ImageBuffer, Paint, PathStorage, PixBgra32,
+    RendererBase, Rgba8, TransAffine,
+};
+use std::time::Instant;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let fname = args.get(1).map_or("tiled_rendering.png", |s| s.as_str());
+    let threads = args.get(2).map_or(0, |s| s.parse::<u32>().unwrap());
+    let (w, h) = (1920, 1080);
+
+    let mut seed = 4321u32;
+    let mut rnd = |n: f64| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        ((seed >> 16) & 0x7FFF) as f64 / 32768. * n
+    };
+
+    let mut list = DrawList::new();
+    for i in 0..3000 {
+        let (x, y, r) = (rnd(w as f64), rnd(h as f64), rnd(40.) + 4.);
+        let c = Rgba8::new_params(
+            rnd(255.) as u32,
+            rnd(255.) as u32,
+            rnd(255.) as u32,
+            rnd(155.) as u32 + 100,
+        );
+        let mut mtx = TransAffine::trans_affine_rotation(rnd(3.14));
+        mtx *= TransAffine::trans_affine_translation(x, y);
+        list.set_transform(&mtx);
+        match i % 3 {
+            0 => {
+                let mut e = Ellipse::new_ellipse(0., 0., r, r * 0.5, 0, false);
+                list.fill_path(&mut e, 0, &c);
+            }
+            1 => {
+                let mut p = PathStorage::new();
+                p.move_to(-r, 0.);
+                p.curve3_ctrl(0., -2. * r, r, 0.);
+                p.line_to(r * 0.5, r);
+                list.set_line_width(rnd(6.) + 1.);
+                list.set_line_join(LineJoin::Round);
+                list.set_line_cap(LineCap::Round);
+                list.stroke_path(&mut p, 0, &c);
+            }
+            _ => {
+                let mut p = PathStorage::new();
+                p.move_to(-r, -r);
+                p.line_to(r, -r * 0.3);
+                p.line_to(-r * 0.2, r);
+                p.close_polygon(0);
+                list.set_line_width(2.);
+                list.set_line_join(LineJoin::Miter);
+                list.set_line_cap(LineCap::Butt);
+                list.fill_path(&mut p, 0, &c);
+                list.stroke_path(&mut p, 0, &Rgba8::new_params(0, 0, 0, 255));
+            }
+        }
+    }
+    // A few shapes across many bands, painted with a gradient
+    list.reset_transform();
+    let center = TransAffine::trans_affine_translation(w as f64 / 2., h as f64 / 2.);
+    let stops = [
+        (0., Rgba8::new_params(200, 20, 20, 160)),
+        (1., Rgba8::new_params(20, 40, 160, 160)),
+    ];
+    let grad = list.add_gradient(GradientShape::Radial, &center, 0., 900., &stops);
+    for i in 0..4 {
+        let mut e = Ellipse::new_ellipse(
+            w as f64 / 2.,
+            h as f64 / 2.,
+            900. - i as f64 * 200.,
+            500. - i as f64 * 110.,
+            0,
+            false,
+        );
+        list.set_line_width(12.);
+        list.stroke_path(&mut e, 0, Paint::Gradient(grad));
+    }
+
+    let white = Rgba8::new_params(255, 255, 255, 255);
+    let mut single = ImageBuffer::<PixBgra32>::new(w, h);
+    single.clear(&white);
+    let t = Instant::now();
+    let mut pix = single.pixfmt();
+    list.render(
+        &mut RendererBase::new_borrowed(&mut pix),
+        &TransAffine::new_default(),
+    );
+    drop(pix);
+    println!(
+        "{} commands, 1 thread: {:.1} ms",
+        list.len(),
+        t.elapsed().as_secs_f64() * 1000.
+    );
+
+    let mut tiled = ImageBuffer::<PixBgra32>::new(w, h);
+    tiled.clear(&white);
+    let t = Instant::now();
+    list.render_tiled(&mut tiled, &TransAffine::new_default(), 32, threads);
+    println!("tiled: {:.1} ms", t.elapsed().as_secs_f64() * 1000.);
+
+    println!(
+        "images are {}",
+        if single.data() == tiled.data() {
+            "identical"
+        } else {
+            "different"
+        }
+    );
+
+    let canvas = Canvas::new_from_image(tiled);
+    canvas.save_png(fname).unwrap();
+    println!("saved {}", fname);
+}
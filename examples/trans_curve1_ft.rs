@@ -3,15 +3,11 @@ use crate::ctrl::slider::Slider;
 use crate::platform::{InputFlag, *};
 use agg::color_rgba::*;
 use agg::conv_bspline::*;
-use agg::conv_curve::*;
-use agg::conv_segmentator::*;
 use agg::conv_stroke::*;
-use agg::conv_transform::ConvTransform;
 use agg::font_cache_manager::*;
 use agg::font_freetype::FreetypeBase;
-use agg::path_storage_integer::*;
 use agg::rendering_buffer::*;
-use agg::trans_single_path::TransSinglePath;
+use agg::text_on_path::TextOnPath;
 use agg::{Color, RasterScanLine, RendererScanlineColor};
 mod ctrl;
 mod platform;
@@ -155,64 +151,28 @@ impl Interface for Application {
         );
         let mut bspline = ConvBspline::new_owned(path);
         bspline.set_interpolation_step(1.0 / self.num_points.borrow().value());
-        let mut tcurve = TransSinglePath::new();
-        tcurve.add_path(&mut bspline, 0);
-        tcurve.set_preserve_x_scale(self.preserve_x_scale.borrow().status());
+        let mut text = TextOnPath::new();
+        text.set_path(&mut bspline, 0);
+        text.trans_path_mut()
+            .set_preserve_x_scale(self.preserve_x_scale.borrow().status());
         if self.fixed_len.borrow().status() {
-            tcurve.set_base_length(1120.);
+            text.trans_path_mut().set_base_length(1120.);
         }
-
-        let mut fcurves: ConvCurve<SerializedIntegerPathAdaptor<i16>> =
-            ConvCurve::new_owned(self.fman.path_adaptor());
-        fcurves.set_approximation_scale(2.0);
-        let mut fsegm = ConvSegmentator::new_owned(fcurves);
-        fsegm.set_approximation_scale(3.0);
-        let mut ftrans = ConvTransform::new_owned(fsegm, tcurve);
+        text.set_baseline_offset(3.0);
+        text.set_approximation_scale(3.0);
 
         if self
             .fman
             .engine_mut()
             .load_font("timesi.ttf\u{0}", 0, GlyphRender::Outline, &[], 0)
         {
-            let mut x = 0.0;
-            let mut y = 3.0;
-            let p = TEXT.as_bytes();
-            let mut i = 0;
             self.fman.engine_mut().set_hinting(false);
             self.fman.engine_mut().set_height(40.);
-
-            while p[i] != 0 {
-                let glyph = self.fman.glyph(p[i] as u32);
-                if !glyph.is_null() {
-                    if x > ftrans.trans().total_length() {
-                        break;
-                    }
-
-                    self.fman.add_kerning(&mut x, &mut y);
-                    //self.fman.init_embedded_adaptors(Some(unsafe { &*glyph }), x, y, 1.0);
-                    let gl = unsafe { &*glyph };
-                    if unsafe { (*glyph).data_type } == GlyphDataType::Outline {
-                        ftrans.source_mut().source_mut().source_mut().init(
-                            gl.data.as_ptr(),
-                            gl.data_size as usize,
-                            x,
-                            y,
-                            1.0,
-                        );
-                        ras.reset();
-                        ras.add_path(&mut ftrans, 0);
-                        r.set_color(Rgba8::new_params(0, 0, 0, 255));
-                        agg::render_scanlines(&mut ras, &mut sl, &mut r);
-                    }
-
-                    unsafe {
-                        // increment pen position
-                        x += (*glyph).advance_x;
-                        y += (*glyph).advance_y;
-                    }
-                }
-                i += 1;
-            }
+            text.layout(&mut self.fman, TEXT);
+            ras.reset();
+            ras.add_path(&mut text, 0);
+            r.set_color(Rgba8::new_params(0, 0, 0, 255));
+            agg::render_scanlines(&mut ras, &mut sl, &mut r);
         } else {
             self.util.borrow_mut().message(
                 "Please copy file timesi.ttf to the current directory\n \
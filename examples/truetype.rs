@@ -0,0 +1,155 @@
+// Renders text with TrueTypeBase, the font engine that reads TrueType
+// and OpenType fonts without FreeType: a size waterfall in gray8, mono
+// and outline glyphs, kerning on and off and a rotated line, and saves
+// the result to a PNG file:
+//
+//   cargo run --example truetype --no-default-features [font.ttf [output.png]]
+//
+// timesi.ttf is in examples/web/timesi.zip.
+
+use agg::{
+    render_scanlines_aa_solid, render_scanlines_bin_solid, Canvas, ConvCurve, FontCacheManager,
+    FontEngine, GlyphDataType, GlyphRender, PixBgr24, RasterScanLine, Rgba8, TransAffine,
+    TrueTypeBase,
+};
+
+type Fman<'a> = FontCacheManager<TrueTypeBase<'a, i32>>;
+
+fn draw_text(
+    canvas: &mut Canvas<PixBgr24>, fman: &mut Fman, text: &str, x: f64, y: f64, kerning: bool,
+    c: &Rgba8,
+) -> f64 {
+    let (ras, sl, mut ren) = canvas.parts_mut();
+    let (mut x, mut y) = (x, y);
+    fman.reset_last_glyph();
+    for ch in text.chars() {
+        let glyph = fman.glyph(ch as u32);
+        if glyph.is_null() {
+            continue;
+        }
+        if kerning {
+            fman.add_kerning(&mut x, &mut y);
+        }
+        let gl = unsafe { &*glyph };
+        match gl.data_type {
+            GlyphDataType::Mono => {
+                let mut adp = fman.mono_adaptor();
+                adp.init(gl.data.as_ptr(), gl.data_size as usize, x, y);
+                render_scanlines_bin_solid(&mut adp, &mut fman.mono_scanline(), &mut ren, c);
+            }
+            GlyphDataType::Gray8 => {
+                let mut adp = fman.gray8_adaptor();
+                adp.init(gl.data.as_ptr(), gl.data_size as usize, x, y);
+                render_scanlines_aa_solid(&mut adp, sl, &mut ren, c);
+            }
+            GlyphDataType::Outline => {
+                let mut path = fman.path_adaptor();
+                path.init(gl.data.as_ptr(), gl.data_size as usize, x, y, 1.);
+                let mut curve: ConvCurve<_> = ConvCurve::new_owned(path);
+                ras.reset();
+                ras.add_path(&mut curve, 0);
+                render_scanlines_aa_solid(ras, sl, &mut ren, c);
+            }
+            _ => {}
+        }
+        x += gl.advance_x;
+        y += gl.advance_y;
+    }
+    x
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let font = args.get(1).map_or("timesi.ttf", |s| s.as_str());
+    let out = args.get(2).map_or("truetype.png", |s| s.as_str());
+
+    let mut fman = FontCacheManager::new(TrueTypeBase::<i32>::new(4), 32);
+    let load = |fman: &mut Fman, ren_type: GlyphRender, height: f64| {
+        let engine = fman.engine_mut();
+        if !engine.load_font(font, 0, ren_type, &[], 0) {
+            println!("Can't load '{}': {}", font, engine.last_error().unwrap());
+            std::process::exit(1);
+        }
+        engine.set_height(height);
+        engine.set_flip_y(true);
+        engine.set_hinting(false);
+    };
+    load(&mut fman, GlyphRender::Outline, 40.);
+    {
+        let face = fman.engine().face().unwrap();
+        println!(
+            "{}: {} glyphs, {} units per em, {} outlines, {}kerning",
+            font,
+            face.num_glyphs(),
+            face.units_per_em(),
+            if face.is_cff() { "CFF" } else { "TrueType" },
+            if face.has_kerning() { "" } else { "no " }
+        );
+    }
+
+    let mut canvas = Canvas::<PixBgr24>::new(800, 600);
+    canvas.clear(&Rgba8::new_params(255, 255, 255, 255));
+    let black = Rgba8::new_params(0, 0, 0, 255);
+    let blue = Rgba8::new_params(0, 0, 160, 255);
+
+    draw_text(
+        &mut canvas,
+        &mut fman,
+        "Anti-Grain Geometry",
+        20.,
+        50.,
+        true,
+        &black,
+    );
+
+    let mut y = 80.;
+    for &h in [8., 10., 12., 14., 18., 24., 32.].iter() {
+        load(&mut fman, GlyphRender::AggGray8, h);
+        let x = draw_text(
+            &mut canvas,
+            &mut fman,
+            &format!("{}px ", h),
+            20.,
+            y,
+            true,
+            &blue,
+        );
+        draw_text(
+            &mut canvas,
+            &mut fman,
+            "The quick brown fox",
+            x,
+            y,
+            true,
+            &black,
+        );
+        load(&mut fman, GlyphRender::AggMono, h);
+        draw_text(
+            &mut canvas,
+            &mut fman,
+            "jumps over the lazy dog",
+            420.,
+            y,
+            true,
+            &black,
+        );
+        y += h * 1.3;
+    }
+
+    load(&mut fman, GlyphRender::AggGray8, 36.);
+    let pairs = "AVAWAY To Te Yo LT";
+    draw_text(&mut canvas, &mut fman, pairs, 20., y + 40., false, &black);
+    draw_text(&mut canvas, &mut fman, pairs, 20., y + 90., true, &blue);
+
+    load(&mut fman, GlyphRender::Outline, 28.);
+    for i in 0..6u32 {
+        let a = -0.5 + i as f64 * 0.2;
+        fman.engine_mut()
+            .transform(&TransAffine::trans_affine_rotation(a));
+        let c = Rgba8::new_params(40 * i, 0, 200 - 40 * i, 255);
+        draw_text(&mut canvas, &mut fman, "Rotated", 560., y + 60., true, &c);
+    }
+
+    canvas.save_png(out).unwrap();
+    println!("saved {}", out);
+}
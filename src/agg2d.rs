@@ -0,0 +1,1129 @@
+//----------------------------------------------------------------------------
+// Agg2D - Version 1.0
+// Based on Anti-Grain Geometry
+// Copyright (C) 2005 Maxim Shemanarev (http://www.antigrain.com)
+//
+// Permission to copy, use, modify, sell and distribute this software
+// is granted provided this copyright notice appears in all copies.
+// This software is provided "as is" without express or implied
+// warranty, and with no claim as to its suitability for any purpose.
+//
+//----------------------------------------------------------------------------
+
+use crate::basics::{FillingRule, RectD, RectI};
+use crate::bezier_arc::BezierArc;
+use crate::color_rgba::Rgba8;
+use crate::gamma_functions::{GammaMultiply, GammaPower};
+use crate::image_accessors::ImageAccessorClip;
+use crate::image_buffer::ImageBuffer;
+use crate::math_stroke::{LineCap, LineJoin};
+use crate::path_storage::PathStorage;
+use crate::pixfmt_rgba::{AlphaBlendRgba, BlenderRgba, CompOp, CompOpRgbaAdaptor, CustomBlendRgba};
+use crate::rasterizer_scanline_aa::RasterizerScanlineAa;
+use crate::renderer_base::RendererBase;
+use crate::renderer_scanline::{render_scanlines_aa, render_scanlines_aa_solid};
+use crate::rendering_buffer::RenderBuf;
+use crate::rounded_rect::RoundedRect;
+use crate::scanline_u::ScanlineU8;
+use crate::span_allocator::VecSpan;
+use crate::span_gradient::{GradientRadial, GradientX, SpanGradient};
+use crate::span_image_filter_rgba::*;
+use crate::span_interpolator_linear::SpanIpLinear;
+use crate::trans_affine::TransAffine;
+use crate::trans_viewport::{AspectRatio, TransViewport};
+use crate::{
+    Color, ConvCurve, ConvStroke, ConvTransform, GammaFn, ImageAccessorRgb, Order, RasterScanLine,
+    RenderBuffer, Renderer, Transformer, VertexSource,
+};
+use std::f64::consts::PI;
+
+// The filters of the image functions
+pub use crate::image_filters::ImageFilterKind;
+
+const APPROX_SCALE: f64 = 2.0;
+
+type PixFormat<'a, O> = AlphaBlendRgba<'a, Rgba8, O, BlenderRgba<Rgba8, O>, RenderBuf>;
+// The images drawn by the image functions
+pub type Agg2DImage<O> = ImageBuffer<PixFormat<'static, O>>;
+type PixFormatComp<'a, O> = CustomBlendRgba<'a, Rgba8, O, CompOpRgbaAdaptor<Rgba8, O>, RenderBuf>;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DrawPathFlag {
+    FillOnly,
+    StrokeOnly,
+    FillAndStroke,
+    FillWithLineColor,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ViewportOption {
+    Anisotropic,
+    XMinYMin,
+    XMidYMin,
+    XMaxYMin,
+    XMinYMid,
+    XMidYMid,
+    XMaxYMid,
+    XMinYMax,
+    XMidYMax,
+    XMaxYMax,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ImageResample {
+    NoResample,
+    ResampleAlways,
+    ResampleOnZoomOut,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    CW,
+    CCW,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GradientFlag {
+    Solid,
+    Linear,
+    Radial,
+}
+
+//------------------------------------------------------------------Paint
+// Color or gradient used to fill or to stroke
+#[derive(Clone)]
+struct Paint {
+    color: Rgba8,
+    flag: GradientFlag,
+    gradient: Vec<Rgba8>,
+    mtx: TransAffine,
+    d1: f64,
+    d2: f64,
+}
+
+impl Paint {
+    fn new(color: Rgba8) -> Self {
+        Paint {
+            color: color,
+            flag: GradientFlag::Solid,
+            gradient: vec![Rgba8::new(); 256],
+            mtx: TransAffine::new_default(),
+            d1: 0.0,
+            d2: 100.0,
+        }
+    }
+
+    fn set_color(&mut self, c: &Rgba8) {
+        self.color = *c;
+        self.flag = GradientFlag::Solid;
+    }
+
+    // The profile is the part of the distance between the gradient ends
+    // where the colors are interpolated, 0...1
+    fn set_colors(&mut self, c1: &Rgba8, c2: &Rgba8, profile: f64) {
+        let start = 128 - (profile * 127.0) as usize;
+        let mut end = 128 + (profile * 127.0) as usize;
+        if end <= start {
+            end = start + 1;
+        }
+        let k = 1.0 / (end - start) as f64;
+        for i in 0..256 {
+            self.gradient[i] = if i < start {
+                *c1
+            } else if i < end {
+                c1.gradient(c2, (i - start) as f64 * k)
+            } else {
+                *c2
+            };
+        }
+    }
+
+    fn set_colors3(&mut self, c1: &Rgba8, c2: &Rgba8, c3: &Rgba8) {
+        for i in 0..128 {
+            self.gradient[i] = c1.gradient(c2, i as f64 / 127.0);
+        }
+        for i in 128..256 {
+            self.gradient[i] = c2.gradient(c3, (i - 128) as f64 / 127.0);
+        }
+    }
+
+    fn set_linear(&mut self, transform: &TransAffine, x1: f64, y1: f64, x2: f64, y2: f64) {
+        self.mtx.reset();
+        self.mtx.multiply(&TransAffine::trans_affine_rotation(
+            (y2 - y1).atan2(x2 - x1),
+        ));
+        self.mtx
+            .multiply(&TransAffine::trans_affine_translation(x1, y1));
+        self.mtx.multiply(transform);
+        self.mtx.invert();
+        self.d1 = 0.0;
+        self.d2 = ((x2 - x1) * (x2 - x1) + (y2 - y1) * (y2 - y1)).sqrt();
+        self.flag = GradientFlag::Linear;
+        // The color only has to be visible
+        self.color = Rgba8::new_params(0, 0, 0, 255);
+    }
+
+    // The center and the radius are converted to screen coordinates, so
+    // the gradient stays circular under any transformation
+    fn set_radial(&mut self, x: f64, y: f64, r: f64) {
+        self.mtx = TransAffine::trans_affine_translation(x, y);
+        self.mtx.invert();
+        self.d1 = 0.0;
+        self.d2 = r;
+        self.flag = GradientFlag::Radial;
+        self.color = Rgba8::new_params(0, 0, 0, 255);
+    }
+
+    fn visible(&self) -> bool {
+        self.color.a > 0
+    }
+}
+
+//----------------------------------------------------------RasterizerGamma
+// Master alpha applied on top of the anti-aliasing gamma
+struct RasterizerGamma {
+    alpha: GammaMultiply,
+    gamma: GammaPower,
+}
+
+impl GammaFn for RasterizerGamma {
+    fn call(&self, x: f64) -> f64 {
+        self.alpha.call(self.gamma.call(x))
+    }
+}
+
+//------------------------------------------------------------------State
+// Everything save_state() and restore_state() keep
+#[derive(Clone)]
+struct State {
+    clip_box: RectD,
+    blend_mode: CompOp,
+    master_alpha: f64,
+    anti_alias_gamma: f64,
+    fill: Paint,
+    line: Paint,
+    line_width: f64,
+    line_cap: LineCap,
+    line_join: LineJoin,
+    even_odd: bool,
+    transform: TransAffine,
+    image_filter: ImageFilterKind,
+    image_resample: ImageResample,
+}
+
+impl State {
+    fn new(width: u32, height: u32) -> Self {
+        State {
+            clip_box: RectD::new(0., 0., width as f64, height as f64),
+            blend_mode: CompOp::CompOpSrcOver,
+            master_alpha: 1.0,
+            anti_alias_gamma: 1.0,
+            fill: Paint::new(Rgba8::new_params(255, 255, 255, 255)),
+            line: Paint::new(Rgba8::new_params(0, 0, 0, 255)),
+            line_width: 1.0,
+            line_cap: LineCap::Round,
+            line_join: LineJoin::Round,
+            even_odd: false,
+            transform: TransAffine::new_default(),
+            image_filter: ImageFilterKind::Bilinear,
+            image_resample: ImageResample::NoResample,
+        }
+    }
+}
+
+//==================================================================Agg2D
+// A stateful drawing context in the manner of the Agg2D class of the C++
+// distribution. It draws on a 32-bit RGBA buffer with the component order
+// O, either a RenderBuf or an ImageBuffer that stays borrowed as long as
+// the context lives; images used by the image functions must have the
+// same layout.
+//
+// The shape functions replace the current path and draw it right away;
+// the path functions build a path to be drawn with draw_path(). Fills
+// and strokes are solid colors or linear and radial gradients, blended
+// with one of the CompOp modes. Coordinates are transformed by the
+// current matrix, and the whole graphics state can be pushed and popped
+// with save_state() and restore_state().
+//
+//   let mut g = Agg2D::<OrderBgra>::new_image(&mut img);
+//   g.clear_all(&Rgba8::new_params(255, 255, 255, 255));
+//   g.set_fill_color(&Rgba8::new_params(200, 60, 0, 255));
+//   g.set_line_width(4.0);
+//   g.ellipse(200., 150., 100., 80.);
+pub struct Agg2D<'a, O: Order> {
+    ren: RendererBase<'a, PixFormat<'a, O>>,
+    ren_comp: RendererBase<'a, PixFormatComp<'a, O>>,
+    ras: RasterizerScanlineAa,
+    sl: ScanlineU8,
+    sa: VecSpan<Rgba8>,
+    path: PathStorage,
+    state: State,
+    stack: Vec<State>,
+}
+
+impl<'a, O: Order> Agg2D<'a, O> {
+    pub fn new(rbuf: RenderBuf) -> Self {
+        let mut g = Agg2D {
+            ren: RendererBase::new_owned(PixFormat::<O>::new_owned(rbuf)),
+            ren_comp: RendererBase::new_owned(PixFormatComp::<O>::new_owned(rbuf)),
+            ras: RasterizerScanlineAa::new(),
+            sl: ScanlineU8::new(),
+            sa: VecSpan::new(),
+            path: PathStorage::new(),
+            state: State::new(rbuf.width(), rbuf.height()),
+            stack: Vec::new(),
+        };
+        g.apply_state();
+        g
+    }
+
+    pub fn new_image(img: &'a mut Agg2DImage<O>) -> Self {
+        Self::new(*img.rbuf())
+    }
+
+    // Starts drawing on another buffer; the graphics state is reset
+    pub fn attach(&mut self, rbuf: RenderBuf) {
+        self.ren.ren_mut().attach_owned(rbuf);
+        self.ren.reset_clipping(true);
+        self.ren_comp.ren_mut().attach_owned(rbuf);
+        self.ren_comp.reset_clipping(true);
+        self.state = State::new(rbuf.width(), rbuf.height());
+        self.stack.clear();
+        self.apply_state();
+    }
+
+    pub fn path_mut(&mut self) -> &mut PathStorage {
+        &mut self.path
+    }
+
+    //--------------------------------------------------------------------
+    pub fn save_state(&mut self) {
+        self.stack.push(self.state.clone());
+    }
+
+    // Does nothing if there's no saved state
+    pub fn restore_state(&mut self) {
+        if let Some(state) = self.stack.pop() {
+            self.state = state;
+            self.apply_state();
+        }
+    }
+
+    fn apply_state(&mut self) {
+        self.apply_clip_box();
+        self.update_rasterizer_gamma();
+        self.ras.set_filling_rule(if self.state.even_odd {
+            FillingRule::FillEvenOdd
+        } else {
+            FillingRule::FillNonZero
+        });
+    }
+
+    //--------------------------------------------------------------------
+    // Clip box in screen coordinates
+    pub fn set_clip_box(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) {
+        self.state.clip_box = RectD::new(x1, y1, x2, y2);
+        self.apply_clip_box();
+    }
+
+    pub fn clip_box(&self) -> RectD {
+        self.state.clip_box
+    }
+
+    fn apply_clip_box(&mut self) {
+        let cb = self.state.clip_box;
+        let (x1, y1, x2, y2) = (cb.x1 as i32, cb.y1 as i32, cb.x2 as i32, cb.y2 as i32);
+        self.ren.set_clip_box(x1, y1, x2, y2);
+        self.ren_comp.set_clip_box(x1, y1, x2, y2);
+        self.ras.clip_box(cb.x1, cb.y1, cb.x2, cb.y2);
+    }
+
+    pub fn clear_all(&mut self, c: &Rgba8) {
+        self.ren.clear(c);
+    }
+
+    pub fn clear_clip_box(&mut self, c: &Rgba8) {
+        let (w, h) = (self.ren.width() as i32, self.ren.height() as i32);
+        self.ren.copy_bar(0, 0, w, h, c);
+    }
+
+    //--------------------------------------------------------------------
+    pub fn world_to_screen(&self, x: &mut f64, y: &mut f64) {
+        self.state.transform.transform(x, y);
+    }
+
+    pub fn screen_to_world(&self, x: &mut f64, y: &mut f64) {
+        self.state.transform.inverse_transform(x, y);
+    }
+
+    pub fn world_to_screen_scalar(&self, scalar: f64) -> f64 {
+        let (mut x1, mut y1, mut x2, mut y2) = (0., 0., scalar, scalar);
+        self.world_to_screen(&mut x1, &mut y1);
+        self.world_to_screen(&mut x2, &mut y2);
+        ((x2 - x1) * (x2 - x1) + (y2 - y1) * (y2 - y1)).sqrt() * 0.7071068
+    }
+
+    pub fn screen_to_world_scalar(&self, scalar: f64) -> f64 {
+        let (mut x1, mut y1, mut x2, mut y2) = (0., 0., scalar, scalar);
+        self.screen_to_world(&mut x1, &mut y1);
+        self.screen_to_world(&mut x2, &mut y2);
+        ((x2 - x1) * (x2 - x1) + (y2 - y1) * (y2 - y1)).sqrt() * 0.7071068
+    }
+
+    // Moves the point to the center of the nearest pixel, for crisp
+    // one pixel wide lines
+    pub fn align_point(&self, x: &mut f64, y: &mut f64) {
+        self.world_to_screen(x, y);
+        *x = x.floor() + 0.5;
+        *y = y.floor() + 0.5;
+        self.screen_to_world(x, y);
+    }
+
+    pub fn in_box(&self, world_x: f64, world_y: f64) -> bool {
+        let (mut x, mut y) = (world_x, world_y);
+        self.world_to_screen(&mut x, &mut y);
+        self.ren.inbox(x as i32, y as i32)
+    }
+
+    //--------------------------------------------------------------------
+    // CompOpSrcOver, the default, uses the plain alpha blender, all other
+    // modes the compositing one
+    pub fn set_blend_mode(&mut self, m: CompOp) {
+        self.state.blend_mode = m;
+    }
+
+    pub fn blend_mode(&self) -> CompOp {
+        self.state.blend_mode
+    }
+
+    fn alpha_blend(&self) -> bool {
+        matches!(
+            self.state.blend_mode,
+            CompOp::CompOpSrcOver | CompOp::EndofCompOp
+        )
+    }
+
+    pub fn set_master_alpha(&mut self, a: f64) {
+        self.state.master_alpha = a;
+        self.update_rasterizer_gamma();
+    }
+
+    pub fn master_alpha(&self) -> f64 {
+        self.state.master_alpha
+    }
+
+    pub fn set_anti_alias_gamma(&mut self, g: f64) {
+        self.state.anti_alias_gamma = g;
+        self.update_rasterizer_gamma();
+    }
+
+    pub fn anti_alias_gamma(&self) -> f64 {
+        self.state.anti_alias_gamma
+    }
+
+    fn update_rasterizer_gamma(&mut self) {
+        self.ras.set_gamma(&RasterizerGamma {
+            alpha: GammaMultiply::new_with_value(self.state.master_alpha),
+            gamma: GammaPower::new_with_gamma(self.state.anti_alias_gamma),
+        });
+    }
+
+    //--------------------------------------------------------------------
+    pub fn set_fill_color(&mut self, c: &Rgba8) {
+        self.state.fill.set_color(c);
+    }
+
+    pub fn fill_color(&self) -> Rgba8 {
+        self.state.fill.color
+    }
+
+    pub fn no_fill(&mut self) {
+        self.state.fill.set_color(&Rgba8::new_params(0, 0, 0, 0));
+    }
+
+    pub fn set_line_color(&mut self, c: &Rgba8) {
+        self.state.line.set_color(c);
+    }
+
+    pub fn line_color(&self) -> Rgba8 {
+        self.state.line.color
+    }
+
+    pub fn no_line(&mut self) {
+        self.state.line.set_color(&Rgba8::new_params(0, 0, 0, 0));
+    }
+
+    // Gradients are fixed in place by the transformation at the time
+    // they are set
+    pub fn fill_linear_gradient(
+        &mut self, x1: f64, y1: f64, x2: f64, y2: f64, c1: &Rgba8, c2: &Rgba8, profile: f64,
+    ) {
+        self.state.fill.set_colors(c1, c2, profile);
+        let mtx = self.state.transform;
+        self.state.fill.set_linear(&mtx, x1, y1, x2, y2);
+    }
+
+    pub fn line_linear_gradient(
+        &mut self, x1: f64, y1: f64, x2: f64, y2: f64, c1: &Rgba8, c2: &Rgba8, profile: f64,
+    ) {
+        self.state.line.set_colors(c1, c2, profile);
+        let mtx = self.state.transform;
+        self.state.line.set_linear(&mtx, x1, y1, x2, y2);
+    }
+
+    pub fn fill_radial_gradient(
+        &mut self, x: f64, y: f64, r: f64, c1: &Rgba8, c2: &Rgba8, profile: f64,
+    ) {
+        self.state.fill.set_colors(c1, c2, profile);
+        self.fill_radial_gradient_pos(x, y, r);
+    }
+
+    pub fn line_radial_gradient(
+        &mut self, x: f64, y: f64, r: f64, c1: &Rgba8, c2: &Rgba8, profile: f64,
+    ) {
+        self.state.line.set_colors(c1, c2, profile);
+        self.line_radial_gradient_pos(x, y, r);
+    }
+
+    // c1 at the center, c2 at half the radius and c3 at the radius
+    pub fn fill_radial_gradient3(
+        &mut self, x: f64, y: f64, r: f64, c1: &Rgba8, c2: &Rgba8, c3: &Rgba8,
+    ) {
+        self.state.fill.set_colors3(c1, c2, c3);
+        self.fill_radial_gradient_pos(x, y, r);
+    }
+
+    pub fn line_radial_gradient3(
+        &mut self, x: f64, y: f64, r: f64, c1: &Rgba8, c2: &Rgba8, c3: &Rgba8,
+    ) {
+        self.state.line.set_colors3(c1, c2, c3);
+        self.line_radial_gradient_pos(x, y, r);
+    }
+
+    // Moves the radial gradient keeping its colors
+    pub fn fill_radial_gradient_pos(&mut self, x: f64, y: f64, r: f64) {
+        let (mut sx, mut sy) = (x, y);
+        self.world_to_screen(&mut sx, &mut sy);
+        let sr = self.world_to_screen_scalar(r);
+        self.state.fill.set_radial(sx, sy, sr);
+    }
+
+    pub fn line_radial_gradient_pos(&mut self, x: f64, y: f64, r: f64) {
+        let (mut sx, mut sy) = (x, y);
+        self.world_to_screen(&mut sx, &mut sy);
+        let sr = self.world_to_screen_scalar(r);
+        self.state.line.set_radial(sx, sy, sr);
+    }
+
+    //--------------------------------------------------------------------
+    // The line width is in world units
+    pub fn set_line_width(&mut self, w: f64) {
+        self.state.line_width = w;
+    }
+
+    pub fn line_width(&self) -> f64 {
+        self.state.line_width
+    }
+
+    pub fn set_line_cap(&mut self, cap: LineCap) {
+        self.state.line_cap = cap;
+    }
+
+    pub fn line_cap(&self) -> LineCap {
+        self.state.line_cap
+    }
+
+    pub fn set_line_join(&mut self, join: LineJoin) {
+        self.state.line_join = join;
+    }
+
+    pub fn line_join(&self) -> LineJoin {
+        self.state.line_join
+    }
+
+    pub fn set_fill_even_odd(&mut self, even_odd: bool) {
+        self.state.even_odd = even_odd;
+        self.ras.set_filling_rule(if even_odd {
+            FillingRule::FillEvenOdd
+        } else {
+            FillingRule::FillNonZero
+        });
+    }
+
+    pub fn fill_even_odd(&self) -> bool {
+        self.state.even_odd
+    }
+
+    //--------------------------------------------------------------------
+    pub fn transform(&self) -> TransAffine {
+        self.state.transform
+    }
+
+    pub fn set_transform(&mut self, mtx: &TransAffine) {
+        self.state.transform = *mtx;
+    }
+
+    pub fn reset_transform(&mut self) {
+        self.state.transform.reset();
+    }
+
+    // The transformations below apply on top of the current one
+    pub fn affine(&mut self, mtx: &TransAffine) {
+        self.state.transform.multiply(mtx);
+    }
+
+    pub fn rotate(&mut self, angle: f64) {
+        self.affine(&TransAffine::trans_affine_rotation(angle));
+    }
+
+    pub fn scale(&mut self, sx: f64, sy: f64) {
+        self.affine(&TransAffine::trans_affine_scaling(sx, sy));
+    }
+
+    pub fn skew(&mut self, sx: f64, sy: f64) {
+        self.affine(&TransAffine::trans_affine_skewing(sx, sy));
+    }
+
+    pub fn translate(&mut self, x: f64, y: f64) {
+        self.affine(&TransAffine::trans_affine_translation(x, y));
+    }
+
+    // Maps the rectangle onto the parallelogram, see TransAffine::parl_to_parl
+    pub fn parallelogram(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, para: &[f64; 6]) {
+        self.affine(&TransAffine::new_parl(x1, y1, x2, y2, para));
+    }
+
+    // Maps the world rectangle onto the screen one
+    pub fn viewport(&mut self, world: &RectD, screen: &RectD, opt: ViewportOption) {
+        let mut vp = TransViewport::new();
+        let (ax, ay, aspect) = match opt {
+            ViewportOption::Anisotropic => (0.0, 0.0, AspectRatio::Stretch),
+            ViewportOption::XMinYMin => (0.0, 0.0, AspectRatio::Meet),
+            ViewportOption::XMidYMin => (0.5, 0.0, AspectRatio::Meet),
+            ViewportOption::XMaxYMin => (1.0, 0.0, AspectRatio::Meet),
+            ViewportOption::XMinYMid => (0.0, 0.5, AspectRatio::Meet),
+            ViewportOption::XMidYMid => (0.5, 0.5, AspectRatio::Meet),
+            ViewportOption::XMaxYMid => (1.0, 0.5, AspectRatio::Meet),
+            ViewportOption::XMinYMax => (0.0, 1.0, AspectRatio::Meet),
+            ViewportOption::XMidYMax => (0.5, 1.0, AspectRatio::Meet),
+            ViewportOption::XMaxYMax => (1.0, 1.0, AspectRatio::Meet),
+        };
+        vp.set_preserve_aspect_ratio(ax, ay, aspect);
+        vp.set_world_viewport(world.x1, world.y1, world.x2, world.y2);
+        vp.set_device_viewport(screen.x1, screen.y1, screen.x2, screen.y2);
+        self.affine(&vp.to_affine());
+    }
+
+    //--------------------------------------------------------------------
+    // Basic shapes
+    pub fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) {
+        self.path.remove_all();
+        self.path.move_to(x1, y1);
+        self.path.line_to(x2, y2);
+        self.draw_path(DrawPathFlag::StrokeOnly);
+    }
+
+    pub fn triangle(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64) {
+        self.path.remove_all();
+        self.path.move_to(x1, y1);
+        self.path.line_to(x2, y2);
+        self.path.line_to(x3, y3);
+        self.path.close_polygon(0);
+        self.draw_path(DrawPathFlag::FillAndStroke);
+    }
+
+    pub fn rectangle(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) {
+        self.path.remove_all();
+        self.path.move_to(x1, y1);
+        self.path.line_to(x2, y1);
+        self.path.line_to(x2, y2);
+        self.path.line_to(x1, y2);
+        self.path.close_polygon(0);
+        self.draw_path(DrawPathFlag::FillAndStroke);
+    }
+
+    pub fn rounded_rect(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, r: f64) {
+        self.rounded_rect_xy(x1, y1, x2, y2, r, r);
+    }
+
+    pub fn rounded_rect_xy(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, rx: f64, ry: f64) {
+        let mut rc = RoundedRect::new(x1, y1, x2, y2, 0.0);
+        rc.radius_xy(rx, ry);
+        rc.normalize_radius();
+        rc.set_approximation_scale(self.world_to_screen_scalar(1.0) * APPROX_SCALE);
+        self.path.remove_all();
+        self.path.concat_path(&mut rc, 0);
+        self.draw_path(DrawPathFlag::FillAndStroke);
+    }
+
+    pub fn ellipse(&mut self, cx: f64, cy: f64, rx: f64, ry: f64) {
+        self.path.remove_all();
+        self.add_ellipse(cx, cy, rx, ry, Direction::CCW);
+        self.draw_path(DrawPathFlag::FillAndStroke);
+    }
+
+    // Angles in radians
+    pub fn arc(&mut self, cx: f64, cy: f64, rx: f64, ry: f64, start: f64, sweep: f64) {
+        let mut arc = BezierArc::new_with_params(cx, cy, rx, ry, start, sweep);
+        self.path.remove_all();
+        self.path.concat_path(&mut arc, 0);
+        self.draw_path(DrawPathFlag::StrokeOnly);
+    }
+
+    // num_rays points alternating between the radii r2 and r1
+    pub fn star(&mut self, cx: f64, cy: f64, r1: f64, r2: f64, start_angle: f64, num_rays: u32) {
+        self.path.remove_all();
+        let da = PI / num_rays as f64;
+        let mut a = start_angle;
+        for i in 0..num_rays {
+            let (x, y) = (a.cos() * r2 + cx, a.sin() * r2 + cy);
+            if i == 0 {
+                self.path.move_to(x, y);
+            } else {
+                self.path.line_to(x, y);
+            }
+            a += da;
+            self.path.line_to(a.cos() * r1 + cx, a.sin() * r1 + cy);
+            a += da;
+        }
+        self.path.close_polygon(0);
+        self.draw_path(DrawPathFlag::FillAndStroke);
+    }
+
+    pub fn curve3(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64) {
+        self.path.remove_all();
+        self.path.move_to(x1, y1);
+        self.path.curve3_ctrl(x2, y2, x3, y3);
+        self.draw_path(DrawPathFlag::StrokeOnly);
+    }
+
+    pub fn curve4(
+        &mut self, x1: f64, y1: f64, x2: f64, y2: f64, x3: f64, y3: f64, x4: f64, y4: f64,
+    ) {
+        self.path.remove_all();
+        self.path.move_to(x1, y1);
+        self.path.curve4_ctrl(x2, y2, x3, y3, x4, y4);
+        self.draw_path(DrawPathFlag::StrokeOnly);
+    }
+
+    // xy holds x and y of each point
+    pub fn polygon(&mut self, xy: &[f64]) {
+        self.path.remove_all();
+        self.path.concat_poly(xy, xy.len() / 2, true);
+        self.path.close_polygon(0);
+        self.draw_path(DrawPathFlag::FillAndStroke);
+    }
+
+    pub fn polyline(&mut self, xy: &[f64]) {
+        self.path.remove_all();
+        self.path.concat_poly(xy, xy.len() / 2, false);
+        self.draw_path(DrawPathFlag::StrokeOnly);
+    }
+
+    //--------------------------------------------------------------------
+    // Path commands
+    pub fn reset_path(&mut self) {
+        self.path.remove_all();
+    }
+
+    pub fn move_to(&mut self, x: f64, y: f64) {
+        self.path.move_to(x, y);
+    }
+
+    pub fn move_rel(&mut self, dx: f64, dy: f64) {
+        self.path.move_rel(dx, dy);
+    }
+
+    pub fn line_to(&mut self, x: f64, y: f64) {
+        self.path.line_to(x, y);
+    }
+
+    pub fn line_rel(&mut self, dx: f64, dy: f64) {
+        self.path.line_rel(dx, dy);
+    }
+
+    pub fn hor_line_to(&mut self, x: f64) {
+        self.path.hline_to(x);
+    }
+
+    pub fn hor_line_rel(&mut self, dx: f64) {
+        self.path.hline_rel(dx);
+    }
+
+    pub fn ver_line_to(&mut self, y: f64) {
+        self.path.vline_to(y);
+    }
+
+    pub fn ver_line_rel(&mut self, dy: f64) {
+        self.path.vline_rel(dy);
+    }
+
+    pub fn arc_to(
+        &mut self, rx: f64, ry: f64, angle: f64, large_arc_flag: bool, sweep_flag: bool, x: f64,
+        y: f64,
+    ) {
+        self.path
+            .arc_to(rx, ry, angle, large_arc_flag, sweep_flag, x, y);
+    }
+
+    pub fn arc_rel(
+        &mut self, rx: f64, ry: f64, angle: f64, large_arc_flag: bool, sweep_flag: bool, dx: f64,
+        dy: f64,
+    ) {
+        self.path
+            .arc_rel(rx, ry, angle, large_arc_flag, sweep_flag, dx, dy);
+    }
+
+    pub fn quadric_curve_to(&mut self, x_ctrl: f64, y_ctrl: f64, x_to: f64, y_to: f64) {
+        self.path.curve3_ctrl(x_ctrl, y_ctrl, x_to, y_to);
+    }
+
+    pub fn quadric_curve_rel(&mut self, dx_ctrl: f64, dy_ctrl: f64, dx_to: f64, dy_to: f64) {
+        self.path.curve3_rel_ctrl(dx_ctrl, dy_ctrl, dx_to, dy_to);
+    }
+
+    // The control point is the reflection of the previous one
+    pub fn quadric_curve_to_smooth(&mut self, x_to: f64, y_to: f64) {
+        self.path.curve3(x_to, y_to);
+    }
+
+    pub fn quadric_curve_rel_smooth(&mut self, dx_to: f64, dy_to: f64) {
+        self.path.curve3_rel(dx_to, dy_to);
+    }
+
+    pub fn cubic_curve_to(
+        &mut self, x_ctrl1: f64, y_ctrl1: f64, x_ctrl2: f64, y_ctrl2: f64, x_to: f64, y_to: f64,
+    ) {
+        self.path
+            .curve4_ctrl(x_ctrl1, y_ctrl1, x_ctrl2, y_ctrl2, x_to, y_to);
+    }
+
+    pub fn cubic_curve_rel(
+        &mut self, dx_ctrl1: f64, dy_ctrl1: f64, dx_ctrl2: f64, dy_ctrl2: f64, dx_to: f64,
+        dy_to: f64,
+    ) {
+        self.path
+            .curve4_rel_ctrl(dx_ctrl1, dy_ctrl1, dx_ctrl2, dy_ctrl2, dx_to, dy_to);
+    }
+
+    pub fn cubic_curve_to_smooth(&mut self, x_ctrl2: f64, y_ctrl2: f64, x_to: f64, y_to: f64) {
+        self.path.curve4(x_ctrl2, y_ctrl2, x_to, y_to);
+    }
+
+    pub fn cubic_curve_rel_smooth(&mut self, dx_ctrl2: f64, dy_ctrl2: f64, dx_to: f64, dy_to: f64) {
+        self.path.curve4_rel(dx_ctrl2, dy_ctrl2, dx_to, dy_to);
+    }
+
+    pub fn add_ellipse(&mut self, cx: f64, cy: f64, rx: f64, ry: f64, dir: Direction) {
+        let sweep = if dir == Direction::CCW {
+            2.0 * PI
+        } else {
+            -2.0 * PI
+        };
+        let mut arc = BezierArc::new_with_params(cx, cy, rx, ry, 0.0, sweep);
+        self.path.concat_path(&mut arc, 0);
+        self.path.close_polygon(0);
+    }
+
+    // Appends any vertex source to the current path
+    pub fn add_path<VS: VertexSource>(&mut self, vs: &mut VS, path_id: u32) {
+        self.path.concat_path(vs, path_id);
+    }
+
+    pub fn close_polygon(&mut self) {
+        self.path.close_polygon(0);
+    }
+
+    pub fn draw_path(&mut self, flag: DrawPathFlag) {
+        self.ras.reset();
+        match flag {
+            DrawPathFlag::FillOnly => {
+                if self.state.fill.visible() {
+                    self.add_fill_path();
+                    self.render(true);
+                }
+            }
+            DrawPathFlag::StrokeOnly => {
+                if self.state.line.visible() && self.state.line_width > 0.0 {
+                    self.add_stroke_path();
+                    self.render(false);
+                }
+            }
+            DrawPathFlag::FillAndStroke => {
+                if self.state.fill.visible() {
+                    self.add_fill_path();
+                    self.render(true);
+                }
+                if self.state.line.visible() && self.state.line_width > 0.0 {
+                    self.ras.reset();
+                    self.add_stroke_path();
+                    self.render(false);
+                }
+            }
+            DrawPathFlag::FillWithLineColor => {
+                if self.state.line.visible() {
+                    self.add_fill_path();
+                    self.render(false);
+                }
+            }
+        }
+    }
+
+    fn add_fill_path(&mut self) {
+        let scale = self.world_to_screen_scalar(1.0) * APPROX_SCALE;
+        let mut curve: ConvCurve<_> = ConvCurve::new_borrowed(&mut self.path);
+        curve.set_approximation_scale(scale);
+        let mut trans = ConvTransform::new_owned(curve, self.state.transform);
+        self.ras.add_path(&mut trans, 0);
+    }
+
+    fn add_stroke_path(&mut self) {
+        let scale = self.world_to_screen_scalar(1.0) * APPROX_SCALE;
+        let mut curve: ConvCurve<_> = ConvCurve::new_borrowed(&mut self.path);
+        curve.set_approximation_scale(scale);
+        let mut stroke: ConvStroke<_> = ConvStroke::new_owned(curve);
+        stroke.set_width(self.state.line_width);
+        stroke.set_line_cap(self.state.line_cap);
+        stroke.set_line_join(self.state.line_join);
+        stroke.set_approximation_scale(scale);
+        let mut trans = ConvTransform::new_owned(stroke, self.state.transform);
+        self.ras.add_path(&mut trans, 0);
+    }
+
+    fn render(&mut self, fill: bool) {
+        let alpha_blend = self.alpha_blend();
+        let paint = if fill {
+            &mut self.state.fill
+        } else {
+            &mut self.state.line
+        };
+        if alpha_blend {
+            render_paint(
+                &mut self.ras,
+                &mut self.sl,
+                &mut self.sa,
+                &mut self.ren,
+                paint,
+            );
+        } else {
+            let op = self.state.blend_mode as u32;
+            self.ren_comp.ren_mut().set_comp_op(op);
+            render_paint(
+                &mut self.ras,
+                &mut self.sl,
+                &mut self.sa,
+                &mut self.ren_comp,
+                paint,
+            );
+        }
+    }
+
+    //--------------------------------------------------------------------
+    // Image transformations. The image must be in the same pixel format as
+    // the target; rect selects its part to draw, inclusive, None means all
+    // of it.
+    pub fn set_image_filter(&mut self, f: ImageFilterKind) {
+        self.state.image_filter = f;
+    }
+
+    pub fn image_filter(&self) -> ImageFilterKind {
+        self.state.image_filter
+    }
+
+    pub fn set_image_resample(&mut self, r: ImageResample) {
+        self.state.image_resample = r;
+    }
+
+    pub fn image_resample(&self) -> ImageResample {
+        self.state.image_resample
+    }
+
+    // Draws the image into the rectangle given in world coordinates
+    pub fn transform_image(
+        &mut self, img: &Agg2DImage<O>, rect: Option<&RectI>, x1: f64, y1: f64, x2: f64, y2: f64,
+    ) {
+        self.path.remove_all();
+        self.path.move_to(x1, y1);
+        self.path.line_to(x2, y1);
+        self.path.line_to(x2, y2);
+        self.path.line_to(x1, y2);
+        self.path.close_polygon(0);
+        self.render_image(img, rect, &[x1, y1, x2, y1, x2, y2]);
+    }
+
+    // Draws the image into the parallelogram x1,y1, x2,y2, x3,y3 whose
+    // first three corners take the top-left, top-right and bottom-right
+    // ones of the image
+    pub fn transform_image_parl(
+        &mut self, img: &Agg2DImage<O>, rect: Option<&RectI>, parl: &[f64; 6],
+    ) {
+        self.path.remove_all();
+        self.path.move_to(parl[0], parl[1]);
+        self.path.line_to(parl[2], parl[3]);
+        self.path.line_to(parl[4], parl[5]);
+        self.path
+            .line_to(parl[0] + parl[4] - parl[2], parl[1] + parl[5] - parl[3]);
+        self.path.close_polygon(0);
+        self.render_image(img, rect, parl);
+    }
+
+    // Fills the current path with the image mapped onto the rectangle
+    pub fn transform_image_path(
+        &mut self, img: &Agg2DImage<O>, rect: Option<&RectI>, x1: f64, y1: f64, x2: f64, y2: f64,
+    ) {
+        self.render_image(img, rect, &[x1, y1, x2, y1, x2, y2]);
+    }
+
+    pub fn transform_image_path_parl(
+        &mut self, img: &Agg2DImage<O>, rect: Option<&RectI>, parl: &[f64; 6],
+    ) {
+        self.render_image(img, rect, parl);
+    }
+
+    fn render_image(&mut self, img: &Agg2DImage<O>, rect: Option<&RectI>, parl: &[f64; 6]) {
+        let r = match rect {
+            Some(r) => *r,
+            None => RectI::new(0, 0, img.width() as i32 - 1, img.height() as i32 - 1),
+        };
+        let mut mtx = TransAffine::new_parl(
+            r.x1 as f64,
+            r.y1 as f64,
+            (r.x2 + 1) as f64,
+            (r.y2 + 1) as f64,
+            parl,
+        );
+        mtx.multiply(&self.state.transform);
+        mtx.invert();
+
+        self.ras.reset();
+        self.add_fill_path();
+
+        let mut ip: SpanIpLinear<TransAffine> = SpanIpLinear::new(mtx);
+        let mut src = ImageAccessorClip::new(
+            PixFormat::<O>::new_owned(*img.rbuf()),
+            &Rgba8::new_params(0, 0, 0, 0),
+        );
+        let (filter, resample) = (self.state.image_filter, self.state.image_resample);
+        if self.alpha_blend() {
+            render_image_spans(
+                &mut self.ras,
+                &mut self.sl,
+                &mut self.sa,
+                &mut self.ren,
+                &mut src,
+                &mut ip,
+                filter,
+                resample,
+            );
+        } else {
+            let op = self.state.blend_mode as u32;
+            self.ren_comp.ren_mut().set_comp_op(op);
+            render_image_spans(
+                &mut self.ras,
+                &mut self.sl,
+                &mut self.sa,
+                &mut self.ren_comp,
+                &mut src,
+                &mut ip,
+                filter,
+                resample,
+            );
+        }
+    }
+
+    //--------------------------------------------------------------------
+    // Image blending without transformations, only the position is
+    // converted to screen coordinates. alpha is 0...255.
+    pub fn blend_image(
+        &mut self, img: &Agg2DImage<O>, rect: Option<&RectI>, dst_x: f64, dst_y: f64, alpha: u32,
+    ) {
+        let (mut x, mut y) = (dst_x, dst_y);
+        self.world_to_screen(&mut x, &mut y);
+        let (dx, dy) = rect.map_or((0, 0), |r| (r.x1, r.y1));
+        let (dx, dy) = (x as i32 - dx, y as i32 - dy);
+        let pix = PixFormat::<O>::new_owned(*img.rbuf());
+        if self.alpha_blend() {
+            self.ren.blend_from(&pix, rect, dx, dy, alpha);
+        } else {
+            let op = self.state.blend_mode as u32;
+            self.ren_comp.ren_mut().set_comp_op(op);
+            self.ren_comp.blend_from(&pix, rect, dx, dy, alpha);
+        }
+    }
+
+    // Copies the pixels, alpha channel included
+    pub fn copy_image(
+        &mut self, img: &Agg2DImage<O>, rect: Option<&RectI>, dst_x: f64, dst_y: f64,
+    ) {
+        let (mut x, mut y) = (dst_x, dst_y);
+        self.world_to_screen(&mut x, &mut y);
+        let (dx, dy) = rect.map_or((0, 0), |r| (r.x1, r.y1));
+        self.ren
+            .copy_from(img.rbuf(), rect, x as i32 - dx, y as i32 - dy);
+    }
+}
+
+fn render_paint<Ren: Renderer<C = Rgba8>>(
+    ras: &mut RasterizerScanlineAa, sl: &mut ScanlineU8, sa: &mut VecSpan<Rgba8>, ren: &mut Ren,
+    paint: &mut Paint,
+) {
+    match paint.flag {
+        GradientFlag::Solid => {
+            render_scanlines_aa_solid(ras, sl, ren, &paint.color);
+        }
+        GradientFlag::Linear => {
+            let mut ip: SpanIpLinear<TransAffine> = SpanIpLinear::new(paint.mtx);
+            let mut gf = GradientX;
+            let mut sg =
+                SpanGradient::new(&mut ip, &mut gf, &mut paint.gradient, paint.d1, paint.d2);
+            render_scanlines_aa(ras, sl, ren, sa, &mut sg);
+        }
+        GradientFlag::Radial => {
+            let mut ip: SpanIpLinear<TransAffine> = SpanIpLinear::new(paint.mtx);
+            let mut gf = GradientRadial;
+            let mut sg =
+                SpanGradient::new(&mut ip, &mut gf, &mut paint.gradient, paint.d1, paint.d2);
+            render_scanlines_aa(ras, sl, ren, sa, &mut sg);
+        }
+    }
+}
+
+fn render_image_spans<Ren: Renderer<C = Rgba8>, S: ImageAccessorRgb<ColorType = Rgba8>>(
+    ras: &mut RasterizerScanlineAa, sl: &mut ScanlineU8, sa: &mut VecSpan<Rgba8>, ren: &mut Ren,
+    src: &mut S, ip: &mut SpanIpLinear<TransAffine>, filter: ImageFilterKind,
+    resample: ImageResample,
+) {
+    if filter == ImageFilterKind::NoFilter {
+        let mut sg = SpanImageFilterRgbaNn::new(src, ip);
+        render_scanlines_aa(ras, sl, ren, sa, &mut sg);
+        return;
+    }
+
+    // The interpolator maps the screen onto the image, so scaling above
+    // one means the image is shrunk
+    let resample = match resample {
+        ImageResample::NoResample => false,
+        ImageResample::ResampleAlways => true,
+        ImageResample::ResampleOnZoomOut => {
+            let (mut sx, mut sy) = (0., 0.);
+            ip.transformer().scaling_abs(&mut sx, &mut sy);
+            sx > 1.125 || sy > 1.125
+        }
+    };
+    if resample {
+        let mut sg = SpanImageResampleRgbaAffine::new(src, ip, filter.lut());
+        render_scanlines_aa(ras, sl, ren, sa, &mut sg);
+    } else if filter == ImageFilterKind::Bilinear {
+        let mut sg = SpanImageFilterRgbaBilinear::new(src, ip);
+        render_scanlines_aa(ras, sl, ren, sa, &mut sg);
+    } else {
+        let lut = filter.lut();
+        if lut.diameter() == 2 {
+            let mut sg = SpanImageFilterRgba2x2::new(src, ip, lut);
+            render_scanlines_aa(ras, sl, ren, sa, &mut sg);
+        } else {
+            let mut sg = SpanImageFilterRgba::new(src, ip, lut);
+            render_scanlines_aa(ras, sl, ren, sa, &mut sg);
+        }
+    }
+}
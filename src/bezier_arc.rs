@@ -39,8 +39,8 @@ const ANGLE_EPSILON: f64 = 0.01;
 fn arc_to_bezier(
     cx: f64, cy: f64, rx: f64, ry: f64, start_angle: f64, sweep_angle: f64, curve: &mut [f64],
 ) {
-    let x0 = sweep_angle.cos() / 2.0;
-    let y0 = sweep_angle.sin() / 2.0;
+    let x0 = (sweep_angle / 2.0).cos();
+    let y0 = (sweep_angle / 2.0).sin();
     let tx = (1.0 - x0) * 4.0 / 3.0;
     let ty = y0 - tx * x0 / y0;
     let px = [x0, x0 + tx, x0 + tx, x0];
@@ -351,10 +351,12 @@ impl BezierArcSvg {
         let len = self.m_arc.num_vertices();
         let aref = self.m_arc.vertices_mut();
         let (mut x, mut y);
-        for i in 2..len as usize - 2 {
+        for i in (2..len as usize - 2).step_by(2) {
             x = aref[i];
             y = aref[i + 1];
             mtx.transform(&mut x, &mut y);
+            aref[i] = x;
+            aref[i + 1] = y;
         }
 
         // We must make sure that the starting and ending points
@@ -0,0 +1,245 @@
+use crate::basics::{FillingRule, RectI};
+use crate::image_buffer::{ImageBuffer, PixView};
+use crate::math_stroke::{LineCap, LineJoin};
+use crate::pixel_format::ConvertColor;
+use crate::png::{save_png, PngError};
+use crate::rasterizer_scanline_aa::RasterizerScanlineAa;
+use crate::renderer_base::RendererBase;
+use crate::renderer_scanline::render_scanlines_aa_solid;
+use crate::scanline_u::ScanlineU8;
+use crate::trans_affine::TransAffine;
+use crate::{
+    ConvCurve, ConvStroke, ConvTransform, GammaFn, PixFmt, PixFmtOwned, RasterScanLine,
+    VertexSource,
+};
+
+//==================================================================Canvas
+// An offscreen rendering context for use without a window: it owns the
+// image, the clip box, the rasterizer and a scanline, and draws through
+// a RendererBase on a view of the image.
+// Paths are flattened, optionally stroked and transformed by the current
+// matrix before rasterizing, the way the examples do in on_draw().
+//
+//   let mut canvas = Canvas::<PixBgr24>::new(400, 300);
+//   canvas.clear(&Rgba8::new_params(255, 255, 255, 255));
+//   let mut e = Ellipse::new_ellipse(200., 150., 100., 80., 0, false);
+//   canvas.fill_path(&mut e, 0, &Rgba8::new_params(200, 60, 0, 255));
+//   canvas.save_png("ellipse.png")?;
+pub struct Canvas<Pix: PixFmtOwned> {
+    img: ImageBuffer<Pix>,
+    pix: Pix,
+    clip_box: RectI,
+    ras: RasterizerScanlineAa,
+    sl: ScanlineU8,
+    mtx: TransAffine,
+    line_width: f64,
+    line_join: LineJoin,
+    line_cap: LineCap,
+    miter_limit: f64,
+}
+
+impl<Pix: PixFmtOwned> Canvas<Pix> {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::new_from_image(ImageBuffer::new(width, height))
+    }
+
+    // Draws on an existing image, e.g. one loaded from a file
+    pub fn new_from_image(mut img: ImageBuffer<Pix>) -> Self {
+        // The pixel format points into the heap storage of the image,
+        // which stays in place when the image is moved. The canvas owns
+        // the image, never resizes it, only lends the pixel format out
+        // immutably for reading and drops it along with the image.
+        let pix = unsafe { img.pixfmt_detached() };
+        let clip_box = RectI {
+            x1: 0,
+            y1: 0,
+            x2: img.width() as i32 - 1,
+            y2: img.height() as i32 - 1,
+        };
+        Canvas {
+            img: img,
+            pix: pix,
+            clip_box: clip_box,
+            ras: RasterizerScanlineAa::new(),
+            sl: ScanlineU8::new(),
+            mtx: TransAffine::new_default(),
+            line_width: 1.0,
+            line_join: LineJoin::Miter,
+            line_cap: LineCap::Butt,
+            miter_limit: 4.0,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.img.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.img.height()
+    }
+
+    pub fn image(&self) -> &ImageBuffer<Pix> {
+        &self.img
+    }
+
+    pub fn into_image(self) -> ImageBuffer<Pix> {
+        self.img
+    }
+
+    // The pixel format attached to the image, e.g. to draw the canvas
+    // onto another one
+    pub fn pixfmt(&self) -> &Pix {
+        &self.pix
+    }
+
+    // The parts of the context, for rendering with scanline renderers or
+    // span generators the canvas doesn't provide. The renderer is clipped
+    // to the clip box of the canvas; changing its clip box doesn't change
+    // the one of the canvas.
+    //
+    //   let (ras, sl, mut ren) = canvas.parts_mut();
+    //   let mut rs = RendererScanlineAA::new_borrowed(&mut ren, &mut sa, &mut sg);
+    //   ras.add_path(&mut path, 0);
+    //   render_scanlines(ras, sl, &mut rs);
+    pub fn parts_mut(
+        &mut self,
+    ) -> (
+        &mut RasterizerScanlineAa,
+        &mut ScanlineU8,
+        RendererBase<'_, PixView<'_, Pix>>,
+    ) {
+        let ren = Self::renderer(&mut self.img, &self.clip_box);
+        (&mut self.ras, &mut self.sl, ren)
+    }
+
+    pub fn ren(&mut self) -> RendererBase<'_, PixView<'_, Pix>> {
+        Self::renderer(&mut self.img, &self.clip_box)
+    }
+
+    fn renderer<'a>(
+        img: &'a mut ImageBuffer<Pix>, clip_box: &RectI,
+    ) -> RendererBase<'a, PixView<'a, Pix>> {
+        let mut ren = RendererBase::new_owned(img.pixfmt());
+        ren.clip_box_naked(clip_box.x1, clip_box.y1, clip_box.x2, clip_box.y2);
+        ren
+    }
+
+    pub fn ras_mut(&mut self) -> &mut RasterizerScanlineAa {
+        &mut self.ras
+    }
+
+    //--------------------------------------------------------------------
+    pub fn clear(&mut self, c: &Pix::C) {
+        self.ren().clear(c);
+    }
+
+    pub fn set_clip_box(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) -> bool {
+        let mut ren = Self::renderer(&mut self.img, &self.clip_box);
+        let visible = ren.set_clip_box(x1, y1, x2, y2);
+        self.clip_box = *ren.clip_box();
+        visible
+    }
+
+    pub fn reset_clipping(&mut self) {
+        let mut ren = Self::renderer(&mut self.img, &self.clip_box);
+        ren.reset_clipping(true);
+        self.clip_box = *ren.clip_box();
+    }
+
+    pub fn set_filling_rule(&mut self, filling_rule: FillingRule) {
+        self.ras.set_filling_rule(filling_rule);
+    }
+
+    pub fn set_gamma<F: GammaFn>(&mut self, gamma_function: &F) {
+        self.ras.set_gamma(gamma_function);
+    }
+
+    //--------------------------------------------------------------------
+    // Transformation applied to all paths
+    pub fn transform(&self) -> &TransAffine {
+        &self.mtx
+    }
+
+    pub fn set_transform(&mut self, mtx: &TransAffine) {
+        self.mtx = *mtx;
+    }
+
+    pub fn reset_transform(&mut self) {
+        self.mtx.reset();
+    }
+
+    //--------------------------------------------------------------------
+    pub fn line_width(&self) -> f64 {
+        self.line_width
+    }
+
+    pub fn set_line_width(&mut self, w: f64) {
+        self.line_width = w;
+    }
+
+    pub fn set_line_join(&mut self, lj: LineJoin) {
+        self.line_join = lj;
+    }
+
+    pub fn set_line_cap(&mut self, lc: LineCap) {
+        self.line_cap = lc;
+    }
+
+    pub fn set_miter_limit(&mut self, ml: f64) {
+        self.miter_limit = ml;
+    }
+
+    //--------------------------------------------------------------------
+    pub fn fill_path<VS: VertexSource>(&mut self, vs: &mut VS, path_id: u32, c: &Pix::C) {
+        let mut curve: ConvCurve<_> = ConvCurve::new_borrowed(vs);
+        curve.set_approximation_scale(self.mtx.scale());
+        let mut trans = ConvTransform::new_owned(curve, self.mtx);
+        self.ras.reset();
+        self.ras.add_path(&mut trans, path_id);
+        let mut ren = Self::renderer(&mut self.img, &self.clip_box);
+        render_scanlines_aa_solid(&mut self.ras, &mut self.sl, &mut ren, c);
+    }
+
+    // Strokes the path with the current line attributes. The line width
+    // is in path units, i.e. it is scaled by the transformation as well.
+    pub fn stroke_path<VS: VertexSource>(&mut self, vs: &mut VS, path_id: u32, c: &Pix::C) {
+        let scale = self.mtx.scale();
+        let mut curve: ConvCurve<_> = ConvCurve::new_borrowed(vs);
+        curve.set_approximation_scale(scale);
+        let mut stroke: ConvStroke<_> = ConvStroke::new_owned(curve);
+        stroke.set_width(self.line_width);
+        stroke.set_line_join(self.line_join);
+        stroke.set_line_cap(self.line_cap);
+        stroke.set_miter_limit(self.miter_limit);
+        stroke.set_approximation_scale(scale);
+        let mut trans = ConvTransform::new_owned(stroke, self.mtx);
+        self.ras.reset();
+        self.ras.add_path(&mut trans, path_id);
+        let mut ren = Self::renderer(&mut self.img, &self.clip_box);
+        render_scanlines_aa_solid(&mut self.ras, &mut self.sl, &mut ren, c);
+    }
+
+    // Blends an image onto the canvas with its top-left corner at (x, y)
+    // in device coordinates; the transformation isn't applied. cover is
+    // the opacity, 0...255.
+    pub fn draw_image<Src: PixFmt<T = Pix::T>>(&mut self, src: &Src, x: i32, y: i32, cover: u32) {
+        self.ren().blend_from(src, None, x, y, cover);
+    }
+
+    //--------------------------------------------------------------------
+    pub fn save_png(&self, fname: &str) -> Result<(), PngError>
+    where
+        Pix::C: ConvertColor,
+    {
+        save_png(self.pixfmt(), fname)
+    }
+
+    // Saves to a BMP, PPM/PGM or TGA file depending on the extension
+    #[cfg(feature = "image_io")]
+    pub fn save_image(&self, fname: &str) -> Result<(), crate::image_io::ImageIoError>
+    where
+        Pix: crate::image_io::ImageIoPixFmt,
+    {
+        crate::image_io::save_image(self.pixfmt(), fname)
+    }
+}
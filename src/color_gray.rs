@@ -159,10 +159,10 @@ impl Color for Gray8 {
     }
 
     fn gradient(&self, c: &Self, k: f64) -> Self {
-        let ik = uround(k * Self::BASE_SCALE as f64) as u32;
+        let ik = uround(k * Self::BASE_SCALE as f64);
         Gray8 {
-            v: (self.v as u32 + (((c.v as u32 - self.v as u32) * ik) >> Self::BASE_SHIFT)) as u8,
-            a: (self.a as u32 + (((c.a as u32 - self.a as u32) * ik) >> Self::BASE_SHIFT)) as u8,
+            v: (self.v as i32 + (((c.v as i32 - self.v as i32) * ik) >> Self::BASE_SHIFT)) as u8,
+            a: (self.a as i32 + (((c.a as i32 - self.a as i32) * ik) >> Self::BASE_SHIFT)) as u8,
         }
     }
     fn no_color() -> Gray8 {
@@ -410,11 +410,11 @@ impl Color for Gray16 {
 
     fn gradient(&self, c: &Gray16, k: f64) -> Self {
         let mut ret = Gray16::new();
-        let ik = uround(k * Self::BASE_SCALE as f64) as u32;
+        let ik = uround(k * Self::BASE_SCALE as f64) as i64;
         ret.v =
-            ((self.v as u32) + (((c.v as u32 - self.v as u32) * ik) >> Self::BASE_SHIFT)) as u16;
+            ((self.v as i64) + (((c.v as i64 - self.v as i64) * ik) >> Self::BASE_SHIFT)) as u16;
         ret.a =
-            ((self.a as u32) + (((c.a as u32 - self.a as u32) * ik) >> Self::BASE_SHIFT)) as u16;
+            ((self.a as i64) + (((c.a as i64 - self.a as i64) * ik) >> Self::BASE_SHIFT)) as u16;
         ret
     }
 }
@@ -423,25 +423,25 @@ impl crate::Color for Rgba8 {
             if c.a == Rgba8::BASE_MASK as u8 {
                 *self = *c;
             } else {
-                cr = (self.r + c.r) as u32;
+                cr = self.r as u32 + c.r as u32;
                 self.r = (if cr > Rgba8::BASE_MASK as u32 {
                     Rgba8::BASE_MASK as u32
                 } else {
                     cr
                 }) as u8;
-                cg = (self.g + c.g) as u32;
+                cg = self.g as u32 + c.g as u32;
                 self.g = (if cg > Rgba8::BASE_MASK as u32 {
                     Rgba8::BASE_MASK as u32
                 } else {
                     cg
                 }) as u8;
-                cb = (self.b + c.b) as u32;
+                cb = self.b as u32 + c.b as u32;
                 self.b = (if cb > Rgba8::BASE_MASK as u32 {
                     Rgba8::BASE_MASK as u32
                 } else {
                     cb
                 }) as u8;
-                ca = (self.a + c.a) as u32;
+                ca = self.a as u32 + c.a as u32;
                 self.a = (if ca > Rgba8::BASE_MASK as u32 {
                     Rgba8::BASE_MASK as u32
                 } else {
@@ -842,25 +842,25 @@ impl crate::Color for Rgba16 {
             if c.a as u32 == Rgba16::BASE_MASK {
                 *self = *c;
             } else {
-                cr = (self.r + c.r) as u32;
+                cr = self.r as u32 + c.r as u32;
                 self.r = if cr as u32 > Rgba16::BASE_MASK {
                     Rgba16::BASE_MASK as u16
                 } else {
                     cr as u16
                 };
-                cg = (self.g + c.g) as u32;
+                cg = self.g as u32 + c.g as u32;
                 self.g = if cg as u32 > Rgba16::BASE_MASK {
                     Rgba16::BASE_MASK as u16
                 } else {
                     cg as u16
                 };
-                cb = (self.b + c.b) as u32;
+                cb = self.b as u32 + c.b as u32;
                 self.b = if cb as u32 > Rgba16::BASE_MASK {
                     Rgba16::BASE_MASK as u16
                 } else {
                     cb as u16
                 };
-                ca = (self.a + c.a) as u32;
+                ca = self.a as u32 + c.a as u32;
                 self.a = if ca as u32 > Rgba16::BASE_MASK {
                     Rgba16::BASE_MASK as u16
                 } else {
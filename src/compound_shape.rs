@@ -0,0 +1,314 @@
+use crate::basics::is_vertex;
+use crate::bounding_rect::bounding_rect;
+use crate::math::calc_distance;
+use crate::math_stroke::{LineCap, LineJoin};
+use crate::path_storage::PathStorage;
+use crate::rasterizer_compound_aa::RasterizerCompoundAa;
+use crate::rasterizer_scanline_aa::RasterizerScanlineAa;
+use crate::rasterizer_sl_clip::RasterizerSlClipDbl;
+use crate::renderer_scanline::{render_scanlines_aa_solid, render_scanlines_compound_layered};
+use crate::scanline_u::ScanlineU8;
+use crate::span_allocator::VecSpan;
+use crate::trans_affine::TransAffine;
+use crate::trans_viewport::{AspectRatio, TransViewport};
+use crate::{
+    Color, ConvCurve, ConvStroke, ConvTransform, RasterScanLine, RasterStyle, Renderer,
+    VertexSource,
+};
+use std::io::{self, BufRead, BufReader};
+use std::ops::Index;
+
+//==============================================================PathStyle
+// The styles of one path of a compound shape: the fills on the left and
+// on the right of it in its direction and the line. -1 stands for none.
+#[derive(Clone, Copy, Debug)]
+pub struct PathStyle {
+    pub path_id: u32,
+    pub left_fill: i32,
+    pub right_fill: i32,
+    pub line: i32,
+}
+
+//==============================================================LineStyle
+// Width in the units of the shape and color of the strokes with the line
+// style of the same index.
+#[derive(Clone, Copy)]
+pub struct LineStyle<C: Color> {
+    pub width: f64,
+    pub color: C,
+}
+
+impl<C: Color> LineStyle<C> {
+    pub fn new(width: f64, color: C) -> Self {
+        LineStyle {
+            width: width,
+            color: color,
+        }
+    }
+}
+
+//==========================================================CompoundShape
+// Paths tagged with fill styles on their left and right and a line style,
+// the way Flash (SWF) describes shapes. The edges shared by two filled
+// areas are stored once, which is what RasterizerCompoundAa expects.
+//
+// read_next() loads the shapes from the text format of the Flash
+// rasterizer examples (shapes.txt):
+//
+//   =======BeginShape
+//   Path <left_fill> <right_fill> <line> <x> <y>
+//   Curve <cx> <cy> <x> <y>
+//   Line <x> <y>
+//   <-------EndPath
+//   !======EndShape
+pub struct CompoundShape {
+    path: PathStorage,
+    styles: Vec<PathStyle>,
+}
+
+impl CompoundShape {
+    pub fn new() -> Self {
+        CompoundShape {
+            path: PathStorage::new(),
+            styles: Vec::new(),
+        }
+    }
+
+    pub fn remove_all(&mut self) {
+        self.path.remove_all();
+        self.styles.clear();
+    }
+
+    // Starts a new path with the given styles, its vertices are added to
+    // path_mut() after that. Returns the index of the path.
+    pub fn begin_path(&mut self, left_fill: i32, right_fill: i32, line: i32) -> usize {
+        let path_id = self.path.start_new_path();
+        self.styles.push(PathStyle {
+            path_id: path_id,
+            left_fill: left_fill,
+            right_fill: right_fill,
+            line: line,
+        });
+        self.styles.len() - 1
+    }
+
+    pub fn add_path<VS: VertexSource>(
+        &mut self, vs: &mut VS, path_id: u32, left_fill: i32, right_fill: i32, line: i32,
+    ) -> usize {
+        let i = self.begin_path(left_fill, right_fill, line);
+        self.path.concat_path(vs, path_id);
+        i
+    }
+
+    pub fn path(&self) -> &PathStorage {
+        &self.path
+    }
+
+    pub fn path_mut(&mut self) -> &mut PathStorage {
+        &mut self.path
+    }
+
+    pub fn paths(&self) -> usize {
+        self.styles.len()
+    }
+
+    pub fn style(&self, i: usize) -> &PathStyle {
+        &self.styles[i]
+    }
+
+    pub fn styles(&self) -> &[PathStyle] {
+        &self.styles
+    }
+
+    // Range of the fill styles in use, None without any fills
+    pub fn fill_range(&self) -> Option<(i32, i32)> {
+        let mut range: Option<(i32, i32)> = None;
+        for s in self.styles.iter() {
+            for &f in [s.left_fill, s.right_fill].iter() {
+                if f >= 0 {
+                    range = Some(match range {
+                        Some((lo, hi)) => (lo.min(f), hi.max(f)),
+                        None => (f, f),
+                    });
+                }
+            }
+        }
+        range
+    }
+
+    // Bounds of all paths including the control points of the curves
+    pub fn bounding_rect(&mut self) -> Option<(f64, f64, f64, f64)> {
+        let ids: Vec<u32> = self.styles.iter().map(|s| s.path_id).collect();
+        let (mut x1, mut y1, mut x2, mut y2) = (0., 0., 0., 0.);
+        let num = ids.len() as u32;
+        if bounding_rect(
+            &mut self.path,
+            ids,
+            0,
+            num,
+            &mut x1,
+            &mut y1,
+            &mut x2,
+            &mut y2,
+        ) {
+            Some((x1, y1, x2, y2))
+        } else {
+            None
+        }
+    }
+
+    // Matrix fitting the shape into a width x height area, centered and
+    // keeping the aspect ratio
+    pub fn viewport(&mut self, width: f64, height: f64) -> TransAffine {
+        match self.bounding_rect() {
+            Some((x1, y1, x2, y2)) if x1 < x2 && y1 < y2 => {
+                let mut vp = TransViewport::new();
+                vp.set_preserve_aspect_ratio(0.5, 0.5, AspectRatio::Meet);
+                vp.set_world_viewport(x1, y1, x2, y2);
+                vp.set_device_viewport(0., 0., width, height);
+                vp.to_affine()
+            }
+            _ => TransAffine::new_default(),
+        }
+    }
+
+    // Index of the first vertex within distance r of (x, y), as used by
+    // PathStorage::modify_vertex()
+    pub fn hit_test_vertex(&self, x: f64, y: f64, r: f64) -> Option<u32> {
+        let (mut vx, mut vy) = (0., 0.);
+        for i in 0..self.path.total_vertices() {
+            let cmd = self.path.vertex_idx(i, &mut vx, &mut vy);
+            if is_vertex(cmd) && calc_distance(x, y, vx, vy) <= r {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    // Replaces the contents with the next shape from the reader. Returns
+    // false at the end of the input.
+    pub fn read_next<R: BufRead>(&mut self, rdr: &mut R) -> io::Result<bool> {
+        self.remove_all();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if rdr.read_line(&mut line)? == 0 {
+                return Ok(false);
+            }
+            if line.starts_with('=') {
+                break;
+            }
+        }
+        loop {
+            line.clear();
+            if rdr.read_line(&mut line)? == 0 || line.starts_with('!') {
+                break;
+            }
+            let mut tok = line.split_whitespace();
+            match tok.next() {
+                Some("Path") => {
+                    let left = parse::<i32>(tok.next())?;
+                    let right = parse::<i32>(tok.next())?;
+                    let ln = parse::<i32>(tok.next())?;
+                    let x = parse::<f64>(tok.next())?;
+                    let y = parse::<f64>(tok.next())?;
+                    self.begin_path(left, right, ln);
+                    self.path.move_to(x, y);
+                }
+                Some("Curve") => {
+                    let cx = parse::<f64>(tok.next())?;
+                    let cy = parse::<f64>(tok.next())?;
+                    let x = parse::<f64>(tok.next())?;
+                    let y = parse::<f64>(tok.next())?;
+                    self.path.curve3_ctrl(cx, cy, x, y);
+                }
+                Some("Line") => {
+                    let x = parse::<f64>(tok.next())?;
+                    let y = parse::<f64>(tok.next())?;
+                    self.path.line_to(x, y);
+                }
+                _ => {}
+            }
+        }
+        Ok(true)
+    }
+}
+
+// Path ids by path index, as render_all_paths() and bounding_rect() take
+impl Index<usize> for CompoundShape {
+    type Output = u32;
+    fn index(&self, i: usize) -> &u32 {
+        &self.styles[i].path_id
+    }
+}
+
+fn parse<T: std::str::FromStr>(s: Option<&str>) -> io::Result<T> {
+    s.and_then(|s| s.parse::<T>().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "bad number in shape data"))
+}
+
+//===================================================load_compound_shapes
+// Reads all shapes of a shapes.txt file
+pub fn load_compound_shapes(fname: &str) -> io::Result<Vec<CompoundShape>> {
+    let mut rdr = BufReader::new(std::fs::File::open(fname)?);
+    let mut shapes = Vec::new();
+    loop {
+        let mut shape = CompoundShape::new();
+        if !shape.read_next(&mut rdr)? {
+            break;
+        }
+        shapes.push(shape);
+    }
+    Ok(shapes)
+}
+
+//==================================================render_compound_shape
+// Renders a compound shape transformed by mtx: the fills in one pass of
+// RasterizerCompoundAa, so that the edges shared by two fills leave no
+// seams, then the strokes of the paths with a line style on top. The fill
+// styles come from the style handler, the line styles are indices into
+// lines; paths with a line style out of that range aren't stroked.
+pub fn render_compound_shape<B: Renderer, SH: RasterStyle<B::C>>(
+    ren: &mut B, shape: &mut CompoundShape, mtx: &TransAffine, fills: &mut SH,
+    lines: &[LineStyle<B::C>],
+) {
+    let scale = mtx.scale();
+    let cb = *ren.bounding_clip_box();
+    let (x1, y1) = (cb.x1 as f64, cb.y1 as f64);
+    let (x2, y2) = (cb.x2 as f64 + 1., cb.y2 as f64 + 1.);
+    let mut sl = ScanlineU8::new();
+
+    let mut curve: ConvCurve<_> = ConvCurve::new_borrowed(&mut shape.path);
+    curve.set_approximation_scale(scale);
+    {
+        let mut trans = ConvTransform::new_borrowed(&mut curve, *mtx);
+        let mut ras: RasterizerCompoundAa<RasterizerSlClipDbl> = RasterizerCompoundAa::new();
+        ras.clip_box(x1, y1, x2, y2);
+        for s in shape.styles.iter() {
+            if s.left_fill >= 0 || s.right_fill >= 0 {
+                ras.set_styles(s.left_fill, s.right_fill);
+                ras.add_path(&mut trans, s.path_id);
+            }
+        }
+        let mut alloc = VecSpan::new();
+        render_scanlines_compound_layered(&mut ras, &mut sl, ren, &mut alloc, fills);
+    }
+
+    let mut stroke: ConvStroke<_> = ConvStroke::new_borrowed(&mut curve);
+    stroke.set_line_join(LineJoin::Round);
+    stroke.set_line_cap(LineCap::Round);
+    stroke.set_approximation_scale(scale);
+    let mut trans = ConvTransform::new_borrowed(&mut stroke, *mtx);
+    let mut ras: RasterizerScanlineAa<RasterizerSlClipDbl> = RasterizerScanlineAa::new();
+    ras.clip_box(x1, y1, x2, y2);
+    for s in shape.styles.iter() {
+        if s.line < 0 || s.line as usize >= lines.len() {
+            continue;
+        }
+        let ls = &lines[s.line as usize];
+        trans.source_mut().set_width(ls.width);
+        ras.reset();
+        ras.add_path(&mut trans, s.path_id);
+        render_scanlines_aa_solid(&mut ras, &mut sl, ren, &ls.color);
+    }
+}
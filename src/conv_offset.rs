@@ -0,0 +1,295 @@
+use crate::basics::{FillingRule, PathCmd, PathFlag, PointD};
+use crate::conv_gpc::GpcOp;
+use crate::math::{calc_distance, cross_product};
+use crate::math_stroke::{LineJoin, MathStroke};
+use crate::poly_bool::{poly_bool, read_contours};
+use crate::vertex_sequence::VertexDist;
+use crate::{Equiv, VertexSource};
+
+#[derive(PartialEq, Eq)]
+enum Status {
+    MoveTo,
+    LineTo,
+    EndPoly,
+}
+
+//==============================================================ConvOffset
+// Offsets the polygons of a path: outwards for a positive offset, inwards
+// for a negative one. Unlike ConvContour the result has no loops or
+// self-intersections, also at concave corners, on curves tighter than the
+// offset and where contours or holes run into each other.
+//
+// The area within the offset distance from the outline is built from a
+// rectangle along every edge and the join at every vertex, calculated
+// by MathStroke. poly_bool() unites these pieces with the polygon, or
+// subtracts them from it. A miter limit of 1.0 gives square joins, cut
+// off at the offset distance from the vertex.
+//
+// Curves have to be flattened before, e.g. with ConvCurve.
+pub struct ConvOffset<'a, VS: VertexSource> {
+    source: Equiv<'a, VS>,
+    stroke: MathStroke<Vec<PointD>>,
+    offset: f64,
+    filling_rule: FillingRule,
+    result: Vec<Vec<PointD>>,
+    status: Status,
+    contour: usize,
+    vertex: usize,
+}
+
+impl<'a, VS: VertexSource> ConvOffset<'a, VS> {
+    pub fn new_owned(source: VS) -> Self {
+        Self::new(Equiv::Own(source))
+    }
+
+    pub fn new_borrowed(source: &'a mut VS) -> Self {
+        Self::new(Equiv::Brw(source))
+    }
+
+    fn new(source: Equiv<'a, VS>) -> Self {
+        ConvOffset {
+            source: source,
+            stroke: MathStroke::new(),
+            offset: 0.,
+            filling_rule: FillingRule::FillNonZero,
+            result: Vec::new(),
+            status: Status::MoveTo,
+            contour: 0,
+            vertex: 0,
+        }
+    }
+
+    pub fn source_mut(&mut self) -> &mut VS {
+        &mut self.source
+    }
+
+    pub fn source(&self) -> &VS {
+        &self.source
+    }
+
+    pub fn set_offset(&mut self, d: f64) {
+        self.offset = d;
+    }
+
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+
+    // Decides which areas of the source path are the polygon
+    pub fn set_filling_rule(&mut self, filling_rule: FillingRule) {
+        self.filling_rule = filling_rule;
+    }
+
+    pub fn filling_rule(&self) -> FillingRule {
+        self.filling_rule
+    }
+
+    pub fn set_line_join(&mut self, lj: LineJoin) {
+        self.stroke.set_line_join(lj);
+    }
+
+    pub fn line_join(&self) -> LineJoin {
+        self.stroke.line_join()
+    }
+
+    pub fn set_miter_limit(&mut self, ml: f64) {
+        self.stroke.set_miter_limit(ml);
+    }
+
+    pub fn miter_limit(&self) -> f64 {
+        self.stroke.miter_limit()
+    }
+
+    pub fn set_approximation_scale(&mut self, a: f64) {
+        self.stroke.set_approximation_scale(a);
+    }
+
+    pub fn approximation_scale(&self) -> f64 {
+        self.stroke.approximation_scale()
+    }
+
+    // Result of the last rewind()
+    pub fn polygons(&self) -> &[Vec<PointD>] {
+        &self.result
+    }
+
+    fn add_pieces(&mut self, c: &[PointD], d: f64, pieces: &mut Vec<Vec<PointD>>) {
+        // Zero-length edges have no direction to offset along, so the
+        // repeated vertices are skipped
+        let mut c = c.to_vec();
+        c.dedup();
+        while c.len() > 1 && c[0] == c[c.len() - 1] {
+            c.pop();
+        }
+        let n = c.len();
+        if n < 3 {
+            return;
+        }
+        // Offset of every edge i -> i+1 to its right, in a y-up system.
+        // The bands and the joins use the same values, so their corners
+        // coincide exactly.
+        let mut len = Vec::with_capacity(n);
+        let mut ofs = Vec::with_capacity(n);
+        for i in 0..n {
+            let (v1, v2) = (c[i], c[(i + 1) % n]);
+            let l = calc_distance(v1.x, v1.y, v2.x, v2.y);
+            len.push(l);
+            ofs.push(((v2.y - v1.y) * d / l, (v1.x - v2.x) * d / l));
+        }
+
+        let mut join: Vec<PointD> = Vec::new();
+        for i in 0..n {
+            let ip = (i + n - 1) % n;
+            let v0 = c[ip];
+            let v1 = c[i];
+            let v2 = c[(i + 1) % n];
+            let (dx, dy) = ofs[i];
+
+            // The band along the edge v1-v2. Its ends run through v1 and
+            // v2, where they meet the sides of the joins.
+            pieces.push(vec![
+                v1,
+                PointD {
+                    x: v1.x + dx,
+                    y: v1.y + dy,
+                },
+                PointD {
+                    x: v2.x + dx,
+                    y: v2.y + dy,
+                },
+                v2,
+                PointD {
+                    x: v2.x - dx,
+                    y: v2.y - dy,
+                },
+                PointD {
+                    x: v1.x - dx,
+                    y: v1.y - dy,
+                },
+            ]);
+
+            // The join on the outer side of the turn at v1, both sides if
+            // the path runs straight or turns back
+            let cp = cross_product(v0.x, v0.y, v1.x, v1.y, v2.x, v2.y);
+            let sides: &[f64] = if cp > 0. {
+                &[-1.]
+            } else if cp < 0. {
+                &[1.]
+            } else {
+                &[1., -1.]
+            };
+            let vd0 = VertexDist::new(v0.x, v0.y);
+            let vd1 = VertexDist::new(v1.x, v1.y);
+            let vd2 = VertexDist::new(v2.x, v2.y);
+            for &s in sides.iter() {
+                self.stroke.set_width(2. * d * s);
+                self.stroke
+                    .calc_join(&mut join, &vd0, &vd1, &vd2, len[ip], len[i]);
+                // A fan from v1 over the ends of both bands, which a join
+                // of nearly collinear edges leaves out
+                let (dx1, dy1) = ofs[ip];
+                let mut piece = Vec::with_capacity(join.len() + 3);
+                piece.push(v1);
+                piece.push(if s > 0. {
+                    PointD {
+                        x: v1.x + dx1,
+                        y: v1.y + dy1,
+                    }
+                } else {
+                    PointD {
+                        x: v1.x - dx1,
+                        y: v1.y - dy1,
+                    }
+                });
+                piece.extend_from_slice(&join);
+                piece.push(if s > 0. {
+                    PointD {
+                        x: v1.x + dx,
+                        y: v1.y + dy,
+                    }
+                } else {
+                    PointD {
+                        x: v1.x - dx,
+                        y: v1.y - dy,
+                    }
+                });
+                pieces.push(piece);
+            }
+        }
+    }
+}
+
+fn area(c: &[PointD]) -> f64 {
+    let mut a = 0.;
+    let mut p = c[c.len() - 1];
+    for q in c.iter() {
+        a += p.x * q.y - q.x * p.y;
+        p = *q;
+    }
+    a * 0.5
+}
+
+impl<'a, VS: VertexSource> VertexSource for ConvOffset<'a, VS> {
+    fn rewind(&mut self, path_id: u32) {
+        let src = read_contours(&mut *self.source, path_id);
+        let polys = poly_bool(&src, &[], GpcOp::Or, self.filling_rule);
+
+        if self.offset == 0. {
+            self.result = polys;
+        } else {
+            let d = self.offset.abs();
+            let mut pieces = Vec::new();
+            for c in polys.iter() {
+                self.add_pieces(c, d, &mut pieces);
+            }
+            // The pieces overlap, so they must all run the same way to be
+            // united by the non-zero rule
+            for p in pieces.iter_mut() {
+                if area(p) < 0. {
+                    p.reverse();
+                }
+            }
+            let op = if self.offset > 0. {
+                GpcOp::Or
+            } else {
+                GpcOp::AMinusB
+            };
+            self.result = poly_bool(&polys, &pieces, op, FillingRule::FillNonZero);
+        }
+        self.status = Status::MoveTo;
+        self.contour = 0;
+        self.vertex = 0;
+    }
+
+    fn vertex(&mut self, x: &mut f64, y: &mut f64) -> u32 {
+        loop {
+            if self.contour >= self.result.len() {
+                return PathCmd::Stop as u32;
+            }
+            let c = &self.result[self.contour];
+            match self.status {
+                Status::MoveTo | Status::LineTo => {
+                    if self.vertex < c.len() {
+                        *x = c[self.vertex].x;
+                        *y = c[self.vertex].y;
+                        self.vertex += 1;
+                        let cmd = if self.status == Status::MoveTo {
+                            PathCmd::MoveTo
+                        } else {
+                            PathCmd::LineTo
+                        };
+                        self.status = Status::LineTo;
+                        return cmd as u32;
+                    }
+                    self.status = Status::EndPoly;
+                }
+                Status::EndPoly => {
+                    self.contour += 1;
+                    self.vertex = 0;
+                    self.status = Status::MoveTo;
+                    return PathCmd::EndPoly as u32 | PathFlag::Close as u32;
+                }
+            }
+        }
+    }
+}
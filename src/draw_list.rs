@@ -0,0 +1,885 @@
+use crate::basics::{is_stop, is_vertex, FillingRule, PathCmd, RectI};
+use crate::gradient_lut::{ColorIp, GradientLut};
+use crate::gsv_text::GsvText;
+use crate::image_accessors::ImageAccessorClip;
+use crate::image_buffer::ImageBuffer;
+use crate::math_stroke::{LineCap, LineJoin};
+use crate::path_storage::PathStorage;
+use crate::pixfmt_rgba::PixRgba32;
+use crate::rasterizer_scanline_aa::RasterizerScanlineAa;
+use crate::renderer_base::RendererBase;
+use crate::renderer_scanline::{render_scanline_aa, render_scanline_aa_solid, render_scanlines};
+use crate::rendering_buffer::RenderBuf;
+use crate::scanline_storage_aa::ScanlineStorageAA8;
+use crate::scanline_u::ScanlineU8;
+use crate::span_allocator::VecSpan;
+use crate::span_gradient::{GradientRadial, GradientX, SpanGradient};
+use crate::span_image_filter_rgba::SpanImageFilterRgbaBilinear;
+use crate::span_interpolator_linear::SpanIpLinear;
+use crate::style_table::GradientShape;
+use crate::trans_affine::TransAffine;
+use crate::{
+    Color, ConvCurve, ConvStroke, ConvTransform, PixFmtOwned, RasterScanLine, Renderer,
+    RendererScanline, Rgba8, Scanline, SpanGenerator, Transformer, VertexSource,
+};
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+//==================================================================Paint
+// What a fill or a stroke is painted with. Gradients and images are
+// added to the list first and referred to by the index returned.
+#[derive(Clone, Copy)]
+pub enum Paint {
+    Solid(Rgba8),
+    Gradient(u32),
+    Image(u32),
+}
+
+impl From<Rgba8> for Paint {
+    fn from(c: Rgba8) -> Self {
+        Paint::Solid(c)
+    }
+}
+
+impl From<&Rgba8> for Paint {
+    fn from(c: &Rgba8) -> Self {
+        Paint::Solid(*c)
+    }
+}
+
+struct Gradient {
+    shape: GradientShape,
+    mtx: TransAffine,
+    d1: f64,
+    d2: f64,
+    stops: Vec<(f64, Rgba8)>,
+}
+
+struct Image {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+    mtx: TransAffine,
+}
+
+// Bytes of an image, which is rendered with an i32 stride and height
+fn image_len(width: u32, height: u32) -> Option<usize> {
+    let stride = i32::try_from(width.checked_mul(4)?).ok()?;
+    i32::try_from(height).ok()?;
+    (stride as usize).checked_mul(height as usize)
+}
+
+#[derive(Clone, Copy)]
+enum DrawOp {
+    Fill(FillingRule),
+    Stroke {
+        width: f64,
+        line_join: LineJoin,
+        line_cap: LineCap,
+        miter_limit: f64,
+    },
+}
+
+#[derive(Clone, Copy)]
+struct DrawCmd {
+    path_id: u32,
+    mtx: TransAffine,
+    paint: Paint,
+    op: DrawOp,
+    // Bounds of the result after mtx, which the viewport maps to the
+    // device rows the command may touch
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+}
+
+// Coverage of a command as rasterized by the last render()
+struct Cached {
+    mtx: TransAffine,
+    frame: RectI,
+    storage: ScanlineStorageAA8,
+}
+
+// Reads a path of the list without moving the iterator of the storage,
+// so that several threads can replay the list at once
+struct PathReader<'a> {
+    path: &'a PathStorage,
+    vertex: u32,
+}
+
+impl<'a> VertexSource for PathReader<'a> {
+    fn rewind(&mut self, path_id: u32) {
+        self.vertex = path_id;
+    }
+
+    fn vertex(&mut self, x: &mut f64, y: &mut f64) -> u32 {
+        if self.vertex >= self.path.total_vertices() {
+            return PathCmd::Stop as u32;
+        }
+        self.vertex += 1;
+        self.path.vertex_idx(self.vertex - 1, x, y)
+    }
+}
+
+//===============================================================DrawList
+// A recorded list of fills and strokes with solid colors, gradients and
+// images. It can be replayed any number of times into renderers of any
+// color type, with a viewport transformation on top of the recorded
+// ones: single-threaded with render(), or split into horizontal bands
+// rendered on several threads with render_tiled(). Both give exactly the
+// same pixels. save() and load() store the list as text.
+//
+// Recording works like Canvas: the transformation and the line
+// attributes in effect when a path is added are stored with it. The
+// vertices are copied, the source isn't needed for replaying.
+//
+// With set_caching(true) render() keeps the coverage of every command in
+// a ScanlineStorageAA and replays it as long as the final transformation
+// and the clip box stay the same, e.g. when the same frame is drawn into
+// another pixel format or again after more commands were added. Filling
+// the cache makes the first render() slower, replaying is faster.
+//
+//   let mut list = DrawList::new();
+//   list.set_transform(&mtx);
+//   list.fill_path(&mut ellipse, 0, &Rgba8::new_params(255, 0, 0, 255));
+//   let grad = list.add_gradient(GradientShape::Linear, &gmtx, 0., 100., &stops);
+//   list.stroke_path(&mut path, 0, Paint::Gradient(grad));
+//   list.render(&mut ren, &TransAffine::trans_affine_scaling_eq(2.));
+pub struct DrawList {
+    path: PathStorage,
+    cmds: Vec<DrawCmd>,
+    gradients: Vec<Gradient>,
+    images: Vec<Image>,
+    cache: Vec<Option<Cached>>,
+    mtx: TransAffine,
+    filling_rule: FillingRule,
+    line_width: f64,
+    line_join: LineJoin,
+    line_cap: LineCap,
+    miter_limit: f64,
+    text_flip: bool,
+    caching: bool,
+}
+
+impl DrawList {
+    pub fn new() -> Self {
+        DrawList {
+            path: PathStorage::new(),
+            cmds: Vec::new(),
+            gradients: Vec::new(),
+            images: Vec::new(),
+            cache: Vec::new(),
+            mtx: TransAffine::new_default(),
+            filling_rule: FillingRule::FillNonZero,
+            line_width: 1.0,
+            line_join: LineJoin::Miter,
+            line_cap: LineCap::Butt,
+            miter_limit: 4.0,
+            text_flip: false,
+            caching: false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.path.remove_all();
+        self.cmds.clear();
+        self.gradients.clear();
+        self.images.clear();
+        self.cache.clear();
+    }
+
+    pub fn set_caching(&mut self, caching: bool) {
+        self.caching = caching;
+        if !caching {
+            self.clear_cache();
+        }
+    }
+
+    pub fn caching(&self) -> bool {
+        self.caching
+    }
+
+    // Frees the rasterizations kept by render()
+    pub fn clear_cache(&mut self) {
+        for c in self.cache.iter_mut() {
+            *c = None;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cmds.len()
+    }
+
+    pub fn transform(&self) -> &TransAffine {
+        &self.mtx
+    }
+
+    pub fn set_transform(&mut self, mtx: &TransAffine) {
+        self.mtx = *mtx;
+    }
+
+    pub fn reset_transform(&mut self) {
+        self.mtx = TransAffine::new_default();
+    }
+
+    pub fn set_filling_rule(&mut self, filling_rule: FillingRule) {
+        self.filling_rule = filling_rule;
+    }
+
+    pub fn line_width(&self) -> f64 {
+        self.line_width
+    }
+
+    pub fn set_line_width(&mut self, w: f64) {
+        self.line_width = w;
+    }
+
+    pub fn set_line_join(&mut self, lj: LineJoin) {
+        self.line_join = lj;
+    }
+
+    pub fn set_line_cap(&mut self, lc: LineCap) {
+        self.line_cap = lc;
+    }
+
+    pub fn set_miter_limit(&mut self, ml: f64) {
+        self.miter_limit = ml;
+    }
+
+    // Draws text() top down, for coordinates with the y axis pointing
+    // down
+    pub fn set_text_flip(&mut self, flip: bool) {
+        self.text_flip = flip;
+    }
+
+    //--------------------------------------------------------------------
+    // A gradient with the given color stops at offsets 0...1. mtx maps it
+    // onto the paths it paints, before their own transformation. A linear
+    // gradient runs along the x axis from d1 to d2, a radial one around
+    // the origin.
+    pub fn add_gradient(
+        &mut self, shape: GradientShape, mtx: &TransAffine, d1: f64, d2: f64,
+        stops: &[(f64, Rgba8)],
+    ) -> u32 {
+        self.gradients.push(Gradient {
+            shape: shape,
+            mtx: *mtx,
+            d1: d1,
+            d2: d2,
+            stops: stops.to_vec(),
+        });
+        self.gradients.len() as u32 - 1
+    }
+
+    // An image of premultiplied RGBA pixels, 4 bytes each, row by row. mtx
+    // maps the area from (0,0) to (width,height) onto the paths it paints,
+    // outside of it the image is transparent. Returns None if the data
+    // doesn't have the size of the image or the image is too large.
+    pub fn add_image(
+        &mut self, width: u32, height: u32, data: &[u8], mtx: &TransAffine,
+    ) -> Option<u32> {
+        if image_len(width, height)? != data.len() {
+            return None;
+        }
+        self.images.push(Image {
+            width: width,
+            height: height,
+            data: data.to_vec(),
+            mtx: *mtx,
+        });
+        Some(self.images.len() as u32 - 1)
+    }
+
+    //--------------------------------------------------------------------
+    pub fn fill_path<VS: VertexSource, P: Into<Paint>>(
+        &mut self, vs: &mut VS, path_id: u32, paint: P,
+    ) {
+        self.add(vs, path_id, paint.into(), DrawOp::Fill(self.filling_rule));
+    }
+
+    // Strokes the path with the current line attributes. The line width
+    // is in path units, i.e. it is scaled by the transformation as well.
+    pub fn stroke_path<VS: VertexSource, P: Into<Paint>>(
+        &mut self, vs: &mut VS, path_id: u32, paint: P,
+    ) {
+        let op = DrawOp::Stroke {
+            width: self.line_width,
+            line_join: self.line_join,
+            line_cap: self.line_cap,
+            miter_limit: self.miter_limit,
+        };
+        self.add(vs, path_id, paint.into(), op);
+    }
+
+    // Text in the built-in vector font of GsvText, starting at (x, y) on
+    // the base line, stroked with the current line attributes
+    pub fn text<P: Into<Paint>>(&mut self, x: f64, y: f64, height: f64, text: &str, paint: P) {
+        let mut t = GsvText::new();
+        t.set_size(height, 0.);
+        t.set_flip(self.text_flip);
+        t.set_start_point(x, y);
+        t.set_text(text);
+        self.stroke_path(&mut t, 0, paint);
+    }
+
+    fn add<VS: VertexSource>(&mut self, vs: &mut VS, path_id: u32, paint: Paint, op: DrawOp) {
+        let start = self.path.start_new_path();
+        self.path.concat_path(vs, path_id);
+        self.push(start, self.mtx, paint, op);
+    }
+
+    fn push(&mut self, path_id: u32, mtx: TransAffine, paint: Paint, op: DrawOp) {
+        // The bounds are taken from the transformed control points, which
+        // enclose the curves, widened by how far a stroke can get from
+        // the path
+        let ext = match op {
+            DrawOp::Fill(_) => 0.,
+            DrawOp::Stroke {
+                width, miter_limit, ..
+            } => width.abs() * 0.5 * miter_limit.max(std::f64::consts::SQRT_2) * norm(&mtx),
+        };
+        let (mut x1, mut y1, mut x2, mut y2) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+        let (mut x, mut y) = (0., 0.);
+        for i in path_id..self.path.total_vertices() {
+            let cmd = self.path.vertex_idx(i, &mut x, &mut y);
+            if is_stop(cmd) {
+                break;
+            }
+            if is_vertex(cmd) {
+                mtx.transform(&mut x, &mut y);
+                x1 = x1.min(x);
+                y1 = y1.min(y);
+                x2 = x2.max(x);
+                y2 = y2.max(y);
+            }
+        }
+        if x1 > x2 {
+            return;
+        }
+        self.cmds.push(DrawCmd {
+            path_id: path_id,
+            mtx: mtx,
+            paint: paint,
+            op: op,
+            x1: x1 - ext,
+            y1: y1 - ext,
+            x2: x2 + ext,
+            y2: y2 + ext,
+        });
+        self.cache.push(None);
+    }
+
+    //--------------------------------------------------------------------
+    pub fn render<R: Renderer>(&mut self, ren: &mut R, viewport: &TransAffine)
+    where
+        R::C: From<Rgba8>,
+    {
+        let frame = *ren.bounding_clip_box();
+        let mut ras = RasterizerScanlineAa::new();
+        let mut sl = ScanlineU8::new();
+        set_frame(&mut ras, &frame);
+        let mut cache = std::mem::take(&mut self.cache);
+        for (cmd, cached) in self.cmds.iter().zip(cache.iter_mut()) {
+            let (y1, y2) = rows(cmd, viewport);
+            if y2 < frame.y1 || y1 > frame.y2 {
+                continue;
+            }
+            let mut mtx = cmd.mtx;
+            mtx *= *viewport;
+            if !self.caching {
+                self.rasterize(&mut ras, cmd, &mtx);
+                if ras.rewind_scanlines() {
+                    sl.reset(ras.min_x(), ras.max_x());
+                    self.paint_rows(&mut ras, &mut sl, ren, cmd.paint, &mtx, frame.y2);
+                }
+                continue;
+            }
+            let hit = match cached {
+                Some(c) => {
+                    let f = &c.frame;
+                    c.mtx == mtx
+                        && (f.x1, f.y1, f.x2, f.y2) == (frame.x1, frame.y1, frame.x2, frame.y2)
+                }
+                None => false,
+            };
+            if !hit {
+                let mut storage = ScanlineStorageAA8::new();
+                storage.prepare();
+                self.rasterize(&mut ras, cmd, &mtx);
+                render_scanlines(&mut ras, &mut sl, &mut storage);
+                *cached = Some(Cached {
+                    mtx: mtx,
+                    frame: frame,
+                    storage: storage,
+                });
+            }
+            let storage = &mut cached.as_mut().unwrap().storage;
+            if storage.rewind_scanlines() {
+                sl.reset(storage.min_x(), storage.max_x());
+                self.paint_rows(storage, &mut sl, ren, cmd.paint, &mtx, frame.y2);
+            }
+        }
+        self.cache = cache;
+    }
+
+    // Renders into the image in bands of band_height rows on the given
+    // number of threads, 0 for one per core.
+    //
+    // Every band renders the commands whose rows it overlaps, with the
+    // renderer clipped to the band. The rasterizer keeps the clip box of
+    // the whole buffer: clipping it to the band would move the vertices of
+    // the edges crossing the band borders and change the coverage there by
+    // rounding.
+    pub fn render_tiled<Pix: PixFmtOwned>(
+        &self, img: &mut ImageBuffer<Pix>, viewport: &TransAffine, band_height: u32, threads: u32,
+    ) where
+        Pix::C: From<Rgba8>,
+    {
+        let (w, h) = (img.width() as i32, img.height() as i32);
+        if w <= 0 || h <= 0 {
+            return;
+        }
+        let band_height = band_height.max(1) as i32;
+        let bands = ((h + band_height - 1) / band_height) as u32;
+        let threads = if threads == 0 {
+            std::thread::available_parallelism().map_or(1, |n| n.get() as u32)
+        } else {
+            threads
+        };
+        let frame = RectI {
+            x1: 0,
+            y1: 0,
+            x2: w - 1,
+            y2: h - 1,
+        };
+
+        // The bands are handed out one by one, so that the threads stay
+        // busy when some bands hold more than others
+        let next = AtomicU32::new(0);
+        let buf = SharedBuf(*img.rbuf());
+        std::thread::scope(|s| {
+            for _ in 0..threads.min(bands) {
+                s.spawn(|| {
+                    let buf = &buf;
+                    let mut ren = RendererBase::new_owned(Pix::new_from_rbuf(buf.0));
+                    let mut ras = RasterizerScanlineAa::new();
+                    let mut sl = ScanlineU8::new();
+                    set_frame(&mut ras, &frame);
+                    loop {
+                        let band = next.fetch_add(1, Ordering::Relaxed);
+                        if band >= bands {
+                            break;
+                        }
+                        let y1 = band as i32 * band_height;
+                        let y2 = (y1 + band_height - 1).min(h - 1);
+                        ren.set_clip_box(0, y1, w - 1, y2);
+                        self.render_rows(&mut ras, &mut sl, &mut ren, viewport, y1, y2);
+                    }
+                });
+            }
+        });
+    }
+
+    fn render_rows<R: Renderer>(
+        &self, ras: &mut RasterizerScanlineAa, sl: &mut ScanlineU8, ren: &mut R,
+        viewport: &TransAffine, y1: i32, y2: i32,
+    ) where
+        R::C: From<Rgba8>,
+    {
+        for cmd in self.cmds.iter() {
+            let (cy1, cy2) = rows(cmd, viewport);
+            if cy2 < y1 || cy1 > y2 {
+                continue;
+            }
+            let mut mtx = cmd.mtx;
+            mtx *= *viewport;
+            self.rasterize(ras, cmd, &mtx);
+            if ras.rewind_scanlines() && ras.navigate_scanline(y1.max(ras.min_y())) {
+                sl.reset(ras.min_x(), ras.max_x());
+                self.paint_rows(ras, sl, ren, cmd.paint, &mtx, y2);
+            }
+        }
+    }
+
+    fn rasterize(&self, ras: &mut RasterizerScanlineAa, cmd: &DrawCmd, mtx: &TransAffine) {
+        let mut path = PathReader {
+            path: &self.path,
+            vertex: 0,
+        };
+        let scale = mtx.scale();
+        let mut curve: ConvCurve<_> = ConvCurve::new_borrowed(&mut path);
+        curve.set_approximation_scale(scale);
+        ras.reset();
+        match cmd.op {
+            DrawOp::Fill(filling_rule) => {
+                let mut trans = ConvTransform::new_owned(curve, *mtx);
+                ras.set_filling_rule(filling_rule);
+                ras.add_path(&mut trans, cmd.path_id);
+            }
+            DrawOp::Stroke {
+                width,
+                line_join,
+                line_cap,
+                miter_limit,
+            } => {
+                let mut stroke: ConvStroke<_> = ConvStroke::new_owned(curve);
+                stroke.set_width(width);
+                stroke.set_line_join(line_join);
+                stroke.set_line_cap(line_cap);
+                stroke.set_miter_limit(miter_limit);
+                stroke.set_approximation_scale(scale);
+                let mut trans = ConvTransform::new_owned(stroke, *mtx);
+                ras.set_filling_rule(FillingRule::FillNonZero);
+                ras.add_path(&mut trans, cmd.path_id);
+            }
+        }
+    }
+
+    // Paints the scanlines the source gives after rewinding up to row y2.
+    // mtx is the transformation of the command, which the gradients and
+    // images follow.
+    fn paint_rows<RS: RasterScanLine, R: Renderer>(
+        &self, src: &mut RS, sl: &mut ScanlineU8, ren: &mut R, paint: Paint, mtx: &TransAffine,
+        y2: i32,
+    ) where
+        R::C: From<Rgba8>,
+    {
+        match paint {
+            Paint::Solid(c) => {
+                let c = R::C::from(c);
+                while src.sweep_scanline(sl) && sl.y() <= y2 {
+                    render_scanline_aa_solid(sl, ren, &c);
+                }
+            }
+            Paint::Gradient(i) => {
+                let g = match self.gradients.get(i as usize) {
+                    Some(g) => g,
+                    None => return,
+                };
+                let mut lut = GradientLut::<ColorIp<R::C>, 256>::new();
+                for &(offset, c) in g.stops.iter() {
+                    lut.add_color(offset, R::C::from(c));
+                }
+                lut.build_lut();
+                let mut m = g.mtx;
+                m *= *mtx;
+                m.invert();
+                let mut ip: SpanIpLinear<TransAffine> = SpanIpLinear::new(m);
+                match g.shape {
+                    GradientShape::Linear => {
+                        let mut gf = GradientX;
+                        let mut sg = SpanGradient::new(&mut ip, &mut gf, &mut lut, g.d1, g.d2);
+                        sweep_rows(src, sl, ren, &mut sg, y2);
+                    }
+                    GradientShape::Radial => {
+                        let mut gf = GradientRadial;
+                        let mut sg = SpanGradient::new(&mut ip, &mut gf, &mut lut, g.d1, g.d2);
+                        sweep_rows(src, sl, ren, &mut sg, y2);
+                    }
+                }
+            }
+            Paint::Image(i) => {
+                let img = match self.images.get(i as usize) {
+                    Some(img) => img,
+                    None => return,
+                };
+                // The image is only read from
+                let rbuf = RenderBuf::new(
+                    img.data.as_ptr() as *mut u8,
+                    img.width,
+                    img.height,
+                    img.width as i32 * 4,
+                );
+                let mut src_img = ImageAccessorClip::new(
+                    PixRgba32::new_from_rbuf(rbuf),
+                    &Rgba8::new_params(0, 0, 0, 0),
+                );
+                let mut m = img.mtx;
+                m *= *mtx;
+                m.invert();
+                let mut ip: SpanIpLinear<TransAffine> = SpanIpLinear::new(m);
+                let sg = SpanImageFilterRgbaBilinear::new(&mut src_img, &mut ip);
+                let mut conv = SpanRgba8Conv {
+                    sg: sg,
+                    buf: Vec::new(),
+                    _c: std::marker::PhantomData,
+                };
+                sweep_rows(src, sl, ren, &mut conv, y2);
+            }
+        }
+    }
+
+    //--------------------------------------------------------------------
+    // Writes the list as text, which load() reads back
+    pub fn save<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "agg_draw_list 1")?;
+        let n = self.path.total_vertices();
+        writeln!(w, "vertices {}", n)?;
+        let (mut x, mut y) = (0., 0.);
+        for i in 0..n {
+            let cmd = self.path.vertex_idx(i, &mut x, &mut y);
+            writeln!(w, "{} {} {}", cmd, x, y)?;
+        }
+        writeln!(w, "gradients {}", self.gradients.len())?;
+        for g in self.gradients.iter() {
+            let shape = match g.shape {
+                GradientShape::Linear => "linear",
+                GradientShape::Radial => "radial",
+            };
+            write!(w, "{} {} {} ", shape, g.d1, g.d2)?;
+            write_mtx(w, &g.mtx)?;
+            write!(w, " {}", g.stops.len())?;
+            for &(offset, c) in g.stops.iter() {
+                write!(w, " {} {} {} {} {}", offset, c.r, c.g, c.b, c.a)?;
+            }
+            writeln!(w)?;
+        }
+        writeln!(w, "images {}", self.images.len())?;
+        for img in self.images.iter() {
+            write!(w, "{} {} ", img.width, img.height)?;
+            write_mtx(w, &img.mtx)?;
+            write!(w, " ")?;
+            for b in img.data.iter() {
+                write!(w, "{:02x}", b)?;
+            }
+            writeln!(w)?;
+        }
+        writeln!(w, "commands {}", self.cmds.len())?;
+        for cmd in self.cmds.iter() {
+            match cmd.op {
+                DrawOp::Fill(_) => write!(w, "fill ")?,
+                DrawOp::Stroke { .. } => write!(w, "stroke ")?,
+            }
+            write!(w, "{} ", cmd.path_id)?;
+            write_mtx(w, &cmd.mtx)?;
+            match cmd.paint {
+                Paint::Solid(c) => write!(w, " solid {} {} {} {}", c.r, c.g, c.b, c.a)?,
+                Paint::Gradient(i) => write!(w, " gradient {}", i)?,
+                Paint::Image(i) => write!(w, " image {}", i)?,
+            }
+            match cmd.op {
+                DrawOp::Fill(FillingRule::FillNonZero) => writeln!(w, " nonzero")?,
+                DrawOp::Fill(FillingRule::FillEvenOdd) => writeln!(w, " evenodd")?,
+                DrawOp::Stroke {
+                    width,
+                    line_join,
+                    line_cap,
+                    miter_limit,
+                } => writeln!(
+                    w,
+                    " {} {} {} {}",
+                    width, line_join as u32, line_cap as u32, miter_limit
+                )?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load<R: BufRead>(rdr: &mut R) -> io::Result<DrawList> {
+        let mut text = String::new();
+        rdr.read_to_string(&mut text)?;
+        let mut tok = text.split_whitespace();
+        let mut list = DrawList::new();
+
+        if tok.next() != Some("agg_draw_list") || parse::<u32>(tok.next())? != 1 {
+            return Err(bad_data("not a draw list"));
+        }
+        expect(&mut tok, "vertices")?;
+        for _ in 0..parse::<u32>(tok.next())? {
+            let cmd = parse::<u32>(tok.next())?;
+            let x = parse::<f64>(tok.next())?;
+            let y = parse::<f64>(tok.next())?;
+            list.path.add_vertex(x, y, cmd);
+        }
+        expect(&mut tok, "gradients")?;
+        for _ in 0..parse::<u32>(tok.next())? {
+            let shape = match tok.next() {
+                Some("linear") => GradientShape::Linear,
+                Some("radial") => GradientShape::Radial,
+                _ => return Err(bad_data("bad gradient shape")),
+            };
+            let d1 = parse::<f64>(tok.next())?;
+            let d2 = parse::<f64>(tok.next())?;
+            let mtx = parse_mtx(&mut tok)?;
+            let mut stops = Vec::new();
+            for _ in 0..parse::<u32>(tok.next())? {
+                let offset = parse::<f64>(tok.next())?;
+                stops.push((offset, parse_rgba8(&mut tok)?));
+            }
+            list.add_gradient(shape, &mtx, d1, d2, &stops);
+        }
+        expect(&mut tok, "images")?;
+        for _ in 0..parse::<u32>(tok.next())? {
+            let width = parse::<u32>(tok.next())?;
+            let height = parse::<u32>(tok.next())?;
+            let mtx = parse_mtx(&mut tok)?;
+            let hex = tok.next().unwrap_or("").as_bytes();
+            match image_len(width, height).and_then(|len| len.checked_mul(2)) {
+                Some(len) if len == hex.len() => {}
+                _ => return Err(bad_data("bad image size")),
+            }
+            let mut data = Vec::with_capacity(hex.len() / 2);
+            for b in hex.chunks(2) {
+                let s = std::str::from_utf8(b).map_err(|_| bad_data("bad image data"))?;
+                data.push(u8::from_str_radix(s, 16).map_err(|_| bad_data("bad image data"))?);
+            }
+            list.add_image(width, height, &data, &mtx)
+                .ok_or_else(|| bad_data("bad image size"))?;
+        }
+        expect(&mut tok, "commands")?;
+        for _ in 0..parse::<u32>(tok.next())? {
+            let kind = tok.next();
+            let path_id = parse::<u32>(tok.next())?;
+            let mtx = parse_mtx(&mut tok)?;
+            let paint = match tok.next() {
+                Some("solid") => Paint::Solid(parse_rgba8(&mut tok)?),
+                Some("gradient") => Paint::Gradient(parse::<u32>(tok.next())?),
+                Some("image") => Paint::Image(parse::<u32>(tok.next())?),
+                _ => return Err(bad_data("bad paint")),
+            };
+            let op = match kind {
+                Some("fill") => DrawOp::Fill(match tok.next() {
+                    Some("nonzero") => FillingRule::FillNonZero,
+                    Some("evenodd") => FillingRule::FillEvenOdd,
+                    _ => return Err(bad_data("bad filling rule")),
+                }),
+                Some("stroke") => DrawOp::Stroke {
+                    width: parse::<f64>(tok.next())?,
+                    line_join: match parse::<u32>(tok.next())? {
+                        0 => LineJoin::Miter,
+                        1 => LineJoin::MiterRevert,
+                        2 => LineJoin::Round,
+                        3 => LineJoin::Bevel,
+                        4 => LineJoin::MiterRound,
+                        _ => return Err(bad_data("bad line join")),
+                    },
+                    line_cap: match parse::<u32>(tok.next())? {
+                        0 => LineCap::Butt,
+                        1 => LineCap::Square,
+                        2 => LineCap::Round,
+                        _ => return Err(bad_data("bad line cap")),
+                    },
+                    miter_limit: parse::<f64>(tok.next())?,
+                },
+                _ => return Err(bad_data("bad command")),
+            };
+            if path_id >= list.path.total_vertices() {
+                return Err(bad_data("bad path id"));
+            }
+            list.push(path_id, mtx, paint, op);
+        }
+        Ok(list)
+    }
+}
+
+// Sweeps the scanlines up to row y2 through a span generator
+fn sweep_rows<RS: RasterScanLine, R: Renderer, SG: SpanGenerator<C = R::C>>(
+    src: &mut RS, sl: &mut ScanlineU8, ren: &mut R, sg: &mut SG, y2: i32,
+) {
+    let mut alloc = VecSpan::new();
+    sg.prepare();
+    while src.sweep_scanline(sl) && sl.y() <= y2 {
+        render_scanline_aa(sl, ren, &mut alloc, sg);
+    }
+}
+
+// Generates the colors of the images as Rgba8 and converts them to the
+// color type of the renderer
+struct SpanRgba8Conv<SG: SpanGenerator<C = Rgba8>, C: Color> {
+    sg: SG,
+    buf: Vec<Rgba8>,
+    _c: std::marker::PhantomData<C>,
+}
+
+impl<SG: SpanGenerator<C = Rgba8>, C: Color + From<Rgba8>> SpanGenerator for SpanRgba8Conv<SG, C> {
+    type C = C;
+
+    fn prepare(&mut self) {
+        self.sg.prepare();
+    }
+
+    fn generate(&mut self, span: &mut [C], x: i32, y: i32, len: u32) {
+        self.buf.resize(len as usize, Rgba8::new());
+        self.sg.generate(&mut self.buf, x, y, len);
+        for (d, s) in span.iter_mut().zip(self.buf.iter()) {
+            *d = C::from(*s);
+        }
+    }
+}
+
+// The threads render into disjoint bands of the same buffer
+struct SharedBuf(RenderBuf);
+unsafe impl Sync for SharedBuf {}
+
+fn set_frame(ras: &mut RasterizerScanlineAa, frame: &RectI) {
+    ras.clip_box(
+        frame.x1 as f64,
+        frame.y1 as f64,
+        frame.x2 as f64 + 1.,
+        frame.y2 as f64 + 1.,
+    );
+}
+
+// Device rows a command may touch under the viewport
+fn rows(cmd: &DrawCmd, viewport: &TransAffine) -> (i32, i32) {
+    let (mut y1, mut y2) = (f64::MAX, f64::MIN);
+    for &(x, y) in [
+        (cmd.x1, cmd.y1),
+        (cmd.x2, cmd.y1),
+        (cmd.x2, cmd.y2),
+        (cmd.x1, cmd.y2),
+    ]
+    .iter()
+    {
+        let (mut x, mut y) = (x, y);
+        viewport.transform(&mut x, &mut y);
+        y1 = y1.min(y);
+        y2 = y2.max(y);
+    }
+    (
+        (y1.floor() as i32).saturating_sub(1),
+        (y2.ceil() as i32).saturating_add(1),
+    )
+}
+
+// An upper bound of how much the matrix stretches a distance
+fn norm(m: &TransAffine) -> f64 {
+    (m.sx * m.sx + m.shx * m.shx + m.shy * m.shy + m.sy * m.sy).sqrt()
+}
+
+fn write_mtx<W: Write>(w: &mut W, m: &TransAffine) -> io::Result<()> {
+    write!(w, "{} {} {} {} {} {}", m.sx, m.shy, m.shx, m.sy, m.tx, m.ty)
+}
+
+fn bad_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn expect<'a, I: Iterator<Item = &'a str>>(tok: &mut I, word: &str) -> io::Result<()> {
+    if tok.next() != Some(word) {
+        return Err(bad_data(&format!("{} expected", word)));
+    }
+    Ok(())
+}
+
+fn parse<T: std::str::FromStr>(s: Option<&str>) -> io::Result<T> {
+    s.and_then(|s| s.parse::<T>().ok())
+        .ok_or_else(|| bad_data("bad number in draw list"))
+}
+
+fn parse_mtx<'a, I: Iterator<Item = &'a str>>(tok: &mut I) -> io::Result<TransAffine> {
+    let mut v = [0.; 6];
+    for e in v.iter_mut() {
+        *e = parse::<f64>(tok.next())?;
+    }
+    Ok(TransAffine::new(v[0], v[1], v[2], v[3], v[4], v[5]))
+}
+
+fn parse_rgba8<'a, I: Iterator<Item = &'a str>>(tok: &mut I) -> io::Result<Rgba8> {
+    let mut v = [0u32; 4];
+    for e in v.iter_mut() {
+        *e = parse::<u8>(tok.next())? as u32;
+    }
+    Ok(Rgba8::new_params(v[0], v[1], v[2], v[3]))
+}
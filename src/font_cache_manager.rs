@@ -1,4 +1,4 @@
-use crate::basics::RectI;
+use crate::basics::{crc32, RectI};
 use crate::FontEngine;
 use std::ptr::null;
 
@@ -8,6 +8,8 @@ pub enum GlyphDataType {
     Mono = 1,
     Gray8 = 2,
     Outline = 3,
+    // COLR layers or a color bitmap, see ColorGlyph
+    Color = 4,
 }
 
 #[derive(Clone)]
@@ -341,3 +343,11 @@ impl<F: FontEngine> FontCacheManager<F> {
         }
     }
 }
+
+pub(crate) fn calc_crc32(buf: &[u8], size: usize) -> u32 {
+    crc32(&[&buf[..size]])
+}
+
+pub(crate) fn dbl_to_plain_fx(d: f64) -> i32 {
+    (d * 65536.0) as i32
+}
@@ -1,20 +1,31 @@
 use freetype_sys::{
-    FT_Attach_File, FT_Bitmap, FT_Done_Face, FT_Done_FreeType, FT_Encoding, FT_Face,
-    FT_Get_Char_Index, FT_Get_Kerning, FT_Init_FreeType, FT_Library, FT_Load_Glyph, FT_New_Face,
-    FT_New_Memory_Face, FT_Outline, FT_Render_Glyph, FT_Select_Charmap, FT_Set_Char_Size,
-    FT_Set_Pixel_Sizes, FT_Vector, FT_ENCODING_NONE, FT_HAS_KERNING, FT_IS_SCALABLE,
-    FT_KERNING_DEFAULT, FT_LOAD_DEFAULT, FT_LOAD_NO_HINTING, FT_RENDER_MODE_MONO,
-    FT_RENDER_MODE_NORMAL,
+    FT_Attach_File, FT_Bitmap, FT_Done_Face, FT_Done_FreeType, FT_Done_MM_Var, FT_Encoding,
+    FT_Error, FT_Face, FT_Fixed, FT_Get_Char_Index, FT_Get_Color_Glyph_Layer, FT_Get_Kerning,
+    FT_Get_MM_Var, FT_Get_Var_Blend_Coordinates, FT_Get_Var_Design_Coordinates,
+    FT_Init_FreeType, FT_LayerIterator, FT_Library, FT_Load_Glyph, FT_Load_Sfnt_Table,
+    FT_MM_Var, FT_New_Face, FT_New_Memory_Face, FT_Outline, FT_Render_Glyph,
+    FT_Select_Charmap, FT_Select_Size, FT_Set_Char_Size, FT_Set_Named_Instance,
+    FT_Set_Pixel_Sizes, FT_Set_Var_Design_Coordinates, FT_Vector, FT_ENCODING_NONE,
+    FT_FACE_FLAG_COLOR, FT_FACE_FLAG_MULTIPLE_MASTERS, FT_FACE_FLAG_SCALABLE,
+    FT_GLYPH_FORMAT_BITMAP, FT_GLYPH_FORMAT_OUTLINE, FT_HAS_KERNING, FT_IS_SCALABLE,
+    FT_KERNING_DEFAULT, FT_LOAD_COLOR, FT_LOAD_DEFAULT, FT_LOAD_NO_HINTING,
+    FT_PIXEL_MODE_BGRA, FT_RENDER_MODE_MONO, FT_RENDER_MODE_NORMAL,
 };
+use std::ffi::CStr;
 use std::marker::PhantomData;
 use std::ops::Neg;
 use std::os::raw::{c_long, c_ulong};
 use std::ptr::null_mut;
+use std::rc::Rc;
 
 use crate::basics::{CoverScale, RectI};
 use crate::bitset_iterator::BitsetIterator;
 use crate::conv_curve::ConvCurve;
-use crate::font_cache_manager::{GlyphDataType, GlyphRender};
+use crate::font_cache_manager::{calc_crc32, dbl_to_plain_fx, GlyphDataType, GlyphRender};
+use crate::glyph_color::{
+    add_color_layer, begin_color_layers, color_bitmap, scale_bgra, ColorPalettes,
+};
+use crate::gpos::GposKerning;
 use crate::path_storage_integer::PathStorageInteger;
 use crate::rasterizer_scanline_aa::{AaScale, RasterizerScanlineAa};
 use crate::renderer_scanline::render_scanlines;
@@ -61,6 +72,16 @@ impl<'a> FontEngine for FreetypeBase<'a, i16> {
     }
 }
 
+// An axis of a variable font, in design coordinates
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariationAxis {
+    pub tag: [u8; 4],
+    pub name: String,
+    pub minimum: f64,
+    pub default: f64,
+    pub maximum: f64,
+}
+
 //-----------------------------------------------FreetypeBase
 pub struct FreetypeBase<'a, T: AggInteger + Neg> {
     m_flag32: bool,
@@ -79,9 +100,18 @@ pub struct FreetypeBase<'a, T: AggInteger + Neg> {
     m_library: FT_Library,
     m_faces: Vec<FT_Face>,
     m_face_names: Vec<String>,
+    m_face_kerning: Vec<Option<Rc<GposKerning>>>,
+    m_face_palettes: Vec<Option<Rc<ColorPalettes>>>,
     m_num_faces: u32,
     m_max_faces: u32,
     m_cur_face: FT_Face,
+    m_cur_kerning: Option<Rc<GposKerning>>,
+    // Normalized axis coordinates of the current face, for GPOS deltas
+    m_var_coords: Vec<f64>,
+    m_cur_palettes: Option<Rc<ColorPalettes>>,
+    m_color_glyphs: bool,
+    m_palette: u32,
+    m_color_data: Vec<u8>,
     m_resolution: u32,
     m_glyph_rendering: GlyphRender,
     m_glyph_index: u32,
@@ -115,6 +145,15 @@ impl<'a,T: AggInteger + Neg> FontEngineBase for FreetypeBase<'a,T> {
     fn change_stamp(&self) -> i32 {
         self.m_change_stamp
     }
+    fn ascender(&self) -> f64 {
+        FreetypeBase::ascender(self)
+    }
+    fn descender(&self) -> f64 {
+        FreetypeBase::descender(self)
+    }
+    fn flip_y(&self) -> bool {
+        self.m_flip_y
+    }
     fn glyph_index(&self) -> u32 {
         self.m_glyph_index
     }
@@ -152,6 +191,9 @@ impl<'a,T: AggInteger + Neg> FontEngineBase for FreetypeBase<'a,T> {
 
     fn prepare_glyph(&mut self, glyph_code: u32) -> bool {
         self.m_glyph_index = unsafe { FT_Get_Char_Index(self.m_cur_face, glyph_code as c_ulong) };
+        if self.m_color_glyphs && self.prepare_color_glyph() {
+            return true;
+        }
         self.m_last_error = unsafe {
             FT_Load_Glyph(
                 self.m_cur_face,
@@ -413,14 +455,26 @@ impl<'a,T: AggInteger + Neg> FontEngineBase for FreetypeBase<'a,T> {
                     self.m_curves16.source_mut().serialize(data);
                 }
             }
+            GlyphDataType::Color => data.copy_from_slice(&self.m_color_data[..data.len()]),
             GlyphDataType::Invalid => (),
         }
     }
 
     fn add_kerning(&self, first: u32, second: u32, x: &mut f64, y: &mut f64) -> bool {
-        if !self.m_cur_face.is_null() {
-            if first != 0 && second != 0 && FT_HAS_KERNING(self.m_cur_face) != false {
-                let mut delta = FT_Vector { x: 0, y: 0 };
+        if !self.m_cur_face.is_null() && first != 0 && second != 0 {
+            let mut delta = FT_Vector { x: 0, y: 0 };
+            if let Some(gpos) = &self.m_cur_kerning {
+                // GPOS takes over from the kern table if the font has both
+                let v = match gpos.kerning(first, second, &self.m_var_coords) {
+                    Some(v) => v,
+                    None => return false,
+                };
+                let x_scale = unsafe { (*(*self.m_cur_face).size).metrics.x_scale };
+                delta.x = (v * x_scale as f64 / 65536.0).round() as c_long;
+                if self.m_hinting {
+                    delta.x = (delta.x + 32) & !63;
+                }
+            } else if FT_HAS_KERNING(self.m_cur_face) != false {
                 let err = unsafe {
                     FT_Get_Kerning(
                         self.m_cur_face,
@@ -433,18 +487,20 @@ impl<'a,T: AggInteger + Neg> FontEngineBase for FreetypeBase<'a,T> {
                 if err != 0 {
                     panic!("FT_Get_Kerning failed: {}", err);
                 }
-                let mut dx = int26p6_to_dbl(delta.x);
-                let mut dy = int26p6_to_dbl(delta.y);
-                if self.m_glyph_rendering == GlyphRender::Outline
-                    || self.m_glyph_rendering == GlyphRender::AggMono
-                    || self.m_glyph_rendering == GlyphRender::AggGray8
-                {
-                    self.m_affine.transform_2x2(&mut dx, &mut dy);
-                }
-                *x += dx;
-                *y += dy;
-                return true;
+            } else {
+                return false;
             }
+            let mut dx = int26p6_to_dbl(delta.x);
+            let mut dy = int26p6_to_dbl(delta.y);
+            if self.m_glyph_rendering == GlyphRender::Outline
+                || self.m_glyph_rendering == GlyphRender::AggMono
+                || self.m_glyph_rendering == GlyphRender::AggGray8
+            {
+                self.m_affine.transform_2x2(&mut dx, &mut dy);
+            }
+            *x += dx;
+            *y += dy;
+            return true;
         }
         false
     }
@@ -471,9 +527,17 @@ impl<'a,T: AggInteger + Neg> FreetypeBase<'a,T> {
             m_library: 0 as FT_Library,
             m_faces: vec![null_mut(); max_faces as usize],
             m_face_names: vec!["".to_string(); max_faces as usize],
+            m_face_kerning: vec![None; max_faces as usize],
+            m_face_palettes: vec![None; max_faces as usize],
             m_num_faces: 0,
             m_max_faces: max_faces,
             m_cur_face: 0 as FT_Face,
+            m_cur_kerning: None,
+            m_var_coords: Vec::new(),
+            m_cur_palettes: None,
+            m_color_glyphs: false,
+            m_palette: 0,
+            m_color_data: Vec::new(),
             m_resolution: 0,
             m_glyph_rendering: GlyphRender::NativeGray8,
             m_glyph_index: 0,
@@ -583,6 +647,8 @@ impl<'a,T: AggInteger + Neg> FreetypeBase<'a,T> {
             let idx = self.find_face(font_name);
             if idx >= 0 {
                 self.m_cur_face = self.m_faces[idx as usize];
+                self.m_cur_kerning = self.m_face_kerning[idx as usize].clone();
+                self.m_cur_palettes = self.m_face_palettes[idx as usize].clone();
                 self.m_name = self.m_face_names[idx as usize].clone();
             } else {
                 if self.m_num_faces >= self.m_max_faces {
@@ -592,6 +658,9 @@ impl<'a,T: AggInteger + Neg> FreetypeBase<'a,T> {
                     for i in 1..self.m_max_faces {
                         self.m_faces[i as usize - 1] = self.m_faces[i as usize];
                         self.m_face_names[i as usize - 1] = self.m_face_names[i as usize].clone();
+                        self.m_face_kerning[i as usize - 1] = self.m_face_kerning[i as usize].take();
+                        self.m_face_palettes[i as usize - 1] =
+                            self.m_face_palettes[i as usize].take();
                     }
                     self.m_num_faces = self.m_max_faces - 1;
                 }
@@ -620,11 +689,22 @@ impl<'a,T: AggInteger + Neg> FreetypeBase<'a,T> {
                 if self.m_last_error == 0 {
                     self.m_face_names[self.m_num_faces as usize] = font_name.to_string();
                     self.m_cur_face = self.m_faces[self.m_num_faces as usize];
+                    self.m_cur_kerning = GposKerning::new(
+                        load_sfnt_table(self.m_cur_face, b"GPOS"),
+                        load_sfnt_table(self.m_cur_face, b"GDEF"),
+                    )
+                    .map(Rc::new);
+                    self.m_face_kerning[self.m_num_faces as usize] = self.m_cur_kerning.clone();
+                    self.m_cur_palettes =
+                        ColorPalettes::new(load_sfnt_table(self.m_cur_face, b"CPAL")).map(Rc::new);
+                    self.m_face_palettes[self.m_num_faces as usize] = self.m_cur_palettes.clone();
                     self.m_name = self.m_face_names[self.m_num_faces as usize].clone();
                     self.m_num_faces += 1;
                 } else {
                     self.m_face_names[self.m_num_faces as usize] = "".to_string();
                     self.m_cur_face = null_mut();
+                    self.m_cur_kerning = None;
+                    self.m_cur_palettes = None;
                     self.m_name = "".to_string();
                 }
             }
@@ -730,6 +810,175 @@ impl<'a,T: AggInteger + Neg> FreetypeBase<'a,T> {
         }
     }
 
+    // Whether the current face is a variable font or a multiple master one
+    pub fn has_variations(&self) -> bool {
+        !self.m_cur_face.is_null()
+            && unsafe { (*self.m_cur_face).face_flags } & FT_FACE_FLAG_MULTIPLE_MASTERS != 0
+    }
+
+    // Whether kerning comes from GPOS rather than the kern table
+    pub fn has_gpos_kerning(&self) -> bool {
+        self.m_cur_kerning.is_some()
+    }
+
+    // Whether glyphs of color fonts come as GlyphDataType::Color data,
+    // COLR layers or sbix/CBDT bitmaps, rather than in the glyph rendering.
+    // It's off by default.
+    pub fn set_color_glyphs(&mut self, c: bool) {
+        self.m_color_glyphs = c;
+        if !self.m_cur_face.is_null() {
+            self.update_signature();
+        }
+    }
+
+    pub fn color_glyphs(&self) -> bool {
+        self.m_color_glyphs
+    }
+
+    // Number of the CPAL palettes of the current face
+    pub fn num_palettes(&self) -> u32 {
+        self.m_cur_palettes.as_ref().map_or(0, |p| p.num_palettes())
+    }
+
+    // The CPAL palette for the COLR layers, 0 is the default one
+    pub fn set_palette(&mut self, palette: u32) {
+        self.m_palette = palette;
+        if !self.m_cur_face.is_null() {
+            self.update_signature();
+        }
+    }
+
+    pub fn palette(&self) -> u32 {
+        self.m_palette
+    }
+
+    pub fn variation_axes(&self) -> Vec<VariationAxis> {
+        let mut axes = Vec::new();
+        if let Some(mm) = self.mm_var() {
+            unsafe {
+                for i in 0..(*mm).num_axis as usize {
+                    let a = &*(*mm).axis.add(i);
+                    axes.push(VariationAxis {
+                        tag: (a.tag as u32).to_be_bytes(),
+                        name: if a.name.is_null() {
+                            "".to_string()
+                        } else {
+                            CStr::from_ptr(a.name).to_string_lossy().into_owned()
+                        },
+                        minimum: a.minimum as f64 / 65536.0,
+                        default: a.def as f64 / 65536.0,
+                        maximum: a.maximum as f64 / 65536.0,
+                    });
+                }
+                FT_Done_MM_Var(self.m_library, mm);
+            }
+        }
+        axes
+    }
+
+    // Number of the named instances (like "Bold" or "Condensed Light") of
+    // a variable font
+    pub fn num_named_instances(&self) -> u32 {
+        if self.has_variations() {
+            return unsafe { ((*self.m_cur_face).style_flags >> 16) as u32 };
+        }
+        0
+    }
+
+    // Selects a named instance, 1 to num_named_instances(), or 0 for the
+    // default coordinates of all axes
+    pub fn set_named_instance(&mut self, index: u32) -> bool {
+        if self.has_variations() {
+            self.m_last_error = unsafe { FT_Set_Named_Instance(self.m_cur_face, index) };
+            if self.m_last_error == 0 {
+                self.update_signature();
+                return true;
+            }
+        }
+        false
+    }
+
+    // The current design coordinate of an axis like b"wght", b"wdth" or
+    // b"opsz"
+    pub fn variation(&self, tag: &[u8; 4]) -> Option<f64> {
+        let i = self.variation_axes().iter().position(|a| &a.tag == tag)?;
+        Some(self.design_coords()[i] as f64 / 65536.0)
+    }
+
+    // Sets the design coordinate of an axis, clipped to its range. The
+    // other axes keep their coordinates.
+    pub fn set_variation(&mut self, tag: &[u8; 4], value: f64) -> bool {
+        let axes = self.variation_axes();
+        let i = match axes.iter().position(|a| &a.tag == tag) {
+            Some(i) => i,
+            None => return false,
+        };
+        let mut coords = self.design_coords();
+        let v = value.max(axes[i].minimum).min(axes[i].maximum);
+        coords[i] = (v * 65536.0).round() as FT_Fixed;
+        self.m_last_error = unsafe {
+            FT_Set_Var_Design_Coordinates(self.m_cur_face, coords.len() as u32, coords.as_ptr())
+        };
+        if self.m_last_error == 0 {
+            self.update_signature();
+            return true;
+        }
+        false
+    }
+
+    fn mm_var(&self) -> Option<*mut FT_MM_Var> {
+        if !self.has_variations() {
+            return None;
+        }
+        let mut mm: *mut FT_MM_Var = null_mut();
+        if unsafe { FT_Get_MM_Var(self.m_cur_face, &mut mm) } != 0 || mm.is_null() {
+            return None;
+        }
+        Some(mm)
+    }
+
+    fn num_axes(&self) -> usize {
+        match self.mm_var() {
+            Some(mm) => unsafe {
+                let n = (*mm).num_axis as usize;
+                FT_Done_MM_Var(self.m_library, mm);
+                n
+            },
+            None => 0,
+        }
+    }
+
+    fn design_coords(&self) -> Vec<FT_Fixed> {
+        let mut coords = vec![0; self.num_axes()];
+        if !coords.is_empty() {
+            unsafe {
+                FT_Get_Var_Design_Coordinates(
+                    self.m_cur_face,
+                    coords.len() as u32,
+                    coords.as_mut_ptr(),
+                )
+            };
+        }
+        coords
+    }
+
+    fn update_var_coords(&mut self) {
+        self.m_var_coords.clear();
+        let mut coords = vec![0; self.num_axes()];
+        if !coords.is_empty()
+            && unsafe {
+                FT_Get_Var_Blend_Coordinates(
+                    self.m_cur_face,
+                    coords.len() as u32,
+                    coords.as_mut_ptr(),
+                )
+            } == 0
+            && coords.iter().any(|&c| c != 0)
+        {
+            self.m_var_coords = coords.iter().map(|&c| c as f64 / 65536.0).collect();
+        }
+    }
+
     pub fn update_signature(&mut self) {
         if !self.m_cur_face.is_null() && self.m_name != "" {
             let name_len = self.m_name.len() as u32;
@@ -782,35 +1031,274 @@ impl<'a,T: AggInteger + Neg> FreetypeBase<'a,T> {
                 );
                 self.m_signature.push_str(&buf);
             }
+            self.update_var_coords();
+            let coords = self.design_coords();
+            if !coords.is_empty() {
+                self.m_signature.push_str(",");
+                for c in coords.iter() {
+                    self.m_signature.push_str(&format!("{:08X}", *c as u32));
+                }
+            }
+            if self.m_color_glyphs {
+                self.m_signature.push_str(&format!(",C{}", self.m_palette));
+            }
             self.m_change_stamp += 1;
         }
     }
 
     fn update_char_size(&mut self) {
         if !self.m_cur_face.is_null() {
+            let err = self.set_face_size();
+            if err != 0 {
+                panic!("FT_Set_Char_Size failed: {}", err);
+            }
+            self.update_signature();
+        }
+    }
+
+    // Bitmap only faces get the strike closest to the size
+    fn set_face_size(&self) -> FT_Error {
+        unsafe {
+            if (*self.m_cur_face).face_flags & FT_FACE_FLAG_SCALABLE == 0 {
+                if let Some((i, _)) = self.best_strike() {
+                    return FT_Select_Size(self.m_cur_face, i);
+                }
+            }
             if self.m_resolution != 0 {
-                let err = unsafe {
-                    FT_Set_Char_Size(
-                        self.m_cur_face,
-                        self.m_width as c_long,
-                        self.m_height as c_long,
-                        self.m_resolution,
-                        self.m_resolution,
-                    )
+                FT_Set_Char_Size(
+                    self.m_cur_face,
+                    self.m_width as c_long,
+                    self.m_height as c_long,
+                    self.m_resolution,
+                    self.m_resolution,
+                )
+            } else {
+                FT_Set_Pixel_Sizes(self.m_cur_face, self.m_width >> 6, self.m_height >> 6)
+            }
+        }
+    }
+
+    // The height in pixels per em
+    fn ppem(&self) -> f64 {
+        let h = self.m_height as f64 / 64.0;
+        if self.m_resolution != 0 {
+            return h * self.m_resolution as f64 / 72.0;
+        }
+        h
+    }
+
+    // The smallest bitmap strike not below the size or else the largest
+    // one, with its pixels per em
+    fn best_strike(&self) -> Option<(i32, f64)> {
+        let ppem = self.ppem();
+        let mut best: Option<(i32, f64)> = None;
+        unsafe {
+            let sizes = (*self.m_cur_face).available_sizes;
+            for i in 0..(*self.m_cur_face).num_fixed_sizes {
+                let s = (*sizes.offset(i as isize)).y_ppem as f64 / 64.0;
+                let better = match best {
+                    None => true,
+                    Some((_, b)) if s >= ppem => b < ppem || s < b,
+                    Some((_, b)) => b < ppem && s > b,
                 };
-                if err != 0 {
-                    panic!("FT_Set_Char_Size failed: {}", err);
+                if better {
+                    best = Some((i, s));
                 }
-            } else {
-                let err = unsafe {
-                    FT_Set_Pixel_Sizes(self.m_cur_face, self.m_width >> 6, self.m_height >> 6)
+            }
+        }
+        best
+    }
+
+    // Prepares the COLR layers or the color bitmap of the glyph, false if
+    // it has neither. The layers are rasterized by AGG whatever the glyph
+    // rendering; bitmaps are scaled to the size but not transformed.
+    fn prepare_color_glyph(&mut self) -> bool {
+        let face = self.m_cur_face;
+        if unsafe { (*face).face_flags } & FT_FACE_FLAG_COLOR == 0 {
+            return false;
+        }
+        let load_flags = if self.m_hinting {
+            FT_LOAD_DEFAULT
+        } else {
+            FT_LOAD_NO_HINTING
+        };
+
+        let mut layers = Vec::new();
+        let mut iter = FT_LayerIterator {
+            num_layers: 0,
+            layer: 0,
+            p: null_mut(),
+        };
+        let (mut layer_glyph, mut color_index) = (0, 0);
+        while unsafe {
+            FT_Get_Color_Glyph_Layer(
+                face,
+                self.m_glyph_index,
+                &mut layer_glyph,
+                &mut color_index,
+                &mut iter,
+            )
+        } {
+            layers.push((layer_glyph, color_index));
+        }
+        if !layers.is_empty() {
+            if unsafe { FT_Load_Glyph(face, self.m_glyph_index, load_flags) } != 0 {
+                return false;
+            }
+            unsafe {
+                self.m_advance_x = int26p6_to_dbl((*(*face).glyph).advance.x);
+                self.m_advance_y = int26p6_to_dbl((*(*face).glyph).advance.y);
+            }
+            self.m_affine
+                .transform(&mut self.m_advance_x, &mut self.m_advance_y);
+
+            begin_color_layers(&mut self.m_color_data);
+            self.m_bounds = RectI::new(1, 1, 0, 0);
+            let mut buf = Vec::new();
+            for &(g, c) in layers.iter() {
+                if unsafe { FT_Load_Glyph(face, g, load_flags) } != 0 || !self.render_layer() {
+                    continue;
+                }
+                // 0xFFFF is the text color, as are colors the palette lacks
+                let color = if c == 0xFFFF {
+                    None
+                } else {
+                    self.m_cur_palettes
+                        .as_ref()
+                        .and_then(|p| p.color(self.m_palette, c))
                 };
-                if err != 0 {
-                    panic!("FT_Set_Pixel_Sizes failed: {}", err);
+                buf.resize(self.m_scanlines_aa.byte_size(), 0);
+                self.m_scanlines_aa.serialize(&mut buf);
+                add_color_layer(&mut self.m_color_data, color, &buf);
+
+                let sl = &self.m_scanlines_aa;
+                if sl.min_x() <= sl.max_x() {
+                    if self.m_bounds.x1 > self.m_bounds.x2 {
+                        self.m_bounds = RectI::new(sl.min_x(), sl.min_y(), sl.max_x(), sl.max_y());
+                    } else {
+                        self.m_bounds.x1 = self.m_bounds.x1.min(sl.min_x());
+                        self.m_bounds.y1 = self.m_bounds.y1.min(sl.min_y());
+                        self.m_bounds.x2 = self.m_bounds.x2.max(sl.max_x());
+                        self.m_bounds.y2 = self.m_bounds.y2.max(sl.max_y());
+                    }
                 }
             }
-            self.update_signature();
+            if self.m_bounds.x1 <= self.m_bounds.x2 {
+                self.m_bounds.x2 += 1;
+                self.m_bounds.y2 += 1;
+            }
+            self.m_data_size = self.m_color_data.len() as u32;
+            self.m_data_type = GlyphDataType::Color;
+            return true;
+        }
+
+        // sbix and CBDT bitmaps come with strikes; scalable faces get theirs
+        // back afterwards
+        let (strike, strike_ppem) = match self.best_strike() {
+            Some(s) => s,
+            None => return false,
+        };
+        let scalable = unsafe { (*face).face_flags } & FT_FACE_FLAG_SCALABLE != 0;
+        if scalable && unsafe { FT_Select_Size(face, strike) } != 0 {
+            return false;
         }
+        let mut ret = false;
+        if unsafe { FT_Load_Glyph(face, self.m_glyph_index, load_flags | FT_LOAD_COLOR) } == 0 {
+            let slot = unsafe { &*(*face).glyph };
+            let bm = &slot.bitmap;
+            if slot.format == FT_GLYPH_FORMAT_BITMAP
+                && bm.pixel_mode as u32 == FT_PIXEL_MODE_BGRA
+                && bm.width > 0
+                && bm.rows > 0
+            {
+                let scale = self.ppem() / strike_ppem;
+                let (w, h) = (bm.width as u32, bm.rows as u32);
+                let dw = ((w as f64 * scale).round() as u32).max(1);
+                let dh = ((h as f64 * scale).round() as u32).max(1);
+                let pitch = bm.pitch.abs() as usize;
+                let src = unsafe { std::slice::from_raw_parts(bm.buffer, pitch * h as usize) };
+                let mut rgba = scale_bgra(src, w, h, pitch, dw, dh);
+                let x = (slot.bitmap_left as f64 * scale).round() as i32;
+                let top = (slot.bitmap_top as f64 * scale).round() as i32;
+                let y = if self.m_flip_y {
+                    -top
+                } else {
+                    // Bottom up like the y axis
+                    let row = dw as usize * 4;
+                    let rows: Vec<Vec<u8>> = rgba.chunks(row).rev().map(|r| r.to_vec()).collect();
+                    rgba = rows.concat();
+                    top - dh as i32
+                };
+                color_bitmap(&mut self.m_color_data, x, y, dw, dh, &rgba);
+                self.m_bounds = RectI::new(x, y, x + dw as i32, y + dh as i32);
+                self.m_data_size = self.m_color_data.len() as u32;
+                self.m_data_type = GlyphDataType::Color;
+                self.m_advance_x = int26p6_to_dbl(slot.advance.x) * scale;
+                self.m_advance_y = int26p6_to_dbl(slot.advance.y) * scale;
+                ret = true;
+            }
+        }
+        if scalable {
+            self.set_face_size();
+        }
+        ret
+    }
+
+    // Rasterizes the loaded glyph into the gray8 scanlines
+    fn render_layer(&mut self) -> bool {
+        let slot = unsafe { (*self.m_cur_face).glyph };
+        if unsafe { (*slot).format } == FT_GLYPH_FORMAT_OUTLINE {
+            self.m_rasterizer.reset();
+            if self.m_flag32 {
+                self.m_curves32.source_mut().remove_all();
+                unsafe {
+                    decompose_ft_outline(
+                        &(*slot).outline,
+                        self.m_flip_y,
+                        &self.m_affine,
+                        &mut *self.m_curves32.source_mut(),
+                    );
+                }
+                self.m_rasterizer.add_path(&mut self.m_curves32, 0);
+            } else {
+                self.m_curves16.source_mut().remove_all();
+                unsafe {
+                    decompose_ft_outline(
+                        &(*slot).outline,
+                        self.m_flip_y,
+                        &self.m_affine,
+                        &mut *self.m_curves16.source_mut(),
+                    );
+                }
+                self.m_rasterizer.add_path(&mut self.m_curves16, 0);
+            }
+            self.m_scanlines_aa.prepare();
+            render_scanlines(
+                &mut self.m_rasterizer,
+                &mut self.m_scanline_aa,
+                &mut self.m_scanlines_aa,
+            );
+            return true;
+        }
+        if unsafe { FT_Render_Glyph(slot, FT_RENDER_MODE_NORMAL) } != 0 {
+            return false;
+        }
+        unsafe {
+            decompose_ft_bitmap_gray8(
+                &(*slot).bitmap,
+                (*slot).bitmap_left,
+                if self.m_flip_y {
+                    -(*slot).bitmap_top
+                } else {
+                    (*slot).bitmap_top
+                },
+                self.m_flip_y,
+                &mut self.m_rasterizer,
+                &mut self.m_scanline_aa,
+                &mut self.m_scanlines_aa,
+            );
+        }
+        true
     }
 }
 
@@ -824,21 +1312,20 @@ impl<'a,T: AggInteger + Neg> Drop for FreetypeBase<'a,T> {
     }
 }
 
-fn calc_crc32(buf: &[u8], size: usize) -> u32 {
-    let mut crc: u32 = !0;
-    //let mut len: usize = 0;
-    let nr: usize = size;
-    //let mut p: &u8 = buf;
-    let mut i = 0;
-    for _ in 0..nr {
-        crc = (crc >> 8) ^ CRC32_TAB[(crc ^ buf[i] as u32) as usize & 0xff];
-        i += 1
-    }
-    !crc
-}
-
-fn dbl_to_plain_fx(d: f64) -> i32 {
-    (d * 65536.0) as i32
+// A table of the font, empty if there's none
+fn load_sfnt_table(face: FT_Face, tag: &[u8; 4]) -> Vec<u8> {
+    let tag = u32::from_be_bytes(*tag) as c_ulong;
+    let mut len: c_ulong = 0;
+    unsafe {
+        if FT_Load_Sfnt_Table(face, tag, 0, null_mut(), &mut len) != 0 {
+            return Vec::new();
+        }
+        let mut buf = vec![0u8; len as usize];
+        if FT_Load_Sfnt_Table(face, tag, 0, buf.as_mut_ptr(), &mut len) != 0 {
+            return Vec::new();
+        }
+        buf
+    }
 }
 
 fn int26p6_to_dbl(p: c_long) -> f64 {
@@ -1192,38 +1679,3 @@ fn decompose_ft_outline<PS: PathStore>(
 
     true
 }
-
-const CRC32_TAB: [u32; 256] = [
-    0x00000000, 0x77073096, 0xee0e612c, 0x990951ba, 0x076dc419, 0x706af48f, 0xe963a535, 0x9e6495a3,
-    0x0edb8832, 0x79dcb8a4, 0xe0d5e91e, 0x97d2d988, 0x09b64c2b, 0x7eb17cbd, 0xe7b82d07, 0x90bf1d91,
-    0x1db71064, 0x6ab020f2, 0xf3b97148, 0x84be41de, 0x1adad47d, 0x6ddde4eb, 0xf4d4b551, 0x83d385c7,
-    0x136c9856, 0x646ba8c0, 0xfd62f97a, 0x8a65c9ec, 0x14015c4f, 0x63066cd9, 0xfa0f3d63, 0x8d080df5,
-    0x3b6e20c8, 0x4c69105e, 0xd56041e4, 0xa2677172, 0x3c03e4d1, 0x4b04d447, 0xd20d85fd, 0xa50ab56b,
-    0x35b5a8fa, 0x42b2986c, 0xdbbbc9d6, 0xacbcf940, 0x32d86ce3, 0x45df5c75, 0xdcd60dcf, 0xabd13d59,
-    0x26d930ac, 0x51de003a, 0xc8d75180, 0xbfd06116, 0x21b4f4b5, 0x56b3c423, 0xcfba9599, 0xb8bda50f,
-    0x2802b89e, 0x5f058808, 0xc60cd9b2, 0xb10be924, 0x2f6f7c87, 0x58684c11, 0xc1611dab, 0xb6662d3d,
-    0x76dc4190, 0x01db7106, 0x98d220bc, 0xefd5102a, 0x71b18589, 0x06b6b51f, 0x9fbfe4a5, 0xe8b8d433,
-    0x7807c9a2, 0x0f00f934, 0x9609a88e, 0xe10e9818, 0x7f6a0dbb, 0x086d3d2d, 0x91646c97, 0xe6635c01,
-    0x6b6b51f4, 0x1c6c6162, 0x856530d8, 0xf262004e, 0x6c0695ed, 0x1b01a57b, 0x8208f4c1, 0xf50fc457,
-    0x65b0d9c6, 0x12b7e950, 0x8bbeb8ea, 0xfcb9887c, 0x62dd1ddf, 0x15da2d49, 0x8cd37cf3, 0xfbd44c65,
-    0x4db26158, 0x3ab551ce, 0xa3bc0074, 0xd4bb30e2, 0x4adfa541, 0x3dd895d7, 0xa4d1c46d, 0xd3d6f4fb,
-    0x4369e96a, 0x346ed9fc, 0xad678846, 0xda60b8d0, 0x44042d73, 0x33031de5, 0xaa0a4c5f, 0xdd0d7cc9,
-    0x5005713c, 0x270241aa, 0xbe0b1010, 0xc90c2086, 0x5768b525, 0x206f85b3, 0xb966d409, 0xce61e49f,
-    0x5edef90e, 0x29d9c998, 0xb0d09822, 0xc7d7a8b4, 0x59b33d17, 0x2eb40d81, 0xb7bd5c3b, 0xc0ba6cad,
-    0xedb88320, 0x9abfb3b6, 0x03b6e20c, 0x74b1d29a, 0xead54739, 0x9dd277af, 0x04db2615, 0x73dc1683,
-    0xe3630b12, 0x94643b84, 0x0d6d6a3e, 0x7a6a5aa8, 0xe40ecf0b, 0x9309ff9d, 0x0a00ae27, 0x7d079eb1,
-    0xf00f9344, 0x8708a3d2, 0x1e01f268, 0x6906c2fe, 0xf762575d, 0x806567cb, 0x196c3671, 0x6e6b06e7,
-    0xfed41b76, 0x89d32be0, 0x10da7a5a, 0x67dd4acc, 0xf9b9df6f, 0x8ebeeff9, 0x17b7be43, 0x60b08ed5,
-    0xd6d6a3e8, 0xa1d1937e, 0x38d8c2c4, 0x4fdff252, 0xd1bb67f1, 0xa6bc5767, 0x3fb506dd, 0x48b2364b,
-    0xd80d2bda, 0xaf0a1b4c, 0x36034af6, 0x41047a60, 0xdf60efc3, 0xa867df55, 0x316e8eef, 0x4669be79,
-    0xcb61b38c, 0xbc66831a, 0x256fd2a0, 0x5268e236, 0xcc0c7795, 0xbb0b4703, 0x220216b9, 0x5505262f,
-    0xc5ba3bbe, 0xb2bd0b28, 0x2bb45a92, 0x5cb36a04, 0xc2d7ffa7, 0xb5d0cf31, 0x2cd99e8b, 0x5bdeae1d,
-    0x9b64c2b0, 0xec63f226, 0x756aa39c, 0x026d930a, 0x9c0906a9, 0xeb0e363f, 0x72076785, 0x05005713,
-    0x95bf4a82, 0xe2b87a14, 0x7bb12bae, 0x0cb61b38, 0x92d28e9b, 0xe5d5be0d, 0x7cdcefb7, 0x0bdbdf21,
-    0x86d3d2d4, 0xf1d4e242, 0x68ddb3f8, 0x1fda836e, 0x81be16cd, 0xf6b9265b, 0x6fb077e1, 0x18b74777,
-    0x88085ae6, 0xff0f6a70, 0x66063bca, 0x11010b5c, 0x8f659eff, 0xf862ae69, 0x616bffd3, 0x166ccf45,
-    0xa00ae278, 0xd70dd2ee, 0x4e048354, 0x3903b3c2, 0xa7672661, 0xd06016f7, 0x4969474d, 0x3e6e77db,
-    0xaed16a4a, 0xd9d65adc, 0x40df0b66, 0x37d83bf0, 0xa9bcae53, 0xdebb9ec5, 0x47b2cf7f, 0x30b5ffe9,
-    0xbdbdf21c, 0xcabac28a, 0x53b39330, 0x24b4a3a6, 0xbad03605, 0xcdd70693, 0x54de5729, 0x23d967bf,
-    0xb3667a2e, 0xc4614ab8, 0x5d681b02, 0x2a6f2b94, 0xb40bbe37, 0xc30c8ea1, 0x5a05df1b, 0x2d02ef8d,
-];
@@ -0,0 +1,596 @@
+use std::io;
+use std::marker::PhantomData;
+use std::ops::Neg;
+
+use crate::basics::{is_close, is_curve3, is_curve4, is_end_poly, is_line_to, is_move_to, RectI};
+use crate::conv_curve::ConvCurve;
+use crate::font_cache_manager::{calc_crc32, dbl_to_plain_fx, GlyphDataType, GlyphRender};
+use crate::path_storage::PathStorage;
+use crate::path_storage_integer::PathStorageInteger;
+use crate::rasterizer_scanline_aa::{AaScale, RasterizerScanlineAa};
+use crate::renderer_scanline::render_scanlines;
+use crate::scanline_bin::ScanlineBin;
+use crate::scanline_storage_aa::ScanlineStorageAA;
+use crate::scanline_storage_bin::ScanlineStorageBin;
+use crate::scanline_u::ScanlineU8;
+use crate::trans_affine::TransAffine;
+use crate::truetype::TrueTypeFace;
+use crate::{
+    AggInteger, AggPrimitive, GammaFn, PathStore, RasterScanLine, RendererScanline, Transformer,
+};
+use crate::{FontEngine, FontEngineBase};
+
+//------------------------------------------------font_engine_truetype_int32
+// Like font_engine_freetype_int32, the vector cache in 26.6 int32 values
+impl<'a> FontEngine for TrueTypeBase<'a, i32> {
+    type PathAdaptorType = crate::SerializedIntegerPathAdaptor<i32>;
+
+    fn new(max_faces: u32) -> Self {
+        TrueTypeBase::new_with_flags(true, max_faces)
+    }
+
+    fn path_adaptor(&self) -> Self::PathAdaptorType {
+        Self::PathAdaptorType::new()
+    }
+}
+
+//------------------------------------------------font_engine_truetype_int16
+// The vector cache in 10.6 int16 values, for glyphs up to about 200 pixels
+impl<'a> FontEngine for TrueTypeBase<'a, i16> {
+    type PathAdaptorType = crate::SerializedIntegerPathAdaptor<i16>;
+
+    fn new(max_faces: u32) -> Self {
+        TrueTypeBase::new_with_flags(false, max_faces)
+    }
+
+    fn path_adaptor(&self) -> Self::PathAdaptorType {
+        Self::PathAdaptorType::new()
+    }
+}
+
+//-----------------------------------------------TrueTypeBase
+// A font engine with the interface of FreetypeBase that reads the fonts
+// with TrueTypeFace instead of FreeType, so it needs no system library.
+// The glyphs are always rendered from their outlines by AGG: NativeMono
+// and NativeGray8 give the same as AggMono and AggGray8. There's no
+// hinting; with hinting on the advances and kerning are rounded to whole
+// pixels as FreeType does. The char map is always Unicode.
+pub struct TrueTypeBase<'a, T: AggInteger + Neg> {
+    m_flag32: bool,
+    m_change_stamp: i32,
+    m_last_error: Option<io::Error>,
+    m_name: String,
+    m_face_index: u32,
+    m_signature: String,
+    m_height: u32,
+    m_width: u32,
+    m_hinting: bool,
+    m_flip_y: bool,
+    m_faces: Vec<TrueTypeFace>,
+    m_face_names: Vec<String>,
+    m_max_faces: u32,
+    m_cur_face: Option<usize>,
+    m_resolution: u32,
+    m_glyph_rendering: GlyphRender,
+    m_glyph_index: u32,
+    m_data_size: u32,
+    m_data_type: GlyphDataType,
+    m_bounds: RectI,
+    m_advance_x: f64,
+    m_advance_y: f64,
+    m_affine: TransAffine,
+    m_outline: PathStorage,
+    m_curves16: ConvCurve<'a, PathStorageInteger<i16, 6>>,
+    m_curves32: ConvCurve<'a, PathStorageInteger<i32, 6>>,
+    m_scanline_aa: ScanlineU8,
+    m_scanline_bin: ScanlineBin,
+    m_scanlines_aa: ScanlineStorageAA<i8>,
+    m_scanlines_bin: ScanlineStorageBin,
+    m_rasterizer: RasterizerScanlineAa,
+    m_dum: PhantomData<T>,
+}
+
+impl<'a, T: AggInteger + Neg> FontEngineBase for TrueTypeBase<'a, T> {
+    type Gray8AdaptorType = crate::scanline_storage_aa::SerializedScanlinesAdaptorAa<u8>;
+    type Gray8ScanlineType = crate::scanline_storage_aa::EmbeddedScanline;
+    type MonoAdaptorType = crate::scanline_storage_bin::SerializedScanlinesAdaptorBin;
+    type MonoScanlineType = crate::scanline_storage_bin::EmbeddedScanline;
+
+    fn font_signature(&self) -> &str {
+        &self.m_signature
+    }
+    fn change_stamp(&self) -> i32 {
+        self.m_change_stamp
+    }
+    fn ascender(&self) -> f64 {
+        TrueTypeBase::ascender(self)
+    }
+    fn descender(&self) -> f64 {
+        TrueTypeBase::descender(self)
+    }
+    fn flip_y(&self) -> bool {
+        self.m_flip_y
+    }
+    fn glyph_index(&self) -> u32 {
+        self.m_glyph_index
+    }
+    fn data_size(&self) -> u32 {
+        self.m_data_size
+    }
+    fn data_type(&self) -> GlyphDataType {
+        self.m_data_type
+    }
+    fn bounds(&self) -> &RectI {
+        &self.m_bounds
+    }
+    fn advance_x(&self) -> f64 {
+        self.m_advance_x
+    }
+    fn advance_y(&self) -> f64 {
+        self.m_advance_y
+    }
+
+    fn gray8_adaptor(&self) -> Self::Gray8AdaptorType {
+        Self::Gray8AdaptorType::new()
+    }
+
+    fn gray8_scanline(&self) -> Self::Gray8ScanlineType {
+        Self::Gray8ScanlineType::new()
+    }
+
+    fn mono_adaptor(&self) -> Self::MonoAdaptorType {
+        Self::MonoAdaptorType::new()
+    }
+
+    fn mono_scanline(&self) -> Self::MonoScanlineType {
+        Self::MonoScanlineType::new()
+    }
+
+    fn prepare_glyph(&mut self, glyph_code: u32) -> bool {
+        let face = match self.m_cur_face {
+            Some(i) => &self.m_faces[i],
+            None => return false,
+        };
+        self.m_glyph_index = face.glyph_index(glyph_code);
+        self.m_outline.remove_all();
+        if !face.outline(self.m_glyph_index, &mut self.m_outline) {
+            return false;
+        }
+        let (sx, sy) = self.scale();
+        self.m_advance_x = face.advance_width(self.m_glyph_index) as f64 * sx;
+        if self.m_hinting {
+            self.m_advance_x = self.m_advance_x.round();
+        }
+        self.m_advance_y = 0.0;
+
+        if self.m_flag32 {
+            self.m_curves32.source_mut().remove_all();
+            decompose_outline(
+                &self.m_outline,
+                sx,
+                sy,
+                self.m_flip_y,
+                &self.m_affine,
+                self.m_curves32.source_mut(),
+            );
+        } else {
+            self.m_curves16.source_mut().remove_all();
+            decompose_outline(
+                &self.m_outline,
+                sx,
+                sy,
+                self.m_flip_y,
+                &self.m_affine,
+                self.m_curves16.source_mut(),
+            );
+        }
+        self.m_affine
+            .transform_2x2(&mut self.m_advance_x, &mut self.m_advance_y);
+
+        match self.m_glyph_rendering {
+            GlyphRender::Outline => {
+                let bnd = if self.m_flag32 {
+                    self.m_data_size = self.m_curves32.source().byte_size() as u32;
+                    self.m_curves32.source_mut().bounding_rect()
+                } else {
+                    self.m_data_size = self.m_curves16.source().byte_size() as u32;
+                    self.m_curves16.source_mut().bounding_rect()
+                };
+                self.m_data_type = GlyphDataType::Outline;
+                self.m_bounds.x1 = bnd.x1 as i32;
+                self.m_bounds.y1 = bnd.y1 as i32;
+                self.m_bounds.x2 = bnd.x2 as i32;
+                self.m_bounds.y2 = bnd.y2 as i32;
+            }
+            GlyphRender::NativeMono | GlyphRender::AggMono => {
+                self.m_rasterizer.reset();
+                if self.m_flag32 {
+                    self.m_rasterizer.add_path(&mut self.m_curves32, 0);
+                } else {
+                    self.m_rasterizer.add_path(&mut self.m_curves16, 0);
+                }
+                self.m_scanlines_bin.prepare(); // Remove all
+                render_scanlines(
+                    &mut self.m_rasterizer,
+                    &mut self.m_scanline_bin,
+                    &mut self.m_scanlines_bin,
+                );
+                self.m_bounds.x1 = self.m_scanlines_bin.min_x();
+                self.m_bounds.y1 = self.m_scanlines_bin.min_y();
+                self.m_bounds.x2 = self.m_scanlines_bin.max_x() + 1;
+                self.m_bounds.y2 = self.m_scanlines_bin.max_y() + 1;
+                self.m_data_size = self.m_scanlines_bin.byte_size() as u32;
+                self.m_data_type = GlyphDataType::Mono;
+            }
+            GlyphRender::NativeGray8 | GlyphRender::AggGray8 => {
+                self.m_rasterizer.reset();
+                if self.m_flag32 {
+                    self.m_rasterizer.add_path(&mut self.m_curves32, 0);
+                } else {
+                    self.m_rasterizer.add_path(&mut self.m_curves16, 0);
+                }
+                self.m_scanlines_aa.prepare(); // Remove all
+                render_scanlines(
+                    &mut self.m_rasterizer,
+                    &mut self.m_scanline_aa,
+                    &mut self.m_scanlines_aa,
+                );
+                self.m_bounds.x1 = self.m_scanlines_aa.min_x();
+                self.m_bounds.y1 = self.m_scanlines_aa.min_y();
+                self.m_bounds.x2 = self.m_scanlines_aa.max_x() + 1;
+                self.m_bounds.y2 = self.m_scanlines_aa.max_y() + 1;
+                self.m_data_size = self.m_scanlines_aa.byte_size() as u32;
+                self.m_data_type = GlyphDataType::Gray8;
+            }
+        }
+        true
+    }
+
+    fn write_glyph_to(&mut self, data: &mut [u8]) {
+        if data.len() == 0 {
+            return;
+        }
+        match self.m_data_type {
+            GlyphDataType::Mono => self.m_scanlines_bin.serialize(data),
+            GlyphDataType::Gray8 => self.m_scanlines_aa.serialize(data),
+            GlyphDataType::Outline => {
+                if self.m_flag32 {
+                    self.m_curves32.source_mut().serialize(data);
+                } else {
+                    self.m_curves16.source_mut().serialize(data);
+                }
+            }
+            GlyphDataType::Invalid | GlyphDataType::Color => (),
+        }
+    }
+
+    fn add_kerning(&self, first: u32, second: u32, x: &mut f64, y: &mut f64) -> bool {
+        if let Some(i) = self.m_cur_face {
+            let face = &self.m_faces[i];
+            if first != 0 && second != 0 && face.has_kerning() {
+                let mut dx = face.kerning(first, second) as f64 * self.scale().0;
+                let mut dy = 0.0;
+                if self.m_hinting {
+                    dx = dx.round();
+                }
+                self.m_affine.transform_2x2(&mut dx, &mut dy);
+                *x += dx;
+                *y += dy;
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl<'a, T: AggInteger + Neg> TrueTypeBase<'a, T> {
+    pub fn new_with_flags(flag32: bool, max_faces: u32) -> Self {
+        let pi16 = PathStorageInteger::<i16, 6>::new();
+        let pi32 = PathStorageInteger::<i32, 6>::new();
+        let mut f = TrueTypeBase {
+            m_flag32: flag32,
+            m_change_stamp: 0,
+            m_last_error: None,
+            m_name: "".to_string(),
+            m_face_index: 0,
+            m_signature: "".to_string(),
+            m_height: 0,
+            m_width: 0,
+            m_hinting: true,
+            m_flip_y: false,
+            m_faces: Vec::new(),
+            m_face_names: Vec::new(),
+            m_max_faces: max_faces.max(1),
+            m_cur_face: None,
+            m_resolution: 0,
+            m_glyph_rendering: GlyphRender::NativeGray8,
+            m_glyph_index: 0,
+            m_data_size: 0,
+            m_data_type: GlyphDataType::Invalid,
+            m_bounds: RectI {
+                x1: 1,
+                y1: 1,
+                x2: 0,
+                y2: 0,
+            },
+            m_advance_x: 0.0,
+            m_advance_y: 0.0,
+            m_affine: TransAffine::new_default(),
+            m_outline: PathStorage::new(),
+            m_curves16: ConvCurve::<PathStorageInteger<i16, 6>>::new_owned(pi16),
+            m_curves32: ConvCurve::<PathStorageInteger<i32, 6>>::new_owned(pi32),
+            m_scanline_aa: ScanlineU8::new(),
+            m_scanline_bin: ScanlineBin::new(),
+            m_scanlines_aa: ScanlineStorageAA::new(),
+            m_scanlines_bin: ScanlineStorageBin::new(),
+            m_rasterizer: RasterizerScanlineAa::new(),
+            m_dum: PhantomData,
+        };
+        f.m_curves16.set_approximation_scale(4.0);
+        f.m_curves32.set_approximation_scale(4.0);
+        f
+    }
+
+    // Why the last load_font failed
+    pub fn last_error(&self) -> Option<&io::Error> {
+        self.m_last_error.as_ref()
+    }
+    pub fn resolution(&self) -> u32 {
+        self.m_resolution
+    }
+    pub fn name(&self) -> &str {
+        &self.m_name
+    }
+    pub fn height(&self) -> f64 {
+        (self.m_height as f64) / 64.0
+    }
+    pub fn width(&self) -> f64 {
+        (self.m_width as f64) / 64.0
+    }
+    pub fn hinting(&self) -> bool {
+        self.m_hinting
+    }
+    pub fn flip_y(&self) -> bool {
+        self.m_flip_y
+    }
+
+    // The current face, to get at its metrics
+    pub fn face(&self) -> Option<&TrueTypeFace> {
+        self.m_cur_face.map(|i| &self.m_faces[i])
+    }
+
+    pub fn set_gamma<F: GammaFn>(&mut self, gamma_function: F) {
+        self.m_rasterizer.set_gamma(&gamma_function);
+        if self.m_cur_face.is_some() {
+            self.update_signature();
+        }
+    }
+
+    pub fn set_resolution(&mut self, dpi: u32) {
+        self.m_resolution = dpi;
+        self.update_char_size();
+    }
+
+    pub fn find_face(&self, face_name: &str) -> i32 {
+        for (i, name) in self.m_face_names.iter().enumerate() {
+            if face_name == name {
+                return i as i32;
+            }
+        }
+        return -1;
+    }
+
+    pub fn ascender(&self) -> f64 {
+        match self.face() {
+            Some(f) => f.ascender() as f64 * self.height() / line_height(f),
+            None => 0.0,
+        }
+    }
+
+    pub fn descender(&self) -> f64 {
+        match self.face() {
+            Some(f) => f.descender() as f64 * self.height() / line_height(f),
+            None => 0.0,
+        }
+    }
+
+    // Loads a face from font_mem if it isn't empty, from the file
+    // font_name otherwise, or selects it if it's loaded already. The name
+    // may end with '\0' as with FreetypeBase.
+    pub fn load_font(
+        &mut self, font_name: &str, face_index: u32, ren_type: GlyphRender, font_mem: &[u8],
+        font_mem_size: u32,
+    ) -> bool {
+        self.m_last_error = None;
+        let idx = self.find_face(font_name);
+        if idx >= 0 {
+            self.m_cur_face = Some(idx as usize);
+        } else {
+            let face = if font_mem.len() > 0 && font_mem_size > 0 {
+                let size = (font_mem_size as usize).min(font_mem.len());
+                TrueTypeFace::from_data(font_mem[..size].to_vec(), face_index)
+            } else {
+                TrueTypeFace::load(font_name.trim_end_matches('\0'), face_index)
+            };
+            match face {
+                Ok(face) => {
+                    if self.m_faces.len() >= self.m_max_faces as usize {
+                        self.m_faces.remove(0);
+                        self.m_face_names.remove(0);
+                    }
+                    self.m_faces.push(face);
+                    self.m_face_names.push(font_name.to_string());
+                    self.m_cur_face = Some(self.m_faces.len() - 1);
+                }
+                Err(e) => {
+                    self.m_last_error = Some(e);
+                    self.m_cur_face = None;
+                    self.m_name = "".to_string();
+                    return false;
+                }
+            }
+        }
+        self.m_name = font_name.to_string();
+        self.m_face_index = face_index;
+        self.m_glyph_rendering = ren_type;
+        self.update_signature();
+        true
+    }
+
+    pub fn num_faces(&self) -> u32 {
+        self.face().map_or(0, |f| f.num_faces())
+    }
+
+    pub fn set_height(&mut self, h: f64) -> bool {
+        self.m_height = (h * 64.0) as u32;
+        if self.m_cur_face.is_some() {
+            self.update_char_size();
+            return true;
+        }
+        return false;
+    }
+
+    pub fn set_width(&mut self, w: f64) -> bool {
+        self.m_width = (w * 64.0) as u32;
+        if self.m_cur_face.is_some() {
+            self.update_char_size();
+            return true;
+        }
+        return false;
+    }
+
+    pub fn set_hinting(&mut self, h: bool) {
+        self.m_hinting = h;
+        if self.m_cur_face.is_some() {
+            self.update_signature();
+        }
+    }
+
+    pub fn set_flip_y(&mut self, f: bool) {
+        self.m_flip_y = f;
+        if self.m_cur_face.is_some() {
+            self.update_signature();
+        }
+    }
+
+    pub fn transform(&mut self, affine: &TransAffine) {
+        self.m_affine = *affine;
+        if self.m_cur_face.is_some() {
+            self.update_signature();
+        }
+    }
+
+    pub fn update_signature(&mut self) {
+        if self.m_cur_face.is_some() && self.m_name != "" {
+            let mut gamma_hash = 0;
+            if self.m_glyph_rendering != GlyphRender::Outline {
+                let mut gamma_table: [u8; AaScale::Scale as usize] = [0; AaScale::Scale as usize];
+                for i in 0..AaScale::Scale as usize {
+                    gamma_table[i] = self.m_rasterizer.apply_gamma(i) as u8;
+                }
+                gamma_hash = calc_crc32(&gamma_table, gamma_table.len());
+            }
+
+            let mut mtx: [f64; 6] = [0.0; 6];
+            self.m_affine.store_to(&mut mtx);
+            self.m_signature = format!(
+                "{},{},{},{}:{}x{},{},{},{:08X},{:08X}{:08X}{:08X}{:08X}{:08X}{:08X}",
+                self.m_name,
+                self.m_face_index,
+                self.m_glyph_rendering as i32,
+                self.m_resolution,
+                self.m_height,
+                self.m_width,
+                self.m_hinting as i32,
+                self.m_flip_y as i32,
+                gamma_hash,
+                dbl_to_plain_fx(mtx[0]),
+                dbl_to_plain_fx(mtx[1]),
+                dbl_to_plain_fx(mtx[2]),
+                dbl_to_plain_fx(mtx[3]),
+                dbl_to_plain_fx(mtx[4]),
+                dbl_to_plain_fx(mtx[5])
+            );
+            self.m_change_stamp += 1;
+        }
+    }
+
+    fn update_char_size(&mut self) {
+        if self.m_cur_face.is_some() {
+            self.update_signature();
+        }
+    }
+
+    // Font units to pixels, the pixel sizes whole as FT_Set_Pixel_Sizes
+    // takes them unless there's a resolution
+    fn scale(&self) -> (f64, f64) {
+        let em = match self.face() {
+            Some(f) => f.units_per_em() as f64,
+            None => return (0.0, 0.0),
+        };
+        let (w, h) = if self.m_resolution != 0 {
+            let r = self.m_resolution as f64 / 72.0;
+            (self.width() * r, self.height() * r)
+        } else {
+            ((self.m_width >> 6) as f64, (self.m_height >> 6) as f64)
+        };
+        let w = if w == 0.0 { h } else { w };
+        (w / em, h / em)
+    }
+}
+
+fn line_height(f: &TrueTypeFace) -> f64 {
+    let h = f.ascender() as f64 - f.descender() as f64 + f.line_gap() as f64;
+    if h > 0.0 {
+        h
+    } else {
+        f.units_per_em() as f64
+    }
+}
+
+macro_rules! from_f64 {
+    ($v:expr) => {
+        <<PS as PathStore>::T as AggPrimitive>::from_f64(($v * 64.0).round())
+    };
+}
+
+// Scales the outline in font units to pixels, flips and transforms it
+// and stores it in 26.6 format
+fn decompose_outline<PS: PathStore>(
+    outline: &PathStorage, sx: f64, sy: f64, flip_y: bool, mtx: &TransAffine, path: &mut PS,
+) {
+    let n = outline.total_vertices();
+    let point = |i: u32| {
+        let (mut x, mut y) = (0.0, 0.0);
+        outline.vertex_idx(i, &mut x, &mut y);
+        x *= sx;
+        y *= if flip_y { -sy } else { sy };
+        mtx.transform(&mut x, &mut y);
+        (from_f64!(x), from_f64!(y))
+    };
+    let mut i = 0;
+    while i < n {
+        let cmd = outline.command(i);
+        if is_move_to(cmd) {
+            let (x, y) = point(i);
+            path.move_to(x, y);
+        } else if is_line_to(cmd) {
+            let (x, y) = point(i);
+            path.line_to(x, y);
+        } else if is_curve3(cmd) && i + 1 < n {
+            let (x1, y1) = point(i);
+            let (x2, y2) = point(i + 1);
+            path.curve3(x1, y1, x2, y2);
+            i += 1;
+        } else if is_curve4(cmd) && i + 2 < n {
+            let (x1, y1) = point(i);
+            let (x2, y2) = point(i + 1);
+            let (x3, y3) = point(i + 2);
+            path.curve4(x1, y1, x2, y2, x3, y3);
+            i += 2;
+        } else if is_end_poly(cmd) && is_close(cmd) {
+            path.close_polygon();
+        }
+        i += 1;
+    }
+}
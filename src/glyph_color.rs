@@ -0,0 +1,261 @@
+use crate::color_rgba::{Rgba, Rgba8};
+use crate::pixfmt_rgba::PixRgba32;
+use crate::renderer_base::RendererBase;
+use crate::renderer_scanline::render_scanlines_aa_solid;
+use crate::rendering_buffer::RenderBuf;
+use crate::scanline_storage_aa::SerializedScanlinesAdaptorAa;
+use crate::truetype::{rd_u16, rd_u32};
+use crate::{Color, PixFmt, PixFmtOwned, Scanline};
+
+//===========================================================ColorPalettes
+// The palettes of an OpenType CPAL table, which give the colors of the
+// COLR layers.
+pub struct ColorPalettes {
+    data: Vec<u8>,
+    num_entries: u32,
+    num_palettes: u32,
+}
+
+impl ColorPalettes {
+    pub fn new(cpal: Vec<u8>) -> Option<ColorPalettes> {
+        let num_entries = rd_u16(&cpal, 2)? as u32;
+        let num_palettes = rd_u16(&cpal, 4)? as u32;
+        let num_records = rd_u16(&cpal, 6)? as usize;
+        let records = rd_u32(&cpal, 8)? as usize;
+        if num_palettes == 0 || records + num_records * 4 > cpal.len() {
+            return None;
+        }
+        Some(ColorPalettes {
+            data: cpal,
+            num_entries: num_entries,
+            num_palettes: num_palettes,
+        })
+    }
+
+    pub fn num_palettes(&self) -> u32 {
+        self.num_palettes
+    }
+
+    // Number of colors in each palette
+    pub fn num_entries(&self) -> u32 {
+        self.num_entries
+    }
+
+    pub fn color(&self, palette: u32, entry: u32) -> Option<Rgba8> {
+        if palette >= self.num_palettes || entry >= self.num_entries {
+            return None;
+        }
+        let d = &self.data;
+        let first = rd_u16(d, 12 + palette as usize * 2)? as usize;
+        let p = rd_u32(d, 8)? as usize + (first + entry as usize) * 4;
+        let bgra = d.get(p..p + 4)?;
+        Some(Rgba8::new_params(
+            bgra[2] as u32,
+            bgra[1] as u32,
+            bgra[0] as u32,
+            bgra[3] as u32,
+        ))
+    }
+}
+
+//==============================================================ColorGlyph
+// Reads the data of a GlyphDataType::Color glyph and renders it. The
+// data is either layers, each one gray8 scanlines like a Gray8 glyph
+// with its own color, painted bottom up, or an RGBA bitmap:
+//
+//   1, then per layer: flags (1 = text color), r g b a, size, scanlines
+//   2, x, y, width, height, width * height RGBA pixels
+//
+// in native endian 32 bit words. The RGBA pixels aren't premultiplied;
+// x and y are the top-left corner relative to the glyph origin.
+pub struct ColorGlyph<'a> {
+    data: &'a [u8],
+}
+
+const COLOR_LAYERS: u32 = 1;
+const COLOR_BITMAP: u32 = 2;
+const LAYER_TEXT_COLOR: u32 = 1;
+
+impl<'a> ColorGlyph<'a> {
+    pub fn new(data: &'a [u8]) -> ColorGlyph<'a> {
+        ColorGlyph { data: data }
+    }
+
+    pub fn is_bitmap(&self) -> bool {
+        rd_word(self.data, 0) == Some(COLOR_BITMAP)
+    }
+
+    // The layers as (color, scanline data), None for the color of the
+    // layers drawn in the text color
+    pub fn layers(&self) -> Vec<(Option<Rgba8>, &'a [u8])> {
+        let d = self.data;
+        let mut layers = Vec::new();
+        if rd_word(d, 0) != Some(COLOR_LAYERS) {
+            return layers;
+        }
+        let mut p = 4;
+        while p + 12 <= d.len() {
+            let flags = rd_word(d, p).unwrap();
+            let c = &d[p + 4..p + 8];
+            let size = rd_word(d, p + 8).unwrap() as usize;
+            let sl = match d.get(p + 12..p + 12 + size) {
+                Some(sl) => sl,
+                None => break,
+            };
+            let color = if flags & LAYER_TEXT_COLOR != 0 {
+                None
+            } else {
+                Some(Rgba8::new_params(
+                    c[0] as u32,
+                    c[1] as u32,
+                    c[2] as u32,
+                    c[3] as u32,
+                ))
+            };
+            layers.push((color, sl));
+            p += 12 + size;
+        }
+        layers
+    }
+
+    // The bitmap as (x, y, width, height, pixels)
+    pub fn bitmap(&self) -> Option<(i32, i32, u32, u32, &'a [u8])> {
+        let d = self.data;
+        if rd_word(d, 0)? != COLOR_BITMAP {
+            return None;
+        }
+        let (w, h) = (rd_word(d, 12)?, rd_word(d, 16)?);
+        let len = w.checked_mul(h)?.checked_mul(4)? as usize;
+        let pix = d.get(20..20 + len)?;
+        Some((rd_word(d, 4)? as i32, rd_word(d, 8)? as i32, w, h, pix))
+    }
+
+    // Renders the glyph with its origin at (x, y). The layers go through
+    // the scanline renderer, the bitmap is blended in with cover, 0...255.
+    pub fn render<Pix: PixFmt<T = u8>, Sl: Scanline>(
+        &self, ren: &mut RendererBase<Pix>, sl: &mut Sl, x: f64, y: f64, text_color: &Pix::C,
+        cover: u32,
+    ) {
+        if let Some((bx, by, w, h, pix)) = self.bitmap() {
+            // The bitmap is only read from
+            let rbuf = RenderBuf::new(pix.as_ptr() as *mut u8, w, h, w as i32 * 4);
+            let src = PixRgba32::new_from_rbuf(rbuf);
+            ren.blend_from(
+                &src,
+                None,
+                x.round() as i32 + bx,
+                y.round() as i32 + by,
+                cover,
+            );
+            return;
+        }
+        for (color, data) in self.layers() {
+            let c = match color {
+                Some(c) => Pix::C::new_from_rgba(&Rgba::new_params(
+                    c.r as f64 / 255.,
+                    c.g as f64 / 255.,
+                    c.b as f64 / 255.,
+                    c.a as f64 / 255. * cover as f64 / 255.,
+                )),
+                None => {
+                    let mut c = *text_color;
+                    c.set_opacity(c.opacity() * cover as f64 / 255.);
+                    c
+                }
+            };
+            let mut adp = SerializedScanlinesAdaptorAa::<u8>::new();
+            adp.init(data.as_ptr(), data.len(), x, y);
+            render_scanlines_aa_solid(&mut adp, sl, ren, &c);
+        }
+    }
+}
+
+#[cfg(feature = "freetype")]
+pub(crate) fn begin_color_layers(out: &mut Vec<u8>) {
+    out.clear();
+    out.extend_from_slice(&COLOR_LAYERS.to_ne_bytes());
+}
+
+#[cfg(feature = "freetype")]
+pub(crate) fn add_color_layer(out: &mut Vec<u8>, color: Option<Rgba8>, scanlines: &[u8]) {
+    let (flags, c) = match color {
+        Some(c) => (0, [c.r, c.g, c.b, c.a]),
+        None => (LAYER_TEXT_COLOR, [0; 4]),
+    };
+    out.extend_from_slice(&flags.to_ne_bytes());
+    out.extend_from_slice(&c);
+    out.extend_from_slice(&(scanlines.len() as u32).to_ne_bytes());
+    out.extend_from_slice(scanlines);
+}
+
+#[cfg(feature = "freetype")]
+pub(crate) fn color_bitmap(out: &mut Vec<u8>, x: i32, y: i32, w: u32, h: u32, rgba: &[u8]) {
+    out.clear();
+    out.extend_from_slice(&COLOR_BITMAP.to_ne_bytes());
+    for v in [x as u32, y as u32, w, h].iter() {
+        out.extend_from_slice(&v.to_ne_bytes());
+    }
+    out.extend_from_slice(rgba);
+}
+
+// Scales a premultiplied BGRA bitmap with a box filter and converts it
+// to RGBA that isn't premultiplied
+#[cfg(feature = "freetype")]
+pub(crate) fn scale_bgra(src: &[u8], w: u32, h: u32, pitch: usize, dw: u32, dh: u32) -> Vec<u8> {
+    let (w, h, dw, dh) = (
+        w as usize,
+        h as usize,
+        dw.max(1) as usize,
+        dh.max(1) as usize,
+    );
+    let sx = w as f64 / dw as f64;
+    let sy = h as f64 / dh as f64;
+    // The source pixels covering [a, b) with their weights
+    let spans = |n: usize, s: f64, len: usize| -> Vec<Vec<(usize, f64)>> {
+        (0..n)
+            .map(|i| {
+                let (a, b) = (i as f64 * s, (i as f64 + 1.) * s);
+                let mut v = Vec::new();
+                let mut j = a.floor() as usize;
+                while (j as f64) < b && j < len {
+                    let wt = (b.min(j as f64 + 1.) - a.max(j as f64)) / s;
+                    if wt > 0. {
+                        v.push((j, wt));
+                    }
+                    j += 1;
+                }
+                v
+            })
+            .collect()
+    };
+    let xs = spans(dw, sx, w);
+    let ys = spans(dh, sy, h);
+    let mut out = vec![0u8; dw * dh * 4];
+    for (dy, yw) in ys.iter().enumerate() {
+        for (dx, xw) in xs.iter().enumerate() {
+            let mut acc = [0.; 4];
+            for &(y, wy) in yw.iter() {
+                for &(x, wx) in xw.iter() {
+                    let p = y * pitch + x * 4;
+                    for k in 0..4 {
+                        acc[k] += src[p + k] as f64 * wx * wy;
+                    }
+                }
+            }
+            let o = (dy * dw + dx) * 4;
+            let a = acc[3];
+            if a > 0. {
+                out[o] = (acc[2] * 255. / a).round().min(255.) as u8;
+                out[o + 1] = (acc[1] * 255. / a).round().min(255.) as u8;
+                out[o + 2] = (acc[0] * 255. / a).round().min(255.) as u8;
+                out[o + 3] = a.round().min(255.) as u8;
+            }
+        }
+    }
+    out
+}
+
+fn rd_word(d: &[u8], p: usize) -> Option<u32> {
+    d.get(p..p + 4)
+        .map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+}
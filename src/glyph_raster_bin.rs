@@ -1,5 +1,6 @@
 use crate::basics::{CoverScale, CoverType};
-use crate::{GlyphGenerator};
+use crate::raster_font::RasterFont;
+use crate::GlyphGenerator;
 
 #[derive(Default, Clone, Copy)]
 pub struct GlyphRect {
@@ -16,27 +17,31 @@ impl GlyphRect {
 	}
 }
 //========================================================GlyphRasterBin
+// Glyphs of a bitmap font, either a table of embedded_raster_fonts or a
+// RasterFont, e.g. read from a BDF or PCF file. Characters without a
+// glyph are left out.
 pub struct GlyphRasterBin {
     font: *const u8,
-    big_endian: bool,
-    span: [CoverType; 32],
-    bits: *const u8,
+    raster_font: Option<RasterFont>,
+    span: Vec<CoverType>,
+    glyph: u32,
     glyph_width: u32,
     glyph_byte_width: u32,
 }
 
 impl GlyphRasterBin {
     pub fn new(font: *const u8) -> GlyphRasterBin {
-        let mut big_endian = false;
-        let t = 1;
-        if font != core::ptr::null() && unsafe { *(t as *const i8) } == 0 {
-            big_endian = true;
-        }
+        let mut g = GlyphRasterBin::new_raster_font(None);
+        g.set_font(font);
+        g
+    }
+
+    pub fn new_raster_font<F: Into<Option<RasterFont>>>(font: F) -> GlyphRasterBin {
         GlyphRasterBin {
-            font: font,
-            big_endian: big_endian,
-            span: [0; 32],
-            bits: std::ptr::null(),
+            font: std::ptr::null(),
+            raster_font: font.into(),
+            span: Vec::new(),
+            glyph: 0,
             glyph_width: 0,
             glyph_byte_width: 0,
         }
@@ -46,69 +51,71 @@ impl GlyphRasterBin {
         self.font
     }
 
+    // Sets a table of embedded_raster_fonts
     pub fn set_font(&mut self, f: *const u8) {
         self.font = f;
+        self.raster_font = if f.is_null() {
+            None
+        } else {
+            let font = unsafe { std::slice::from_raw_parts(f, embedded_len(f)) };
+            RasterFont::from_embedded(font).ok()
+        };
+    }
+
+    pub fn raster_font(&self) -> Option<&RasterFont> {
+        self.raster_font.as_ref()
+    }
+
+    pub fn set_raster_font(&mut self, font: RasterFont) {
+        self.font = std::ptr::null();
+        self.raster_font = Some(font);
     }
 
     pub fn height(&self) -> f64 {
-        unsafe { *self.font as f64 }
+        self.raster_font.as_ref().map_or(0.0, |f| f.height() as f64)
     }
 
     pub fn base_line(&self) -> f64 {
-        unsafe { *self.font.offset(1) as f64 }
+        self.raster_font.as_ref().map_or(0.0, |f| f.base_line() as f64)
     }
 
     pub fn width(&self, str: &str) -> f64 {
-        let start_char = unsafe { *self.font.offset(2) };
-        let num_chars = unsafe { *self.font.offset(3) };
-
-        let mut w = 0;
-        for c in str.chars() {
-            let glyph = c as u8;
-            unsafe {
-                let bits = self.font.offset(
-                    4 + num_chars as isize * 2
-                        + self.value(self.font.offset(4 + (glyph - start_char) as isize * 2))
-                            as isize,
-                );
-                w += *bits;
-            }
-        }
-        w as f64
+        let font = match self.raster_font.as_ref() {
+            Some(f) => f,
+            None => return 0.0,
+        };
+        str.chars()
+            .filter_map(|c| font.glyph_width(c as u32))
+            .sum::<u32>() as f64
     }
-	
-    fn value(&self, p: *const u8) -> u16 {
-        let mut v0: u16 = 0;
-		let v = &mut v0 as *mut u16;
-        if self.big_endian {
-            unsafe {
-                *(v as *mut u8) = *p.offset(1);
-                *((v as *mut u8).offset(1)) = *p;
-            }
-        } else {
-            unsafe {
-                *(v as *mut u8) = *p;
-                *((v as *mut u8).offset(1)) = *p.offset(1);
-            }
-        }
-        v0
+}
+
+// The length of an embedded font, up to the end of its last glyph
+unsafe fn embedded_len(f: *const u8) -> usize {
+    let (height, num) = (*f as usize, *f.offset(3) as usize);
+    let start = 4 + num * 2;
+    let mut len = start;
+    for i in 0..num {
+        let p = f.add(4 + i * 2);
+        let pos = start + u16::from_le_bytes([*p, *p.offset(1)]) as usize;
+        let w = *f.add(pos) as usize;
+        len = len.max(pos + 1 + height * ((w + 7) >> 3));
     }
+    len
 }
 
 impl GlyphGenerator for GlyphRasterBin {
     fn prepare(&mut self, r: &mut GlyphRect, x: f64, y: f64, glyph: u32, flip: bool) {
-        let start_char = unsafe { *self.font.offset(2) } as u32;
-        let num_chars = unsafe { *self.font.offset(3) } as u32;
-        unsafe {
-            self.bits = self.font.offset(
-                4 + num_chars as isize * 2
-                    + self.value(self.font.offset(4 + (glyph - start_char) as isize * 2)) as isize,
-            );
-
-            self.glyph_width =  *self.bits  as u32;
-			self.bits = self.bits.offset(1);
-        }
+        self.glyph = glyph;
+        self.glyph_width = self
+            .raster_font
+            .as_ref()
+            .and_then(|f| f.glyph_width(glyph))
+            .unwrap_or(0);
         self.glyph_byte_width = (self.glyph_width + 7) >> 3;
+        if self.span.len() < self.glyph_width as usize {
+            self.span.resize(self.glyph_width as usize, 0);
+        }
 
         r.x1 = x as i32;
         r.x2 = r.x1 + self.glyph_width as i32 - 1;
@@ -124,28 +131,21 @@ impl GlyphGenerator for GlyphRasterBin {
     }
 
     fn span(&mut self, i0: u32) -> &mut [u8] {
-        let i = unsafe { *self.font.offset(0) } as u32 - i0 - 1;
-        let bits = unsafe {
-            self.bits
-                .offset(i as isize * self.glyph_byte_width as isize)
-        };
-        let mut val = unsafe { *bits };
-        let mut nb = 0;
-        for j in 0..self.glyph_width {
-            self.span[j as usize] = {
-                if (val & 0x80) != 0 {
+        let w = self.glyph_width as usize;
+        let bits = self.raster_font.as_ref().and_then(|f| {
+            let i = (f.height() - i0 - 1) as usize;
+            let bw = self.glyph_byte_width as usize;
+            f.glyph(self.glyph).map(|g| &g[1 + i * bw..1 + (i + 1) * bw])
+        });
+        if let Some(bits) = bits {
+            for (j, c) in self.span[..w].iter_mut().enumerate() {
+                *c = if bits[j >> 3] & (0x80 >> (j & 7)) != 0 {
                     CoverScale::FULL
                 } else {
                     CoverScale::None
-                }
-            } as u8;
-            val <<= 1;
-			nb += 1;
-            if nb >= 8 {
-                val = unsafe { *bits.offset(1) };
-                nb = 0;
+                } as u8;
             }
         }
-        &mut self.span
+        &mut self.span[..w]
     }
 }
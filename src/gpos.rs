@@ -0,0 +1,306 @@
+use crate::truetype::{rd_f2dot14, rd_u16, rd_u32, rd_u8};
+
+//==============================================================GposKerning
+// Pair kerning from the OpenType GPOS table: the pair adjustment
+// subtables (lookup type 2, also behind extension lookups) of the 'kern'
+// features of all scripts. In variable fonts the adjustments can refer to
+// deltas in the item variation store of GDEF; they're applied for the
+// normalized axis coordinates given to kerning().
+//
+// A pair is looked up in the subtables in lookup order and the first one
+// that covers it wins. The kerning is the x advance of the first glyph
+// plus the x placement of the second one, in font units.
+pub struct GposKerning {
+    gpos: Vec<u8>,
+    gdef: Vec<u8>,
+    // Offsets of the PairPos subtables in gpos
+    subtables: Vec<usize>,
+    // Offset of the item variation store in gdef
+    var_store: Option<usize>,
+}
+
+impl GposKerning {
+    // Takes the GPOS and GDEF tables, GDEF may be empty. None if GPOS is
+    // broken or has no pair adjustments for kerning.
+    pub fn new(gpos: Vec<u8>, gdef: Vec<u8>) -> Option<GposKerning> {
+        let d = &gpos[..];
+        if rd_u16(d, 0)? != 1 {
+            return None;
+        }
+        let features = rd_u16(d, 6)? as usize;
+        let lookups = rd_u16(d, 8)? as usize;
+
+        let mut kern_lookups: Vec<usize> = Vec::new();
+        for i in 0..rd_u16(d, features)? as usize {
+            let rec = features + 2 + i * 6;
+            if d.get(rec..rec + 4)? != b"kern" {
+                continue;
+            }
+            let f = features + rd_u16(d, rec + 4)? as usize;
+            for j in 0..rd_u16(d, f + 2)? as usize {
+                kern_lookups.push(rd_u16(d, f + 4 + j * 2)? as usize);
+            }
+        }
+        kern_lookups.sort();
+        kern_lookups.dedup();
+
+        let num_lookups = rd_u16(d, lookups)? as usize;
+        let mut subtables = Vec::new();
+        for &l in kern_lookups.iter().filter(|&&l| l < num_lookups) {
+            let lookup = lookups + rd_u16(d, lookups + 2 + l * 2)? as usize;
+            let lookup_type = rd_u16(d, lookup)?;
+            for j in 0..rd_u16(d, lookup + 4)? as usize {
+                let mut sub = lookup + rd_u16(d, lookup + 6 + j * 2)? as usize;
+                let mut sub_type = lookup_type;
+                if sub_type == 9 {
+                    // Extension: the real type and a 32 bit offset
+                    sub_type = rd_u16(d, sub + 2)?;
+                    sub += rd_u32(d, sub + 4)? as usize;
+                }
+                if sub_type == 2 {
+                    subtables.push(sub);
+                }
+            }
+        }
+        if subtables.is_empty() {
+            return None;
+        }
+
+        // The item variation store came with GDEF 1.3
+        let mut var_store = None;
+        if rd_u16(&gdef, 0) == Some(1) && rd_u16(&gdef, 2).map_or(false, |v| v >= 3) {
+            var_store = rd_u32(&gdef, 14).filter(|&o| o != 0).map(|o| o as usize);
+        }
+        Some(GposKerning {
+            gpos: gpos,
+            gdef: gdef,
+            subtables: subtables,
+            var_store: var_store,
+        })
+    }
+
+    // Whether the adjustments can vary with the axis coordinates
+    pub fn has_variations(&self) -> bool {
+        self.var_store.is_some()
+    }
+
+    // Kerning between two glyphs in font units for the normalized axis
+    // coordinates (-1..1, empty for the default instance), None if no
+    // subtable covers the pair
+    pub fn kerning(&self, left: u32, right: u32, coords: &[f64]) -> Option<f64> {
+        self.subtables
+            .iter()
+            .find_map(|&sub| self.pair_value(sub, left, right, coords))
+    }
+
+    fn pair_value(&self, sub: usize, left: u32, right: u32, coords: &[f64]) -> Option<f64> {
+        let d = &self.gpos[..];
+        let cov = coverage_index(d, sub + rd_u16(d, sub + 2)? as usize, left)?;
+        let format1 = rd_u16(d, sub + 4)?;
+        let format2 = rd_u16(d, sub + 6)?;
+        let size1 = format1.count_ones() as usize * 2;
+        let size2 = format2.count_ones() as usize * 2;
+        let rec = match rd_u16(d, sub)? {
+            1 => {
+                if cov >= rd_u16(d, sub + 8)? as usize {
+                    return None;
+                }
+                let set = sub + rd_u16(d, sub + 10 + cov * 2)? as usize;
+                let size = 2 + size1 + size2;
+                let (mut lo, mut hi) = (0, rd_u16(d, set)? as usize);
+                loop {
+                    if lo >= hi {
+                        return None;
+                    }
+                    let mid = (lo + hi) / 2;
+                    let p = set + 2 + mid * size;
+                    let g = rd_u16(d, p)? as u32;
+                    if g == right {
+                        break p + 2;
+                    }
+                    if g < right {
+                        lo = mid + 1;
+                    } else {
+                        hi = mid;
+                    }
+                }
+            }
+            2 => {
+                let class1 = class_of(d, sub + rd_u16(d, sub + 8)? as usize, left);
+                let class2 = class_of(d, sub + rd_u16(d, sub + 10)? as usize, right);
+                let count1 = rd_u16(d, sub + 12)? as usize;
+                let count2 = rd_u16(d, sub + 14)? as usize;
+                if class1 >= count1 || class2 >= count2 {
+                    return None;
+                }
+                sub + 16 + (class1 * count2 + class2) * (size1 + size2)
+            }
+            _ => return None,
+        };
+        // XAdvance of the first glyph, XPlacement of the second
+        Some(
+            self.value(sub, rec, format1, 0x04, coords)
+                + self.value(sub, rec + size1, format2, 0x01, coords),
+        )
+    }
+
+    // A field of a value record with the delta of its device table, which
+    // comes with the bit of the field << 4
+    fn value(&self, sub: usize, rec: usize, format: u16, field: u16, coords: &[f64]) -> f64 {
+        let d = &self.gpos[..];
+        let pos = |bit: u16| rec + (format & (bit - 1)).count_ones() as usize * 2;
+        let mut v = 0.;
+        if format & field != 0 {
+            v += rd_u16(d, pos(field)).unwrap_or(0) as i16 as f64;
+        }
+        let device = field << 4;
+        if format & device != 0 && !coords.is_empty() {
+            let off = rd_u16(d, pos(device)).unwrap_or(0) as usize;
+            if off != 0 {
+                v += self.delta(sub + off, coords).unwrap_or(0.);
+            }
+        }
+        v
+    }
+
+    // The delta of a VariationIndex table; the hinting Device tables of
+    // the other formats are ignored
+    fn delta(&self, device: usize, coords: &[f64]) -> Option<f64> {
+        let d = &self.gpos[..];
+        if rd_u16(d, device + 4)? != 0x8000 {
+            return None;
+        }
+        let outer = rd_u16(d, device)? as usize;
+        let inner = rd_u16(d, device + 2)? as usize;
+
+        let g = &self.gdef[..];
+        let store = self.var_store?;
+        let regions = store + rd_u32(g, store + 2)? as usize;
+        if outer >= rd_u16(g, store + 6)? as usize {
+            return None;
+        }
+        let data = store + rd_u32(g, store + 8 + outer * 4)? as usize;
+        let item_count = rd_u16(g, data)? as usize;
+        let word_count = rd_u16(g, data + 2)?;
+        let long_words = word_count & 0x8000 != 0;
+        let word_count = (word_count & 0x7FFF) as usize;
+        let region_count = rd_u16(g, data + 4)? as usize;
+        if inner >= item_count || word_count > region_count {
+            return None;
+        }
+        let (word, short) = if long_words { (4, 2) } else { (2, 1) };
+        let row_size = word_count * word + (region_count - word_count) * short;
+        let mut p = data + 6 + region_count * 2 + inner * row_size;
+
+        let axis_count = rd_u16(g, regions)? as usize;
+        let num_regions = rd_u16(g, regions + 2)? as usize;
+        let mut sum = 0.;
+        for i in 0..region_count {
+            let delta = match (i < word_count, long_words) {
+                (true, true) => rd_u32(g, p)? as i32 as f64,
+                (true, false) | (false, true) => rd_u16(g, p)? as i16 as f64,
+                (false, false) => rd_u8(g, p)? as i8 as f64,
+            };
+            p += if i < word_count { word } else { short };
+            let r = rd_u16(g, data + 6 + i * 2)? as usize;
+            if r < num_regions {
+                let region = regions + 4 + r * axis_count * 6;
+                sum += delta * region_scalar(g, region, axis_count, coords)?;
+            }
+        }
+        Some(sum)
+    }
+}
+
+// How much a region of the variation space applies at the coordinates
+fn region_scalar(d: &[u8], region: usize, axis_count: usize, coords: &[f64]) -> Option<f64> {
+    let mut scalar = 1.;
+    for a in 0..axis_count {
+        let p = region + a * 6;
+        let start = rd_f2dot14(d, p)?;
+        let peak = rd_f2dot14(d, p + 2)?;
+        let end = rd_f2dot14(d, p + 4)?;
+        if peak == 0. || start > peak || peak > end || (start < 0. && end > 0.) {
+            continue;
+        }
+        let v = coords.get(a).copied().unwrap_or(0.);
+        if v < start || v > end {
+            return Some(0.);
+        }
+        if v < peak {
+            scalar *= (v - start) / (peak - start);
+        } else if v > peak {
+            scalar *= (end - v) / (end - peak);
+        }
+    }
+    Some(scalar)
+}
+
+fn coverage_index(d: &[u8], cov: usize, glyph: u32) -> Option<usize> {
+    let (mut lo, mut hi) = (0, rd_u16(d, cov + 2)? as usize);
+    match rd_u16(d, cov)? {
+        1 => {
+            while lo < hi {
+                let mid = (lo + hi) / 2;
+                let g = rd_u16(d, cov + 4 + mid * 2)? as u32;
+                if g == glyph {
+                    return Some(mid);
+                }
+                if g < glyph {
+                    lo = mid + 1;
+                } else {
+                    hi = mid;
+                }
+            }
+            None
+        }
+        2 => {
+            while lo < hi {
+                let mid = (lo + hi) / 2;
+                let r = cov + 4 + mid * 6;
+                let start = rd_u16(d, r)? as u32;
+                if glyph < start {
+                    hi = mid;
+                } else if glyph > rd_u16(d, r + 2)? as u32 {
+                    lo = mid + 1;
+                } else {
+                    return Some(rd_u16(d, r + 4)? as usize + (glyph - start) as usize);
+                }
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+// The class of a glyph in a ClassDef table, 0 if it has none
+fn class_of(d: &[u8], def: usize, glyph: u32) -> usize {
+    let class = || -> Option<usize> {
+        match rd_u16(d, def)? {
+            1 => {
+                let start = rd_u16(d, def + 2)? as u32;
+                if glyph < start || glyph - start >= rd_u16(d, def + 4)? as u32 {
+                    return None;
+                }
+                Some(rd_u16(d, def + 6 + (glyph - start) as usize * 2)? as usize)
+            }
+            2 => {
+                let (mut lo, mut hi) = (0, rd_u16(d, def + 2)? as usize);
+                while lo < hi {
+                    let mid = (lo + hi) / 2;
+                    let r = def + 4 + mid * 6;
+                    if glyph < rd_u16(d, r)? as u32 {
+                        hi = mid;
+                    } else if glyph > rd_u16(d, r + 2)? as u32 {
+                        lo = mid + 1;
+                    } else {
+                        return Some(rd_u16(d, r + 4)? as usize);
+                    }
+                }
+                None
+            }
+            _ => None,
+        }
+    };
+    class().unwrap_or(0)
+}
@@ -0,0 +1,889 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::sync::{Arc, OnceLock};
+
+use crate::gsv_text::GSV_DEFAULT_FONT;
+
+// The code point and data range of each glyph
+type GlyphIndex = Vec<(u32, usize, usize)>;
+
+struct FontData {
+    data: Vec<u8>,
+    base_height: f64,
+    glyphs: GlyphIndex,
+}
+
+//=================================================================GsvFont
+// A stroke font of GsvText. Two formats are read, both little-endian:
+//
+// - the 8-bit one of AGG's gsv_default_font: the u16 offset of the glyph
+//   index at 0 and the u16 base height at 4, then at the index 257 u16
+//   offsets of the glyphs in the data that follows it, one per code
+//   0..256, which are taken as code points;
+// - a Unicode one: "GSVU", u16 version 1, u16 base height, u32 number of
+//   glyphs, then a (u32 code point, u32 offset) per glyph in increasing
+//   order of code points and the u32 end offset, then the glyph data.
+//
+// A glyph is a run of (dx, dy) byte pairs relative to the pen, dx signed
+// and dy a 7-bit signed number with 0x80 set for a move. The last move
+// leaves the pen at the advance. The font is shared when cloned.
+#[derive(Clone)]
+pub struct GsvFont {
+    font: Arc<FontData>,
+}
+
+impl GsvFont {
+    // The 8-bit font of AGG: ASCII, and Cyrillic from 128 in the order
+    // of code page 866
+    pub fn new_default() -> GsvFont {
+        GsvFont::from_data(GSV_DEFAULT_FONT.to_vec()).unwrap()
+    }
+
+    // The built-in Unicode font: the glyphs of the default font for ASCII
+    // and Cyrillic, with Latin-1, Latin Extended-A, modern Greek and the
+    // other Cyrillic letters of the Slavic languages made up from them.
+    // It is built once.
+    pub fn new_unicode() -> GsvFont {
+        static FONT: OnceLock<GsvFont> = OnceLock::new();
+        FONT.get_or_init(build_unicode).clone()
+    }
+
+    // A Unicode font of the glyphs, given as code points and glyph data
+    pub fn from_glyphs(base_height: u16, glyphs: &[(u32, Vec<u8>)]) -> io::Result<GsvFont> {
+        let mut glyphs: Vec<&(u32, Vec<u8>)> = glyphs.iter().collect();
+        glyphs.sort_by_key(|g| g.0);
+        let mut data = b"GSVU".to_vec();
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&base_height.to_le_bytes());
+        data.extend_from_slice(&(glyphs.len() as u32).to_le_bytes());
+        let mut offset = 0;
+        for (code, g) in glyphs.iter().map(|g| (g.0, &g.1)) {
+            data.extend_from_slice(&code.to_le_bytes());
+            data.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += g.len();
+        }
+        data.extend_from_slice(&(offset as u32).to_le_bytes());
+        for g in glyphs.iter() {
+            data.extend_from_slice(&g.1);
+        }
+        GsvFont::from_data(data)
+    }
+
+    // The font in data, in either format, after checking its header and
+    // glyph offsets
+    pub fn from_data(data: Vec<u8>) -> io::Result<GsvFont> {
+        let (base_height, glyphs) = if data.starts_with(b"GSVU") {
+            parse_unicode(&data)?
+        } else {
+            parse_8bit(&data)?
+        };
+        Ok(GsvFont {
+            font: Arc::new(FontData {
+                data: data,
+                base_height: base_height as f64,
+                glyphs: glyphs,
+            }),
+        })
+    }
+
+    pub fn load(file: &str) -> io::Result<GsvFont> {
+        GsvFont::from_data(fs::read(file)?)
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.font.data
+    }
+
+    // The height of the capitals in font units
+    pub fn base_height(&self) -> f64 {
+        self.font.base_height
+    }
+
+    pub fn num_glyphs(&self) -> usize {
+        self.font.glyphs.len()
+    }
+
+    // The code points that have a glyph, in increasing order
+    pub fn codes(&self) -> impl Iterator<Item = u32> + '_ {
+        self.font.glyphs.iter().map(|g| g.0)
+    }
+
+    pub fn glyph(&self, code: u32) -> Option<&[u8]> {
+        self.glyph_range(code).map(|(b, e)| &self.font.data[b..e])
+    }
+
+    pub(crate) fn glyph_range(&self, code: u32) -> Option<(usize, usize)> {
+        let glyphs = &self.font.glyphs;
+        glyphs
+            .binary_search_by_key(&code, |g| g.0)
+            .ok()
+            .map(|i| (glyphs[i].1, glyphs[i].2))
+    }
+}
+
+fn bad_font(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("GSV font: {}", msg))
+}
+
+fn le16(d: &[u8], pos: usize) -> Option<usize> {
+    d.get(pos..pos + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+}
+
+fn le32(d: &[u8], pos: usize) -> Option<usize> {
+    d.get(pos..pos + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+}
+
+fn check_glyph(d: &[u8], b: usize, e: usize) -> io::Result<()> {
+    if b > e || e > d.len() || (e - b) % 2 != 0 {
+        return Err(bad_font("bad glyph offsets"));
+    }
+    Ok(())
+}
+
+fn parse_8bit(d: &[u8]) -> io::Result<(usize, GlyphIndex)> {
+    let (index, base_height) = match (le16(d, 0), le16(d, 4)) {
+        (Some(i), Some(h)) => (i, h),
+        _ => return Err(bad_font("file too short")),
+    };
+    if index < 6 || base_height == 0 {
+        return Err(bad_font("bad header"));
+    }
+    let start = index + 257 * 2;
+    if start > d.len() {
+        return Err(bad_font("file too short"));
+    }
+    let mut glyphs = Vec::new();
+    for c in 0..256 {
+        let b = start + le16(d, index + c * 2).unwrap();
+        let e = start + le16(d, index + c * 2 + 2).unwrap();
+        check_glyph(d, b, e)?;
+        if e > b {
+            glyphs.push((c as u32, b, e));
+        }
+    }
+    Ok((base_height, glyphs))
+}
+
+fn parse_unicode(d: &[u8]) -> io::Result<(usize, GlyphIndex)> {
+    let (version, base_height, num) = match (le16(d, 4), le16(d, 6), le32(d, 8)) {
+        (Some(v), Some(h), Some(n)) => (v, h, n),
+        _ => return Err(bad_font("file too short")),
+    };
+    if version != 1 {
+        return Err(bad_font(&format!("unsupported version {}", version)));
+    }
+    if base_height == 0 {
+        return Err(bad_font("bad header"));
+    }
+    let start = match num.checked_mul(8).and_then(|n| n.checked_add(16)) {
+        Some(s) if s <= d.len() => s,
+        _ => return Err(bad_font("file too short")),
+    };
+    let mut glyphs: GlyphIndex = Vec::with_capacity(num);
+    for i in 0..num {
+        let code = le32(d, 12 + i * 8).unwrap();
+        let b = start + le32(d, 16 + i * 8).unwrap();
+        let e = start
+            + le32(
+                d,
+                if i + 1 < num {
+                    24 + i * 8
+                } else {
+                    12 + num * 8
+                },
+            )
+            .unwrap();
+        if code > 0x10ffff || glyphs.last().is_some_and(|g| g.0 as usize >= code) {
+            return Err(bad_font("code points out of order"));
+        }
+        check_glyph(d, b, e)?;
+        glyphs.push((code as u32, b, e));
+    }
+    Ok((base_height, glyphs))
+}
+
+//------------------------------------------------------------------------
+// The strokes of a glyph in font units while the Unicode font is built
+#[derive(Clone)]
+struct Strokes {
+    lines: Vec<Vec<(f64, f64)>>,
+    advance: f64,
+}
+
+impl Strokes {
+    fn decode(g: &[u8]) -> Strokes {
+        let mut lines: Vec<Vec<(f64, f64)>> = Vec::new();
+        let (mut x, mut y) = (0., 0.);
+        let mut open = false;
+        for p in g.chunks(2) {
+            let prev = (x, y);
+            x += p[0] as i8 as f64;
+            y += ((p[1] << 1) as i8 >> 1) as f64;
+            if p[1] & 0x80 != 0 {
+                open = false;
+            } else {
+                if !open {
+                    lines.push(vec![prev]);
+                    open = true;
+                }
+                lines.last_mut().unwrap().push((x, y));
+            }
+        }
+        Strokes {
+            lines: lines,
+            advance: x,
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut g = Vec::new();
+        let mut pen = (0i32, 0i32);
+        let mut step = |to: (f64, f64), mv: bool, g: &mut Vec<u8>| {
+            let to = ((to.0 + 0.5).floor() as i32, (to.1 + 0.5).floor() as i32);
+            let (dx, dy) = (to.0 - pen.0, to.1 - pen.1);
+            let n = ((dx.abs() + 126) / 127).max((dy.abs() + 62) / 63).max(1);
+            let mut done = (0, 0);
+            for i in 1..=n {
+                let d = (dx * i / n, dy * i / n);
+                g.push((d.0 - done.0) as i8 as u8);
+                g.push(((d.1 - done.1) as u8 & 0x7f) | if mv { 0x80 } else { 0 });
+                done = d;
+            }
+            pen = to;
+        };
+        for line in self.lines.iter().filter(|l| l.len() > 1) {
+            step(line[0], true, &mut g);
+            for &p in line[1..].iter() {
+                step(p, false, &mut g);
+            }
+        }
+        step((self.advance, 0.), true, &mut g);
+        g
+    }
+
+    // (x1, y1, x2, y2) of the strokes
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        let mut b = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+        for &(x, y) in self.lines.iter().flatten() {
+            b = (b.0.min(x), b.1.min(y), b.2.max(x), b.3.max(y));
+        }
+        if b.0 > b.2 {
+            (0., 0., self.advance, 0.)
+        } else {
+            b
+        }
+    }
+
+    fn add(&mut self, other: &Strokes, s: f64, dx: f64, dy: f64) {
+        for line in other.lines.iter() {
+            self.lines.push(
+                line.iter()
+                    .map(|&(x, y)| (x * s + dx, y * s + dy))
+                    .collect(),
+            );
+        }
+    }
+}
+
+// The marks of the recipes, with the origin at the point they are put on
+const MARKS: &[(&str, &str)] = &[
+    ("grave", "M1,0 L-3,4"),
+    ("acute", "M-1,0 L3,4"),
+    ("circumflex", "M-4,0 L0,4 L4,0"),
+    ("caron", "M-4,4 L0,0 L4,4"),
+    ("tilde", "M-5,0 L-4,2 L-2,3 L2,1 L4,2 L5,4"),
+    (
+        "diaeresis",
+        "M-4,0 L-5,1 L-4,2 L-3,1 L-4,0 M4,0 L3,1 L4,2 L5,1 L4,0",
+    ),
+    ("dot", "M0,0 L-1,1 L0,2 L1,1 L0,0"),
+    ("ring", "M-1,0 L-2,1 L-2,3 L-1,4 L1,4 L2,3 L2,1 L1,0 L-1,0"),
+    ("macron", "M-5,1 L5,1"),
+    ("breve", "M-4,4 L-3,1 L-1,0 L1,0 L3,1 L4,4"),
+    ("dblacute", "M-4,0 L-1,4 M1,0 L4,4"),
+    (
+        "dialytika_tonos",
+        "M-4,0 L-5,1 L-4,2 L-3,1 L-4,0 M4,0 L3,1 L4,2 L5,1 L4,0 M-1,0 L2,4",
+    ),
+    ("turned_comma", "M1,0 L0,1 L0,3 L1,4"),
+    ("cedilla", "M0,0 L1,-2 L3,-3 L3,-5 L1,-6 L-2,-6"),
+    ("comma", "M1,-2 L0,-3 L-1,-2 L0,-1 L1,-2 L1,-4 L0,-6 L-1,-7"),
+    ("ogonek", "M0,0 L-2,-2 L-2,-4 L-1,-5 L1,-5"),
+];
+
+// The glyphs of the Unicode font that are not in the default font, in
+// an order where the glyphs a recipe takes come before it. A recipe is
+// a list of:
+//
+//   Mx,y Lx,y   strokes in font units, y up from the base line
+//   @hhhh       the strokes of the glyph of the code point hhhh
+//   *s,dx,dy    the scaling and offset of the following @ glyphs
+//   ^mark       a mark centered 2 units over the strokes so far
+//   _mark       a mark centered under them, or at their right end for
+//               an ogonek
+//   +w          the advance, which is else the one of the first @ glyph
+const RECIPES: &[(u32, &str)] = &[
+    // Latin-1 Supplement
+    (0xa0, "@20"),
+    (0xa1, "M5,14 L4,13 L5,12 L6,13 L5,14 M5,9 L5,-7 +10"),
+    (0xa2, "@63 M10,17 L10,-3"),
+    (
+        0xa3,
+        "M16,17 L15,19 L13,21 L10,21 L8,20 L7,18 L7,3 L6,1 L4,0 L3,0 L3,1 L5,2 L8,1 L11,0 \
+            L14,0 L16,2 M3,11 L12,11 +19",
+    ),
+    (
+        0xa4,
+        "M8,15 L6,14 L5,12 L5,8 L6,6 L8,5 L12,5 L14,6 L15,8 L15,12 L14,14 L12,15 L8,15 \
+            M3,17 L6,14 M17,17 L14,14 M3,3 L6,6 M17,3 L14,6 +20",
+    ),
+    (0xa5, "@59 M4,9 L14,9 M4,5 L14,5"),
+    (0xa6, "@7c"),
+    (
+        0xa7,
+        "M13,18 L11,21 L7,21 L5,19 L5,17 L7,15 L11,13 L13,11 L13,8 L11,6 M7,15 L5,13 \
+            L5,10 L7,8 L11,6 L13,4 L13,2 L11,0 L7,0 L5,3 +18",
+    ),
+    (
+        0xa8,
+        "M3,17 L2,18 L3,19 L4,18 L3,17 M9,17 L8,18 L9,19 L10,18 L9,17 +12",
+    ),
+    (
+        0xa9,
+        "M21,10 L21,14 L19,16 L17,19 L14,20 L8,20 L5,19 L3,16 L1,14 L1,7 L3,5 L5,2 L8,1 \
+            L11,0 L14,1 L17,2 L19,5 L21,7 L21,10 *0.5,6,5 @43 +23",
+    ),
+    (0xaa, "*0.5,3,10 @61 M4,8 L11,8 +14"),
+    (0xab, "M8,13 L3,8 L8,3 M14,13 L9,8 L14,3 +17"),
+    (0xac, "M4,12 L22,12 L22,6 +26"),
+    (0xad, "@2d"),
+    (
+        0xae,
+        "M21,10 L21,14 L19,16 L17,19 L14,20 L8,20 L5,19 L3,16 L1,14 L1,7 L3,5 L5,2 L8,1 \
+            L11,0 L14,1 L17,2 L19,5 L21,7 L21,10 *0.5,5,5 @52 +23",
+    ),
+    (0xaf, "M2,20 L12,20 +14"),
+    (
+        0xb0,
+        "M7,21 L5,20 L4,18 L5,16 L7,15 L9,16 L10,18 L9,20 L7,21 +14",
+    ),
+    (0xb1, "M13,19 L13,5 M4,12 L22,12 M4,1 L22,1 +26"),
+    (0xb2, "*0.5,2,10 @32 +13"),
+    (0xb3, "*0.5,2,10 @33 +13"),
+    (0xb4, "M5,17 L9,21 +12"),
+    (
+        0xb5,
+        "M4,14 L4,-7 M4,4 L5,1 L7,0 L10,0 L12,1 L15,4 M15,14 L15,0 +19",
+    ),
+    (
+        0xb6,
+        "M10,12 L7,12 L5,13 L4,15 L4,18 L5,20 L7,21 L17,21 M10,21 L10,-3 M15,21 L15,-3 \
+            +20",
+    ),
+    (0xb7, "M5,10 L4,9 L5,8 L6,9 L5,10 +10"),
+    (0xb8, "M5,0 L6,-2 L8,-3 L8,-5 L6,-6 L3,-6 +12"),
+    (0xb9, "*0.5,1,10 @31 +11"),
+    (0xba, "*0.5,3,10 @6f M4,8 L11,8 +14"),
+    (0xbb, "M3,13 L8,8 L3,3 M9,13 L14,8 L9,3 +17"),
+    (0xbc, "*0.5,1,10 @31 M17,21 L5,0 *0.5,11,0 @34 +23"),
+    (0xbd, "*0.5,1,10 @31 M17,21 L5,0 *0.5,11,0 @32 +23"),
+    (0xbe, "*0.5,1,10 @33 M17,21 L5,0 *0.5,11,0 @34 +23"),
+    (
+        0xbf,
+        "M15,-2 L15,-3 L14,-5 L13,-6 L11,-7 L7,-7 L5,-6 L4,-5 L3,-3 L3,-1 L4,1 L5,2 \
+            L9,4 L9,7 M9,12 L10,13 L9,14 L8,13 L9,12 +18",
+    ),
+    (0xc0, "@41 ^grave"),
+    (0xc1, "@41 ^acute"),
+    (0xc2, "@41 ^circumflex"),
+    (0xc3, "@41 ^tilde"),
+    (0xc4, "@41 ^diaeresis"),
+    (0xc5, "@41 ^ring"),
+    (
+        0xc6,
+        "M12,21 L1,0 M5,7 L12,7 M12,21 L24,21 M12,21 L12,0 L24,0 M12,11 L20,11 +26",
+    ),
+    (0xc7, "@43 _cedilla"),
+    (0xc8, "@45 ^grave"),
+    (0xc9, "@45 ^acute"),
+    (0xca, "@45 ^circumflex"),
+    (0xcb, "@45 ^diaeresis"),
+    (0xcc, "@49 ^grave"),
+    (0xcd, "@49 ^acute"),
+    (0xce, "@49 ^circumflex"),
+    (0xcf, "@49 ^diaeresis"),
+    (0xd0, "@44 M1,11 L9,11"),
+    (0xd1, "@4e ^tilde"),
+    (0xd2, "@4f ^grave"),
+    (0xd3, "@4f ^acute"),
+    (0xd4, "@4f ^circumflex"),
+    (0xd5, "@4f ^tilde"),
+    (0xd6, "@4f ^diaeresis"),
+    (0xd7, "M6,15 L20,3 M20,15 L6,3 +26"),
+    (0xd8, "@4f M2,-1 L20,22"),
+    (0xd9, "@55 ^grave"),
+    (0xda, "@55 ^acute"),
+    (0xdb, "@55 ^circumflex"),
+    (0xdc, "@55 ^diaeresis"),
+    (0xdd, "@59 ^acute"),
+    (
+        0xde,
+        "M4,21 L4,0 M4,16 L13,16 L16,15 L17,14 L18,12 L18,9 L17,7 L16,6 L13,5 L4,5 +21",
+    ),
+    (
+        0xdf,
+        "M4,0 L4,16 L5,19 L7,21 L10,21 L12,20 L13,18 L13,16 L12,14 L9,12 L12,11 L14,9 \
+            L15,7 L15,4 L14,2 L12,1 L10,0 L8,0 +18",
+    ),
+    (0xe0, "@61 ^grave"),
+    (0xe1, "@61 ^acute"),
+    (0xe2, "@61 ^circumflex"),
+    (0xe3, "@61 ^tilde"),
+    (0xe4, "@61 ^diaeresis"),
+    (0xe5, "@61 ^ring"),
+    (0xe6, "@61 *1,12,0 @65 +30"),
+    (0xe7, "@63 _cedilla"),
+    (0xe8, "@65 ^grave"),
+    (0xe9, "@65 ^acute"),
+    (0xea, "@65 ^circumflex"),
+    (0xeb, "@65 ^diaeresis"),
+    (0x131, "M4,14 L4,0 +8"),
+    (0x237, "M6,14 L6,-3 L5,-6 L3,-7 L1,-7 +10"),
+    (0xec, "@131 ^grave"),
+    (0xed, "@131 ^acute"),
+    (0xee, "@131 ^circumflex"),
+    (0xef, "@131 ^diaeresis"),
+    (
+        0xf0,
+        "M16,8 L15,11 L13,13 L11,14 L8,14 L6,13 L4,11 L3,8 L3,6 L4,3 L6,1 L8,0 L11,0 \
+            L13,1 L15,3 L16,6 L16,12 L15,16 L13,19 L10,21 M7,17 L14,20 +19",
+    ),
+    (0xf1, "@6e ^tilde"),
+    (0xf2, "@6f ^grave"),
+    (0xf3, "@6f ^acute"),
+    (0xf4, "@6f ^circumflex"),
+    (0xf5, "@6f ^tilde"),
+    (0xf6, "@6f ^diaeresis"),
+    (
+        0xf7,
+        "M4,9 L22,9 M13,16 L12,15 L13,14 L14,15 L13,16 M13,4 L12,3 L13,2 L14,3 L13,4 \
+            +26",
+    ),
+    (0xf8, "@6f M3,-1 L16,15"),
+    (0xf9, "@75 ^grave"),
+    (0xfa, "@75 ^acute"),
+    (0xfb, "@75 ^circumflex"),
+    (0xfc, "@75 ^diaeresis"),
+    (0xfd, "@79 ^acute"),
+    (0xfe, "@70 M4,14 L4,21"),
+    (0xff, "@79 ^diaeresis"),
+    // Latin Extended-A
+    (0x100, "@41 ^macron"),
+    (0x101, "@61 ^macron"),
+    (0x102, "@41 ^breve"),
+    (0x103, "@61 ^breve"),
+    (0x104, "@41 _ogonek"),
+    (0x105, "@61 _ogonek"),
+    (0x106, "@43 ^acute"),
+    (0x107, "@63 ^acute"),
+    (0x108, "@43 ^circumflex"),
+    (0x109, "@63 ^circumflex"),
+    (0x10a, "@43 ^dot"),
+    (0x10b, "@63 ^dot"),
+    (0x10c, "@43 ^caron"),
+    (0x10d, "@63 ^caron"),
+    (0x10e, "@44 ^caron"),
+    (0x10f, "@64 M19,21 L19,18 L18,16 +22"),
+    (0x110, "@d0"),
+    (0x111, "@64 M11,18 L19,18"),
+    (0x112, "@45 ^macron"),
+    (0x113, "@65 ^macron"),
+    (0x114, "@45 ^breve"),
+    (0x115, "@65 ^breve"),
+    (0x116, "@45 ^dot"),
+    (0x117, "@65 ^dot"),
+    (0x118, "@45 _ogonek"),
+    (0x119, "@65 _ogonek"),
+    (0x11a, "@45 ^caron"),
+    (0x11b, "@65 ^caron"),
+    (0x11c, "@47 ^circumflex"),
+    (0x11d, "@67 ^circumflex"),
+    (0x11e, "@47 ^breve"),
+    (0x11f, "@67 ^breve"),
+    (0x120, "@47 ^dot"),
+    (0x121, "@67 ^dot"),
+    (0x122, "@47 _comma"),
+    (0x123, "@67 ^turned_comma"),
+    (0x124, "@48 ^circumflex"),
+    (0x125, "@68 ^circumflex"),
+    (0x126, "@48 M1,16 L21,16"),
+    (0x127, "@68 M1,18 L9,18"),
+    (0x128, "@49 ^tilde"),
+    (0x129, "@131 ^tilde"),
+    (0x12a, "@49 ^macron"),
+    (0x12b, "@131 ^macron"),
+    (0x12c, "@49 ^breve"),
+    (0x12d, "@131 ^breve"),
+    (0x12e, "@49 _ogonek"),
+    (0x12f, "@69 _ogonek"),
+    (0x130, "@49 ^dot"),
+    (0x132, "@49 *1,6,0 @4a"),
+    (0x133, "@69 *1,6,0 @6a"),
+    (0x134, "@4a ^circumflex"),
+    (0x135, "@237 ^circumflex"),
+    (0x136, "@4b _comma"),
+    (0x137, "@6b _comma"),
+    (0x138, "M4,14 L4,0 M14,14 L4,4 M8,8 L15,0 +17"),
+    (0x139, "@4c ^acute"),
+    (0x13a, "@6c ^acute"),
+    (0x13b, "@4c _comma"),
+    (0x13c, "@6c _comma"),
+    (0x13d, "@4c M9,21 L9,18 L8,16"),
+    (0x13e, "@6c M8,21 L8,18 L7,16 +11"),
+    (0x13f, "@4c M10,11 L9,10 L10,9 L11,10 L10,11"),
+    (0x140, "@6c M8,11 L7,10 L8,9 L9,10 L8,11 +11"),
+    (0x141, "@4c M1,8 L9,14"),
+    (0x142, "@6c M1,9 L7,13"),
+    (0x143, "@4e ^acute"),
+    (0x144, "@6e ^acute"),
+    (0x145, "@4e _comma"),
+    (0x146, "@6e _comma"),
+    (0x147, "@4e ^caron"),
+    (0x148, "@6e ^caron"),
+    (0x149, "M2,21 L2,18 L1,16 *1,3,0 @6e"),
+    (
+        0x14a,
+        "M4,21 L4,0 M4,21 L18,0 M18,21 L18,-3 L17,-6 L15,-7 L13,-7 +22",
+    ),
+    (
+        0x14b,
+        "M4,14 L4,0 M4,10 L7,13 L9,14 L12,14 L14,13 L15,10 L15,-3 L14,-6 L12,-7 L10,-7 \
+             +19",
+    ),
+    (0x14c, "@4f ^macron"),
+    (0x14d, "@6f ^macron"),
+    (0x14e, "@4f ^breve"),
+    (0x14f, "@6f ^breve"),
+    (0x150, "@4f ^dblacute"),
+    (0x151, "@6f ^dblacute"),
+    (
+        0x152,
+        "M11,21 L9,21 L7,20 L5,18 L4,16 L3,13 L3,8 L4,5 L5,3 L7,1 L9,0 L11,0 M11,21 \
+             L11,0 M11,21 L24,21 M11,11 L19,11 M11,0 L24,0 +26",
+    ),
+    (0x153, "@6f *1,13,0 @65"),
+    (0x154, "@52 ^acute"),
+    (0x155, "@72 ^acute"),
+    (0x156, "@52 _comma"),
+    (0x157, "@72 _comma"),
+    (0x158, "@52 ^caron"),
+    (0x159, "@72 ^caron"),
+    (0x15a, "@53 ^acute"),
+    (0x15b, "@73 ^acute"),
+    (0x15c, "@53 ^circumflex"),
+    (0x15d, "@73 ^circumflex"),
+    (0x15e, "@53 _cedilla"),
+    (0x15f, "@73 _cedilla"),
+    (0x160, "@53 ^caron"),
+    (0x161, "@73 ^caron"),
+    (0x162, "@54 _cedilla"),
+    (0x163, "@74 _cedilla"),
+    (0x164, "@54 ^caron"),
+    (0x165, "@74 M9,21 L9,18 L8,16 +13"),
+    (0x166, "@54 M4,11 L12,11"),
+    (0x167, "@74 M2,8 L9,8"),
+    (0x168, "@55 ^tilde"),
+    (0x169, "@75 ^tilde"),
+    (0x16a, "@55 ^macron"),
+    (0x16b, "@75 ^macron"),
+    (0x16c, "@55 ^breve"),
+    (0x16d, "@75 ^breve"),
+    (0x16e, "@55 ^ring"),
+    (0x16f, "@75 ^ring"),
+    (0x170, "@55 ^dblacute"),
+    (0x171, "@75 ^dblacute"),
+    (0x172, "@55 _ogonek"),
+    (0x173, "@75 _ogonek"),
+    (0x174, "@57 ^circumflex"),
+    (0x175, "@77 ^circumflex"),
+    (0x176, "@59 ^circumflex"),
+    (0x177, "@79 ^circumflex"),
+    (0x178, "@59 ^diaeresis"),
+    (0x179, "@5a ^acute"),
+    (0x17a, "@7a ^acute"),
+    (0x17b, "@5a ^dot"),
+    (0x17c, "@7a ^dot"),
+    (0x17d, "@5a ^caron"),
+    (0x17e, "@7a ^caron"),
+    (0x17f, "M5,0 L5,17 L6,20 L8,21 L10,21 +12"),
+    // Romanian letters of Latin Extended-B
+    (0x218, "@53 _comma"),
+    (0x219, "@73 _comma"),
+    (0x21a, "@54 _comma"),
+    (0x21b, "@74 _comma"),
+    // Greek
+    (0x391, "@41"),
+    (0x392, "@42"),
+    (0x393, "M4,21 L4,0 M4,21 L16,21 +17"),
+    (0x394, "M9,21 L1,0 L17,0 L9,21 +18"),
+    (0x395, "@45"),
+    (0x396, "@5a"),
+    (0x397, "@48"),
+    (0x398, "@4f M8,11 L14,11"),
+    (0x399, "@49"),
+    (0x39a, "@4b"),
+    (0x39b, "M9,21 L1,0 M9,21 L17,0 +18"),
+    (0x39c, "@4d"),
+    (0x39d, "@4e"),
+    (0x39e, "M3,21 L17,21 M6,11 L14,11 M3,0 L17,0 +20"),
+    (0x39f, "@4f"),
+    (0x3a0, "M4,21 L4,0 M18,21 L18,0 M4,21 L18,21 +22"),
+    (0x3a1, "@50"),
+    (0x3a3, "M17,21 L3,21 L10,11 L3,0 L17,0 +20"),
+    (0x3a4, "@54"),
+    (0x3a5, "@59"),
+    (
+        0x3a6,
+        "M11,21 L11,0 M9,16 L6,15 L4,13 L3,10 L4,7 L6,5 L9,4 L13,4 L16,5 L18,7 L19,10 \
+             L18,13 L16,15 L13,16 L9,16 +22",
+    ),
+    (0x3a7, "@58"),
+    (
+        0x3a8,
+        "M11,21 L11,0 M2,15 L3,15 L4,14 L5,10 L6,8 L7,7 L10,6 L12,6 L15,7 L16,8 L17,10 \
+             L18,14 L19,15 L20,15 +22",
+    ),
+    (
+        0x3a9,
+        "M3,0 L7,0 L4,7 L3,11 L3,15 L4,18 L6,20 L9,21 L13,21 L16,20 L18,18 L19,15 \
+             L19,11 L18,7 L15,0 L19,0 +22",
+    ),
+    (0x386, "*1,3,0 @391 M1,17 L3,21"),
+    (0x388, "*1,3,0 @395 M1,17 L3,21"),
+    (0x389, "*1,3,0 @397 M1,17 L3,21"),
+    (0x38a, "*1,3,0 @399 M1,17 L3,21"),
+    (0x38c, "*1,3,0 @39f M1,17 L3,21"),
+    (0x38e, "*1,3,0 @3a5 M1,17 L3,21"),
+    (0x38f, "*1,3,0 @3a9 M1,17 L3,21"),
+    (0x3aa, "@399 ^diaeresis"),
+    (0x3ab, "@3a5 ^diaeresis"),
+    (
+        0x3b1,
+        "M8,14 L6,13 L4,11 L3,8 L3,6 L4,3 L6,1 L8,0 L10,0 L12,1 L14,4 L16,8 L17,14 \
+             M8,14 L10,14 L11,13 L12,11 L14,3 L15,1 L16,0 L17,0 +20",
+    ),
+    (
+        0x3b2,
+        "M4,-7 L4,17 L5,20 L7,21 L10,21 L12,20 L13,18 L13,15 L12,13 L9,12 L12,11 \
+             L14,9 L15,7 L15,4 L14,2 L12,1 L10,0 L7,0 L5,1 L4,3 +18",
+    ),
+    (0x3b3, "M2,14 L4,14 L6,12 L9,4 L9,-7 M16,14 L9,4 +18"),
+    (
+        0x3b4,
+        "M12,21 L8,21 L6,20 L6,18 L8,16 L12,14 L8,14 L6,13 L4,11 L3,8 L3,6 L4,3 L6,1 \
+             L8,0 L11,0 L13,1 L15,3 L16,6 L16,8 L15,11 L13,13 L12,14 +19",
+    ),
+    (
+        0x3b5,
+        "M14,12 L12,14 L8,14 L6,13 L5,11 L6,9 L8,8 L11,8 M8,8 L5,7 L4,5 L4,3 L6,1 \
+             L8,0 L11,0 L13,1 L14,2 +17",
+    ),
+    (
+        0x3b6,
+        "M5,21 L13,21 L8,15 L5,11 L3,7 L3,3 L5,1 L9,0 L11,-1 L11,-4 L9,-6 L7,-7 +16",
+    ),
+    (
+        0x3b7,
+        "M4,14 L4,0 M4,10 L7,13 L9,14 L12,14 L14,13 L15,10 L15,-7 +19",
+    ),
+    (0x3b8, "@30 M3,11 L17,11"),
+    (0x3b9, "M4,14 L4,3 L5,1 L7,0 L8,0 +10"),
+    (0x3ba, "M4,14 L4,0 M14,14 L4,6 M8,9 L15,0 +17"),
+    (0x3bb, "M3,21 L5,21 L7,19 L15,0 M9,13 L3,0 +17"),
+    (0x3bc, "@b5"),
+    (0x3bd, "M2,14 L8,0 L13,9 L14,14 +16"),
+    (
+        0x3be,
+        "M5,21 L12,21 L7,19 L5,17 L5,15 L7,13 L11,12 L7,11 L4,9 L3,7 L3,5 L5,2 L9,0 \
+             L11,-1 L11,-4 L9,-6 L7,-7 +16",
+    ),
+    (0x3bf, "@6f"),
+    (
+        0x3c0,
+        "M2,14 L17,14 M6,14 L6,0 M13,14 L13,3 L14,1 L16,0 +19",
+    ),
+    (
+        0x3c1,
+        "M4,-7 L4,7 L5,11 L7,13 L9,14 L11,14 L13,13 L15,11 L16,8 L16,6 L15,3 L13,1 \
+             L11,0 L8,0 L6,1 L4,3 +19",
+    ),
+    (
+        0x3c2,
+        "M15,11 L13,13 L11,14 L8,14 L6,13 L4,11 L3,8 L3,6 L4,3 L6,1 L9,0 L11,-1 \
+             L11,-4 L9,-6 L7,-7 +18",
+    ),
+    (
+        0x3c3,
+        "M18,14 L8,14 L6,13 L4,11 L3,8 L3,6 L4,3 L6,1 L8,0 L11,0 L13,1 L15,3 L16,6 \
+             L16,8 L15,11 L13,13 L11,14 +19",
+    ),
+    (0x3c4, "M2,14 L15,14 M9,14 L9,3 L10,1 L12,0 L13,0 +17"),
+    (
+        0x3c5,
+        "M3,14 L3,5 L4,2 L6,0 L9,0 L12,1 L14,3 L15,6 L15,10 L14,14 +18",
+    ),
+    (
+        0x3c6,
+        "M9,14 L6,13 L4,11 L3,8 L3,6 L4,3 L6,1 L9,0 L12,0 L15,1 L17,3 L18,6 L18,8 \
+             L17,11 L15,13 L13,14 L11,12 L11,-7 +21",
+    ),
+    (
+        0x3c7,
+        "M2,14 L4,14 L6,12 L14,-5 L16,-7 L18,-7 M17,14 L3,-7 +20",
+    ),
+    (
+        0x3c8,
+        "M11,21 L11,-7 M2,14 L3,13 L3,5 L4,2 L6,1 L9,0 L13,0 L16,1 L18,2 L19,5 \
+             L19,13 L20,14 +22",
+    ),
+    (
+        0x3c9,
+        "M6,14 L4,12 L3,9 L3,5 L4,2 L6,0 L8,0 L10,2 L11,5 L11,8 M11,5 L12,2 L14,0 \
+             L16,0 L18,2 L19,5 L19,9 L18,12 L16,14 +22",
+    ),
+    (0x3ac, "@3b1 ^acute"),
+    (0x3ad, "@3b5 ^acute"),
+    (0x3ae, "@3b7 ^acute"),
+    (0x3af, "@3b9 ^acute"),
+    (0x3ca, "@3b9 ^diaeresis"),
+    (0x3cb, "@3c5 ^diaeresis"),
+    (0x3cc, "@3bf ^acute"),
+    (0x3cd, "@3c5 ^acute"),
+    (0x3ce, "@3c9 ^acute"),
+    (0x390, "@3b9 ^dialytika_tonos"),
+    (0x3b0, "@3c5 ^dialytika_tonos"),
+    // Cyrillic
+    (0x400, "@415 ^grave"),
+    (0x401, "@415 ^diaeresis"),
+    (
+        0x402,
+        "M1,21 L13,21 M6,21 L6,0 M6,10 L7,12 L9,13 L14,13 L16,11 L17,8 L17,0 L16,-2 \
+             L14,-3 L12,-3 +20",
+    ),
+    (0x403, "@413 ^acute"),
+    (0x404, "@421 M3,11 L12,11"),
+    (0x405, "@53"),
+    (0x406, "@49"),
+    (0x407, "@49 ^diaeresis"),
+    (0x408, "@4a"),
+    (0x409, "@41b *1,12,0 @42c +29"),
+    (0x40a, "@41d *1,13,0 @42c +30"),
+    (
+        0x40b,
+        "M1,21 L13,21 M6,21 L6,0 M6,10 L7,12 L9,13 L14,13 L16,11 L17,8 L17,0 +20",
+    ),
+    (0x40c, "@41a ^acute"),
+    (0x40d, "@418 ^grave"),
+    (0x40e, "@423 ^breve"),
+    (0x40f, "M3,21 L3,0 L15,0 L15,21 M9,0 L9,-3 +18"),
+    (0x42a, "*1,2,0 @42c M0,21 L5,21"),
+    (0x450, "@435 ^grave"),
+    (0x451, "@435 ^diaeresis"),
+    (
+        0x452,
+        "M4,21 L4,0 M1,18 L9,18 M4,10 L7,13 L9,14 L12,14 L14,13 L15,10 L15,-3 L14,-6 \
+             L12,-7 L10,-7 +19",
+    ),
+    (0x453, "@433 ^acute"),
+    (0x454, "@441 M3,8 L10,8"),
+    (0x455, "@73"),
+    (0x456, "@69"),
+    (0x457, "@131 ^diaeresis"),
+    (0x458, "@6a"),
+    (0x459, "@43b *1,10,0 @44c +25"),
+    (0x45a, "@43d *1,10,0 @44c +25"),
+    (0x45b, "@127"),
+    (0x45c, "@43a ^acute"),
+    (0x45d, "@438 ^grave"),
+    (0x45e, "@443 ^breve"),
+    (0x45f, "M3,15 L3,0 L13,0 L13,15 M8,0 L8,-2 +16"),
+    (0x490, "M3,0 L3,21 L15,21 L15,24 +17"),
+    (0x491, "M3,0 L3,15 L13,15 L13,18 +15"),
+];
+
+fn parse_pair(s: &str) -> (f64, f64) {
+    let mut it = s.split(',').map(|v| v.parse::<f64>().unwrap());
+    (it.next().unwrap(), it.next().unwrap())
+}
+
+fn mark(name: &str) -> Strokes {
+    let m = MARKS.iter().find(|m| m.0 == name).unwrap();
+    recipe(m.1, &BTreeMap::new())
+}
+
+fn recipe(r: &str, glyphs: &BTreeMap<u32, Strokes>) -> Strokes {
+    let mut g = Strokes {
+        lines: Vec::new(),
+        advance: f64::NAN,
+    };
+    let (mut s, mut dx, mut dy) = (1., 0., 0.);
+    for tok in r.split_whitespace() {
+        let (op, arg) = tok.split_at(1);
+        match op {
+            "M" => g.lines.push(vec![parse_pair(arg)]),
+            "L" => g.lines.last_mut().unwrap().push(parse_pair(arg)),
+            "@" => {
+                let src = &glyphs[&u32::from_str_radix(arg, 16).unwrap()];
+                g.add(src, s, dx, dy);
+                if g.advance.is_nan() {
+                    g.advance = src.advance * s + dx;
+                }
+            }
+            "*" => {
+                let (a, b) = arg.split_once(',').unwrap();
+                s = a.parse().unwrap();
+                (dx, dy) = parse_pair(b);
+            }
+            "^" | "_" => {
+                let (x1, y1, x2, y2) = g.bounds();
+                let (mx, my) = match (op, arg) {
+                    ("^", _) => ((x1 + x2) / 2., y2 + 2.),
+                    (_, "ogonek") => (x2, y1),
+                    _ => ((x1 + x2) / 2., y1),
+                };
+                g.add(&mark(arg), 1., mx, my);
+            }
+            "+" => g.advance = arg.parse().unwrap(),
+            _ => panic!("bad glyph recipe {}", r),
+        }
+    }
+    g
+}
+
+// The built-in Unicode font, see GsvFont::new_unicode()
+fn build_unicode() -> GsvFont {
+    let font = GsvFont::new_default();
+    let mut glyphs = BTreeMap::new();
+    let mut add = |code: u32, c: u32| {
+        glyphs.insert(code, Strokes::decode(font.glyph(c).unwrap()));
+    };
+    for c in 0x20..0x7f {
+        add(c, c);
+    }
+    // А..п at 128..176 and р..я at 224..240 in code page 866
+    for c in 0x410..0x440 {
+        add(c, c - 0x410 + 128);
+    }
+    for c in 0x440..0x450 {
+        add(c, c - 0x440 + 224);
+    }
+    for &(code, r) in RECIPES.iter() {
+        let g = recipe(r, &glyphs);
+        glyphs.insert(code, g);
+    }
+    let glyphs: Vec<(u32, Vec<u8>)> = glyphs.iter().map(|(&c, g)| (c, g.encode())).collect();
+    GsvFont::from_glyphs(font.base_height() as u16, &glyphs).unwrap()
+}
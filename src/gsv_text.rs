@@ -17,13 +17,11 @@
 //
 //----------------------------------------------------------------------------
 
-use std::fs::File;
-use std::io::Read;
-use std::ptr::null_mut;
+use std::io;
 
-use crate::array::PodArray;
 use crate::basics::PathCmd;
 use crate::bounding_rect::bounding_rect_single;
+use crate::gsv_font::GsvFont;
 use crate::math_stroke::{LineCap, LineJoin};
 use crate::{ VertexSource, Transformer };
 use crate::conv_stroke::ConvStroke;
@@ -37,27 +35,27 @@ enum Status {
     Glyph,
 }
 
+// Text in stroke fonts. It starts with the built-in Unicode font, more
+// can be added and one chosen; a character without a glyph in the chosen
+// font is taken from the first other font that has it, or else left out.
 pub struct GsvText {
     x: f64,
     y: f64,
     start_x: f64,
+    start_y: f64,
     width: f64,
     height: f64,
     space: f64,
     line_space: f64,
-    chr: [u8; 2],
-    text: *mut u8,
-    text_buf: PodArray<u8>,
-    cur_chr: *mut u8,
-    font: *const u8,
-    loaded_font: PodArray<u8>,
+    text: Vec<char>,
+    cur_chr: usize,
+    fonts: Vec<GsvFont>,
+    font: usize,
     status: Status,
-    big_endian: bool,
     flip: bool,
-    indices: *mut u8,
-    glyphs: *mut u8,
-    bglyph: *mut u8,
-    eglyph: *mut u8,
+    gfont: usize,
+    bglyph: usize,
+    eglyph: usize,
     w: f64,
     h: f64,
 }
@@ -68,57 +66,51 @@ impl GsvText {
             x: 0.0,
             y: 0.0,
             start_x: 0.0,
+            start_y: 0.0,
             width: 0.0,
             height: 0.0,
             space: 0.0,
             line_space: 0.0,
-            chr: [0; 2],
-            text: std::ptr::null_mut::<u8>(),
-            text_buf: PodArray::<u8>::new(),
-            cur_chr: null_mut(),
-            font: GSV_DEFAULT_FONT.as_ptr(),
-            loaded_font: PodArray::<u8>::new(),
+            text: Vec::new(),
+            cur_chr: 0,
+            fonts: vec![GsvFont::new_unicode()],
+            font: 0,
             status: Status::Initial,
-            big_endian: false,
             flip: false,
-            indices: 0 as *mut u8,
-            glyphs: 0 as *mut u8,
-            bglyph: 0 as *mut u8,
-            eglyph: 0 as *mut u8,
+            gfont: 0,
+            bglyph: 0,
+            eglyph: 0,
             w: 0.0,
             h: 0.0,
         }
-        //chr[0] = chr[1] = 0;
+    }
 
-        //int t = 1;
-        //if(*(char*)&t == 0) big_endian = true;
+    // Adds the font and returns its index, the current font is unchanged
+    pub fn add_font(&mut self, font: GsvFont) -> usize {
+        self.fonts.push(font);
+        self.fonts.len() - 1
     }
 
-    pub fn set_font(&mut self, font: *const u8) {
-        self.font = font;
-        if self.font.is_null() && !self.loaded_font.is_empty() {
-            self.font = &self.loaded_font[0];
+    // Makes the font of index idx the current one
+    pub fn set_font(&mut self, idx: usize) {
+        if idx < self.fonts.len() {
+            self.font = idx;
         }
     }
 
-    pub fn load_font(&mut self, file: &str) {
-        self.loaded_font.clear();
-        let mut fd = match File::open(file) {
-            Ok(fd) => fd,
-            Err(_) => return,
-        };
+    pub fn font(&self) -> usize {
+        self.font
+    }
 
-        let len = match fd.metadata() {
-            Ok(data) => data.len() as usize,
-            Err(_) => return,
-        };
+    pub fn fonts(&self) -> &[GsvFont] {
+        &self.fonts
+    }
 
-        if len > 0 {
-            let mut buf = vec![0; len];
-            fd.read(&mut buf).unwrap();
-            self.loaded_font = buf;
-            self.font = &self.loaded_font[0];
-        }
+    // Loads a font file, adds it and makes it the current font
+    pub fn load_font(&mut self, file: &str) -> io::Result<usize> {
+        let idx = self.add_font(GsvFont::load(file)?);
+        self.font = idx;
+        Ok(idx)
     }
 
     pub fn set_flip(&mut self, flip_y: bool) {
@@ -139,115 +131,121 @@ impl GsvText {
         self.x = x;
         self.y = y;
         self.start_x = x;
+        self.start_y = y;
     }
 
-    pub fn value(&self, p: *mut u8) -> u16 {
-        let mut v: u16;
-        let mut _u: u16;
-        unsafe {
-            if self.big_endian {
-                v = *(p as *const u8) as u16;
-                v = (v << 8) | (*(p as *const u8).offset(1)) as u16;
-            } else {
-                v = *(p as *const u8).offset(1) as u16;
-                _u = *(p as *const u8) as u16;
-                v = (v << 8) | (*(p as *const u8)) as u16;
-            }
-        }
-        v
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.chars().collect();
     }
 
-    pub fn set_text(&mut self, text: &str) {
-        if text.len() == 0 {
-            self.text_buf = self.chr.to_vec();
+    // The font and the glyph data range of c, see GsvText
+    fn find_glyph(&self, c: char) -> Option<(usize, usize, usize)> {
+        let code = c as u32;
+        std::iter::once(self.font)
+            .chain((0..self.fonts.len()).filter(|&i| i != self.font))
+            .find_map(|i| self.fonts[i].glyph_range(code).map(|(b, e)| (i, b, e)))
+    }
+
+    // The horizontal and vertical scale of the glyphs of font idx
+    fn scale(&self, idx: usize) -> (f64, f64) {
+        let base_height = self.fonts[idx].base_height();
+        let h = self.height / base_height;
+        let w = if self.width == 0.0 {
+            h
+        } else {
+            self.width / base_height
+        };
+        (w, if self.flip { -h } else { h })
+    }
+
+    pub fn text_width(&mut self) -> f64 {
+        self.bounding_rect().map_or(0.0, |(x1, _, x2, _)| x2 - x1)
+    }
+
+    // (x1, y1, x2, y2) of the strokes of the text at its start point
+    pub fn bounding_rect(&mut self) -> Option<(f64, f64, f64, f64)> {
+        let (mut x1, mut y1, mut x2, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        let (x, y) = (self.x, self.y);
+        self.x = self.start_x;
+        self.y = self.start_y;
+        let found = bounding_rect_single(self, 0, &mut x1, &mut y1, &mut x2, &mut y2);
+        self.x = x;
+        self.y = y;
+        if found {
+            Some((x1, y1, x2, y2))
         } else {
-            self.text_buf = text.as_bytes().to_vec();
+            None
         }
-        if self.text_buf[self.text_buf.len() - 1] != 0 {
-            self.text_buf.push(0);
+    }
+
+    // Pen advance of each line of the text: the glyph widths plus the
+    // space after each glyph, where bounding_rect() takes the strokes
+    pub fn line_advances(&self) -> Vec<f64> {
+        let mut lines = vec![0.0];
+        for &c in self.text.iter() {
+            if c == '\n' {
+                lines.push(0.0);
+            } else if let Some((i, b, e)) = self.find_glyph(c) {
+                let data = &self.fonts[i].data()[b..e];
+                let dx: i32 = data.chunks(2).map(|p| p[0] as i8 as i32).sum();
+                *lines.last_mut().unwrap() += dx as f64 * self.scale(i).0 + self.space;
+            }
         }
-        self.text = self.text_buf.as_mut_ptr();
+        lines
     }
 
-    pub fn text_width(&mut self) -> f64 {
-        let mut x1: f64 = 0.0;
-        let mut y1: f64 = 0.0;
-        let mut x2: f64 = 0.0;
-        let mut y2: f64 = 0.0;
-        bounding_rect_single(self, 0, &mut x1, &mut y1, &mut x2, &mut y2);
-        return x2 - x1;
+    // Pen advance of the last line of the text
+    pub fn text_advance(&self) -> f64 {
+        *self.line_advances().last().unwrap()
+    }
+
+    pub fn num_lines(&self) -> usize {
+        self.text.iter().filter(|&&c| c == '\n').count() + 1
+    }
+
+    // The distance of the base lines of two lines
+    pub fn line_height(&self) -> f64 {
+        self.height + self.line_space
+    }
+
+    // From the top of the capitals of the first line to the base line of
+    // the last one
+    pub fn text_height(&self) -> f64 {
+        self.num_lines() as f64 * self.line_height() - self.line_space
     }
 }
 
 impl VertexSource for GsvText {
     fn rewind(&mut self, _: u32) {
         self.status = Status::Initial;
-        if self.font.is_null() {
-            return;
-        }
-        self.indices = self.font as *mut u8;
-        unsafe {
-            let base_height = self.value(self.indices.offset(4)) as f64;
-            self.indices = self.indices.offset(self.value(self.indices) as isize);
-            self.glyphs = self.indices.offset(257 * 2) as *mut u8;
-            self.h = self.height / base_height;
-            self.w = if self.width == 0.0 {
-                self.h
-            } else {
-                self.width / base_height
-            };
-        }
-        if self.flip {
-            self.h = -self.h;
-        }
-        self.cur_chr = self.text;
+        self.cur_chr = 0;
     }
 
     fn vertex(&mut self, x: &mut f64, y: &mut f64) -> u32 {
-        let mut quit = false;
-        let (mut yc, yf): (i8, i8);
-        let (dx, dy): (i32, i32);
-        let mut idx: u32;
-
-        while !quit {
+        loop {
             match self.status {
                 Status::Initial => {
-                    if self.font.is_null() {
-                        quit = true;
-                    } else {
-                        self.status = Status::NextChar;
-                    }
+                    self.status = Status::NextChar;
                 }
                 Status::NextChar => {
-                    if unsafe { *self.cur_chr } == 0 {
-                        quit = true;
-                    } else {
-                        unsafe {
-                            idx = (*self.cur_chr) as u32 & 0xFF;
-                            self.cur_chr = self.cur_chr.offset(1);
-                        }
-                        if idx == '\n' as u32 {
-                            self.x = self.start_x;
-                            self.y -= if self.flip {
-                                -self.height - self.line_space
-                            } else {
-                                self.height + self.line_space
-                            };
-                            self.status = Status::NextChar;
+                    let c = match self.text.get(self.cur_chr) {
+                        Some(&c) => c,
+                        None => break,
+                    };
+                    self.cur_chr += 1;
+                    if c == '\n' {
+                        self.x = self.start_x;
+                        self.y -= if self.flip {
+                            -self.height - self.line_space
                         } else {
-                            idx = idx << 1;
-                            unsafe {
-                                self.bglyph =
-                                    self.glyphs
-                                        .offset(self.value(self.indices.offset(idx as isize))
-                                            as isize);
-                                self.eglyph = self
-                                    .glyphs
-                                    .offset(self.value(self.indices.offset(idx as isize + 2))
-                                        as isize);
-                            }
-                            self.status = Status::StartGlyph;
-                        }
+                            self.height + self.line_space
+                        };
+                    } else if let Some((i, b, e)) = self.find_glyph(c) {
+                        self.gfont = i;
+                        self.bglyph = b;
+                        self.eglyph = e;
+                        (self.w, self.h) = self.scale(i);
+                        self.status = Status::StartGlyph;
                     }
                 }
                 Status::StartGlyph => {
@@ -261,21 +259,16 @@ impl VertexSource for GsvText {
                         self.status = Status::NextChar;
                         self.x += self.space;
                     } else {
-                        unsafe {
-                            dx = *self.bglyph as i8 as i32;
-                            self.bglyph = self.bglyph.offset(1);
-                            yc = *self.bglyph as i8;
-                        }
-                        yf = (yc as u8 & 0x80) as i8;
-                        yc <<= 1;
-                        yc >>= 1;
-                        dy = yc as i32;
-                        self.bglyph = unsafe { self.bglyph.offset(1) };
+                        let data = self.fonts[self.gfont].data();
+                        let dx = data[self.bglyph] as i8 as i32;
+                        let yc = data[self.bglyph + 1];
+                        let dy = ((yc << 1) as i8 >> 1) as i32;
+                        self.bglyph += 2;
                         self.x += dx as f64 * self.w;
                         self.y += dy as f64 * self.h;
                         *x = self.x;
                         *y = self.y;
-                        return if yf != 0 {
+                        return if yc & 0x80 != 0 {
                             PathCmd::MoveTo as u32
                         } else {
                             PathCmd::LineTo as u32
@@ -329,7 +322,7 @@ impl<'a, T: Transformer> VertexSource for  GsvTextOutline<'a, T> {
     }
 }
 
-const GSV_DEFAULT_FONT: [u8; 4526] = [
+pub(crate) const GSV_DEFAULT_FONT: [u8; 4526] = [
     0x40, 0x00, 0x6c, 0x0f, 0x15, 0x00, 0x0e, 0x00, 0xf9, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
     0x00, 0x00, 0x00, 0x00, 0x0d, 0x0a, 0x0d, 0x0a, 0x46, 0x6f, 0x6e, 0x74, 0x20, 0x28, 0x63, 0x29,
     0x20, 0x4d, 0x69, 0x63, 0x72, 0x6f, 0x50, 0x72, 0x6f, 0x66, 0x20, 0x32, 0x37, 0x20, 0x53, 0x65,
@@ -0,0 +1,439 @@
+use crate::rendering_buffer::RenderBuf;
+use crate::{ImageSrc, PixFmt, PixFmtOwned, RenderBuffer, RowData};
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+//=============================================================ImageBuffer
+// An image that owns its pixel storage. The rows are stored top-down
+// without padding; with flip_y set the rendering buffer addresses them
+// bottom-up (negative stride), as the platform layer does for windows.
+//
+// The pixel formats and rows handed out by the buffer point into its
+// storage, so their lifetimes are tied to a borrow of the image and no
+// unsafe is needed at the call site. The RenderBuf it keeps is never
+// handed out, not even through the pixel formats, as a copy of it would
+// outlive the storage:
+//
+//   let mut img = ImageBuffer::<PixBgr24>::new(320, 200);
+//   let mut pix = img.pixfmt();
+//   let mut ren = RendererBase::new_borrowed(&mut pix);
+//   ren.clear(&Rgba8::new_params(255, 255, 255, 255));
+pub struct ImageBuffer<Pix: PixFmtOwned> {
+    data: Vec<u8>,
+    rbuf: RenderBuf,
+    flip_y: bool,
+    pix: PhantomData<Pix>,
+}
+
+impl<Pix: PixFmtOwned> ImageBuffer<Pix> {
+    // Panics if the size of the data overflows
+    pub fn new(width: u32, height: u32) -> Self {
+        let len = Self::data_len(width, height).expect("ImageBuffer: image too large");
+        let mut img = ImageBuffer {
+            data: vec![0; len],
+            rbuf: RenderBuf::new_default(),
+            flip_y: false,
+            pix: PhantomData,
+        };
+        img.attach(width, height);
+        img
+    }
+
+    // Takes over existing pixel data, top-down rows without padding.
+    // Returns None if the data is too short for the given size.
+    pub fn from_vec(width: u32, height: u32, data: Vec<u8>) -> Option<Self> {
+        let len = Self::data_len(width, height)?;
+        if data.len() < len {
+            return None;
+        }
+        let mut img = ImageBuffer {
+            data: data,
+            rbuf: RenderBuf::new_default(),
+            flip_y: false,
+            pix: PhantomData,
+        };
+        img.data.truncate(len);
+        img.attach(width, height);
+        Some(img)
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.data
+    }
+
+    // Bytes of the data, None if a row doesn't fit into the i32 stride of
+    // the rendering buffer or the data into memory
+    fn data_len(width: u32, height: u32) -> Option<usize> {
+        let stride = width.checked_mul(Pix::PIXEL_WIDTH)?;
+        i32::try_from(stride).ok()?;
+        (stride as usize).checked_mul(height as usize)
+    }
+
+    fn attach(&mut self, width: u32, height: u32) {
+        let stride = width
+            .checked_mul(Pix::PIXEL_WIDTH)
+            .and_then(|s| i32::try_from(s).ok())
+            .expect("ImageBuffer: image too large");
+        self.rbuf.attach(
+            self.data.as_mut_ptr(),
+            width,
+            height,
+            if self.flip_y { -stride } else { stride },
+        );
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.rbuf.width()
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.rbuf.height()
+    }
+
+    // Number of bytes per row
+    #[inline]
+    pub fn stride(&self) -> u32 {
+        self.rbuf.stride_abs()
+    }
+
+    pub fn flip_y(&self) -> bool {
+        self.flip_y
+    }
+
+    pub fn set_flip_y(&mut self, flip_y: bool) {
+        self.flip_y = flip_y;
+        self.attach(self.width(), self.height());
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    pub fn pixfmt(&mut self) -> PixView<'_, Pix> {
+        PixView {
+            pix: Pix::new_from_rbuf(self.rbuf),
+            life: PhantomData,
+        }
+    }
+
+    // The rendering buffer, for the parts of the library that draw on the
+    // image or read from it while they hold a borrow of it
+    pub(crate) fn rbuf(&self) -> &RenderBuf {
+        &self.rbuf
+    }
+
+    // A pixel format attached to the image that isn't tied to a borrow of
+    // it.
+    //
+    // Safety: the pixel format must not be used after the image is
+    // dropped or resized, nor while the image is borrowed elsewhere.
+    pub(crate) unsafe fn pixfmt_detached(&mut self) -> Pix {
+        Pix::new_from_rbuf(self.rbuf)
+    }
+
+    // Rows are numbered as seen through the rendering buffer, i.e. from
+    // the bottom if flip_y is set.
+    pub fn row(&self, y: u32) -> &[u8] {
+        let (start, end) = self.row_range(y);
+        &self.data[start..end]
+    }
+
+    pub fn row_mut(&mut self, y: u32) -> &mut [u8] {
+        let (start, end) = self.row_range(y);
+        &mut self.data[start..end]
+    }
+
+    fn row_range(&self, y: u32) -> (usize, usize) {
+        if y >= self.height() {
+            panic!("ImageBuffer: row {} out of range", y);
+        }
+        let y = if self.flip_y {
+            self.height() - 1 - y
+        } else {
+            y
+        };
+        let stride = self.stride() as usize;
+        (y as usize * stride, (y as usize + 1) * stride)
+    }
+
+    pub fn clear(&mut self, c: &Pix::C) {
+        let (w, h) = (self.width(), self.height());
+        if w > 0 {
+            let mut pix = self.pixfmt();
+            for y in 0..h {
+                pix.copy_hline(0, y as i32, w, c);
+            }
+        }
+    }
+
+    // Changes the size of the image keeping the pixels of the area that
+    // both sizes have in common. New pixels are zero.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == self.width() && height == self.height() {
+            return;
+        }
+        let pw = Pix::PIXEL_WIDTH as usize;
+        let size = Self::data_len(width, height).expect("ImageBuffer: image too large");
+        let mut data = vec![0; size];
+        let len = width.min(self.width()) as usize * pw;
+        let (old_stride, new_stride) = (self.stride() as usize, width as usize * pw);
+        for y in 0..height.min(self.height()) {
+            // Keep rows in place as seen through the rendering buffer
+            let (src, dst) = if self.flip_y {
+                (self.height() - 1 - y, height - 1 - y)
+            } else {
+                (y, y)
+            };
+            let src = src as usize * old_stride;
+            let dst = dst as usize * new_stride;
+            data[dst..dst + len].copy_from_slice(&self.data[src..src + len]);
+        }
+        self.data = data;
+        self.attach(width, height);
+    }
+
+    // A view of a rectangular area of the image, None if it doesn't fit
+    // into the image.
+    pub fn sub_image(
+        &mut self, x: u32, y: u32, width: u32, height: u32,
+    ) -> Option<ImageView<'_, Pix>> {
+        if x as u64 + width as u64 > self.width() as u64
+            || y as u64 + height as u64 > self.height() as u64
+        {
+            return None;
+        }
+        let stride = self.stride() as usize;
+        let pw = Pix::PIXEL_WIDTH as usize;
+        // Offset of the first row in memory
+        let row = if self.flip_y {
+            self.height() - y - height
+        } else {
+            y
+        };
+        let mut offset = row as usize * stride + x as usize * pw;
+        if width == 0 || height == 0 {
+            offset = 0;
+        }
+        let ptr = self.data[offset..].as_mut_ptr();
+        let stride = stride as i32;
+        Some(ImageView {
+            rbuf: RenderBuf::new(
+                ptr,
+                width,
+                height,
+                if self.flip_y { -stride } else { stride },
+            ),
+            pix: PhantomData,
+            life: PhantomData,
+        })
+    }
+
+    // A view of the entire image
+    pub fn view(&mut self) -> ImageView<'_, Pix> {
+        ImageView {
+            rbuf: self.rbuf,
+            pix: PhantomData,
+            life: PhantomData,
+        }
+    }
+}
+
+impl<Pix: PixFmtOwned> Clone for ImageBuffer<Pix> {
+    fn clone(&self) -> Self {
+        let mut img = ImageBuffer {
+            data: self.data.clone(),
+            rbuf: RenderBuf::new_default(),
+            flip_y: self.flip_y,
+            pix: PhantomData,
+        };
+        img.attach(self.width(), self.height());
+        img
+    }
+}
+
+//===============================================================ImageView
+// A mutable view of the whole or a part of an ImageBuffer.
+pub struct ImageView<'a, Pix: PixFmtOwned> {
+    rbuf: RenderBuf,
+    pix: PhantomData<Pix>,
+    life: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a, Pix: PixFmtOwned> ImageView<'a, Pix> {
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.rbuf.width()
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.rbuf.height()
+    }
+
+    pub fn pixfmt(&mut self) -> PixView<'_, Pix> {
+        PixView {
+            pix: Pix::new_from_rbuf(self.rbuf),
+            life: PhantomData,
+        }
+    }
+}
+
+//=================================================================PixView
+// A pixel format attached to an ImageBuffer or an ImageView. It is a
+// pixel format itself and dereferences to the wrapped one for reading.
+pub struct PixView<'a, Pix: PixFmt> {
+    pix: Pix,
+    life: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a, Pix: PixFmt> Deref for PixView<'a, Pix> {
+    type Target = Pix;
+    fn deref(&self) -> &Pix {
+        &self.pix
+    }
+}
+
+impl<'a, Pix: PixFmt> ImageSrc for PixView<'a, Pix> {}
+
+// The drawing methods of the pixel format. The view doesn't dereference
+// mutably, as the pixel format could then be swapped out of it or hand
+// out its RenderBuf, and it refuses to be attached to another one.
+impl<'a, Pix: PixFmt> PixFmt for PixView<'a, Pix> {
+    type C = Pix::C;
+    type O = Pix::O;
+    type T = Pix::T;
+    const PIXEL_WIDTH: u32 = Pix::PIXEL_WIDTH;
+    const SUBPIXELS: u32 = Pix::SUBPIXELS;
+
+    fn attach_pixfmt<Pix_: PixFmt>(
+        &mut self, _pixf: &Pix_, _x1: i32, _y1: i32, _x2: i32, _y2: i32,
+    ) -> bool {
+        false
+    }
+
+    fn width(&self) -> u32 {
+        self.pix.width()
+    }
+
+    fn height(&self) -> u32 {
+        self.pix.height()
+    }
+
+    fn stride(&self) -> i32 {
+        self.pix.stride()
+    }
+
+    fn pix_ptr(&self, x: i32, y: i32) -> (&[u8], usize) {
+        self.pix.pix_ptr(x, y)
+    }
+
+    fn pix_ptr_mut(&mut self, x: i32, y: i32) -> (&mut [u8], usize) {
+        self.pix.pix_ptr_mut(x, y)
+    }
+
+    fn row(&self, y: i32) -> &[Self::T] {
+        self.pix.row(y)
+    }
+
+    fn row_mut(&mut self, y: i32) -> &mut [Self::T] {
+        self.pix.row_mut(y)
+    }
+
+    fn row_data(&self, y: i32) -> RowData<Self::T> {
+        self.pix.row_data(y)
+    }
+
+    fn make_pix(&self, p: &mut [u8], c: &Self::C) {
+        self.pix.make_pix(p, c)
+    }
+
+    fn copy_pixel(&mut self, x: i32, y: i32, c: &Self::C) {
+        self.pix.copy_pixel(x, y, c)
+    }
+
+    fn blend_from<R: PixFmt<T = Self::T>>(
+        &mut self, from: &R, xdst: i32, ydst: i32, xsrc: i32, ysrc: i32, len: u32, cover: u32,
+    ) {
+        self.pix.blend_from(from, xdst, ydst, xsrc, ysrc, len, cover)
+    }
+
+    fn blend_from_color<R: PixFmt<T = Self::T>>(
+        &mut self, from: &R, color: &Self::C, xdst: i32, ydst: i32, xsrc: i32, ysrc: i32, len: u32,
+        cover: u32,
+    ) {
+        self.pix
+            .blend_from_color(from, color, xdst, ydst, xsrc, ysrc, len, cover)
+    }
+
+    fn blend_from_lut<R: PixFmt<T = Self::T>>(
+        &mut self, from: &R, color_lut: &[Self::C], xdst: i32, ydst: i32, xsrc: i32, ysrc: i32,
+        len: u32, cover: u32,
+    ) {
+        self.pix
+            .blend_from_lut(from, color_lut, xdst, ydst, xsrc, ysrc, len, cover)
+    }
+
+    fn blend_pixel(&mut self, x: i32, y: i32, c: &Self::C, cover: u8) {
+        self.pix.blend_pixel(x, y, c, cover)
+    }
+
+    fn pixel(&self, x: i32, y: i32) -> Self::C {
+        self.pix.pixel(x, y)
+    }
+
+    fn copy_hline(&mut self, x: i32, y: i32, len: u32, c: &Self::C) {
+        self.pix.copy_hline(x, y, len, c)
+    }
+
+    fn copy_vline(&mut self, x: i32, y: i32, len: u32, c: &Self::C) {
+        self.pix.copy_vline(x, y, len, c)
+    }
+
+    fn blend_hline(&mut self, x: i32, y: i32, len: u32, c: &Self::C, cover: u8) {
+        self.pix.blend_hline(x, y, len, c, cover)
+    }
+
+    fn blend_vline(&mut self, x: i32, y: i32, len: u32, c: &Self::C, cover: u8) {
+        self.pix.blend_vline(x, y, len, c, cover)
+    }
+
+    fn blend_solid_hspan(&mut self, x: i32, y: i32, len: u32, c: &Self::C, covers: &[u8]) {
+        self.pix.blend_solid_hspan(x, y, len, c, covers)
+    }
+
+    fn blend_solid_vspan(&mut self, x: i32, y: i32, len: u32, c: &Self::C, covers: &[u8]) {
+        self.pix.blend_solid_vspan(x, y, len, c, covers)
+    }
+
+    fn blend_color_hspan(
+        &mut self, x: i32, y: i32, len: u32, colors: &[Self::C], covers: &[u8], cover: u8,
+    ) {
+        self.pix.blend_color_hspan(x, y, len, colors, covers, cover)
+    }
+
+    fn blend_color_vspan(
+        &mut self, x: i32, y: i32, len: u32, colors: &[Self::C], covers: &[u8], cover: u8,
+    ) {
+        self.pix.blend_color_vspan(x, y, len, colors, covers, cover)
+    }
+
+    fn copy_color_hspan(&mut self, x: i32, y: i32, len: u32, colors: &[Self::C]) {
+        self.pix.copy_color_hspan(x, y, len, colors)
+    }
+
+    fn copy_color_vspan(&mut self, x: i32, y: i32, len: u32, colors: &[Self::C]) {
+        self.pix.copy_color_vspan(x, y, len, colors)
+    }
+
+    fn copy_from<Ren: RenderBuffer<T = Self::T>>(
+        &mut self, from: &Ren, xdst: i32, ydst: i32, xsrc: i32, ysrc: i32, len: u32,
+    ) {
+        self.pix.copy_from(from, xdst, ydst, xsrc, ysrc, len)
+    }
+}
@@ -182,6 +182,41 @@ impl<F: FilterF> ImageFilter<F> {
     }
 }
 
+//----------------------------------------------------ImageFilterKind
+// The filters Agg2D and StyleTable offer for images, by name
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ImageFilterKind {
+    NoFilter,
+    Bilinear,
+    Hanning,
+    Hermite,
+    Quadric,
+    Bicubic,
+    Catrom,
+    Spline16,
+    Spline36,
+    Blackman144,
+}
+
+impl ImageFilterKind {
+    pub fn lut(&self) -> ImageFilterLut {
+        match *self {
+            Self::NoFilter => ImageFilterLut::new(),
+            Self::Bilinear => ImageFilterLut::new_filter(&ImageFilterBilinear::new(), true),
+            Self::Hanning => ImageFilterLut::new_filter(&ImageFilterHanning::new(), true),
+            Self::Hermite => ImageFilterLut::new_filter(&ImageFilterHermite::new(), true),
+            Self::Quadric => ImageFilterLut::new_filter(&ImageFilterQuadric::new(), true),
+            Self::Bicubic => ImageFilterLut::new_filter(&ImageFilterBicubic::new(), true),
+            Self::Catrom => ImageFilterLut::new_filter(&ImageFilterCatrom::new(), true),
+            Self::Spline16 => ImageFilterLut::new_filter(&ImageFilterSpline16::new(), true),
+            Self::Spline36 => ImageFilterLut::new_filter(&ImageFilterSpline36::new(), true),
+            Self::Blackman144 => {
+                ImageFilterLut::new_filter(&ImageFilterBlackman::new_parms(6.0), true)
+            }
+        }
+    }
+}
+
 //-----------------------------------------------ImageFilterBilinear
 pub struct ImageFilterBilinear;
 
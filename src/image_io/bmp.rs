@@ -0,0 +1,192 @@
+use crate::image_buffer::ImageBuffer;
+use crate::image_io::{le16, le32, ImageIoError, ImageIoPixFmt, RawImage};
+use crate::pixel_format::PixelFormat;
+use crate::PixFmtOwned;
+use std::io::{Read, Write};
+
+const FILE_HEADER_SIZE: usize = 14;
+const INFO_HEADER_SIZE: usize = 40;
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+
+// Rows are padded to a multiple of 4 bytes
+fn calc_row_len(width: u32, bits_per_pixel: u32) -> usize {
+    ((width as usize * bits_per_pixel as usize + 31) / 32) * 4
+}
+
+//-------------------------------------------------------------read_bmp
+pub fn read_bmp<R: Read>(r: &mut R) -> Result<RawImage, ImageIoError> {
+    let mut data = Vec::new();
+    r.read_to_end(&mut data)?;
+    decode_bmp(&data)
+}
+
+//-------------------------------------------------------------load_bmp
+pub fn load_bmp<Pix: ImageIoPixFmt + PixFmtOwned>(
+    fname: &str,
+) -> Result<ImageBuffer<Pix>, ImageIoError> {
+    let data = std::fs::read(fname)?;
+    Ok(decode_bmp(&data)?.to_image())
+}
+
+//-----------------------------------------------------------decode_bmp
+// Decodes uncompressed 8-bit paletted, 24-bit and 32-bit bitmaps, stored
+// either bottom-up (positive height) or top-down (negative height).
+// 24-bit images come out as Bgr24, 32-bit ones as Bgra32 and paletted
+// ones as Gray8 if the palette is a gray ramp, Bgr24 otherwise.
+pub fn decode_bmp(data: &[u8]) -> Result<RawImage, ImageIoError> {
+    if data.len() < FILE_HEADER_SIZE + INFO_HEADER_SIZE || &data[..2] != b"BM" {
+        return Err(ImageIoError::UnknownFormat);
+    }
+    let off_bits = le32(data, 10) as usize;
+    let info_size = le32(data, 14) as usize;
+    if info_size < INFO_HEADER_SIZE {
+        return Err(ImageIoError::Unsupported("OS/2 bitmap header"));
+    }
+    let width = le32(data, 18) as i32;
+    let height = le32(data, 22) as i32;
+    let bits = le16(data, 28);
+    let compression = le32(data, 30);
+    let clr_used = le32(data, 46);
+    if width <= 0 || height == 0 {
+        return Err(ImageIoError::Corrupt("invalid bitmap size"));
+    }
+    let (width, top_down) = (width as u32, height < 0);
+    let height = height.unsigned_abs();
+
+    let format = match (bits, compression) {
+        (8, BI_RGB) => PixelFormat::Bgr24,
+        (24, BI_RGB) => PixelFormat::Bgr24,
+        (32, BI_RGB) => PixelFormat::Bgra32,
+        (32, BI_BITFIELDS) => {
+            // Only the masks of the usual B-G-R-A layout are supported
+            let masks = FILE_HEADER_SIZE + INFO_HEADER_SIZE;
+            if data.len() < masks + 12
+                || le32(data, masks) != 0xFF0000
+                || le32(data, masks + 4) != 0xFF00
+                || le32(data, masks + 8) != 0xFF
+            {
+                return Err(ImageIoError::Unsupported("bitmap color masks"));
+            }
+            PixelFormat::Bgra32
+        }
+        (_, BI_RGB) | (_, BI_BITFIELDS) => {
+            return Err(ImageIoError::Unsupported("bitmap bit depth"))
+        }
+        _ => return Err(ImageIoError::Unsupported("compressed bitmap")),
+    };
+
+    // Palette of BGR0 quads following the info header
+    let mut palette = Vec::new();
+    let mut gray = true;
+    if bits == 8 {
+        let n = if clr_used == 0 || clr_used > 256 {
+            256
+        } else {
+            clr_used as usize
+        };
+        let start = FILE_HEADER_SIZE + info_size;
+        if data.len() < start + n * 4 {
+            return Err(ImageIoError::Corrupt("bitmap palette too short"));
+        }
+        for q in data[start..start + n * 4].chunks(4) {
+            gray = gray && q[0] == q[1] && q[1] == q[2];
+            palette.push([q[0], q[1], q[2]]);
+        }
+        palette.resize(256, [0, 0, 0]);
+    }
+
+    let row_len = calc_row_len(width, bits);
+    if data.len() < off_bits + row_len * height as usize {
+        return Err(ImageIoError::Corrupt("bitmap data too short"));
+    }
+    let format = if bits == 8 && gray {
+        PixelFormat::Gray8
+    } else {
+        format
+    };
+    let mut img = RawImage::new(width, height, format);
+    let len = img.stride();
+    for y in 0..height {
+        let src_y = if top_down { y } else { height - 1 - y };
+        let src = &data[off_bits + src_y as usize * row_len..][..row_len];
+        let dst = img.row_mut(y);
+        if bits == 8 {
+            for (i, &v) in src[..width as usize].iter().enumerate() {
+                if gray {
+                    dst[i] = palette[v as usize][0];
+                } else {
+                    dst[i * 3..i * 3 + 3].copy_from_slice(&palette[v as usize]);
+                }
+            }
+        } else {
+            dst.copy_from_slice(&src[..len]);
+        }
+    }
+
+    // The fourth byte of 32-bit pixels is often left zero; such images
+    // are meant to be opaque.
+    if format == PixelFormat::Bgra32 && img.data().chunks(4).all(|p| p[3] == 0) {
+        for p in img.data_mut().chunks_mut(4) {
+            p[3] = 255;
+        }
+    }
+    Ok(img)
+}
+
+//-----------------------------------------------------------encode_bmp
+// Writes a bottom-up bitmap: 8-bit with a gray palette for Gray8 images,
+// 32-bit for images with alpha and 24-bit otherwise.
+pub fn encode_bmp(img: &RawImage) -> Vec<u8> {
+    let (bits, format) = match img.format() {
+        f if f.is_gray() => (8, PixelFormat::Gray8),
+        f if f.has_alpha() => (32, PixelFormat::Bgra32),
+        _ => (24, PixelFormat::Bgr24),
+    };
+    let img = img.convert(format);
+    let (width, height) = (img.width(), img.height());
+    let row_len = calc_row_len(width, bits);
+    let palette_size = if bits == 8 { 256 * 4 } else { 0 };
+    let off_bits = FILE_HEADER_SIZE + INFO_HEADER_SIZE + palette_size;
+    let img_size = row_len * height as usize;
+
+    let mut out = Vec::with_capacity(off_bits + img_size);
+    out.extend_from_slice(b"BM");
+    out.extend_from_slice(&((off_bits + img_size) as u32).to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&(off_bits as u32).to_le_bytes());
+
+    out.extend_from_slice(&(INFO_HEADER_SIZE as u32).to_le_bytes());
+    out.extend_from_slice(&(width as i32).to_le_bytes());
+    out.extend_from_slice(&(height as i32).to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&(bits as u16).to_le_bytes());
+    out.extend_from_slice(&BI_RGB.to_le_bytes());
+    out.extend_from_slice(&(img_size as u32).to_le_bytes());
+    // Resolution and color counts
+    out.extend_from_slice(&[0; 16]);
+
+    if bits == 8 {
+        for i in 0..=255u8 {
+            out.extend_from_slice(&[i, i, i, 0]);
+        }
+    }
+    let pad = row_len - img.stride();
+    for y in (0..height).rev() {
+        out.extend_from_slice(img.row(y));
+        out.extend_from_slice(&[0; 3][..pad]);
+    }
+    out
+}
+
+//------------------------------------------------------------write_bmp
+pub fn write_bmp<W: Write>(img: &RawImage, w: &mut W) -> Result<(), ImageIoError> {
+    w.write_all(&encode_bmp(img))?;
+    Ok(())
+}
+
+//-------------------------------------------------------------save_bmp
+pub fn save_bmp<Pix: ImageIoPixFmt>(pix: &Pix, fname: &str) -> Result<(), ImageIoError> {
+    std::fs::write(fname, encode_bmp(&RawImage::from_pixfmt(pix)))?;
+    Ok(())
+}
@@ -0,0 +1,340 @@
+// Reading and writing of simple uncompressed or RLE image files: Windows
+// BMP, binary PPM/PGM and Targa. Files are decoded into a RawImage in the
+// byte order the file uses, which is then converted to the order of the
+// destination pixel format with the util::color_conv functors, the same
+// way the platform support converts its window and image buffers. The
+// layouts with 16 bits per component are converted through PixFmtDyn.
+//
+//   let img: ImageBuffer<PixBgr24> = image_io::load_image("spheres.bmp")?;
+//   image_io::save_image(&*img.pixfmt(), "spheres.tga")?;
+pub mod bmp;
+pub mod pnm;
+pub mod tga;
+
+pub use bmp::{decode_bmp, encode_bmp, load_bmp, read_bmp, save_bmp, write_bmp};
+pub use pnm::{decode_pnm, encode_pnm, load_pnm, read_pnm, save_pnm, write_pnm};
+pub use tga::{decode_tga, encode_tga, load_tga, read_tga, save_tga, write_tga};
+
+use crate::color_gray::Gray8;
+use crate::color_rgba::{Rgba16, Rgba8};
+use crate::image_buffer::{ImageBuffer, PixView};
+use crate::pixel_format::PixelFormat;
+use crate::pixfmt_dyn::PixFmtDyn;
+use crate::pixfmt_gray::AlphaBlendGray;
+use crate::pixfmt_rgb::AlphaBlendRgb;
+use crate::pixfmt_rgba::AlphaBlendRgba;
+use crate::util::*;
+use crate::{AggInteger, Blender, BlenderG, Order, PixFmt, PixFmtOwned, RenderBuf, RenderBuffer};
+use std::fmt;
+
+//-------------------------------------------------------------conv_row_fn
+// Returns the color_conv functor converting a row of pixels from one
+// layout to the other. There are functors between the layouts with 8 bits
+// per component, None for the others.
+pub fn conv_row_fn(src: PixelFormat, dst: PixelFormat) -> Option<CopyRowFn> {
+    use PixelFormat::*;
+    Some(match (src, dst) {
+        (Gray8, Gray8) => Gray8ToGray8::convert,
+        (Gray8, Rgb24) => Gray8ToRgb24::convert,
+        (Gray8, Bgr24) => Gray8ToBgr24::convert,
+        (Gray8, Rgba32) => Gray8ToRgba32::convert,
+        (Gray8, Argb32) => Gray8ToArgb32::convert,
+        (Gray8, Abgr32) => Gray8ToAbgr32::convert,
+        (Gray8, Bgra32) => Gray8ToBgra32::convert,
+
+        (Rgb24, Gray8) => Rgb24ToGray8::convert,
+        (Rgb24, Rgb24) => Rgb24ToRgb24::convert,
+        (Rgb24, Bgr24) => Rgb24ToBgr24::convert,
+        (Rgb24, Rgba32) => Rgb24ToRgba32::convert,
+        (Rgb24, Argb32) => Rgb24ToArgb32::convert,
+        (Rgb24, Abgr32) => Rgb24ToAbgr32::convert,
+        (Rgb24, Bgra32) => Rgb24ToBgra32::convert,
+
+        (Bgr24, Gray8) => Bgr24ToGray8::convert,
+        (Bgr24, Rgb24) => Bgr24ToRgb24::convert,
+        (Bgr24, Bgr24) => Bgr24ToBgr24::convert,
+        (Bgr24, Rgba32) => Bgr24ToRgba32::convert,
+        (Bgr24, Argb32) => Bgr24ToArgb32::convert,
+        (Bgr24, Abgr32) => Bgr24ToAbgr32::convert,
+        (Bgr24, Bgra32) => Bgr24ToBgra32::convert,
+
+        (Rgba32, Gray8) => Rgba32ToGray8::convert,
+        (Rgba32, Rgb24) => Rgba32ToRgb24::convert,
+        (Rgba32, Bgr24) => Rgba32ToBgr24::convert,
+        (Rgba32, Rgba32) => Rgba32ToRgba32::convert,
+        (Rgba32, Argb32) => Rgba32ToArgb32::convert,
+        (Rgba32, Abgr32) => Rgba32ToAbgr32::convert,
+        (Rgba32, Bgra32) => Rgba32ToBgra32::convert,
+
+        (Argb32, Gray8) => Argb32ToGray8::convert,
+        (Argb32, Rgb24) => Argb32ToRgb24::convert,
+        (Argb32, Bgr24) => Argb32ToBgr24::convert,
+        (Argb32, Rgba32) => Argb32ToRgba32::convert,
+        (Argb32, Argb32) => Argb32ToArgb32::convert,
+        (Argb32, Abgr32) => Argb32ToAbgr32::convert,
+        (Argb32, Bgra32) => Argb32ToBgra32::convert,
+
+        (Abgr32, Gray8) => Abgr32ToGray8::convert,
+        (Abgr32, Rgb24) => Abgr32ToRgb24::convert,
+        (Abgr32, Bgr24) => Abgr32ToBgr24::convert,
+        (Abgr32, Rgba32) => Abgr32ToRgba32::convert,
+        (Abgr32, Argb32) => Abgr32ToArgb32::convert,
+        (Abgr32, Abgr32) => Abgr32ToAbgr32::convert,
+        (Abgr32, Bgra32) => Abgr32ToBgra32::convert,
+
+        (Bgra32, Gray8) => Bgra32ToGray8::convert,
+        (Bgra32, Rgb24) => Bgra32ToRgb24::convert,
+        (Bgra32, Bgr24) => Bgra32ToBgr24::convert,
+        (Bgra32, Rgba32) => Bgra32ToRgba32::convert,
+        (Bgra32, Argb32) => Bgra32ToArgb32::convert,
+        (Bgra32, Abgr32) => Bgra32ToAbgr32::convert,
+        (Bgra32, Bgra32) => Bgra32ToBgra32::convert,
+
+        _ => return None,
+    })
+}
+
+// Converts a row of width pixels, through PixFmtDyn and 16-bit RGBA for
+// the layouts conv_row_fn() has no functor for
+fn conv_row(
+    dst: &mut [u8], dst_format: PixelFormat, src: &[u8], src_format: PixelFormat, width: u32,
+) {
+    if let Some(conv) = conv_row_fn(src_format, dst_format) {
+        color_conv_row(dst, src, width, conv);
+        return;
+    }
+    let rsrc = RenderBuf::new(src.as_ptr() as *mut u8, width, 1, src.len() as i32);
+    let rdst = RenderBuf::new(dst.as_mut_ptr(), width, 1, dst.len() as i32);
+    let psrc = PixFmtDyn::<Rgba16>::new(src_format, rsrc);
+    let mut pdst = PixFmtDyn::<Rgba16>::new(dst_format, rdst);
+    for x in 0..width as i32 {
+        pdst.copy_pixel(x, 0, &psrc.pixel(x, 0));
+    }
+}
+
+//===========================================================ImageIoPixFmt
+// Pixel formats whose rows can be read and written by image_io, i.e.
+// the 8-bit gray, RGB and RGBA formats.
+pub trait ImageIoPixFmt: PixFmt<T = u8> {
+    const FORMAT: PixelFormat;
+}
+
+impl<'a, O: Order, Blend: Blender<Rgba8, O>, RenBuf: RenderBuffer<T = u8>> ImageIoPixFmt
+    for AlphaBlendRgb<'a, Rgba8, O, Blend, RenBuf>
+{
+    const FORMAT: PixelFormat = if O::R == 0 {
+        PixelFormat::Rgb24
+    } else {
+        PixelFormat::Bgr24
+    };
+}
+
+impl<
+        'a,
+        O: Order,
+        Blend: Blender<Rgba8, O>,
+        RenBuf: RenderBuffer<T = u8>,
+        PixelSize: AggInteger,
+    > ImageIoPixFmt for AlphaBlendRgba<'a, Rgba8, O, Blend, RenBuf, PixelSize>
+{
+    const FORMAT: PixelFormat = match (O::R, O::A) {
+        (0, 3) => PixelFormat::Rgba32,
+        (1, 0) => PixelFormat::Argb32,
+        (3, 0) => PixelFormat::Abgr32,
+        _ => PixelFormat::Bgra32,
+    };
+}
+
+impl<'a, Blend: BlenderG<Gray8>, RenBuf: RenderBuffer<T = u8>> ImageIoPixFmt
+    for AlphaBlendGray<'a, Gray8, Blend, RenBuf, 1, 0>
+{
+    const FORMAT: PixelFormat = PixelFormat::Gray8;
+}
+
+impl<'a, Pix: ImageIoPixFmt> ImageIoPixFmt for PixView<'a, Pix> {
+    const FORMAT: PixelFormat = Pix::FORMAT;
+}
+
+//============================================================ImageIoError
+#[derive(Debug)]
+pub enum ImageIoError {
+    Io(std::io::Error),
+    UnknownFormat,
+    Corrupt(&'static str),
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for ImageIoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageIoError::Io(e) => write!(f, "I/O error: {}", e),
+            ImageIoError::UnknownFormat => write!(f, "unknown image file format"),
+            ImageIoError::Corrupt(s) => write!(f, "corrupt image data: {}", s),
+            ImageIoError::Unsupported(s) => write!(f, "unsupported image feature: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for ImageIoError {}
+
+impl From<std::io::Error> for ImageIoError {
+    fn from(e: std::io::Error) -> Self {
+        ImageIoError::Io(e)
+    }
+}
+
+//================================================================RawImage
+// Pixels in one of the PixelFormat layouts, top-down rows without padding.
+#[derive(Clone)]
+pub struct RawImage {
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+    data: Vec<u8>,
+}
+
+impl RawImage {
+    pub fn new(width: u32, height: u32, format: PixelFormat) -> Self {
+        RawImage {
+            width: width,
+            height: height,
+            format: format,
+            data: vec![0; width as usize * format.pix_width() as usize * height as usize],
+        }
+    }
+
+    // Copies the contents of a pixel format, keeping its layout
+    pub fn from_pixfmt<Pix: ImageIoPixFmt>(pix: &Pix) -> Self {
+        let mut img = RawImage::new(pix.width(), pix.height(), Pix::FORMAT);
+        let len = img.stride();
+        for y in 0..img.height {
+            img.row_mut(y).copy_from_slice(&pix.row(y as i32)[..len]);
+        }
+        img
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    // Number of bytes per row
+    pub fn stride(&self) -> usize {
+        self.width as usize * self.format.pix_width() as usize
+    }
+
+    pub fn row(&self, y: u32) -> &[u8] {
+        let stride = self.stride();
+        &self.data[y as usize * stride..(y as usize + 1) * stride]
+    }
+
+    pub fn row_mut(&mut self, y: u32) -> &mut [u8] {
+        let stride = self.stride();
+        &mut self.data[y as usize * stride..(y as usize + 1) * stride]
+    }
+
+    // Returns the image converted to another layout
+    pub fn convert(&self, format: PixelFormat) -> RawImage {
+        if format == self.format {
+            return self.clone();
+        }
+        let mut img = RawImage::new(self.width, self.height, format);
+        for y in 0..self.height {
+            conv_row(img.row_mut(y), format, self.row(y), self.format, self.width);
+        }
+        img
+    }
+
+    // Copies the image into a pixel format with its top-left corner at
+    // the origin, clipped to the smaller of both sizes like color_conv()
+    pub fn copy_to<Pix: ImageIoPixFmt>(&self, pix: &mut Pix) {
+        let width = self.width.min(pix.width());
+        let height = self.height.min(pix.height());
+        if width == 0 {
+            return;
+        }
+        let len = (width * Pix::FORMAT.pix_width()) as usize;
+        for y in 0..height {
+            let row = &mut pix.row_mut(y as i32)[..len];
+            conv_row(row, Pix::FORMAT, self.row(y), self.format, width);
+        }
+    }
+
+    pub fn to_image<Pix: ImageIoPixFmt + PixFmtOwned>(&self) -> ImageBuffer<Pix> {
+        let mut img = ImageBuffer::<Pix>::new(self.width, self.height);
+        self.copy_to(&mut img.pixfmt());
+        img
+    }
+}
+
+//-------------------------------------------------------------load_image
+// Loads a BMP, PPM/PGM or TGA file, detected by its contents.
+pub fn load_image<Pix: ImageIoPixFmt + PixFmtOwned>(
+    fname: &str,
+) -> Result<ImageBuffer<Pix>, ImageIoError> {
+    let data = std::fs::read(fname)?;
+    Ok(decode_image(&data)?.to_image())
+}
+
+//-----------------------------------------------------------decode_image
+pub fn decode_image(data: &[u8]) -> Result<RawImage, ImageIoError> {
+    if data.starts_with(b"BM") {
+        decode_bmp(data)
+    } else if data.starts_with(b"P5") || data.starts_with(b"P6") {
+        decode_pnm(data)
+    } else if is_tga(data) {
+        decode_tga(data)
+    } else {
+        Err(ImageIoError::UnknownFormat)
+    }
+}
+
+// Targa files have no signature, check the header fields for sane values
+fn is_tga(data: &[u8]) -> bool {
+    data.len() >= 18 && data[1] <= 1 && matches!(data[2], 1 | 2 | 3 | 9 | 10 | 11)
+}
+
+//-------------------------------------------------------------save_image
+// Saves a pixel format to a file whose type is taken from the extension
+// of the name: .bmp, .ppm, .pgm, .pnm or .tga. Targa files are written
+// run-length encoded.
+pub fn save_image<Pix: ImageIoPixFmt>(pix: &Pix, fname: &str) -> Result<(), ImageIoError> {
+    let ext = match fname.rfind('.') {
+        Some(i) => fname[i + 1..].to_lowercase(),
+        None => String::new(),
+    };
+    let img = RawImage::from_pixfmt(pix);
+    let data = match ext.as_str() {
+        "bmp" => encode_bmp(&img),
+        "ppm" | "pgm" | "pnm" => encode_pnm(&img),
+        "tga" => encode_tga(&img, true),
+        _ => return Err(ImageIoError::UnknownFormat),
+    };
+    std::fs::write(fname, data)?;
+    Ok(())
+}
+
+#[inline]
+pub(crate) fn le16(d: &[u8], pos: usize) -> u32 {
+    u16::from_le_bytes([d[pos], d[pos + 1]]) as u32
+}
+
+#[inline]
+pub(crate) fn le32(d: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([d[pos], d[pos + 1], d[pos + 2], d[pos + 3]])
+}
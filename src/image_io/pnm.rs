@@ -0,0 +1,119 @@
+use crate::image_buffer::ImageBuffer;
+use crate::image_io::{ImageIoError, ImageIoPixFmt, RawImage};
+use crate::pixel_format::PixelFormat;
+use crate::PixFmtOwned;
+use std::io::{Read, Write};
+
+//-------------------------------------------------------------read_pnm
+pub fn read_pnm<R: Read>(r: &mut R) -> Result<RawImage, ImageIoError> {
+    let mut data = Vec::new();
+    r.read_to_end(&mut data)?;
+    decode_pnm(&data)
+}
+
+//-------------------------------------------------------------load_pnm
+pub fn load_pnm<Pix: ImageIoPixFmt + PixFmtOwned>(
+    fname: &str,
+) -> Result<ImageBuffer<Pix>, ImageIoError> {
+    let data = std::fs::read(fname)?;
+    Ok(decode_pnm(&data)?.to_image())
+}
+
+// Skips white space and comments and parses the next number of the
+// header
+fn header_value(data: &[u8], pos: &mut usize) -> Result<u32, ImageIoError> {
+    loop {
+        match data.get(*pos) {
+            Some(b'#') => {
+                while *pos < data.len() && data[*pos] != b'\n' {
+                    *pos += 1;
+                }
+            }
+            Some(c) if c.is_ascii_whitespace() => *pos += 1,
+            _ => break,
+        }
+    }
+    let start = *pos;
+    while *pos < data.len() && data[*pos].is_ascii_digit() {
+        *pos += 1;
+    }
+    std::str::from_utf8(&data[start..*pos])
+        .unwrap()
+        .parse::<u32>()
+        .map_err(|_| ImageIoError::Corrupt("invalid PNM header"))
+}
+
+//-----------------------------------------------------------decode_pnm
+// Decodes binary PGM (P5) and PPM (P6) files into Gray8 or Rgb24 images.
+// Samples with a maximum value other than 255, including 16-bit ones,
+// are scaled to 8 bits.
+pub fn decode_pnm(data: &[u8]) -> Result<RawImage, ImageIoError> {
+    let format = match data.get(..2) {
+        Some(b"P5") => PixelFormat::Gray8,
+        Some(b"P6") => PixelFormat::Rgb24,
+        Some(b"P1") | Some(b"P2") | Some(b"P3") | Some(b"P4") => {
+            return Err(ImageIoError::Unsupported("ASCII or bitmap PNM"))
+        }
+        _ => return Err(ImageIoError::UnknownFormat),
+    };
+    let mut pos = 2;
+    let width = header_value(data, &mut pos)?;
+    let height = header_value(data, &mut pos)?;
+    let max_val = header_value(data, &mut pos)?;
+    if width == 0 || height == 0 || max_val == 0 || max_val > 65535 {
+        return Err(ImageIoError::Corrupt("invalid PNM header"));
+    }
+    // A single white space character separates the header from the data
+    pos += 1;
+
+    // The size is checked against the data before the image is allocated
+    let sample_size = if max_val > 255 { 2 } else { 1 };
+    let len = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(format.pix_width() as usize * sample_size));
+    let src = match len.and_then(|len| data.get(pos..pos.checked_add(len)?)) {
+        Some(src) => src,
+        None => return Err(ImageIoError::Corrupt("PNM data too short")),
+    };
+    let mut img = RawImage::new(width, height, format);
+    if max_val == 255 {
+        img.data_mut().copy_from_slice(src);
+    } else {
+        for (i, v) in img.data_mut().iter_mut().enumerate() {
+            let s = if sample_size == 2 {
+                u16::from_be_bytes([src[i * 2], src[i * 2 + 1]]) as u32
+            } else {
+                src[i] as u32
+            };
+            *v = ((s.min(max_val) * 255 + max_val / 2) / max_val) as u8;
+        }
+    }
+    Ok(img)
+}
+
+//-----------------------------------------------------------encode_pnm
+// Writes a PGM file for Gray8 images and a PPM file for all others. The
+// alpha channel is dropped.
+pub fn encode_pnm(img: &RawImage) -> Vec<u8> {
+    let (magic, format) = if img.format().is_gray() {
+        ("P5", PixelFormat::Gray8)
+    } else {
+        ("P6", PixelFormat::Rgb24)
+    };
+    let img = img.convert(format);
+    let mut out = format!("{}\n{} {}\n255\n", magic, img.width(), img.height()).into_bytes();
+    out.extend_from_slice(img.data());
+    out
+}
+
+//------------------------------------------------------------write_pnm
+pub fn write_pnm<W: Write>(img: &RawImage, w: &mut W) -> Result<(), ImageIoError> {
+    w.write_all(&encode_pnm(img))?;
+    Ok(())
+}
+
+//-------------------------------------------------------------save_pnm
+pub fn save_pnm<Pix: ImageIoPixFmt>(pix: &Pix, fname: &str) -> Result<(), ImageIoError> {
+    std::fs::write(fname, encode_pnm(&RawImage::from_pixfmt(pix)))?;
+    Ok(())
+}
@@ -0,0 +1,251 @@
+use crate::image_buffer::ImageBuffer;
+use crate::image_io::{le16, ImageIoError, ImageIoPixFmt, RawImage};
+use crate::pixel_format::PixelFormat;
+use crate::PixFmtOwned;
+use std::io::{Read, Write};
+
+const HEADER_SIZE: usize = 18;
+
+// Image types
+const TGA_MAPPED: u8 = 1;
+const TGA_TRUE_COLOR: u8 = 2;
+const TGA_GRAY: u8 = 3;
+const TGA_RLE: u8 = 8;
+
+// Bits of the image descriptor
+const TGA_RIGHT_TO_LEFT: u8 = 0x10;
+const TGA_TOP_DOWN: u8 = 0x20;
+
+//-------------------------------------------------------------read_tga
+pub fn read_tga<R: Read>(r: &mut R) -> Result<RawImage, ImageIoError> {
+    let mut data = Vec::new();
+    r.read_to_end(&mut data)?;
+    decode_tga(&data)
+}
+
+//-------------------------------------------------------------load_tga
+pub fn load_tga<Pix: ImageIoPixFmt + PixFmtOwned>(
+    fname: &str,
+) -> Result<ImageBuffer<Pix>, ImageIoError> {
+    let data = std::fs::read(fname)?;
+    Ok(decode_tga(&data)?.to_image())
+}
+
+//-----------------------------------------------------------decode_tga
+// Decodes raw and run-length encoded Targa files: 24 and 32-bit true
+// color images come out as Bgr24 and Bgra32, 8-bit gray ones as Gray8,
+// color mapped ones as Bgr24 or Bgra32 depending on the color map.
+pub fn decode_tga(data: &[u8]) -> Result<RawImage, ImageIoError> {
+    if data.len() < HEADER_SIZE {
+        return Err(ImageIoError::UnknownFormat);
+    }
+    let id_len = data[0] as usize;
+    let cmap_type = data[1];
+    let image_type = data[2];
+    let cmap_first = le16(data, 3) as usize;
+    let cmap_len = le16(data, 5) as usize;
+    let cmap_bits = data[7];
+    let width = le16(data, 12);
+    let height = le16(data, 14);
+    let bits = data[16];
+    let descriptor = data[17];
+    if width == 0 || height == 0 {
+        return Err(ImageIoError::Corrupt("invalid Targa image size"));
+    }
+
+    let mut pos = HEADER_SIZE + id_len;
+    if data.len() < pos {
+        return Err(ImageIoError::Corrupt("Targa image id too short"));
+    }
+    let mut cmap: Vec<u8> = Vec::new();
+    let mut cmap_pix_width = 0;
+    if cmap_type == 1 {
+        cmap_pix_width = match cmap_bits {
+            24 => 3,
+            32 => 4,
+            _ => return Err(ImageIoError::Unsupported("Targa color map depth")),
+        };
+        let len = cmap_len * cmap_pix_width;
+        if data.len() < pos + len {
+            return Err(ImageIoError::Corrupt("Targa color map too short"));
+        }
+        cmap = data[pos..pos + len].to_vec();
+        pos += len;
+    }
+
+    // A color map may come with any image type, but only mapped images
+    // use it
+    let mapped = image_type & !TGA_RLE == TGA_MAPPED;
+    let (format, pix_width) = match (image_type & !TGA_RLE, bits) {
+        (TGA_TRUE_COLOR, 24) => (PixelFormat::Bgr24, 3),
+        (TGA_TRUE_COLOR, 32) => (PixelFormat::Bgra32, 4),
+        (TGA_GRAY, 8) => (PixelFormat::Gray8, 1),
+        (TGA_MAPPED, 8) if cmap_type == 1 => {
+            if cmap_pix_width == 3 {
+                (PixelFormat::Bgr24, 1)
+            } else {
+                (PixelFormat::Bgra32, 1)
+            }
+        }
+        (TGA_TRUE_COLOR, _) | (TGA_GRAY, _) | (TGA_MAPPED, _) => {
+            return Err(ImageIoError::Unsupported("Targa pixel depth"))
+        }
+        _ => return Err(ImageIoError::Unsupported("Targa image type")),
+    };
+
+    // Pixels in file order
+    let n = width as usize * height as usize * pix_width;
+    let pixels = if image_type & TGA_RLE != 0 {
+        unpack_rle(&data[pos..], n, pix_width)?
+    } else {
+        if data.len() < pos + n {
+            return Err(ImageIoError::Corrupt("Targa data too short"));
+        }
+        data[pos..pos + n].to_vec()
+    };
+
+    let mut img = RawImage::new(width, height, format);
+    let row_len = width as usize * pix_width;
+    let out_width = format.pix_width() as usize;
+    for y in 0..height {
+        let src_y = if descriptor & TGA_TOP_DOWN != 0 {
+            y
+        } else {
+            height - 1 - y
+        };
+        let src = &pixels[src_y as usize * row_len..(src_y as usize + 1) * row_len];
+        let dst = img.row_mut(y);
+        for x in 0..width as usize {
+            let sx = if descriptor & TGA_RIGHT_TO_LEFT != 0 {
+                width as usize - 1 - x
+            } else {
+                x
+            };
+            let d = &mut dst[x * out_width..(x + 1) * out_width];
+            if mapped {
+                let i = (src[sx] as usize).wrapping_sub(cmap_first);
+                if i < cmap_len {
+                    d.copy_from_slice(&cmap[i * cmap_pix_width..(i + 1) * cmap_pix_width]);
+                }
+            } else {
+                d.copy_from_slice(&src[sx * pix_width..(sx + 1) * pix_width]);
+            }
+        }
+    }
+
+    // Files without attribute bits may leave the alpha bytes zero; such
+    // images are meant to be opaque.
+    if format == PixelFormat::Bgra32 && img.data().chunks(4).all(|p| p[3] == 0) {
+        for p in img.data_mut().chunks_mut(4) {
+            p[3] = 255;
+        }
+    }
+    Ok(img)
+}
+
+fn unpack_rle(data: &[u8], n: usize, pix_width: usize) -> Result<Vec<u8>, ImageIoError> {
+    // A packet of 1 + pix_width bytes gives at most 128 pixels, so the
+    // data bounds the size before the packets are decoded
+    let mut out = Vec::with_capacity(n.min(data.len() / (1 + pix_width) * 128 * pix_width));
+    let mut pos = 0;
+    while out.len() < n {
+        let h = *data
+            .get(pos)
+            .ok_or(ImageIoError::Corrupt("Targa data too short"))?;
+        let count = (h & 0x7F) as usize + 1;
+        pos += 1;
+        let len = if h & 0x80 != 0 {
+            pix_width
+        } else {
+            count * pix_width
+        };
+        if pos + len > data.len() {
+            return Err(ImageIoError::Corrupt("Targa data too short"));
+        }
+        if h & 0x80 != 0 {
+            for _ in 0..count {
+                out.extend_from_slice(&data[pos..pos + len]);
+            }
+        } else {
+            out.extend_from_slice(&data[pos..pos + len]);
+        }
+        pos += len;
+    }
+    out.truncate(n);
+    Ok(out)
+}
+
+//-----------------------------------------------------------encode_tga
+// Writes a top-down Targa file, 8-bit gray for Gray8 images, 32-bit for
+// images with alpha and 24-bit otherwise. With rle set the rows are
+// run-length encoded.
+pub fn encode_tga(img: &RawImage, rle: bool) -> Vec<u8> {
+    let (image_type, bits, descriptor, format) = match img.format() {
+        f if f.is_gray() => (TGA_GRAY, 8, TGA_TOP_DOWN, PixelFormat::Gray8),
+        f if f.has_alpha() => (TGA_TRUE_COLOR, 32, TGA_TOP_DOWN | 8, PixelFormat::Bgra32),
+        _ => (TGA_TRUE_COLOR, 24, TGA_TOP_DOWN, PixelFormat::Bgr24),
+    };
+    let image_type = if rle {
+        image_type | TGA_RLE
+    } else {
+        image_type
+    };
+    let img = img.convert(format);
+
+    let mut out = Vec::with_capacity(HEADER_SIZE + img.data().len());
+    out.extend_from_slice(&[0, 0, image_type]);
+    // Color map specification and origin
+    out.extend_from_slice(&[0; 9]);
+    out.extend_from_slice(&(img.width() as u16).to_le_bytes());
+    out.extend_from_slice(&(img.height() as u16).to_le_bytes());
+    out.extend_from_slice(&[bits, descriptor]);
+
+    let pix_width = format.pix_width() as usize;
+    for y in 0..img.height() {
+        let row = img.row(y);
+        if rle {
+            pack_rle_row(&mut out, row, pix_width);
+        } else {
+            out.extend_from_slice(row);
+        }
+    }
+    out
+}
+
+// Packets never cross rows, as the specification recommends
+fn pack_rle_row(out: &mut Vec<u8>, row: &[u8], pix_width: usize) {
+    let pix = |i: usize| &row[i * pix_width..(i + 1) * pix_width];
+    let n = row.len() / pix_width;
+    let mut i = 0;
+    while i < n {
+        let mut run = 1;
+        while i + run < n && run < 128 && pix(i + run) == pix(i) {
+            run += 1;
+        }
+        if run > 1 {
+            out.push(0x80 | (run - 1) as u8);
+            out.extend_from_slice(pix(i));
+            i += run;
+        } else {
+            let mut j = i + 1;
+            while j < n && j - i < 128 && (j + 1 >= n || pix(j) != pix(j + 1)) {
+                j += 1;
+            }
+            out.push((j - i - 1) as u8);
+            out.extend_from_slice(&row[i * pix_width..j * pix_width]);
+            i = j;
+        }
+    }
+}
+
+//------------------------------------------------------------write_tga
+pub fn write_tga<W: Write>(img: &RawImage, rle: bool, w: &mut W) -> Result<(), ImageIoError> {
+    w.write_all(&encode_tga(img, rle))?;
+    Ok(())
+}
+
+//-------------------------------------------------------------save_tga
+pub fn save_tga<Pix: ImageIoPixFmt>(pix: &Pix, fname: &str, rle: bool) -> Result<(), ImageIoError> {
+    std::fs::write(fname, encode_tga(&RawImage::from_pixfmt(pix), rle))?;
+    Ok(())
+}
@@ -3,6 +3,7 @@
 use std::ops::*;
 use std::os::raw::c_long;
 
+pub mod agg2d;
 pub mod alpha_mask_u8;
 pub mod arc;
 pub mod array;
@@ -13,9 +14,11 @@ pub mod bitset_iterator;
 pub mod blur;
 pub mod bounding_rect;
 pub mod bspline;
+pub mod canvas;
 pub mod clip_liang_barsky;
 pub mod color_gray;
 pub mod color_rgba;
+pub mod compound_shape;
 pub mod conv_adaptor_vcgen;
 pub mod conv_adaptor_vpgen;
 pub mod conv_bspline;
@@ -28,36 +31,54 @@ pub mod conv_gpc;
 pub mod conv_marker;
 pub mod conv_marker_adaptor;
 pub mod conv_marker_concat;
+pub mod conv_offset;
 pub mod conv_segmentator;
 pub mod conv_smooth_poly1;
 pub mod conv_stroke;
 pub mod conv_transform;
 pub mod curves;
 pub mod dda_line;
+pub mod draw_list;
 pub mod ellipse;
 pub mod ellipse_bresenham;
 pub mod embedded_raster_fonts;
 pub mod font_cache_manager;
+#[cfg(feature = "freetype")]
 pub mod font_freetype;
+pub mod font_truetype;
 pub mod gamma_functions;
 pub mod gamma_lut;
+pub mod glyph_color;
 pub mod glyph_raster_bin;
+pub mod gpos;
 pub mod gradient_lut;
+pub mod gsv_font;
 pub mod gsv_text;
 pub mod image_accessors;
+pub mod image_buffer;
 pub mod image_filters;
+#[cfg(feature = "image_io")]
+pub mod image_io;
 pub mod line_aa_basics;
 pub mod math;
 pub mod math_stroke;
+pub mod molecule;
+pub mod path_picker;
 pub mod path_storage;
 pub mod path_storage_integer;
 pub mod pattern_filters_rgba;
+pub mod pixel_format;
 pub mod pixfmt_amask_adaptor;
+pub mod pixfmt_dyn;
 pub mod pixfmt_gray;
+pub mod pixfmt_lcd;
 pub mod pixfmt_rgb;
 pub mod pixfmt_rgb_packed;
 pub mod pixfmt_rgba;
 pub mod pixfmt_transposer;
+pub mod png;
+pub mod poly_bool;
+pub mod raster_font;
 mod rasterizer_cells_aa;
 pub mod rasterizer_compound_aa;
 pub mod rasterizer_outline;
@@ -80,6 +101,7 @@ pub mod scanline_p;
 pub mod scanline_storage_aa;
 pub mod scanline_storage_bin;
 pub mod scanline_u;
+pub mod sdf;
 pub mod shorten_path;
 pub mod simul_eq;
 pub mod span_allocator;
@@ -102,12 +124,17 @@ pub mod span_pattern_rgb;
 pub mod span_pattern_rgba;
 pub mod span_solid;
 pub mod span_subdiv_adaptor;
+pub mod style_table;
+pub mod svg;
+pub mod text_layout;
+pub mod text_on_path;
 pub mod trans_affine;
 pub mod trans_bilinear;
 pub mod trans_double_path;
 pub mod trans_perspective;
 pub mod trans_single_path;
 pub mod trans_viewport;
+pub mod truetype;
 pub mod vcgen_bspline;
 pub mod vcgen_contour;
 pub mod vcgen_dash;
@@ -130,14 +157,19 @@ pub mod rendering_buffer_dynarrow;
 pub mod trans_wrap_magnifier;
 
 // Namespace
+pub use agg2d::{Agg2D, Agg2DImage};
 pub use array::{PodBVector, VecPodB};
 pub use arrowhead::Arrowhead;
 pub use basics::{deg2rad, is_vertex, FillingRule, RectD, RectI, RowData, Span};
 pub use blur::{stack_blur_gray8, stack_blur_rgb24, stack_blur_rgb32};
 pub use bounding_rect::{bounding_rect, bounding_rect_single};
 pub use bspline::Bspline;
+pub use canvas::Canvas;
 pub use color_gray::{Gray16, Gray8};
 pub use color_rgba::{Rgba, Rgba16, Rgba8};
+pub use compound_shape::{
+    load_compound_shapes, render_compound_shape, CompoundShape, LineStyle, PathStyle,
+};
 pub use conv_bspline::ConvBspline;
 pub use conv_concat::ConvConcat;
 pub use conv_contour::ConvContour;
@@ -147,18 +179,26 @@ pub use conv_gpc::{ConvGpc, GpcOp};
 pub use conv_marker::ConvMarker;
 pub use conv_marker_adaptor::ConvMarkerAdaptor;
 pub use conv_marker_concat::ConvMarkerConcat;
+pub use conv_offset::ConvOffset;
 pub use conv_segmentator::ConvSegmentator;
 pub use conv_smooth_poly1::{ConvSmoothPoly1, ConvSmoothPoly1Curve};
 pub use conv_stroke::ConvStroke;
 pub use conv_transform::ConvTransform;
 pub use curves::{Curve3, Curve4};
+pub use draw_list::{DrawList, Paint};
 pub use ellipse::Ellipse;
 pub use font_cache_manager::{FontCacheManager, GlyphCache, GlyphDataType, GlyphRender};
-pub use font_freetype::FreetypeBase;
+#[cfg(feature = "freetype")]
+pub use font_freetype::{FreetypeBase, VariationAxis};
+pub use font_truetype::TrueTypeBase;
 pub use gamma_functions::{GammaLinear, GammaMultiply, GammaNone, GammaPower, GammaThreshold};
 pub use gamma_lut::GammaLut;
+pub use glyph_color::{ColorGlyph, ColorPalettes};
+pub use gpos::GposKerning;
 pub use gradient_lut::{ColorIp, GradientLut};
+pub use gsv_font::GsvFont;
 pub use gsv_text::{GsvText, GsvTextOutline};
+pub use image_buffer::{ImageBuffer, ImageView, PixView};
 pub use image_accessors::{
     ImageAccessorClip, ImageAccessorClone, ImageAccessorNoClip, ImageAccessorWrap, WrapModeReflect,
     WrapModeReflectAutoPow2, WrapModeRepeat, WrapModeRepeatAutoPow2, WrapModeRepeatPow2,
@@ -166,23 +206,31 @@ pub use image_accessors::{
 pub use image_filters::{
     ImageFilter, ImageFilterBessel, ImageFilterBicubic, ImageFilterBilinear, ImageFilterBlackman,
     ImageFilterCatrom, ImageFilterGaussian, ImageFilterHamming, ImageFilterHanning,
-    ImageFilterHermite, ImageFilterKaiser, ImageFilterLanczos, ImageFilterLut, ImageFilterMitchell,
-    ImageFilterQuadric, ImageFilterScale, ImageFilterSinc, ImageFilterSpline16,
-    ImageFilterSpline36, ImageSubpixelScale,
+    ImageFilterHermite, ImageFilterKaiser, ImageFilterKind, ImageFilterLanczos, ImageFilterLut,
+    ImageFilterMitchell, ImageFilterQuadric, ImageFilterScale, ImageFilterSinc,
+    ImageFilterSpline16, ImageFilterSpline36, ImageSubpixelScale,
 };
 pub use line_aa_basics::LineCoord;
 pub use math::{calc_distance, point_in_triangle};
 pub use math_stroke::{InnerJoin, LineCap, LineJoin, MathStroke};
+pub use molecule::{
+    load_sdf, render_molecule, Atom, Bond, BondOrder, BondStereo, Molecule, MoleculeStyle,
+};
+pub use path_picker::PathPicker;
 pub use path_storage::{PathBase, PathStorage, PolyPlainAdaptor};
 pub use path_storage_integer::{PathStorageInteger, SerializedIntegerPathAdaptor};
 pub use pattern_filters_rgba::{PatternFilterBilinearRgba16, PatternFilterBilinearRgba8};
+pub use pixel_format::{ConvertColor, PixelFormat};
+pub use pixfmt_dyn::PixFmtDyn;
 pub use pixfmt_gray::{
     AlphaBlendGray, BlenderGray, BlenderGray16, BlenderGray8, BlenderGrayPre, PixGray16, PixGray8,
 };
+pub use pixfmt_lcd::{LcdFilter, LcdOrder, PixLcd};
 pub use pixfmt_rgb::{
     BlenderRgb, BlenderRgbGamma, BlenderRgbPre, PixBgr24, PixBgr24Gamma, PixBgr24Pre, BlenderBgr24, BlenderBgr24Pre
 };
 pub use pixfmt_rgba::{BlenderBgra32, BlenderBgra32Pre, BlenderRgbaPre, PixBgra32, PixBgra32Pre};
+pub use raster_font::RasterFont;
 pub use rasterizer_compound_aa::{LayerOrder, RasterizerCompoundAa};
 pub use rasterizer_outline::RasterizerOutline;
 pub use rasterizer_outline_aa::RasterizerOutlineAa;
@@ -218,6 +266,7 @@ pub use scanline_storage_aa::{
 };
 pub use scanline_storage_bin::ScanlineStorageBin;
 pub use scanline_u::ScanlineU8;
+pub use sdf::{SdfGenerator, SpanSdf};
 pub use span_allocator::VecSpan;
 pub use span_converter::SpanProcess;
 pub use span_gouraud_gray::SpanGouraudGray;
@@ -243,11 +292,15 @@ pub use span_interpolator_linear::{SpanIpLinear, SpanIpLinearSubdiv};
 pub use span_interpolator_persp::{SpanIpPerspExact, SpanIpPerspLerp};
 pub use span_interpolator_trans::SpanIpTrans;
 pub use span_subdiv_adaptor::SpanSubdivAdaptor;
+pub use style_table::{GradientShape, StyleTable};
+pub use text_layout::{LayoutGlyph, LayoutLine, TextAlign, TextLayout};
+pub use text_on_path::{GlyphOrientation, PathGlyph, TextOnPath};
 pub use trans_affine::TransAffine;
 pub use trans_bilinear::TransBilinear;
 pub use trans_perspective::TransPerspective;
 pub use trans_viewport::{AspectRatio, TransViewport};
 pub use trans_wrap_magnifier::TransWarpMagnifier;
+pub use truetype::TrueTypeFace;
 pub use vcgen_markers_term::VcgenMarkersTerm;
 pub use vcgen_stroke::VcgenStroke;
 pub use vcgen_vertex_sequence::VcgenVertexSequence;
@@ -276,11 +329,18 @@ pub trait PixFmtGray: PixFmt {
     const PIXEL_OFFSET: u32;
 }
 
+pub trait PixFmtOwned: PixFmt {
+    fn new_from_rbuf(rbuf: RenderBuf) -> Self;
+}
+
 pub trait PixFmt: ImageSrc {
     type C: Color;
     type O: Order;
     type T: AggPrimitive;
     const PIXEL_WIDTH: u32;
+    // Columns of the format per pixel of an image blended onto it, 3 for
+    // the subpixels of PixLcd
+    const SUBPIXELS: u32 = 1;
     fn attach_pixfmt<Pix: PixFmt>(
         &mut self, pixf: &Pix, x1: i32, y1: i32, x2: i32, y2: i32,
     ) -> bool;
@@ -835,8 +895,11 @@ pub trait ImageWrap {
     fn get(&mut self, v: i32) -> u32;
     fn inc(&mut self) -> u32;
 }
+pub trait GlyphPathAdaptor: VertexSource {
+    fn init(&mut self, data: *const u8, size: usize, dx: f64, dy: f64, scale: f64);
+}
 pub trait FontEngine: FontEngineBase {
-    type PathAdaptorType: VertexSource;
+    type PathAdaptorType: GlyphPathAdaptor;
 
     fn path_adaptor(&self) -> Self::PathAdaptorType;
     fn new(max_faces: u32) -> Self;
@@ -859,6 +922,9 @@ pub trait FontEngineBase {
     fn advance_y(&self) -> f64;
     fn font_signature(&self) -> &str;
     fn change_stamp(&self) -> i32;
+    fn ascender(&self) -> f64;
+    fn descender(&self) -> f64;
+    fn flip_y(&self) -> bool;
     fn gray8_adaptor(&self) -> Self::Gray8AdaptorType;
     fn gray8_scanline(&self) -> Self::Gray8ScanlineType;
     fn mono_adaptor(&self) -> Self::MonoAdaptorType;
@@ -0,0 +1,571 @@
+use crate::gsv_text::GsvText;
+use crate::path_storage::PathStorage;
+use crate::rasterizer_scanline_aa::RasterizerScanlineAa;
+use crate::rasterizer_sl_clip::RasterizerSlClipDbl;
+use crate::renderer_scanline::render_scanlines_aa_solid;
+use crate::scanline_u::ScanlineU8;
+use crate::trans_affine::TransAffine;
+use crate::trans_viewport::{AspectRatio, TransViewport};
+use crate::{Color, ConvStroke, ConvTransform, LineCap, LineJoin, RasterScanLine, Renderer};
+use std::io::{self, BufRead, BufReader};
+use std::str::FromStr;
+
+//===================================================================Atom
+// An atom of the atom block. The coordinates are the ones of the file,
+// usually in Angstroms and with y pointing up.
+#[derive(Clone, Debug)]
+pub struct Atom {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub element: String,
+    pub charge: i32,
+    pub mass_diff: i32,
+}
+
+impl Atom {
+    // The element followed by the charge, e.g. "N+" or "O2-"
+    pub fn label(&self) -> String {
+        match self.charge {
+            0 => self.element.clone(),
+            1 => format!("{}+", self.element),
+            -1 => format!("{}-", self.element),
+            c if c > 0 => format!("{}{}+", self.element, c),
+            c => format!("{}{}-", self.element, -c),
+        }
+    }
+}
+
+//==============================================================BondOrder
+// The bond types of V2000, the last four are for queries
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BondOrder {
+    Single = 1,
+    Double = 2,
+    Triple = 3,
+    Aromatic = 4,
+    SingleOrDouble = 5,
+    SingleOrAromatic = 6,
+    DoubleOrAromatic = 7,
+    Any = 8,
+}
+
+impl BondOrder {
+    fn from_mdl(v: u32) -> Option<BondOrder> {
+        Some(match v {
+            1 => BondOrder::Single,
+            2 => BondOrder::Double,
+            3 => BondOrder::Triple,
+            4 => BondOrder::Aromatic,
+            5 => BondOrder::SingleOrDouble,
+            6 => BondOrder::SingleOrAromatic,
+            7 => BondOrder::DoubleOrAromatic,
+            8 => BondOrder::Any,
+            _ => return None,
+        })
+    }
+}
+
+//=============================================================BondStereo
+// Up and Down are wedges pointing from the first atom to the second one,
+// CisTransEither marks double bonds of unknown geometry
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BondStereo {
+    None = 0,
+    Up = 1,
+    CisTransEither = 3,
+    Either = 4,
+    Down = 6,
+}
+
+impl BondStereo {
+    fn from_mdl(v: u32) -> BondStereo {
+        match v {
+            1 => BondStereo::Up,
+            3 => BondStereo::CisTransEither,
+            4 => BondStereo::Either,
+            6 => BondStereo::Down,
+            _ => BondStereo::None,
+        }
+    }
+}
+
+//===================================================================Bond
+// A bond between two atoms given by their indices into Molecule::atoms,
+// counting from 0. topology is 0 for either, 1 for ring and 2 for chain.
+#[derive(Clone, Copy, Debug)]
+pub struct Bond {
+    pub atom1: u32,
+    pub atom2: u32,
+    pub order: BondOrder,
+    pub stereo: BondStereo,
+    pub topology: u32,
+}
+
+//===============================================================Molecule
+// One molecule of an MDL MOL (V2000) or SDF file: the name from the
+// header, the atoms and bonds and the data items that follow them in an
+// SDF file. Charges come from the atom block or, if there are any, from
+// the "M  CHG" lines, which replace them.
+//
+//   MFCD00191150
+//     Mt7.00  09020210442D
+//
+//    23 23  0  0  1  0  0  0  0  0999 V2000
+//      -2.6793   -0.2552    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+//      . . .
+//     1  2  1  0  0  0  0
+//      . . .
+//   M  END
+//   > <MDLNUMBER>
+//   MFCD00191150
+//
+//   $$$$
+#[derive(Clone, Debug)]
+pub struct Molecule {
+    pub name: String,
+    pub comment: String,
+    pub atoms: Vec<Atom>,
+    pub bonds: Vec<Bond>,
+    pub data: Vec<(String, String)>,
+}
+
+impl Molecule {
+    pub fn new() -> Self {
+        Molecule {
+            name: String::new(),
+            comment: String::new(),
+            atoms: Vec::new(),
+            bonds: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    // The value of the data item with the given name
+    pub fn data_item(&self, name: &str) -> Option<&str> {
+        self.data
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn average_bond_len(&self) -> f64 {
+        if self.bonds.is_empty() {
+            return 0.;
+        }
+        let mut len = 0.;
+        for b in self.bonds.iter() {
+            let a1 = &self.atoms[b.atom1 as usize];
+            let a2 = &self.atoms[b.atom2 as usize];
+            len += ((a2.x - a1.x) * (a2.x - a1.x) + (a2.y - a1.y) * (a2.y - a1.y)).sqrt();
+        }
+        len / self.bonds.len() as f64
+    }
+
+    // (x1, y1, x2, y2) of the atom centers, None without atoms
+    pub fn bounding_rect(&self) -> Option<(f64, f64, f64, f64)> {
+        let first = self.atoms.first()?;
+        let (mut x1, mut y1, mut x2, mut y2) = (first.x, first.y, first.x, first.y);
+        for a in self.atoms.iter() {
+            x1 = x1.min(a.x);
+            y1 = y1.min(a.y);
+            x2 = x2.max(a.x);
+            y2 = y2.max(a.y);
+        }
+        Some((x1, y1, x2, y2))
+    }
+
+    // Matrix fitting the molecule into a width x height area with y
+    // pointing down, centered and keeping the aspect ratio. Half a bond
+    // length is left around the atoms for the labels.
+    pub fn viewport(&self, width: f64, height: f64) -> TransAffine {
+        match self.bounding_rect() {
+            Some((x1, y1, x2, y2)) => {
+                let len = self.average_bond_len();
+                let m = if len > 0. { len * 0.5 } else { 1. };
+                let mut vp = TransViewport::new();
+                vp.set_preserve_aspect_ratio(0.5, 0.5, AspectRatio::Meet);
+                vp.set_world_viewport(x1 - m, -y2 - m, x2 + m, -y1 + m);
+                vp.set_device_viewport(0., 0., width, height);
+                let mut mtx = TransAffine::trans_affine_scaling(1., -1.);
+                mtx *= vp.to_affine();
+                mtx
+            }
+            None => TransAffine::new_default(),
+        }
+    }
+
+    // Replaces the contents with the next molecule from the reader.
+    // Returns false at the end of the input.
+    pub fn read_next<R: BufRead>(&mut self, rdr: &mut R) -> io::Result<bool> {
+        *self = Molecule::new();
+        // The name, program, comment and counts lines. Blank lines after
+        // the last molecule end the input as well.
+        let mut header = Vec::new();
+        let mut line = String::new();
+        while header.len() < 4 && rdr.read_line(&mut line)? > 0 {
+            header.push(std::mem::take(&mut line));
+        }
+        if header.iter().all(|l| l.trim().is_empty()) && rest_is_blank(rdr)? {
+            return Ok(false);
+        }
+        if header.len() < 4 {
+            return Err(unexpected_eof());
+        }
+        self.name = header[0].trim().to_string();
+        self.comment = header[2].trim().to_string();
+
+        line = std::mem::take(&mut header[3]);
+        if line.contains("V3000") {
+            return Err(bad_data("V3000 molfiles aren't supported"));
+        }
+        let num_atoms = field::<usize>(&line, 0, 3)?;
+        let num_bonds = field::<usize>(&line, 3, 6)?;
+
+        for _ in 0..num_atoms {
+            next_line(rdr, &mut line)?;
+            let element = col(&line, 31, 34);
+            if element.is_empty() {
+                return Err(bad_data("missing element in molfile"));
+            }
+            // 1...3 are +3...+1, 5...7 are -1...-3, 4 is a radical
+            let ccc = opt_field::<i32>(&line, 36, 39)?;
+            self.atoms.push(Atom {
+                x: field(&line, 0, 10)?,
+                y: field(&line, 10, 20)?,
+                z: field(&line, 20, 30)?,
+                element: element.to_string(),
+                charge: if ccc > 0 && ccc != 4 { 4 - ccc } else { 0 },
+                mass_diff: opt_field(&line, 34, 36)?,
+            });
+        }
+
+        for _ in 0..num_bonds {
+            next_line(rdr, &mut line)?;
+            let atom1 = field::<u32>(&line, 0, 3)?;
+            let atom2 = field::<u32>(&line, 3, 6)?;
+            if atom1 == 0 || atom2 == 0 || atom1 as usize > num_atoms || atom2 as usize > num_atoms
+            {
+                return Err(bad_data("bad atom number in molfile bond"));
+            }
+            let order = BondOrder::from_mdl(field(&line, 6, 9)?)
+                .ok_or_else(|| bad_data("bad bond type in molfile"))?;
+            self.bonds.push(Bond {
+                atom1: atom1 - 1,
+                atom2: atom2 - 1,
+                order: order,
+                stereo: BondStereo::from_mdl(opt_field(&line, 9, 12)?),
+                topology: opt_field(&line, 15, 18)?,
+            });
+        }
+
+        // The properties block up to "M  END"
+        let mut charges = false;
+        loop {
+            line.clear();
+            if rdr.read_line(&mut line)? == 0 || line.starts_with("$$$$") {
+                return Ok(true);
+            }
+            if line.starts_with("M  END") {
+                break;
+            }
+            if line.starts_with("M  CHG") {
+                if !charges {
+                    charges = true;
+                    for a in self.atoms.iter_mut() {
+                        a.charge = 0;
+                    }
+                }
+                let v = line[6..]
+                    .split_whitespace()
+                    .map(|s| s.parse::<i32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| bad_data("bad number in molfile"))?;
+                for p in v.get(1..).unwrap_or(&[]).chunks(2) {
+                    let atom = p[0].checked_sub(1).and_then(|i| usize::try_from(i).ok());
+                    match (p, atom.and_then(|i| self.atoms.get_mut(i))) {
+                        ([_, c], Some(a)) => a.charge = *c,
+                        _ => return Err(bad_data("bad charge in molfile")),
+                    }
+                }
+            }
+        }
+
+        // The data items of an SDF file, "> <name>" followed by the value
+        // lines up to an empty one
+        loop {
+            line.clear();
+            if rdr.read_line(&mut line)? == 0 || line.starts_with("$$$$") {
+                return Ok(true);
+            }
+            if !line.starts_with('>') {
+                continue;
+            }
+            let name = match (line.find('<'), line.rfind('>')) {
+                (Some(i), Some(j)) if i < j => line[i + 1..j].to_string(),
+                _ => String::new(),
+            };
+            let mut value = String::new();
+            loop {
+                line.clear();
+                let end = rdr.read_line(&mut line)? == 0 || line.starts_with("$$$$");
+                if end || line.trim().is_empty() {
+                    self.data.push((name, value));
+                    if end {
+                        return Ok(true);
+                    }
+                    break;
+                }
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(line.trim_end());
+            }
+        }
+    }
+}
+
+// Columns from..to of a line of fixed width fields
+fn col(line: &str, from: usize, to: usize) -> &str {
+    let len = line.len();
+    line.get(from.min(len)..to.min(len)).unwrap_or("").trim()
+}
+
+fn field<T: FromStr>(line: &str, from: usize, to: usize) -> io::Result<T> {
+    col(line, from, to)
+        .parse::<T>()
+        .map_err(|_| bad_data("bad number in molfile"))
+}
+
+// A field that may be left out at the end of a line
+fn opt_field<T: FromStr + Default>(line: &str, from: usize, to: usize) -> io::Result<T> {
+    if col(line, from, to).is_empty() {
+        return Ok(T::default());
+    }
+    field(line, from, to)
+}
+
+fn next_line<R: BufRead>(rdr: &mut R, line: &mut String) -> io::Result<()> {
+    line.clear();
+    if rdr.read_line(line)? == 0 {
+        return Err(unexpected_eof());
+    }
+    Ok(())
+}
+
+// Reads the rest of the input, true if it holds nothing but whitespace
+fn rest_is_blank<R: BufRead>(rdr: &mut R) -> io::Result<bool> {
+    let mut line = String::new();
+    while rdr.read_line(&mut line)? > 0 {
+        if !line.trim().is_empty() {
+            return Ok(false);
+        }
+        line.clear();
+    }
+    Ok(true)
+}
+
+fn unexpected_eof() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected end of molfile")
+}
+
+fn bad_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+//===============================================================load_sdf
+// Reads all molecules of an SDF file, or the one of a MOL file
+pub fn load_sdf(fname: &str) -> io::Result<Vec<Molecule>> {
+    let mut rdr = BufReader::new(std::fs::File::open(fname)?);
+    let mut molecules = Vec::new();
+    loop {
+        let mut mol = Molecule::new();
+        if !mol.read_next(&mut rdr)? {
+            break;
+        }
+        molecules.push(mol);
+    }
+    Ok(molecules)
+}
+
+//==========================================================MoleculeStyle
+// How render_molecule() draws a molecule. The sizes are fractions of the
+// average bond length: line_width of the bonds and labels, bond_gap
+// between the lines of double and triple bonds and the width of wedges,
+// label_size the height of the element labels.
+#[derive(Clone)]
+pub struct MoleculeStyle<C: Color> {
+    pub line_width: f64,
+    pub bond_gap: f64,
+    pub label_size: f64,
+    pub bond_color: C,
+    pub element_colors: Vec<(String, C)>,
+}
+
+impl<C: Color> MoleculeStyle<C> {
+    pub fn new(color: C) -> Self {
+        MoleculeStyle {
+            line_width: 0.06,
+            bond_gap: 0.2,
+            label_size: 0.4,
+            bond_color: color,
+            element_colors: Vec::new(),
+        }
+    }
+
+    pub fn set_element_color(&mut self, element: &str, c: C) {
+        match self.element_colors.iter_mut().find(|(e, _)| e == element) {
+            Some(ec) => ec.1 = c,
+            None => self.element_colors.push((element.to_string(), c)),
+        }
+    }
+
+    // The color of the label of an element, the bond color by default
+    pub fn element_color(&self, element: &str) -> C {
+        self.element_colors
+            .iter()
+            .find(|(e, _)| e == element)
+            .map_or(self.bond_color, |ec| ec.1)
+    }
+}
+
+//========================================================render_molecule
+// Renders the bonds and labels of a molecule transformed by mtx, e.g. the
+// one of Molecule::viewport(). Bonds are stroked with ConvStroke, double
+// and triple bonds as parallel lines, aromatic ones with a dashed second
+// line, and stereo bonds as solid or hashed wedges. Atoms other than
+// carbon, charged or lone atoms get a GsvText label and the bonds stop
+// short of it.
+pub fn render_molecule<B: Renderer>(
+    ren: &mut B, mol: &Molecule, mtx: &TransAffine, style: &MoleculeStyle<B::C>,
+) {
+    let len = mol.average_bond_len();
+    let unit = if len > 0. { len } else { 1. };
+    let width = style.line_width * unit;
+    let gap = style.bond_gap * unit;
+    let label_size = style.label_size * unit;
+    // The hashes of wedges and the dashes of aromatic bonds are at least
+    // this far apart, so that a zero line width or bond gap doesn't make
+    // them endless
+    let min_step = unit * 0.02;
+
+    let mut degree = vec![0; mol.atoms.len()];
+    for b in mol.bonds.iter() {
+        degree[b.atom1 as usize] += 1;
+        degree[b.atom2 as usize] += 1;
+    }
+    let labelled: Vec<bool> = mol
+        .atoms
+        .iter()
+        .zip(degree.iter())
+        .map(|(a, &d)| a.element != "C" || a.charge != 0 || a.mass_diff != 0 || d == 0)
+        .collect();
+
+    let mut lines = PathStorage::new();
+    let mut wedges = PathStorage::new();
+    for b in mol.bonds.iter() {
+        let (a1, a2) = (&mol.atoms[b.atom1 as usize], &mol.atoms[b.atom2 as usize]);
+        let d = ((a2.x - a1.x) * (a2.x - a1.x) + (a2.y - a1.y) * (a2.y - a1.y)).sqrt();
+        let t1 = if labelled[b.atom1 as usize] {
+            label_size * 0.7
+        } else {
+            0.
+        };
+        let t2 = if labelled[b.atom2 as usize] {
+            label_size * 0.7
+        } else {
+            0.
+        };
+        if t1 + t2 >= d {
+            continue;
+        }
+        let (ux, uy) = ((a2.x - a1.x) / d, (a2.y - a1.y) / d);
+        let (nx, ny) = (-uy, ux);
+        let (x1, y1) = (a1.x + ux * t1, a1.y + uy * t1);
+        let (x2, y2) = (a2.x - ux * t2, a2.y - uy * t2);
+        let mut add_line = |off: f64| {
+            lines.move_to(x1 + nx * off, y1 + ny * off);
+            lines.line_to(x2 + nx * off, y2 + ny * off);
+        };
+        match (b.order, b.stereo) {
+            (BondOrder::Single, BondStereo::Up) => {
+                wedges.move_to(x1, y1);
+                wedges.line_to(x2 + nx * gap * 0.5, y2 + ny * gap * 0.5);
+                wedges.line_to(x2 - nx * gap * 0.5, y2 - ny * gap * 0.5);
+                wedges.close_polygon(0);
+            }
+            (BondOrder::Single, BondStereo::Down) => {
+                let n = ((d - t1 - t2) / (width * 3.).max(min_step)).max(3.) as u32;
+                for i in 1..=n {
+                    let k = i as f64 / n as f64;
+                    let (x, y) = (x1 + (x2 - x1) * k, y1 + (y2 - y1) * k);
+                    let w = gap * 0.5 * k;
+                    lines.move_to(x - nx * w, y - ny * w);
+                    lines.line_to(x + nx * w, y + ny * w);
+                }
+            }
+            (BondOrder::Double, _)
+            | (BondOrder::SingleOrDouble, _)
+            | (BondOrder::DoubleOrAromatic, _) => {
+                add_line(-gap * 0.5);
+                add_line(gap * 0.5);
+            }
+            (BondOrder::Triple, _) => {
+                add_line(-gap);
+                add_line(0.);
+                add_line(gap);
+            }
+            (BondOrder::Aromatic, _) => {
+                add_line(-gap * 0.5);
+                let l = d - t1 - t2;
+                let n = (l / (gap * 1.5).max(min_step)).max(1.).round();
+                let dash = l / n;
+                for i in 0..n as u32 {
+                    let k1 = (i as f64 * dash + dash * 0.2) / l;
+                    let k2 = (i as f64 * dash + dash * 0.8) / l;
+                    let (ox, oy) = (nx * gap * 0.5, ny * gap * 0.5);
+                    lines.move_to(x1 + (x2 - x1) * k1 + ox, y1 + (y2 - y1) * k1 + oy);
+                    lines.line_to(x1 + (x2 - x1) * k2 + ox, y1 + (y2 - y1) * k2 + oy);
+                }
+            }
+            _ => add_line(0.),
+        }
+    }
+
+    let cb = *ren.bounding_clip_box();
+    let mut ras: RasterizerScanlineAa<RasterizerSlClipDbl> = RasterizerScanlineAa::new();
+    ras.clip_box(
+        cb.x1 as f64,
+        cb.y1 as f64,
+        cb.x2 as f64 + 1.,
+        cb.y2 as f64 + 1.,
+    );
+    let mut sl = ScanlineU8::new();
+
+    let mut stroke: ConvStroke<_> = ConvStroke::new_borrowed(&mut lines);
+    stroke.set_width(width);
+    stroke.set_line_join(LineJoin::Round);
+    stroke.set_line_cap(LineCap::Round);
+    stroke.set_approximation_scale(mtx.scale());
+    ras.add_path(&mut ConvTransform::new_borrowed(&mut stroke, *mtx), 0);
+    ras.add_path(&mut ConvTransform::new_borrowed(&mut wedges, *mtx), 0);
+    render_scanlines_aa_solid(&mut ras, &mut sl, ren, &style.bond_color);
+
+    let mut text = GsvText::new();
+    text.set_size(label_size, 0.);
+    let mut stroke: ConvStroke<_> = ConvStroke::new_owned(text);
+    stroke.set_width(width * 0.8);
+    stroke.set_line_join(LineJoin::Round);
+    stroke.set_line_cap(LineCap::Round);
+    stroke.set_approximation_scale(mtx.scale());
+    let mut trans = ConvTransform::new_owned(stroke, *mtx);
+    for (a, _) in mol.atoms.iter().zip(labelled.iter()).filter(|(_, &l)| l) {
+        let text = trans.source_mut().source_mut();
+        text.set_text(&a.label());
+        let w = text.text_width();
+        text.set_start_point(a.x - w * 0.5, a.y - label_size * 0.5);
+        ras.reset();
+        ras.add_path(&mut trans, 0);
+        render_scanlines_aa_solid(&mut ras, &mut sl, ren, &style.element_color(&a.element));
+    }
+}
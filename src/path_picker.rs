@@ -0,0 +1,302 @@
+use crate::basics::FillingRule;
+use crate::bounding_rect::bounding_rect_single;
+use crate::math_stroke::{LineCap, LineJoin};
+use crate::path_storage::PathStorage;
+use crate::rasterizer_scanline_aa::RasterizerScanlineAa;
+use crate::rasterizer_sl_clip::RasterizerSlClipDbl;
+use crate::scanline_bin::ScanlineBin;
+use crate::{ConvCurve, ConvStroke, RasterScanLine, Scanline, VertexSource};
+use std::collections::HashMap;
+
+// Entries covering more grid cells than this are kept in a list of their
+// own and tested on every query
+const MAX_CELLS: f64 = 256.;
+
+struct PickEntry {
+    id: u32,
+    path_id: u32,
+    filling_rule: FillingRule,
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+}
+
+//=============================================================PathPicker
+// Finds the paths under a point or in a rectangle, e.g. for selecting
+// shapes with the mouse. Paths are added with an id, which needn't be
+// unique, and are stacked in the order they are added: the last one is
+// on top. A fill is hit by the area that would be drawn with its filling
+// rule, a stroke by its outline as ConvStroke makes it with the width,
+// joins and caps set at the time it's added.
+//
+// The bounding boxes of the paths go into a grid, so a query rasterizes
+// only the paths whose boxes contain the point or touch the rectangle.
+// The coordinates are the ones of the paths; add them with ConvTransform
+// to pick in screen coordinates.
+//
+//   let mut picker = PathPicker::new();
+//   picker.add_fill(&mut ellipse, 0, 1, FillingRule::FillNonZero);
+//   picker.add_stroke(&mut polyline, 0, 2, 5.);
+//   let top = picker.pick(x, y);
+pub struct PathPicker {
+    path: PathStorage,
+    entries: Vec<Option<PickEntry>>,
+    grid: HashMap<(i32, i32), Vec<u32>>,
+    large: Vec<u32>,
+    cell_size: f64,
+    line_join: LineJoin,
+    line_cap: LineCap,
+    miter_limit: f64,
+    ras: RasterizerScanlineAa<RasterizerSlClipDbl>,
+    sl: ScanlineBin,
+}
+
+impl PathPicker {
+    pub fn new() -> Self {
+        Self::new_with_cell_size(64.)
+    }
+
+    // cell_size is the size of the grid cells, best about the size of the
+    // typical path
+    pub fn new_with_cell_size(cell_size: f64) -> Self {
+        PathPicker {
+            path: PathStorage::new(),
+            entries: Vec::new(),
+            grid: HashMap::new(),
+            large: Vec::new(),
+            cell_size: cell_size,
+            line_join: LineJoin::Round,
+            line_cap: LineCap::Round,
+            miter_limit: 4.,
+            ras: RasterizerScanlineAa::new(),
+            sl: ScanlineBin::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.path.remove_all();
+        self.entries.clear();
+        self.grid.clear();
+        self.large.clear();
+    }
+
+    // Number of paths added, including the removed ones
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn set_line_join(&mut self, lj: LineJoin) {
+        self.line_join = lj;
+    }
+
+    pub fn line_join(&self) -> LineJoin {
+        self.line_join
+    }
+
+    pub fn set_line_cap(&mut self, lc: LineCap) {
+        self.line_cap = lc;
+    }
+
+    pub fn line_cap(&self) -> LineCap {
+        self.line_cap
+    }
+
+    pub fn set_miter_limit(&mut self, ml: f64) {
+        self.miter_limit = ml;
+    }
+
+    pub fn miter_limit(&self) -> f64 {
+        self.miter_limit
+    }
+
+    // The area of the path
+    pub fn add_fill<VS: VertexSource>(
+        &mut self, vs: &mut VS, path_id: u32, id: u32, filling_rule: FillingRule,
+    ) {
+        let mut curve: ConvCurve<_> = ConvCurve::new_borrowed(vs);
+        let start = self.path.start_new_path();
+        self.path.concat_path(&mut curve, path_id);
+        self.add_entry(id, start, filling_rule);
+    }
+
+    // The outline of the path stroked with the given width
+    pub fn add_stroke<VS: VertexSource>(&mut self, vs: &mut VS, path_id: u32, id: u32, width: f64) {
+        let mut curve: ConvCurve<_> = ConvCurve::new_borrowed(vs);
+        let mut stroke: ConvStroke<_> = ConvStroke::new_borrowed(&mut curve);
+        stroke.set_width(width);
+        stroke.set_line_join(self.line_join);
+        stroke.set_line_cap(self.line_cap);
+        stroke.set_miter_limit(self.miter_limit);
+        let start = self.path.start_new_path();
+        self.path.concat_path(&mut stroke, path_id);
+        self.add_entry(id, start, FillingRule::FillNonZero);
+    }
+
+    // Removes all paths with the given id
+    pub fn remove(&mut self, id: u32) {
+        for i in 0..self.entries.len() {
+            if !matches!(&self.entries[i], Some(e) if e.id == id) {
+                continue;
+            }
+            let e = self.entries[i].take().unwrap();
+            let idx = i as u32;
+            match self.cells(e.x1, e.y1, e.x2, e.y2) {
+                Some((cx1, cy1, cx2, cy2)) => {
+                    for cy in cy1..=cy2 {
+                        for cx in cx1..=cx2 {
+                            if let Some(v) = self.grid.get_mut(&(cx, cy)) {
+                                v.retain(|&j| j != idx);
+                            }
+                        }
+                    }
+                }
+                None => self.large.retain(|&j| j != idx),
+            }
+        }
+    }
+
+    // The id of the topmost path at (x, y)
+    pub fn pick(&mut self, x: f64, y: f64) -> Option<u32> {
+        for i in self.candidates(x, y, x, y) {
+            if self.hit_point(i, x, y) {
+                return Some(self.entry(i).id);
+            }
+        }
+        None
+    }
+
+    // The ids of all paths at (x, y), the topmost first
+    pub fn pick_all(&mut self, x: f64, y: f64) -> Vec<u32> {
+        let mut ids = Vec::new();
+        for i in self.candidates(x, y, x, y) {
+            let id = self.entry(i).id;
+            if !ids.contains(&id) && self.hit_point(i, x, y) {
+                ids.push(id);
+            }
+        }
+        ids
+    }
+
+    // The ids of all paths with some area within the rectangle, the
+    // topmost first
+    pub fn pick_rect(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) -> Vec<u32> {
+        let (x1, x2) = (x1.min(x2), x1.max(x2));
+        let (y1, y2) = (y1.min(y2), y1.max(y2));
+        let mut ids = Vec::new();
+        for i in self.candidates(x1, y1, x2, y2) {
+            let id = self.entry(i).id;
+            if !ids.contains(&id) && self.hit_rect(i, x1, y1, x2, y2) {
+                ids.push(id);
+            }
+        }
+        ids
+    }
+
+    fn entry(&self, i: u32) -> &PickEntry {
+        self.entries[i as usize].as_ref().unwrap()
+    }
+
+    fn add_entry(&mut self, id: u32, path_id: u32, filling_rule: FillingRule) {
+        let (mut x1, mut y1, mut x2, mut y2) = (0., 0., 0., 0.);
+        if !bounding_rect_single(&mut self.path, path_id, &mut x1, &mut y1, &mut x2, &mut y2) {
+            // An empty path can't be hit, but keeps its place in the order
+            self.entries.push(None);
+            return;
+        }
+        // The rasterizer also covers the pixels the edges pass through
+        let (x1, y1, x2, y2) = (x1 - 1., y1 - 1., x2 + 1., y2 + 1.);
+        let idx = self.entries.len() as u32;
+        self.entries.push(Some(PickEntry {
+            id: id,
+            path_id: path_id,
+            filling_rule: filling_rule,
+            x1: x1,
+            y1: y1,
+            x2: x2,
+            y2: y2,
+        }));
+        match self.cells(x1, y1, x2, y2) {
+            Some((cx1, cy1, cx2, cy2)) => {
+                for cy in cy1..=cy2 {
+                    for cx in cx1..=cx2 {
+                        self.grid.entry((cx, cy)).or_insert_with(Vec::new).push(idx);
+                    }
+                }
+            }
+            None => self.large.push(idx),
+        }
+    }
+
+    // Grid cells of a box, None if there are too many
+    fn cells(&self, x1: f64, y1: f64, x2: f64, y2: f64) -> Option<(i32, i32, i32, i32)> {
+        let cx1 = (x1 / self.cell_size).floor();
+        let cy1 = (y1 / self.cell_size).floor();
+        let cx2 = (x2 / self.cell_size).floor();
+        let cy2 = (y2 / self.cell_size).floor();
+        let n = (cx2 - cx1 + 1.) * (cy2 - cy1 + 1.);
+        if n > MAX_CELLS {
+            return None;
+        }
+        Some((cx1 as i32, cy1 as i32, cx2 as i32, cy2 as i32))
+    }
+
+    // Entries whose boxes touch the given one, the topmost first
+    fn candidates(&self, x1: f64, y1: f64, x2: f64, y2: f64) -> Vec<u32> {
+        let mut found = self.large.clone();
+        match self.cells(x1, y1, x2, y2) {
+            Some((cx1, cy1, cx2, cy2)) => {
+                for cy in cy1..=cy2 {
+                    for cx in cx1..=cx2 {
+                        if let Some(v) = self.grid.get(&(cx, cy)) {
+                            found.extend_from_slice(v);
+                        }
+                    }
+                }
+            }
+            None => {
+                for (i, e) in self.entries.iter().enumerate() {
+                    if e.is_some() {
+                        found.push(i as u32);
+                    }
+                }
+            }
+        }
+        found.sort_unstable_by(|a, b| b.cmp(a));
+        found.dedup();
+        found.retain(|&i| {
+            let e = self.entry(i);
+            e.x1 <= x2 && x1 <= e.x2 && e.y1 <= y2 && y1 <= e.y2
+        });
+        found
+    }
+
+    fn rasterize(&mut self, i: u32) {
+        let (path_id, filling_rule) = {
+            let e = self.entry(i);
+            (e.path_id, e.filling_rule)
+        };
+        self.ras.reset();
+        self.ras.set_filling_rule(filling_rule);
+        self.ras.add_path(&mut self.path, path_id);
+    }
+
+    fn hit_point(&mut self, i: u32, x: f64, y: f64) -> bool {
+        self.ras.reset_clipping();
+        self.rasterize(i);
+        self.ras.hit_test(x.floor() as i32, y.floor() as i32)
+    }
+
+    fn hit_rect(&mut self, i: u32, x1: f64, y1: f64, x2: f64, y2: f64) -> bool {
+        // Clipped to the rectangle, whatever is left of the path has area
+        // in it. The pixels of the corners count as inside.
+        self.ras
+            .clip_box(x1.floor(), y1.floor(), x2.floor() + 1., y2.floor() + 1.);
+        self.rasterize(i);
+        if !self.ras.rewind_scanlines() {
+            return false;
+        }
+        self.sl.reset(self.ras.min_x(), self.ras.max_x());
+        self.ras.sweep_scanline(&mut self.sl)
+    }
+}
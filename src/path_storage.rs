@@ -282,6 +282,7 @@ impl<VC: VertexContainer> PathBase<VC> {
                         cmd
                     },
                 );
+                cmd = vs.vertex(&mut x, &mut y);
             }
         }
     }
@@ -342,6 +343,12 @@ impl<VC: VertexContainer> PathBase<VC> {
         }
     }
 
+    // Appends a vertex with any command, e.g. when restoring the vertices
+    // read with vertex_idx()
+    pub fn add_vertex(&mut self, x: f64, y: f64, cmd: u32) {
+        self.m_vertices.add_vertex(x, y, cmd);
+    }
+
     pub fn move_to(&mut self, x: f64, y: f64) {
         self.m_vertices.add_vertex(x, y, PathCmd::MoveTo as u32);
     }
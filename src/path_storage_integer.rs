@@ -3,7 +3,7 @@ use std::marker::PhantomData;
 use crate::AggInteger;
 use crate::{
     basics::{is_move_to, PathCmd, PathFlag, RectD},
-    PathStore, VertexSource,
+    GlyphPathAdaptor, PathStore, VertexSource,
 };
 
 //---------------------------------------------------------VertexInteger
@@ -234,6 +234,14 @@ impl<T: AggInteger, const COORD_SHIFT: u32> SerializedIntegerPathAdaptor<T, COOR
     }
 }
 
+impl<T: AggInteger, const COORD_SHIFT: u32> GlyphPathAdaptor
+    for SerializedIntegerPathAdaptor<T, COORD_SHIFT>
+{
+    fn init(&mut self, data: *const u8, size: usize, dx: f64, dy: f64, scale: f64) {
+        SerializedIntegerPathAdaptor::init(self, data, size, dx, dy, scale)
+    }
+}
+
 impl<T: AggInteger, const COORD_SHIFT: u32> VertexSource
     for SerializedIntegerPathAdaptor<T, COORD_SHIFT>
 {
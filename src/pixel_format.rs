@@ -0,0 +1,132 @@
+use crate::color_gray::{Gray16, Gray8};
+use crate::color_rgba::{Rgba16, Rgba8};
+use crate::Color;
+
+//=============================================================PixelFormat
+// Byte layouts of pixels chosen at run time: the formats PixFmtDyn can be
+// attached with and the layouts of the RawImage of image_io. The names
+// follow the pixel formats and the orders of their components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    Gray8,
+    Gray16,
+    Rgb555,
+    Rgb565,
+    Rgb24,
+    Bgr24,
+    Rgb48,
+    Bgr48,
+    Rgba32,
+    Argb32,
+    Abgr32,
+    Bgra32,
+    Rgba64,
+    Argb64,
+    Abgr64,
+    Bgra64,
+}
+
+impl PixelFormat {
+    // Bytes per pixel
+    pub fn pix_width(&self) -> u32 {
+        match self {
+            PixelFormat::Gray8 => 1,
+            PixelFormat::Gray16 | PixelFormat::Rgb555 | PixelFormat::Rgb565 => 2,
+            PixelFormat::Rgb24 | PixelFormat::Bgr24 => 3,
+            PixelFormat::Rgba32
+            | PixelFormat::Argb32
+            | PixelFormat::Abgr32
+            | PixelFormat::Bgra32 => 4,
+            PixelFormat::Rgb48 | PixelFormat::Bgr48 => 6,
+            PixelFormat::Rgba64
+            | PixelFormat::Argb64
+            | PixelFormat::Abgr64
+            | PixelFormat::Bgra64 => 8,
+        }
+    }
+
+    pub fn is_gray(&self) -> bool {
+        matches!(self, PixelFormat::Gray8 | PixelFormat::Gray16)
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.pix_width() == 4 || self.pix_width() == 8
+    }
+}
+
+//============================================================ConvertColor
+// Conversion of the color types of the pixel formats to and from 16-bit
+// RGBA, which PixFmtDyn converts the colors it is driven with through and
+// the PNG decoder and encoder exchange pixels in. Gray types take the
+// luminance. GRAY and DEPTH tell the channels and the bits per channel.
+pub trait ConvertColor: Color {
+    const GRAY: bool;
+    const DEPTH: u8;
+    fn to_rgba16(&self) -> [u16; 4];
+    fn from_rgba16(c: [u16; 4]) -> Self;
+}
+
+#[inline]
+fn to8(v: u16) -> u8 {
+    ((v as u32 * 255 + 32767) / 65535) as u8
+}
+
+#[inline]
+fn luminance(c: [u16; 4]) -> u16 {
+    ((c[0] as u32 * 77 + c[1] as u32 * 150 + c[2] as u32 * 29) >> 8) as u16
+}
+
+impl ConvertColor for Rgba8 {
+    const GRAY: bool = false;
+    const DEPTH: u8 = 8;
+    fn to_rgba16(&self) -> [u16; 4] {
+        [
+            self.r as u16 * 257,
+            self.g as u16 * 257,
+            self.b as u16 * 257,
+            self.a as u16 * 257,
+        ]
+    }
+    fn from_rgba16(c: [u16; 4]) -> Self {
+        Rgba8::new_params(
+            to8(c[0]) as u32,
+            to8(c[1]) as u32,
+            to8(c[2]) as u32,
+            to8(c[3]) as u32,
+        )
+    }
+}
+
+impl ConvertColor for Rgba16 {
+    const GRAY: bool = false;
+    const DEPTH: u8 = 16;
+    fn to_rgba16(&self) -> [u16; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+    fn from_rgba16(c: [u16; 4]) -> Self {
+        Rgba16::new_params(c[0] as u32, c[1] as u32, c[2] as u32, c[3] as u32)
+    }
+}
+
+impl ConvertColor for Gray8 {
+    const GRAY: bool = true;
+    const DEPTH: u8 = 8;
+    fn to_rgba16(&self) -> [u16; 4] {
+        let v = self.v as u16 * 257;
+        [v, v, v, self.a as u16 * 257]
+    }
+    fn from_rgba16(c: [u16; 4]) -> Self {
+        Gray8::new_params(to8(luminance(c)) as u32, to8(c[3]) as u32)
+    }
+}
+
+impl ConvertColor for Gray16 {
+    const GRAY: bool = true;
+    const DEPTH: u8 = 16;
+    fn to_rgba16(&self) -> [u16; 4] {
+        [self.v, self.v, self.v, self.a]
+    }
+    fn from_rgba16(c: [u16; 4]) -> Self {
+        Gray16::new_params(luminance(c) as u32, c[3] as u32)
+    }
+}
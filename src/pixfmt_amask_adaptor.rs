@@ -59,6 +59,7 @@ impl<'a, P: PixFmt, A: AlphaMask<CoverType = u8>> PixFmt for PixAmaskAdaptor<'a,
     type O = P::O;
     type T = P::T;
     const PIXEL_WIDTH: u32 = P::PIXEL_WIDTH;
+    const SUBPIXELS: u32 = P::SUBPIXELS;
 
     fn width(&self) -> u32 {
         self.pixf.width()
@@ -0,0 +1,318 @@
+use crate::basics::RowData;
+use crate::color_gray::{Gray16, Gray8};
+use crate::color_rgba::{OrderRgba, Rgba16, Rgba8};
+use crate::pixel_format::{ConvertColor, PixelFormat};
+use crate::pixfmt_gray::{PixGray16, PixGray8};
+use crate::pixfmt_rgb::{PixBgr24, PixBgr48, PixRgb24, PixRgb48};
+use crate::pixfmt_rgb_packed::{PixRgb555, PixRgb565};
+use crate::pixfmt_rgba::{
+    PixAbgr32, PixAbgr64, PixArgb32, PixArgb64, PixBgra32, PixBgra64, PixRgba32, PixRgba64,
+};
+use crate::{ImageSrc, PixFmt, PixFmtOwned, RenderBuf, RenderBuffer};
+use std::marker::PhantomData;
+
+enum Pix<'a> {
+    Gray8(PixGray8<'a>),
+    Gray16(PixGray16<'a>),
+    Rgb555(PixRgb555<'a>),
+    Rgb565(PixRgb565<'a>),
+    Rgb24(PixRgb24<'a>),
+    Bgr24(PixBgr24<'a>),
+    Rgb48(PixRgb48<'a>),
+    Bgr48(PixBgr48<'a>),
+    Rgba32(PixRgba32<'a>),
+    Argb32(PixArgb32<'a>),
+    Abgr32(PixAbgr32<'a>),
+    Bgra32(PixBgra32<'a>),
+    Rgba64(PixRgba64<'a>),
+    Argb64(PixArgb64<'a>),
+    Abgr64(PixAbgr64<'a>),
+    Bgra64(PixBgra64<'a>),
+}
+
+// Evaluates the expression with p bound to the pixel format of the
+// variant; the color arguments are converted inside the expression.
+macro_rules! dispatch {
+    ($pix:expr, $p:ident => $e:expr) => {
+        match $pix {
+            Pix::Gray8($p) => $e,
+            Pix::Gray16($p) => $e,
+            Pix::Rgb555($p) => $e,
+            Pix::Rgb565($p) => $e,
+            Pix::Rgb24($p) => $e,
+            Pix::Bgr24($p) => $e,
+            Pix::Rgb48($p) => $e,
+            Pix::Bgr48($p) => $e,
+            Pix::Rgba32($p) => $e,
+            Pix::Argb32($p) => $e,
+            Pix::Abgr32($p) => $e,
+            Pix::Bgra32($p) => $e,
+            Pix::Rgba64($p) => $e,
+            Pix::Argb64($p) => $e,
+            Pix::Abgr64($p) => $e,
+            Pix::Bgra64($p) => $e,
+        }
+    };
+}
+
+#[inline]
+fn cv<S: ConvertColor, D: ConvertColor>(c: &S) -> D {
+    D::from_rgba16(c.to_rgba16())
+}
+
+// Buffers for the converted colors of spans and lookup tables
+#[derive(Default)]
+struct Spans {
+    gray8: Vec<Gray8>,
+    gray16: Vec<Gray16>,
+    rgba8: Vec<Rgba8>,
+    rgba16: Vec<Rgba16>,
+}
+
+trait SpanColor: ConvertColor {
+    fn buf(spans: &mut Spans) -> &mut Vec<Self>;
+}
+
+impl SpanColor for Gray8 {
+    fn buf(spans: &mut Spans) -> &mut Vec<Self> {
+        &mut spans.gray8
+    }
+}
+
+impl SpanColor for Gray16 {
+    fn buf(spans: &mut Spans) -> &mut Vec<Self> {
+        &mut spans.gray16
+    }
+}
+
+impl SpanColor for Rgba8 {
+    fn buf(spans: &mut Spans) -> &mut Vec<Self> {
+        &mut spans.rgba8
+    }
+}
+
+impl SpanColor for Rgba16 {
+    fn buf(spans: &mut Spans) -> &mut Vec<Self> {
+        &mut spans.rgba16
+    }
+}
+
+fn cv_span<'s, S: ConvertColor, D: SpanColor>(spans: &'s mut Spans, colors: &[S]) -> &'s [D] {
+    let buf = D::buf(spans);
+    buf.clear();
+    buf.extend(colors.iter().map(cv::<S, D>));
+    buf
+}
+
+//===============================================================PixFmtDyn
+// A pixel format chosen at run time. It is driven with the color type C
+// (Rgba8 or Rgba16, or a gray one) and converts the colors to the color
+// type of the actual format on every call:
+//
+//   let fmt = PixelFormat::Gray8;
+//   let mut data = vec![0u8; (w * h * fmt.pix_width()) as usize];
+//   let rbuf = RenderBuf::new(data.as_mut_ptr(), w, h, (w * fmt.pix_width()) as i32);
+//   let mut ren = RendererBase::new_owned(PixFmtDyn::<Rgba8>::new(fmt, rbuf));
+//
+// PIXEL_WIDTH and the component order aren't known at compile time, so
+// the wrapper can be the target of a renderer but not a source. Its
+// PIXEL_WIDTH is a panic: generic code that reads it for the wrapper, as
+// the image accessors, span image filters and blur do, fails to build
+// once it is instantiated with it. row(), row_data() and
+// pix_ptr(), which blend_from(), copy_from() and attach_pixfmt() read the
+// source with, panic at run time. Attach a static pixel format to the
+// same buffer for those.
+pub struct PixFmtDyn<'a, C: ConvertColor> {
+    pix: Pix<'a>,
+    format: PixelFormat,
+    spans: Spans,
+    dummy: PhantomData<C>,
+}
+
+impl<'a, C: ConvertColor> PixFmtDyn<'a, C> {
+    pub fn new(format: PixelFormat, rbuf: RenderBuf) -> Self {
+        let pix = match format {
+            PixelFormat::Gray8 => Pix::Gray8(PixGray8::new_from_rbuf(rbuf)),
+            PixelFormat::Gray16 => Pix::Gray16(PixGray16::new_from_rbuf(rbuf)),
+            PixelFormat::Rgb555 => Pix::Rgb555(PixRgb555::new_from_rbuf(rbuf)),
+            PixelFormat::Rgb565 => Pix::Rgb565(PixRgb565::new_from_rbuf(rbuf)),
+            PixelFormat::Rgb24 => Pix::Rgb24(PixRgb24::new_from_rbuf(rbuf)),
+            PixelFormat::Bgr24 => Pix::Bgr24(PixBgr24::new_from_rbuf(rbuf)),
+            PixelFormat::Rgb48 => Pix::Rgb48(PixRgb48::new_from_rbuf(rbuf)),
+            PixelFormat::Bgr48 => Pix::Bgr48(PixBgr48::new_from_rbuf(rbuf)),
+            PixelFormat::Rgba32 => Pix::Rgba32(PixRgba32::new_from_rbuf(rbuf)),
+            PixelFormat::Argb32 => Pix::Argb32(PixArgb32::new_from_rbuf(rbuf)),
+            PixelFormat::Abgr32 => Pix::Abgr32(PixAbgr32::new_from_rbuf(rbuf)),
+            PixelFormat::Bgra32 => Pix::Bgra32(PixBgra32::new_from_rbuf(rbuf)),
+            PixelFormat::Rgba64 => Pix::Rgba64(PixRgba64::new_from_rbuf(rbuf)),
+            PixelFormat::Argb64 => Pix::Argb64(PixArgb64::new_from_rbuf(rbuf)),
+            PixelFormat::Abgr64 => Pix::Abgr64(PixAbgr64::new_from_rbuf(rbuf)),
+            PixelFormat::Bgra64 => Pix::Bgra64(PixBgra64::new_from_rbuf(rbuf)),
+        };
+        PixFmtDyn {
+            pix: pix,
+            format: format,
+            spans: Spans::default(),
+            dummy: PhantomData,
+        }
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+}
+
+impl<'a, C: ConvertColor> ImageSrc for PixFmtDyn<'a, C> {}
+
+impl<'a, C: ConvertColor> PixFmt for PixFmtDyn<'a, C> {
+    type C = C;
+    type O = OrderRgba;
+    type T = u8;
+    // Varies with the format, see PixelFormat::pix_width()
+    const PIXEL_WIDTH: u32 = panic!("PixFmtDyn can't be the source of pixels");
+
+    fn attach_pixfmt<Pix_: PixFmt>(
+        &mut self, pixf: &Pix_, x1: i32, y1: i32, x2: i32, y2: i32,
+    ) -> bool {
+        dispatch!(&mut self.pix, p => p.attach_pixfmt(pixf, x1, y1, x2, y2))
+    }
+
+    fn width(&self) -> u32 {
+        dispatch!(&self.pix, p => p.width())
+    }
+
+    fn height(&self) -> u32 {
+        dispatch!(&self.pix, p => p.height())
+    }
+
+    fn stride(&self) -> i32 {
+        dispatch!(&self.pix, p => p.stride())
+    }
+
+    fn pix_ptr(&self, _x: i32, _y: i32) -> (&[u8], usize) {
+        panic!("PixFmtDyn can't be the source of pixels")
+    }
+
+    fn pix_ptr_mut(&mut self, x: i32, y: i32) -> (&mut [u8], usize) {
+        dispatch!(&mut self.pix, p => p.pix_ptr_mut(x, y))
+    }
+
+    fn row(&self, _y: i32) -> &[u8] {
+        panic!("PixFmtDyn can't be the source of pixels")
+    }
+
+    fn row_mut(&mut self, y: i32) -> &mut [u8] {
+        dispatch!(&mut self.pix, p => p.row_mut(y))
+    }
+
+    fn row_data(&self, _y: i32) -> RowData<u8> {
+        panic!("PixFmtDyn can't be the source of pixels")
+    }
+
+    fn make_pix(&self, pix: &mut [u8], c: &C) {
+        dispatch!(&self.pix, p => p.make_pix(pix, &cv(c)))
+    }
+
+    fn copy_pixel(&mut self, x: i32, y: i32, c: &C) {
+        dispatch!(&mut self.pix, p => p.copy_pixel(x, y, &cv(c)))
+    }
+
+    fn blend_from<R: PixFmt<T = u8>>(
+        &mut self, from: &R, xdst: i32, ydst: i32, xsrc: i32, ysrc: i32, len: u32, cover: u32,
+    ) {
+        dispatch!(&mut self.pix, p => p.blend_from(from, xdst, ydst, xsrc, ysrc, len, cover))
+    }
+
+    fn blend_from_color<R: PixFmt<T = u8>>(
+        &mut self, from: &R, color: &C, xdst: i32, ydst: i32, xsrc: i32, ysrc: i32, len: u32,
+        cover: u32,
+    ) {
+        dispatch!(&mut self.pix, p => {
+            p.blend_from_color(from, &cv(color), xdst, ydst, xsrc, ysrc, len, cover)
+        })
+    }
+
+    fn blend_from_lut<R: PixFmt<T = u8>>(
+        &mut self, from: &R, color_lut: &[C], xdst: i32, ydst: i32, xsrc: i32, ysrc: i32, len: u32,
+        cover: u32,
+    ) {
+        dispatch!(&mut self.pix, p => {
+            let lut = cv_span(&mut self.spans, color_lut);
+            p.blend_from_lut(from, lut, xdst, ydst, xsrc, ysrc, len, cover)
+        })
+    }
+
+    fn blend_pixel(&mut self, x: i32, y: i32, c: &C, cover: u8) {
+        dispatch!(&mut self.pix, p => p.blend_pixel(x, y, &cv(c), cover))
+    }
+
+    fn pixel(&self, x: i32, y: i32) -> C {
+        dispatch!(&self.pix, p => cv(&p.pixel(x, y)))
+    }
+
+    fn copy_hline(&mut self, x: i32, y: i32, len: u32, c: &C) {
+        dispatch!(&mut self.pix, p => p.copy_hline(x, y, len, &cv(c)))
+    }
+
+    fn copy_vline(&mut self, x: i32, y: i32, len: u32, c: &C) {
+        dispatch!(&mut self.pix, p => p.copy_vline(x, y, len, &cv(c)))
+    }
+
+    fn blend_hline(&mut self, x: i32, y: i32, len: u32, c: &C, cover: u8) {
+        dispatch!(&mut self.pix, p => p.blend_hline(x, y, len, &cv(c), cover))
+    }
+
+    fn blend_vline(&mut self, x: i32, y: i32, len: u32, c: &C, cover: u8) {
+        dispatch!(&mut self.pix, p => p.blend_vline(x, y, len, &cv(c), cover))
+    }
+
+    fn blend_solid_hspan(&mut self, x: i32, y: i32, len: u32, c: &C, covers: &[u8]) {
+        dispatch!(&mut self.pix, p => p.blend_solid_hspan(x, y, len, &cv(c), covers))
+    }
+
+    fn blend_solid_vspan(&mut self, x: i32, y: i32, len: u32, c: &C, covers: &[u8]) {
+        dispatch!(&mut self.pix, p => p.blend_solid_vspan(x, y, len, &cv(c), covers))
+    }
+
+    fn blend_color_hspan(
+        &mut self, x: i32, y: i32, len: u32, colors: &[C], covers: &[u8], cover: u8,
+    ) {
+        let colors = &colors[..len as usize];
+        dispatch!(&mut self.pix, p => {
+            let colors = cv_span(&mut self.spans, colors);
+            p.blend_color_hspan(x, y, len, colors, covers, cover)
+        })
+    }
+
+    fn blend_color_vspan(
+        &mut self, x: i32, y: i32, len: u32, colors: &[C], covers: &[u8], cover: u8,
+    ) {
+        let colors = &colors[..len as usize];
+        dispatch!(&mut self.pix, p => {
+            let colors = cv_span(&mut self.spans, colors);
+            p.blend_color_vspan(x, y, len, colors, covers, cover)
+        })
+    }
+
+    fn copy_color_hspan(&mut self, x: i32, y: i32, len: u32, colors: &[C]) {
+        let colors = &colors[..len as usize];
+        dispatch!(&mut self.pix, p => {
+            let colors = cv_span(&mut self.spans, colors);
+            p.copy_color_hspan(x, y, len, colors)
+        })
+    }
+
+    fn copy_color_vspan(&mut self, x: i32, y: i32, len: u32, colors: &[C]) {
+        let colors = &colors[..len as usize];
+        dispatch!(&mut self.pix, p => {
+            let colors = cv_span(&mut self.spans, colors);
+            p.copy_color_vspan(x, y, len, colors)
+        })
+    }
+
+    fn copy_from<RB: RenderBuffer<T = u8>>(
+        &mut self, from: &RB, xdst: i32, ydst: i32, xsrc: i32, ysrc: i32, len: u32,
+    ) {
+        dispatch!(&mut self.pix, p => p.copy_from(from, xdst, ydst, xsrc, ysrc, len))
+    }
+}
@@ -6,7 +6,7 @@ use crate::{
 };
 use crate::{
     slice_t_to_vt, slice_t_to_vt_mut, AggPrimitive, Args, BlenderG, Color, Equiv, GrayArgs,
-    ImageSrc, PixFmt, PixFmtGray, RenderBuffer,
+    ImageSrc, PixFmt, PixFmtGray, PixFmtOwned, RenderBuffer,
 };
 use std::marker::PhantomData;
 use wrapping_arithmetic::wrappit;
@@ -151,6 +151,14 @@ impl<
 {
 }
 
+impl<'a, C: Color + GrayArgs, Blend: BlenderG<C>, const STEP: u32, const OFFSET: u32> PixFmtOwned
+    for AlphaBlendGray<'a, C, Blend, RenderBuf, STEP, OFFSET>
+{
+    fn new_from_rbuf(rbuf: RenderBuf) -> Self {
+        Self::new_owned(rbuf)
+    }
+}
+
 impl<
         'a,
         C: Color + GrayArgs,
@@ -336,7 +344,7 @@ impl<
         let p = slice_t_to_vt!(self.rbuf.row(y), x as u32 * STEP + OFFSET, C::ValueType);
 
         //C::new_from_rgba(&Rgba::new_params(v * 0.299, v * 0.587, v * 0.114, 1.0))
-        C::new_init(p[0], C::ValueType::from_u32(255))
+        C::new_init(p[0], C::ValueType::from_u32(C::BASE_MASK))
     }
 
     fn pix_ptr(&self, x: i32, y: i32) -> (&[u8], usize) {
@@ -0,0 +1,474 @@
+use crate::basics::RowData;
+use crate::color_rgba::Rgba8;
+use crate::trans_affine::TransAffine;
+use crate::{Color, ImageSrc, Order, PixFmt, RenderBuffer};
+
+//===============================================================LcdFilter
+// The FIR filter that spreads the coverage of a subpixel over it and its
+// two neighbors on each side, as FreeType does for LCD rendering, to
+// tame the color fringes. The weights are in 1/256, like the ones of
+// FT_Library_SetLcdFilterWeights, and are normalized to add up to one.
+#[derive(Clone)]
+pub struct LcdFilter {
+    weights: [u32; 5],
+    lut: Vec<[u8; 256]>,
+}
+
+impl LcdFilter {
+    pub fn new(weights: [u32; 5]) -> LcdFilter {
+        let sum = weights.iter().sum::<u32>().max(1) as f64;
+        let lut = weights
+            .iter()
+            .map(|&w| {
+                let mut t = [0u8; 256];
+                for (c, v) in t.iter_mut().enumerate() {
+                    *v = (c as f64 * w as f64 / sum).round() as u8;
+                }
+                t
+            })
+            .collect();
+        LcdFilter {
+            weights: weights,
+            lut: lut,
+        }
+    }
+
+    // FreeType's FT_LCD_FILTER_DEFAULT
+    pub fn new_default() -> LcdFilter {
+        LcdFilter::new([0x08, 0x4D, 0x56, 0x4D, 0x08])
+    }
+
+    // FreeType's FT_LCD_FILTER_LIGHT, sharper with more color fringes
+    pub fn new_light() -> LcdFilter {
+        LcdFilter::new([0x00, 0x55, 0x56, 0x55, 0x00])
+    }
+
+    // No filtering, every subpixel keeps its own coverage
+    pub fn new_none() -> LcdFilter {
+        LcdFilter::new([0, 0, 1, 0, 0])
+    }
+
+    pub fn weights(&self) -> [u32; 5] {
+        self.weights
+    }
+
+    // Filters the covers of consecutive subpixels into out, which gets
+    // two more on each side: out[0] is two subpixels left of covers[0]
+    pub fn apply(&self, covers: &[u8], out: &mut Vec<u8>) {
+        out.clear();
+        out.resize(covers.len() + 4, 0);
+        for (i, &c) in covers.iter().enumerate() {
+            for (k, t) in self.lut.iter().enumerate() {
+                out[i + k] = out[i + k].saturating_add(t[c as usize]);
+            }
+        }
+    }
+}
+
+impl Default for LcdFilter {
+    fn default() -> Self {
+        Self::new_default()
+    }
+}
+
+//================================================================LcdOrder
+// The order of the subpixels of the display from left to right
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LcdOrder {
+    Rgb,
+    Bgr,
+}
+
+//==================================================================PixLcd
+// A pixel format for subpixel rendering on top of an RGB or RGBA one:
+// it's three times as wide as the pixel format it wraps, every x
+// addresses one color channel of a pixel in the order of the display.
+// The coverage of the spans is spread by the filter, then each channel
+// is blended with its own coverage, so anything rendered with a
+// renderer on it gets three times the horizontal resolution.
+//
+// The shapes have to be rasterized three times as wide, e.g. with
+// PixLcd::transform() in a ConvTransform, and glyphs rendered with it as
+// the transformation of the font engine, their advances are in
+// subpixels then. All x coordinates of the wrapper are subpixels.
+//
+// The solid spans of a row in one color are collected and filtered as a
+// whole, so the coverage the filter spreads over the ends of adjacent
+// spans is blended once. They are blended on the next row or color, by
+// flush() or when the wrapper is dropped; pixel() doesn't see them before.
+//
+// The wrapper is only the target of renderers; read the pixels from the
+// wrapped pixel format. It has no PIXEL_WIDTH, code that reads it for the
+// wrapper, as the blending of one pixel format onto another does for its
+// source, fails to build. Images blended onto it by RendererBase are
+// placed at whole pixels, dx is in subpixels and rounded down.
+//
+//   let mut lcd = PixLcd::new(&mut pix, LcdOrder::Rgb);
+//   let mut ren = RendererBase::new_borrowed(&mut lcd);
+//   fman.engine_mut().transform(&PixLcd::<PixBgr24>::transform());
+//   ...
+//   adp.init(gl.data.as_ptr(), gl.data_size as usize, x * 3., y);
+//   render_scanlines_aa_solid(&mut adp, &mut sl, &mut ren, &black);
+pub struct PixLcd<'a, P: PixFmt<C = Rgba8, T = u8>> {
+    pixf: &'a mut P,
+    filter: LcdFilter,
+    order: LcdOrder,
+    span: Vec<u8>,
+    // The solid spans not blended yet
+    pending: Vec<u8>,
+    pending_x: i32,
+    pending_y: i32,
+    pending_c: Rgba8,
+}
+
+impl<'a, P: PixFmt<C = Rgba8, T = u8>> PixLcd<'a, P> {
+    pub fn new(pixf: &'a mut P, order: LcdOrder) -> Self {
+        PixLcd {
+            pixf: pixf,
+            filter: LcdFilter::new_default(),
+            order: order,
+            span: Vec::new(),
+            pending: Vec::new(),
+            pending_x: 0,
+            pending_y: 0,
+            pending_c: Rgba8::new(),
+        }
+    }
+
+    // The scaling from pixels to subpixels
+    pub fn transform() -> TransAffine {
+        TransAffine::trans_affine_scaling(3., 1.)
+    }
+
+    pub fn filter(&self) -> &LcdFilter {
+        &self.filter
+    }
+
+    pub fn set_filter(&mut self, filter: LcdFilter) {
+        self.flush();
+        self.filter = filter;
+    }
+
+    pub fn order(&self) -> LcdOrder {
+        self.order
+    }
+
+    pub fn set_order(&mut self, order: LcdOrder) {
+        self.flush();
+        self.order = order;
+    }
+
+    pub fn pixfmt(&self) -> &P {
+        self.pixf
+    }
+
+    pub fn pixfmt_mut(&mut self) -> &mut P {
+        self.flush();
+        self.pixf
+    }
+
+    // Blends the solid spans collected for the current row
+    pub fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let covers = std::mem::take(&mut self.pending);
+        let span = self.filtered(&covers);
+        let c = self.pending_c;
+        self.blend_subpixels(self.pending_x - 2, self.pending_y, &span, |_| c);
+        self.span = span;
+        self.pending = covers;
+        self.pending.clear();
+    }
+
+    // Adds a solid span to the collected ones. It starts over on another
+    // row or color and if the span overlaps the collected ones or is too
+    // far right of them for the filter to join them.
+    fn add_solid(&mut self, x: i32, y: i32, c: &Rgba8, covers: &[u8]) {
+        let end = self.pending_x + self.pending.len() as i32;
+        let p = &self.pending_c;
+        if self.pending.is_empty()
+            || y != self.pending_y
+            || [c.r, c.g, c.b, c.a] != [p.r, p.g, p.b, p.a]
+            || x < end
+            || x - end >= 4
+        {
+            self.flush();
+            self.pending_x = x;
+            self.pending_y = y;
+            self.pending_c = *c;
+        } else {
+            self.pending.resize((x - self.pending_x) as usize, 0);
+        }
+        self.pending.extend_from_slice(covers);
+    }
+
+    // The pixel an image blended at subpixel xdst starts at, the source
+    // pixel of it and how many of the len pixels fit into the row
+    fn image_pixels(&self, xdst: i32, xsrc: i32, len: u32) -> (i32, i32, u32) {
+        let x = xdst.div_euclid(3);
+        let skip = (-x).max(0);
+        let len = (len as i64 - skip as i64)
+            .min(self.pixf.width() as i64 - (x + skip) as i64)
+            .max(0);
+        (x + skip, xsrc + skip, len as u32)
+    }
+
+    // The byte offsets of the channels of a pixel in display order
+    fn channels(&self) -> [usize; 3] {
+        match self.order {
+            LcdOrder::Rgb => [P::O::R, P::O::G, P::O::B],
+            LcdOrder::Bgr => [P::O::B, P::O::G, P::O::R],
+        }
+    }
+
+    // Blends the subpixels from x on with the covers, the color of each
+    // one given by color(). Out of the row ones are skipped. Pixels with
+    // alpha get the largest coverage of their channels.
+    fn blend_subpixels<F: Fn(usize) -> Rgba8>(&mut self, x: i32, y: i32, covers: &[u8], color: F) {
+        let width = self.width() as i32;
+        let start = x.max(0);
+        let end = (x + covers.len() as i32).min(width);
+        if start >= end {
+            return;
+        }
+        let channels = self.channels();
+        let pw = P::PIXEL_WIDTH as usize;
+        let has_alpha = pw == 4;
+        let row = self.pixf.row_mut(y);
+        let mut px = start / 3;
+        while px * 3 < end {
+            let p = &mut row[px as usize * pw..];
+            let mut alpha_max = 0;
+            for (k, &ch) in channels.iter().enumerate() {
+                let sx = px * 3 + k as i32;
+                if sx < start || sx >= end {
+                    continue;
+                }
+                let i = (sx - x) as usize;
+                let c = color(i);
+                let alpha = covers[i] as u32 * c.a as u32;
+                if alpha == 0 {
+                    continue;
+                }
+                let v = [c.r, c.g, c.b][match self.order {
+                    LcdOrder::Rgb => k,
+                    LcdOrder::Bgr => 2 - k,
+                }] as u32;
+                p[ch] = if alpha == 255 * 255 {
+                    v as u8
+                } else {
+                    let d = p[ch] as u32;
+                    ((d * (255 * 255 - alpha) + v * alpha + 255 * 255 / 2) / (255 * 255)) as u8
+                };
+                alpha_max = alpha_max.max(alpha);
+            }
+            if has_alpha && alpha_max > 0 {
+                let a = p[P::O::A] as u32;
+                p[P::O::A] = (a + ((255 - a) * alpha_max + 255 * 255 / 2) / (255 * 255)) as u8;
+            }
+            px += 1;
+        }
+    }
+
+    fn filtered(&mut self, covers: &[u8]) -> Vec<u8> {
+        let mut span = std::mem::replace(&mut self.span, Vec::new());
+        self.filter.apply(covers, &mut span);
+        span
+    }
+}
+
+impl<'a, P: PixFmt<C = Rgba8, T = u8>> Drop for PixLcd<'a, P> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl<'a, P: PixFmt<C = Rgba8, T = u8>> ImageSrc for PixLcd<'a, P> {}
+
+impl<'a, P: PixFmt<C = Rgba8, T = u8>> PixFmt for PixLcd<'a, P> {
+    type C = Rgba8;
+    type O = P::O;
+    type T = u8;
+    // A pixel has three subpixels, the wrapper can't be a source
+    const PIXEL_WIDTH: u32 = panic!("PixLcd can't be the source of pixels");
+    const SUBPIXELS: u32 = 3;
+
+    fn width(&self) -> u32 {
+        self.pixf.width() * 3
+    }
+
+    fn height(&self) -> u32 {
+        self.pixf.height()
+    }
+
+    fn stride(&self) -> i32 {
+        self.pixf.stride()
+    }
+
+    fn pix_ptr(&self, _x: i32, _y: i32) -> (&[u8], usize) {
+        panic!("PixLcd can't be the source of pixels")
+    }
+
+    fn make_pix(&self, p: &mut [u8], c: &Rgba8) {
+        self.pixf.make_pix(p, c)
+    }
+
+    fn row(&self, _y: i32) -> &[u8] {
+        panic!("PixLcd can't be the source of pixels")
+    }
+
+    // The bytes of the row of the wrapped pixel format
+    fn row_mut(&mut self, y: i32) -> &mut [u8] {
+        self.flush();
+        self.pixf.row_mut(y)
+    }
+
+    fn row_data(&self, _y: i32) -> RowData<u8> {
+        panic!("PixLcd can't be the source of pixels")
+    }
+
+    fn attach_pixfmt<P2: PixFmt>(&mut self, pixf: &P2, x1: i32, y1: i32, x2: i32, y2: i32) -> bool {
+        self.flush();
+        self.pixf.attach_pixfmt(pixf, x1, y1, x2, y2)
+    }
+
+    // Images are blended whole pixel by whole pixel: xdst is the first
+    // subpixel of a pixel, a multiple of 3, xsrc and len are pixels of the
+    // source. Renderers clip len against the width in subpixels, the
+    // pixels past the end of the row are left out here.
+    fn blend_from<R: PixFmt<T = u8>>(
+        &mut self, from: &R, xdst: i32, ydst: i32, xsrc: i32, ysrc: i32, len: u32, cover: u32,
+    ) {
+        self.flush();
+        let (x, xsrc, len) = self.image_pixels(xdst, xsrc, len);
+        if len > 0 {
+            self.pixf.blend_from(from, x, ydst, xsrc, ysrc, len, cover)
+        }
+    }
+
+    fn blend_from_color<R: PixFmt<T = u8>>(
+        &mut self, from: &R, color: &Rgba8, xdst: i32, ydst: i32, xsrc: i32, ysrc: i32, len: u32,
+        cover: u32,
+    ) {
+        self.flush();
+        let (x, xsrc, len) = self.image_pixels(xdst, xsrc, len);
+        if len > 0 {
+            self.pixf
+                .blend_from_color(from, color, x, ydst, xsrc, ysrc, len, cover)
+        }
+    }
+
+    fn blend_from_lut<R: PixFmt<T = u8>>(
+        &mut self, from: &R, color_lut: &[Rgba8], xdst: i32, ydst: i32, xsrc: i32, ysrc: i32,
+        len: u32, cover: u32,
+    ) {
+        self.flush();
+        let (x, xsrc, len) = self.image_pixels(xdst, xsrc, len);
+        if len > 0 {
+            self.pixf
+                .blend_from_lut(from, color_lut, x, ydst, xsrc, ysrc, len, cover)
+        }
+    }
+
+    fn copy_from<Ren: RenderBuffer<T = u8>>(
+        &mut self, from: &Ren, xdst: i32, ydst: i32, xsrc: i32, ysrc: i32, len: u32,
+    ) {
+        self.flush();
+        let (x, xsrc, len) = self.image_pixels(xdst, xsrc, len);
+        if len > 0 {
+            self.pixf.copy_from(from, x, ydst, xsrc, ysrc, len);
+        }
+    }
+
+    fn pixel(&self, x: i32, y: i32) -> Rgba8 {
+        self.pixf.pixel(x / 3, y)
+    }
+
+    fn copy_pixel(&mut self, x: i32, y: i32, c: &Rgba8) {
+        self.copy_hline(x, y, 1, c);
+    }
+
+    fn blend_pixel(&mut self, x: i32, y: i32, c: &Rgba8, cover: u8) {
+        self.blend_solid_hspan(x, y, 1, c, &[cover]);
+    }
+
+    fn copy_hline(&mut self, x: i32, y: i32, len: u32, c: &Rgba8) {
+        self.flush();
+        let mut c = *c;
+        c.a = 255;
+        self.blend_subpixels(x, y, &vec![255; len as usize], |_| c);
+    }
+
+    fn copy_vline(&mut self, x: i32, y: i32, len: u32, c: &Rgba8) {
+        for i in 0..len as i32 {
+            self.copy_hline(x, y + i, 1, c);
+        }
+    }
+
+    fn blend_hline(&mut self, x: i32, y: i32, len: u32, c: &Rgba8, cover: u8) {
+        self.add_solid(x, y, c, &vec![cover; len as usize]);
+    }
+
+    fn blend_vline(&mut self, x: i32, y: i32, len: u32, c: &Rgba8, cover: u8) {
+        for i in 0..len as i32 {
+            self.blend_solid_hspan(x, y + i, 1, c, &[cover]);
+        }
+    }
+
+    fn blend_solid_hspan(&mut self, x: i32, y: i32, len: u32, c: &Rgba8, covers: &[u8]) {
+        self.add_solid(x, y, c, &covers[..len as usize]);
+    }
+
+    fn blend_solid_vspan(&mut self, x: i32, y: i32, len: u32, c: &Rgba8, covers: &[u8]) {
+        for i in 0..len as usize {
+            self.blend_solid_hspan(x, y + i as i32, 1, c, &covers[i..]);
+        }
+    }
+
+    // The colors are filtered like the coverage, each subpixel gets its
+    // channel of the weighted colors around it
+    fn blend_color_hspan(
+        &mut self, x: i32, y: i32, len: u32, colors: &[Rgba8], covers: &[u8], cover: u8,
+    ) {
+        self.flush();
+        if len == 0 {
+            return;
+        }
+        let len = len as usize;
+        let cv: Vec<u8> = (0..len)
+            .map(|i| covers.get(i).map_or(cover, |&c| c))
+            .collect();
+        let span = self.filtered(&cv);
+        let colors = &colors[..len];
+        let channel = |i: usize| {
+            // Subpixel i of the span is x - 2 + i, its color the nearest
+            // one of the colors
+            let j = (i as i32 - 2).max(0).min(len as i32 - 1) as usize;
+            colors[j]
+        };
+        self.blend_subpixels(x - 2, y, &span, channel);
+        self.span = span;
+    }
+
+    fn blend_color_vspan(
+        &mut self, x: i32, y: i32, len: u32, colors: &[Rgba8], covers: &[u8], cover: u8,
+    ) {
+        for i in 0..len as usize {
+            let c = covers.get(i).map_or(cover, |&c| c);
+            self.blend_solid_hspan(x, y + i as i32, 1, &colors[i], &[c]);
+        }
+    }
+
+    fn copy_color_hspan(&mut self, x: i32, y: i32, len: u32, colors: &[Rgba8]) {
+        for i in 0..len as usize {
+            self.copy_hline(x + i as i32, y, 1, &colors[i]);
+        }
+    }
+
+    fn copy_color_vspan(&mut self, x: i32, y: i32, len: u32, colors: &[Rgba8]) {
+        for i in 0..len as usize {
+            self.copy_hline(x, y + i as i32, 1, &colors[i]);
+        }
+    }
+}
@@ -27,7 +27,7 @@ use crate::gamma_lut::GammaLut;
 use crate::rendering_buffer::RenderBuf;
 use crate::{
     slice_t_to_vt, slice_t_to_vt_mut, AggPrimitive, Args, Blender, Color, Equiv, Gamma, ImageSrc,
-    Order, PixFmt, RenderBuffer, RgbArgs,
+    Order, PixFmt, PixFmtOwned, RenderBuffer, RgbArgs,
 };
 use std::marker::PhantomData;
 use wrapping_arithmetic::wrappit;
@@ -418,6 +418,14 @@ impl<'a, C: Color + RgbArgs, O: Order, Blend: Blender<C, O>, RenBuf: RenderBuffe
 {
 }
 
+impl<'a, C: Color + RgbArgs, O: Order, Blend: Blender<C, O>> PixFmtOwned
+    for AlphaBlendRgb<'a, C, O, Blend, RenderBuf>
+{
+    fn new_from_rbuf(rbuf: RenderBuf) -> Self {
+        Self::new_owned(rbuf)
+    }
+}
+
 impl<'a, C: Color + RgbArgs, O: Order, Blend: Blender<C, O>, RenBuf: RenderBuffer<T = u8>> PixFmt
     for AlphaBlendRgb<'a, C, O, Blend, RenBuf>
 {
@@ -494,7 +502,9 @@ impl<'a, C: Color + RgbArgs, O: Order, Blend: Blender<C, O>, RenBuf: RenderBuffe
 
     fn copy_pixel(&mut self, x: i32, y: i32, c: &C) {
         unsafe {
-            let p = self.rbuf.row_mut(y).as_mut_ptr().offset(3 * x as isize) as *mut C::ValueType;
+            let p = self.rbuf.row_mut(y).as_mut_ptr()
+                .offset((x * <Self as PixFmt>::PIXEL_WIDTH as i32) as isize)
+                as *mut C::ValueType;
             *p.offset(O::R as isize) = c.r();
             *p.offset(O::G as isize) = c.g();
             *p.offset(O::B as isize) = c.b();
@@ -515,12 +525,14 @@ impl<'a, C: Color + RgbArgs, O: Order, Blend: Blender<C, O>, RenBuf: RenderBuffe
 
     fn pixel(&self, x: i32, y: i32) -> C {
         unsafe {
-            let p = self.rbuf.row(y).as_ptr().offset(3 * x as isize) as *const C::ValueType;
+            let p = self.rbuf.row(y).as_ptr()
+                .offset((x * <Self as PixFmt>::PIXEL_WIDTH as i32) as isize)
+                as *const C::ValueType;
             C::new_init(
                 (*p.offset(O::R as isize)),
                 (*p.offset(O::G as isize)),
                 (*p.offset(O::B as isize)),
-                C::ValueType::from_u32(255),
+                C::ValueType::from_u32(C::BASE_MASK),
             )
         }
     }
@@ -775,21 +787,21 @@ impl<'a, C: Color + RgbArgs, O: Order, Blend: Blender<C, O>, RenBuf: RenderBuffe
             let psrc = slice_t_to_vt!(psrc, xsrc * 4, <<R as PixFmt>::C as Args>::ValueType);
             if cover == 255 {
                 for i in 0..len as usize {
-                    let alpha = psrc[i * 3 + R::O::A].into_u32();
+                    let alpha = psrc[i * 4 + R::O::A].into_u32();
                     if alpha != 0 {
                         if alpha == C::BASE_MASK {
-                            pdst[i * 4 + O::R] =
-                                C::ValueType::from_u32(psrc[i * 3 + R::O::R].into_u32());
-                            pdst[i * 4 + O::G] =
-                                C::ValueType::from_u32(psrc[i * 3 + R::O::G].into_u32());
-                            pdst[i * 4 + O::B] =
-                                C::ValueType::from_u32(psrc[i * 3 + R::O::B].into_u32());
+                            pdst[i * 3 + O::R] =
+                                C::ValueType::from_u32(psrc[i * 4 + R::O::R].into_u32());
+                            pdst[i * 3 + O::G] =
+                                C::ValueType::from_u32(psrc[i * 4 + R::O::G].into_u32());
+                            pdst[i * 3 + O::B] =
+                                C::ValueType::from_u32(psrc[i * 4 + R::O::B].into_u32());
                         } else {
                             self.blender.blend_pix(
-                                &mut pdst[i * 4..],
-                                psrc[i * 3 + R::O::R].into_u32(),
-                                psrc[i * 3 + R::O::G].into_u32(),
-                                psrc[i * 3 + R::O::B].into_u32(),
+                                &mut pdst[i * 3..],
+                                psrc[i * 4 + R::O::R].into_u32(),
+                                psrc[i * 4 + R::O::G].into_u32(),
+                                psrc[i * 4 + R::O::B].into_u32(),
                                 alpha,
                             );
                         }
@@ -798,11 +810,11 @@ impl<'a, C: Color + RgbArgs, O: Order, Blend: Blender<C, O>, RenBuf: RenderBuffe
             } else {
                 let mut color = C::new();
                 for i in 0..len as usize {
-                    *color.r_mut() = C::ValueType::from_u32(psrc[i * 3 + R::O::R].into_u32());
-                    *color.g_mut() = C::ValueType::from_u32(psrc[i * 3 + R::O::G].into_u32());
-                    *color.b_mut() = C::ValueType::from_u32(psrc[i * 3 + R::O::B].into_u32());
-                    *color.a_mut() = C::ValueType::from_u32(psrc[i * 3 + R::O::A].into_u32());
-                    Self::copy_or_blend_pix_cover(&mut pdst[i * 4..], &color, cover, &self.blender);
+                    *color.r_mut() = C::ValueType::from_u32(psrc[i * 4 + R::O::R].into_u32());
+                    *color.g_mut() = C::ValueType::from_u32(psrc[i * 4 + R::O::G].into_u32());
+                    *color.b_mut() = C::ValueType::from_u32(psrc[i * 4 + R::O::B].into_u32());
+                    *color.a_mut() = C::ValueType::from_u32(psrc[i * 4 + R::O::A].into_u32());
+                    Self::copy_or_blend_pix_cover(&mut pdst[i * 3..], &color, cover, &self.blender);
                 }
             }
         }
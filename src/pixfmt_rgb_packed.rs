@@ -3,7 +3,7 @@ use crate::color_rgba::{OrderBgra, Rgba8, Rgba16};
 use crate::gamma_lut::GammaLut;
 use crate::{
     slice_t_to_vt, slice_t_to_vt_mut, AggPrimitive, Args, BlenderPacked, Color, Equiv, Gamma,
-    ImageSrc, Order, PixFmt, RenderBuf, RenderBuffer, RgbArgs,
+    ImageSrc, Order, PixFmt, PixFmtOwned, RenderBuf, RenderBuffer, RgbArgs,
 };
 
 use wrapping_arithmetic::wrappit;
@@ -1169,6 +1169,11 @@ impl<'a, B: BlenderPacked, R: RenderBuffer<T = u8>> AlphaBlendRgbPacked<'a, B, R
 }
 
 impl<'a, B: BlenderPacked, R: RenderBuffer<T = u8>> ImageSrc for AlphaBlendRgbPacked<'a, B, R> {}
+impl<'a, B: BlenderPacked> PixFmtOwned for AlphaBlendRgbPacked<'a, B, RenderBuf> {
+    fn new_from_rbuf(rbuf: RenderBuf) -> Self {
+        Self::new_owned(rbuf)
+    }
+}
 impl<'a, B: BlenderPacked, R: RenderBuffer<T = u8>> PixFmt for AlphaBlendRgbPacked<'a, B, R> {
     type C = B::ColorType;
     type O = OrderBgra;
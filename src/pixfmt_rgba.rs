@@ -30,7 +30,7 @@ use crate::color_rgba::{OrderAbgr, OrderArgb, OrderBgra, OrderRgba, Rgba16, Rgba
 use crate::rendering_buffer::RenderBuf;
 use crate::{
     slice_t_to_vt, slice_t_to_vt_mut, AggInteger, AggPrimitive, Blender, BlenderOp, Color, Equiv,
-    ImageSrc, Order, PixFmt, RenderBuffer, RgbArgs, Args
+    ImageSrc, Order, PixFmt, PixFmtOwned, RenderBuffer, RgbArgs, Args
 };
 
 macro_rules! from_u32 {
@@ -1614,6 +1614,7 @@ impl<C: Color, O: Order> CompOpTableRgba<C, O> {
 }
 
 //==============================================================CompOp
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum CompOp {
     CompOpClear,      //----CompOpClear
     CompOpSrc,        //----CompOpSrc
@@ -1905,6 +1906,14 @@ impl<
 {
 }
 
+impl<'a, C: Color + RgbArgs, O: Order, Blend: Blender<C, O>, PixelSize: AggInteger> PixFmtOwned
+    for AlphaBlendRgba<'a, C, O, Blend, RenderBuf, PixelSize>
+{
+    fn new_from_rbuf(rbuf: RenderBuf) -> Self {
+        Self::new_owned(rbuf)
+    }
+}
+
 impl<
         'a,
         C: Color + RgbArgs,
@@ -2010,8 +2019,9 @@ impl<
 
     fn copy_pixel(&mut self, x: i32, y: i32, c: &C) {
         unsafe {
-            let p =
-                self.rbuf.row_mut(y).as_mut_ptr().offset((x << 2) as isize) as *mut C::ValueType;
+            let p = self.rbuf.row_mut(y).as_mut_ptr()
+                .offset((x * <Self as PixFmt>::PIXEL_WIDTH as i32) as isize)
+                as *mut C::ValueType;
             *p.offset(O::R as isize) = c.r();
             *p.offset(O::G as isize) = c.g();
             *p.offset(O::B as isize) = c.b();
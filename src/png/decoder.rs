@@ -0,0 +1,380 @@
+use crate::basics::crc32;
+use crate::image_buffer::ImageBuffer;
+use crate::pixel_format::ConvertColor;
+use crate::png::zlib::inflate;
+use crate::png::{PngColorType, PngError, PNG_SIGNATURE};
+use crate::{PixFmt, PixFmtOwned};
+use std::io::Read;
+
+// Adam7 passes: x0, y0, dx, dy
+const ADAM7: [(u32, u32, u32, u32); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+//================================================================PngImage
+// A decoded PNG image, kept as 16-bit RGBA regardless of the format of
+// the file.
+pub struct PngImage {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: PngColorType,
+    pixels: Vec<[u16; 4]>,
+}
+
+impl PngImage {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // Bit depth and color type as stored in the file
+    pub fn bit_depth(&self) -> u8 {
+        self.bit_depth
+    }
+
+    pub fn color_type(&self) -> PngColorType {
+        self.color_type
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u16; 4] {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    // Copies the image into a pixel format with its top-left corner at
+    // (x, y). Pixels falling outside the rendering buffer are skipped.
+    pub fn copy_to<Pix: PixFmt>(&self, pix: &mut Pix, x: i32, y: i32)
+    where
+        Pix::C: ConvertColor,
+    {
+        for sy in 0..self.height {
+            let dy = y + sy as i32;
+            if dy < 0 || dy >= pix.height() as i32 {
+                continue;
+            }
+            for sx in 0..self.width {
+                let dx = x + sx as i32;
+                if dx < 0 || dx >= pix.width() as i32 {
+                    continue;
+                }
+                pix.copy_pixel(dx, dy, &Pix::C::from_rgba16(self.pixel(sx, sy)));
+            }
+        }
+    }
+
+    pub fn to_image<Pix: PixFmtOwned>(&self) -> ImageBuffer<Pix>
+    where
+        Pix::C: ConvertColor,
+    {
+        let mut img = ImageBuffer::<Pix>::new(self.width, self.height);
+        self.copy_to(&mut img.pixfmt(), 0, 0);
+        img
+    }
+}
+
+//-------------------------------------------------------------read_png
+pub fn read_png<R: Read>(r: &mut R) -> Result<PngImage, PngError> {
+    let mut data = Vec::new();
+    r.read_to_end(&mut data)?;
+    decode_png(&data)
+}
+
+//-------------------------------------------------------------load_png
+pub fn load_png<Pix: PixFmtOwned>(fname: &str) -> Result<ImageBuffer<Pix>, PngError>
+where
+    Pix::C: ConvertColor,
+{
+    let data = std::fs::read(fname)?;
+    Ok(decode_png(&data)?.to_image())
+}
+
+struct Header {
+    width: u32,
+    height: u32,
+    depth: u8,
+    color_type: PngColorType,
+    interlace: bool,
+}
+
+//-----------------------------------------------------------decode_png
+pub fn decode_png(data: &[u8]) -> Result<PngImage, PngError> {
+    if data.len() < 8 || data[..8] != PNG_SIGNATURE {
+        return Err(PngError::NotPng);
+    }
+
+    let mut hdr: Option<Header> = None;
+    let mut palette: Vec<[u16; 4]> = Vec::new();
+    let mut trns: Option<Vec<u8>> = None;
+    let mut idat = Vec::new();
+    let mut pos = 8;
+    loop {
+        if pos + 12 > data.len() {
+            return Err(PngError::Corrupt("unexpected end of file"));
+        }
+        let len =
+            u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let ctype = [data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]];
+        if len > 0x7FFFFFFF || pos + 12 + len > data.len() {
+            return Err(PngError::Corrupt("unexpected end of file"));
+        }
+        let body = &data[pos + 8..pos + 8 + len];
+        let crc = &data[pos + 8 + len..pos + 12 + len];
+        if crc32(&[&ctype, body]).to_be_bytes() != crc {
+            return Err(PngError::Crc(ctype));
+        }
+        pos += 12 + len;
+
+        if hdr.is_none() && &ctype != b"IHDR" {
+            return Err(PngError::Corrupt("IHDR must be the first chunk"));
+        }
+        match &ctype {
+            b"IHDR" => hdr = Some(parse_header(body)?),
+            b"PLTE" => {
+                if len % 3 != 0 || len / 3 > 256 {
+                    return Err(PngError::Corrupt("invalid palette"));
+                }
+                palette = body
+                    .chunks(3)
+                    .map(|c| {
+                        [
+                            c[0] as u16 * 257,
+                            c[1] as u16 * 257,
+                            c[2] as u16 * 257,
+                            65535,
+                        ]
+                    })
+                    .collect();
+            }
+            b"tRNS" => trns = Some(body.to_vec()),
+            b"IDAT" => idat.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {
+                // Bit 5 of the first byte clear means "critical"
+                if ctype[0] & 0x20 == 0 {
+                    return Err(PngError::Unsupported("unknown critical chunk"));
+                }
+            }
+        }
+    }
+
+    let hdr = hdr.unwrap();
+    if hdr.color_type == PngColorType::Palette {
+        if palette.is_empty() {
+            return Err(PngError::Corrupt("missing palette"));
+        }
+        if let Some(t) = &trns {
+            for (p, &a) in palette.iter_mut().zip(t.iter()) {
+                p[3] = a as u16 * 257;
+            }
+        }
+    }
+    // Color key transparency of gray and RGB images, raw sample values
+    let key: Option<[u16; 3]> = match (&trns, hdr.color_type) {
+        (Some(t), PngColorType::Gray) if t.len() >= 2 => {
+            let v = u16::from_be_bytes([t[0], t[1]]);
+            Some([v, v, v])
+        }
+        (Some(t), PngColorType::Rgb) if t.len() >= 6 => Some([
+            u16::from_be_bytes([t[0], t[1]]),
+            u16::from_be_bytes([t[2], t[3]]),
+            u16::from_be_bytes([t[4], t[5]]),
+        ]),
+        _ => None,
+    };
+
+    let raw = inflate(&idat)?;
+    let mut img = PngImage {
+        width: hdr.width,
+        height: hdr.height,
+        bit_depth: hdr.depth,
+        color_type: hdr.color_type,
+        pixels: vec![[0, 0, 0, 0]; hdr.width as usize * hdr.height as usize],
+    };
+
+    let passes: &[(u32, u32, u32, u32)] = if hdr.interlace {
+        &ADAM7
+    } else {
+        &[(0, 0, 1, 1)]
+    };
+    let channels = hdr.color_type.channels();
+    let bits = channels * hdr.depth as u32;
+    let bpp = ((bits + 7) / 8) as usize;
+    let mut pos = 0;
+    for &(x0, y0, dx, dy) in passes.iter() {
+        if x0 >= hdr.width || y0 >= hdr.height {
+            continue;
+        }
+        let pw = (hdr.width - x0 + dx - 1) / dx;
+        let ph = (hdr.height - y0 + dy - 1) / dy;
+        let row_len = ((pw as u64 * bits as u64 + 7) / 8) as usize;
+        let mut prev = vec![0u8; row_len];
+        let mut cur = vec![0u8; row_len];
+        for j in 0..ph {
+            if pos + 1 + row_len > raw.len() {
+                return Err(PngError::Corrupt("image data too short"));
+            }
+            cur.copy_from_slice(&raw[pos + 1..pos + 1 + row_len]);
+            unfilter(raw[pos], &mut cur, &prev, bpp)?;
+            pos += 1 + row_len;
+
+            let y = y0 + j * dy;
+            for i in 0..pw {
+                let x = x0 + i * dx;
+                let mut s = [0u16; 4];
+                for (c, v) in s.iter_mut().enumerate().take(channels as usize) {
+                    *v = sample(&cur, (i * channels) as usize + c, hdr.depth);
+                }
+                img.pixels[(y * hdr.width + x) as usize] = to_rgba16(&s, &hdr, &palette, &key)?;
+            }
+            std::mem::swap(&mut prev, &mut cur);
+        }
+    }
+    Ok(img)
+}
+
+fn parse_header(d: &[u8]) -> Result<Header, PngError> {
+    if d.len() != 13 {
+        return Err(PngError::Corrupt("invalid IHDR"));
+    }
+    let width = u32::from_be_bytes([d[0], d[1], d[2], d[3]]);
+    let height = u32::from_be_bytes([d[4], d[5], d[6], d[7]]);
+    let depth = d[8];
+    let color_type = match PngColorType::from_u8(d[9]) {
+        Some(t) => t,
+        None => return Err(PngError::Corrupt("invalid color type")),
+    };
+    let valid = match color_type {
+        PngColorType::Gray => [1, 2, 4, 8, 16].contains(&depth),
+        PngColorType::Palette => [1, 2, 4, 8].contains(&depth),
+        _ => depth == 8 || depth == 16,
+    };
+    if !valid {
+        return Err(PngError::Corrupt("invalid bit depth"));
+    }
+    if width == 0 || height == 0 || width > 0x7FFFFFFF || height > 0x7FFFFFFF {
+        return Err(PngError::Corrupt("invalid image size"));
+    }
+    if (width as u64) * (height as u64) > 1 << 28 {
+        return Err(PngError::Unsupported("image too large"));
+    }
+    if d[10] != 0 || d[11] != 0 {
+        return Err(PngError::Unsupported("compression or filter method"));
+    }
+    if d[12] > 1 {
+        return Err(PngError::Corrupt("invalid interlace method"));
+    }
+    Ok(Header {
+        width: width,
+        height: height,
+        depth: depth,
+        color_type: color_type,
+        interlace: d[12] == 1,
+    })
+}
+
+fn unfilter(filter: u8, cur: &mut [u8], prev: &[u8], bpp: usize) -> Result<(), PngError> {
+    match filter {
+        0 => (),
+        1 => {
+            for i in bpp..cur.len() {
+                cur[i] = cur[i].wrapping_add(cur[i - bpp]);
+            }
+        }
+        2 => {
+            for i in 0..cur.len() {
+                cur[i] = cur[i].wrapping_add(prev[i]);
+            }
+        }
+        3 => {
+            for i in 0..cur.len() {
+                let a = if i >= bpp { cur[i - bpp] as u32 } else { 0 };
+                cur[i] = cur[i].wrapping_add(((a + prev[i] as u32) / 2) as u8);
+            }
+        }
+        4 => {
+            for i in 0..cur.len() {
+                let (a, c) = if i >= bpp {
+                    (cur[i - bpp], prev[i - bpp])
+                } else {
+                    (0, 0)
+                };
+                cur[i] = cur[i].wrapping_add(paeth(a, prev[i], c));
+            }
+        }
+        _ => return Err(PngError::Corrupt("invalid filter type")),
+    }
+    Ok(())
+}
+
+#[inline]
+pub(crate) fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// The n-th sample of a row, samples of less than 8 bits are packed
+// starting with the most significant bits.
+#[inline]
+fn sample(row: &[u8], n: usize, depth: u8) -> u16 {
+    match depth {
+        16 => u16::from_be_bytes([row[n * 2], row[n * 2 + 1]]),
+        8 => row[n] as u16,
+        _ => {
+            let bit = n * depth as usize;
+            let shift = 8 - depth as usize - bit % 8;
+            ((row[bit / 8] >> shift) & ((1 << depth) - 1)) as u16
+        }
+    }
+}
+
+fn to_rgba16(
+    s: &[u16; 4], hdr: &Header, palette: &[[u16; 4]], key: &Option<[u16; 3]>,
+) -> Result<[u16; 4], PngError> {
+    let max = ((1u32 << hdr.depth) - 1) as u32;
+    let scale = |v: u16| (v as u32 * 65535 / max) as u16;
+    let keyed = |k: [u16; 3]| match key {
+        Some(key) if *key == k => 0,
+        _ => 65535,
+    };
+    Ok(match hdr.color_type {
+        PngColorType::Gray => {
+            let v = scale(s[0]);
+            [v, v, v, keyed([s[0], s[0], s[0]])]
+        }
+        PngColorType::GrayAlpha => {
+            let v = scale(s[0]);
+            [v, v, v, scale(s[1])]
+        }
+        PngColorType::Rgb => [
+            scale(s[0]),
+            scale(s[1]),
+            scale(s[2]),
+            keyed([s[0], s[1], s[2]]),
+        ],
+        PngColorType::Rgba => [scale(s[0]), scale(s[1]), scale(s[2]), scale(s[3])],
+        PngColorType::Palette => match palette.get(s[0] as usize) {
+            Some(c) => *c,
+            None => return Err(PngError::Corrupt("palette index out of range")),
+        },
+    })
+}
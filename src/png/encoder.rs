@@ -0,0 +1,131 @@
+use crate::basics::crc32;
+use crate::pixel_format::ConvertColor;
+use crate::png::decoder::paeth;
+use crate::png::zlib::deflate;
+use crate::png::{PngColorType, PngError, PNG_SIGNATURE};
+use crate::PixFmt;
+use std::io::Write;
+
+// Maximum size of an IDAT chunk written
+const IDAT_SIZE: usize = 1 << 20;
+
+//-----------------------------------------------------------encode_png
+// Encodes the contents of a pixel format. The PNG is gray or RGB with
+// the bit depth of the color type; an alpha channel is written only if
+// some pixel is not fully opaque.
+pub fn encode_png<Pix: PixFmt>(pix: &Pix) -> Vec<u8>
+where
+    Pix::C: ConvertColor,
+{
+    let (width, height) = (pix.width(), pix.height());
+    let mut pixels = Vec::with_capacity(width as usize * height as usize);
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            pixels.push(pix.pixel(x, y).to_rgba16());
+        }
+    }
+    let alpha = pixels.iter().any(|p| p[3] != 65535);
+    let color_type = match (Pix::C::GRAY, alpha) {
+        (true, false) => PngColorType::Gray,
+        (true, true) => PngColorType::GrayAlpha,
+        (false, false) => PngColorType::Rgb,
+        (false, true) => PngColorType::Rgba,
+    };
+    let depth = Pix::C::DEPTH;
+    let bpp = (color_type.channels() * depth as u32 / 8) as usize;
+    let row_len = width as usize * bpp;
+
+    // Filtered image data, one filter byte in front of every row
+    let mut raw = Vec::with_capacity((row_len + 1) * height as usize);
+    let mut prev = vec![0u8; row_len];
+    let mut cur = Vec::with_capacity(row_len);
+    let mut best = vec![0u8; row_len];
+    let mut tmp = vec![0u8; row_len];
+    for y in 0..height as usize {
+        cur.clear();
+        for p in pixels[y * width as usize..(y + 1) * width as usize].iter() {
+            let samples: &[u16] = match color_type {
+                PngColorType::Gray => &p[..1],
+                PngColorType::GrayAlpha => &[p[0], p[3]],
+                PngColorType::Rgb => &p[..3],
+                _ => &p[..],
+            };
+            for &s in samples.iter() {
+                if depth == 16 {
+                    cur.extend_from_slice(&s.to_be_bytes());
+                } else {
+                    cur.push((s >> 8) as u8);
+                }
+            }
+        }
+
+        // Choose the filter with the smallest sum of absolute values
+        let mut best_filter = 0;
+        let mut best_sum = u64::MAX;
+        for filter in 0..5u8 {
+            apply_filter(filter, &cur, &prev, bpp, &mut tmp);
+            let sum: u64 = tmp.iter().map(|&v| (v as i8).unsigned_abs() as u64).sum();
+            if sum < best_sum {
+                best_sum = sum;
+                best_filter = filter;
+                best.copy_from_slice(&tmp);
+            }
+        }
+        raw.push(best_filter);
+        raw.extend_from_slice(&best);
+        prev.copy_from_slice(&cur);
+    }
+
+    let mut out = PNG_SIGNATURE.to_vec();
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[depth, color_type as u8, 0, 0, 0]);
+    write_chunk(&mut out, b"IHDR", &ihdr);
+    let z = deflate(&raw);
+    for chunk in z.chunks(IDAT_SIZE) {
+        write_chunk(&mut out, b"IDAT", chunk);
+    }
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+//------------------------------------------------------------write_png
+pub fn write_png<Pix: PixFmt, W: Write>(pix: &Pix, w: &mut W) -> Result<(), PngError>
+where
+    Pix::C: ConvertColor,
+{
+    w.write_all(&encode_png(pix))?;
+    Ok(())
+}
+
+//-------------------------------------------------------------save_png
+pub fn save_png<Pix: PixFmt>(pix: &Pix, fname: &str) -> Result<(), PngError>
+where
+    Pix::C: ConvertColor,
+{
+    std::fs::write(fname, encode_png(pix))?;
+    Ok(())
+}
+
+fn write_chunk(out: &mut Vec<u8>, ctype: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(ctype);
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc32(&[ctype, data]).to_be_bytes());
+}
+
+fn apply_filter(filter: u8, cur: &[u8], prev: &[u8], bpp: usize, out: &mut [u8]) {
+    for i in 0..cur.len() {
+        let a = if i >= bpp { cur[i - bpp] } else { 0 };
+        let c = if i >= bpp { prev[i - bpp] } else { 0 };
+        let b = prev[i];
+        out[i] = match filter {
+            0 => cur[i],
+            1 => cur[i].wrapping_sub(a),
+            2 => cur[i].wrapping_sub(b),
+            3 => cur[i].wrapping_sub(((a as u32 + b as u32) / 2) as u8),
+            _ => cur[i].wrapping_sub(paeth(a, b, c)),
+        };
+    }
+}
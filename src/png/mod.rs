@@ -0,0 +1,77 @@
+// PNG reading and writing for rendering buffers. Decoding handles all
+// the standard color types and bit depths, including palettes,
+// transparency chunks and interlacing; pixels are stored into any pixel
+// format whose color type implements ConvertColor. Compression is done by
+// the built-in zlib implementation, no external libraries are needed.
+pub mod decoder;
+pub mod encoder;
+pub mod zlib;
+
+pub use decoder::{decode_png, load_png, read_png, PngImage};
+pub use encoder::{encode_png, save_png, write_png};
+
+use std::fmt;
+
+pub(crate) const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+//=============================================================PngColorType
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PngColorType {
+    Gray = 0,
+    Rgb = 2,
+    Palette = 3,
+    GrayAlpha = 4,
+    Rgba = 6,
+}
+
+impl PngColorType {
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(PngColorType::Gray),
+            2 => Some(PngColorType::Rgb),
+            3 => Some(PngColorType::Palette),
+            4 => Some(PngColorType::GrayAlpha),
+            6 => Some(PngColorType::Rgba),
+            _ => None,
+        }
+    }
+
+    pub fn channels(&self) -> u32 {
+        match self {
+            PngColorType::Gray | PngColorType::Palette => 1,
+            PngColorType::GrayAlpha => 2,
+            PngColorType::Rgb => 3,
+            PngColorType::Rgba => 4,
+        }
+    }
+}
+
+//================================================================PngError
+#[derive(Debug)]
+pub enum PngError {
+    Io(std::io::Error),
+    NotPng,
+    Crc([u8; 4]),
+    Corrupt(&'static str),
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for PngError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PngError::Io(e) => write!(f, "I/O error: {}", e),
+            PngError::NotPng => write!(f, "not a PNG file"),
+            PngError::Crc(t) => write!(f, "CRC mismatch in chunk {}", String::from_utf8_lossy(t)),
+            PngError::Corrupt(s) => write!(f, "corrupt PNG data: {}", s),
+            PngError::Unsupported(s) => write!(f, "unsupported PNG feature: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for PngError {}
+
+impl From<std::io::Error> for PngError {
+    fn from(e: std::io::Error) -> Self {
+        PngError::Io(e)
+    }
+}
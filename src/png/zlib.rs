@@ -0,0 +1,447 @@
+// zlib streams (RFC 1950) with deflate compression (RFC 1951).
+// Inflate handles all block types; deflate uses LZ77 with hash chains
+// and the fixed Huffman codes, which is simple and good enough for
+// rendered images.
+use crate::png::PngError;
+
+//-----------------------------------------------------------------tables
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// Order of the code length code lengths in a dynamic block header
+const CLEN_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+//-----------------------------------------------------------------adler32
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the largest n such that the sums don't overflow
+    for chunk in data.chunks(5552) {
+        for &v in chunk {
+            a += v as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+//===============================================================BitReader
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buf: u32,
+    bit_cnt: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader {
+            data: data,
+            pos: 0,
+            bit_buf: 0,
+            bit_cnt: 0,
+        }
+    }
+
+    fn bits(&mut self, n: u32) -> Result<u32, PngError> {
+        while self.bit_cnt < n {
+            if self.pos >= self.data.len() {
+                return Err(PngError::Corrupt("unexpected end of compressed data"));
+            }
+            self.bit_buf |= (self.data[self.pos] as u32) << self.bit_cnt;
+            self.pos += 1;
+            self.bit_cnt += 8;
+        }
+        let v = self.bit_buf & ((1u64 << n) - 1) as u32;
+        self.bit_buf >>= n;
+        self.bit_cnt -= n;
+        Ok(v)
+    }
+
+    // Drops the remaining bits of the current byte
+    fn align(&mut self) {
+        self.bit_buf = 0;
+        self.bit_cnt = 0;
+    }
+}
+
+//=================================================================Huffman
+// Canonical Huffman decoding table: the number of codes of every length
+// and the symbols ordered by code.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, PngError> {
+        let mut counts = [0u16; 16];
+        for &l in lengths {
+            counts[l as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // Check for an over-subscribed set of lengths
+        let mut left = 1i32;
+        for len in 1..16 {
+            left <<= 1;
+            left -= counts[len] as i32;
+            if left < 0 {
+                return Err(PngError::Corrupt("invalid Huffman code lengths"));
+            }
+        }
+
+        let mut offs = [0u16; 16];
+        for len in 1..15 {
+            offs[len + 1] = offs[len] + counts[len];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (sym, &l) in lengths.iter().enumerate() {
+            if l != 0 {
+                symbols[offs[l as usize] as usize] = sym as u16;
+                offs[l as usize] += 1;
+            }
+        }
+        Ok(Huffman {
+            counts: counts,
+            symbols: symbols,
+        })
+    }
+
+    fn decode(&self, br: &mut BitReader) -> Result<u16, PngError> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= br.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err(PngError::Corrupt("invalid Huffman code"))
+    }
+}
+
+//-----------------------------------------------------------------inflate
+// Decompresses a zlib stream and checks its Adler-32 checksum.
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, PngError> {
+    if data.len() < 6 {
+        return Err(PngError::Corrupt("zlib stream too short"));
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0F != 8 || cmf >> 4 > 7 || ((cmf as u32) << 8 | flg as u32) % 31 != 0 {
+        return Err(PngError::Corrupt("invalid zlib header"));
+    }
+    if flg & 0x20 != 0 {
+        return Err(PngError::Unsupported("zlib preset dictionary"));
+    }
+
+    let (out, len) = inflate_raw(&data[2..])?;
+    let pos = 2 + len;
+    if pos + 4 > data.len() {
+        return Err(PngError::Corrupt("missing zlib checksum"));
+    }
+    let sum = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+    if sum != adler32(&out) {
+        return Err(PngError::Corrupt("zlib checksum mismatch"));
+    }
+    Ok(out)
+}
+
+//-------------------------------------------------------------inflate_raw
+// Decompresses a bare deflate stream, as in zlib and gzip streams, and
+// returns the data and the length of the stream in bytes.
+pub fn inflate_raw(data: &[u8]) -> Result<(Vec<u8>, usize), PngError> {
+    let mut out = Vec::new();
+    let mut br = BitReader::new(data);
+    loop {
+        let last = br.bits(1)?;
+        match br.bits(2)? {
+            0 => inflate_stored(&mut br, &mut out)?,
+            1 => {
+                let (lit, dist) = fixed_tables()?;
+                inflate_codes(&mut br, &mut out, &lit, &dist)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_tables(&mut br)?;
+                inflate_codes(&mut br, &mut out, &lit, &dist)?;
+            }
+            _ => return Err(PngError::Corrupt("invalid deflate block type")),
+        }
+        if last != 0 {
+            break;
+        }
+    }
+
+    br.align();
+    Ok((out, br.pos))
+}
+
+fn inflate_stored(br: &mut BitReader, out: &mut Vec<u8>) -> Result<(), PngError> {
+    br.align();
+    let d = &br.data[br.pos..];
+    if d.len() < 4 {
+        return Err(PngError::Corrupt("unexpected end of compressed data"));
+    }
+    let len = u16::from_le_bytes([d[0], d[1]]) as usize;
+    let nlen = u16::from_le_bytes([d[2], d[3]]) as usize;
+    if len != !nlen & 0xFFFF {
+        return Err(PngError::Corrupt("invalid stored block length"));
+    }
+    if d.len() < 4 + len {
+        return Err(PngError::Corrupt("unexpected end of compressed data"));
+    }
+    out.extend_from_slice(&d[4..4 + len]);
+    br.pos += 4 + len;
+    Ok(())
+}
+
+fn fixed_tables() -> Result<(Huffman, Huffman), PngError> {
+    let mut lengths = [0u8; 288];
+    for (i, l) in lengths.iter_mut().enumerate() {
+        *l = match i {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5u8; 30])?))
+}
+
+fn dynamic_tables(br: &mut BitReader) -> Result<(Huffman, Huffman), PngError> {
+    let nlen = br.bits(5)? as usize + 257;
+    let ndist = br.bits(5)? as usize + 1;
+    let ncode = br.bits(4)? as usize + 4;
+    if nlen > 286 || ndist > 30 {
+        return Err(PngError::Corrupt("invalid dynamic block header"));
+    }
+
+    let mut clen = [0u8; 19];
+    for &i in CLEN_ORDER.iter().take(ncode) {
+        clen[i] = br.bits(3)? as u8;
+    }
+    let clen = Huffman::new(&clen)?;
+
+    let mut lengths = vec![0u8; nlen + ndist];
+    let mut i = 0;
+    while i < nlen + ndist {
+        let sym = clen.decode(br)?;
+        let (val, rep) = match sym {
+            0..=15 => (sym as u8, 1),
+            16 => {
+                if i == 0 {
+                    return Err(PngError::Corrupt("repeat with no previous length"));
+                }
+                (lengths[i - 1], 3 + br.bits(2)? as usize)
+            }
+            17 => (0, 3 + br.bits(3)? as usize),
+            _ => (0, 11 + br.bits(7)? as usize),
+        };
+        if i + rep > nlen + ndist {
+            return Err(PngError::Corrupt("too many code lengths"));
+        }
+        for l in lengths[i..i + rep].iter_mut() {
+            *l = val;
+        }
+        i += rep;
+    }
+    if lengths[256] == 0 {
+        return Err(PngError::Corrupt("missing end-of-block code"));
+    }
+    Ok((
+        Huffman::new(&lengths[..nlen])?,
+        Huffman::new(&lengths[nlen..])?,
+    ))
+}
+
+fn inflate_codes(
+    br: &mut BitReader, out: &mut Vec<u8>, lit: &Huffman, dist: &Huffman,
+) -> Result<(), PngError> {
+    loop {
+        let sym = lit.decode(br)? as usize;
+        if sym < 256 {
+            out.push(sym as u8);
+        } else if sym == 256 {
+            return Ok(());
+        } else {
+            let sym = sym - 257;
+            if sym >= 29 {
+                return Err(PngError::Corrupt("invalid length code"));
+            }
+            let len = LENGTH_BASE[sym] as usize + br.bits(LENGTH_EXTRA[sym] as u32)? as usize;
+            let dsym = dist.decode(br)? as usize;
+            if dsym >= 30 {
+                return Err(PngError::Corrupt("invalid distance code"));
+            }
+            let d = DIST_BASE[dsym] as usize + br.bits(DIST_EXTRA[dsym] as u32)? as usize;
+            if d > out.len() {
+                return Err(PngError::Corrupt("distance too far back"));
+            }
+            // The source may overlap the bytes being copied
+            let start = out.len() - d;
+            for i in 0..len {
+                let v = out[start + i];
+                out.push(v);
+            }
+        }
+    }
+}
+
+//===============================================================BitWriter
+struct BitWriter {
+    out: Vec<u8>,
+    bit_buf: u32,
+    bit_cnt: u32,
+}
+
+impl BitWriter {
+    fn put(&mut self, v: u32, n: u32) {
+        self.bit_buf |= v << self.bit_cnt;
+        self.bit_cnt += n;
+        while self.bit_cnt >= 8 {
+            self.out.push(self.bit_buf as u8);
+            self.bit_buf >>= 8;
+            self.bit_cnt -= 8;
+        }
+    }
+
+    // Huffman codes are stored starting with the most significant bit
+    fn put_code(&mut self, code: u32, n: u32) {
+        self.put(code.reverse_bits() >> (32 - n), n);
+    }
+
+    fn flush(&mut self) {
+        if self.bit_cnt > 0 {
+            self.out.push(self.bit_buf as u8);
+            self.bit_buf = 0;
+            self.bit_cnt = 0;
+        }
+    }
+
+    fn literal(&mut self, sym: u32) {
+        match sym {
+            0..=143 => self.put_code(0x30 + sym, 8),
+            144..=255 => self.put_code(0x190 + sym - 144, 9),
+            256..=279 => self.put_code(sym - 256, 7),
+            _ => self.put_code(0xC0 + sym - 280, 8),
+        }
+    }
+
+    fn matched(&mut self, len: usize, dist: usize) {
+        let l = LENGTH_BASE
+            .iter()
+            .rposition(|&b| b as usize <= len)
+            .unwrap();
+        self.literal(257 + l as u32);
+        self.put(
+            (len - LENGTH_BASE[l] as usize) as u32,
+            LENGTH_EXTRA[l] as u32,
+        );
+        let d = DIST_BASE.iter().rposition(|&b| b as usize <= dist).unwrap();
+        self.put_code(d as u32, 5);
+        self.put((dist - DIST_BASE[d] as usize) as u32, DIST_EXTRA[d] as u32);
+    }
+}
+
+const WINDOW_SIZE: usize = 32768;
+const HASH_BITS: u32 = 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 128;
+
+#[inline]
+fn hash3(d: &[u8]) -> usize {
+    let v = (d[0] as u32) << 16 | (d[1] as u32) << 8 | d[2] as u32;
+    (v.wrapping_mul(0x9E3779B1) >> (32 - HASH_BITS)) as usize
+}
+
+//-----------------------------------------------------------------deflate
+// Compresses data into a zlib stream.
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut bw = BitWriter {
+        out: vec![0x78, 0x9C],
+        bit_buf: 0,
+        bit_cnt: 0,
+    };
+    // A single final block with the fixed codes
+    bw.put(1, 1);
+    bw.put(1, 2);
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+    let insert = |head: &mut Vec<usize>, prev: &mut Vec<usize>, i: usize| {
+        if i + MIN_MATCH <= data.len() {
+            let h = hash3(&data[i..]);
+            prev[i % WINDOW_SIZE] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let (mut best_len, mut best_dist) = (0, 0);
+        if i + MIN_MATCH <= data.len() {
+            let max_len = (data.len() - i).min(MAX_MATCH);
+            let mut cand = head[hash3(&data[i..])];
+            let mut chain = MAX_CHAIN;
+            while cand != usize::MAX && i - cand <= WINDOW_SIZE && chain > 0 {
+                if data[cand + best_len.min(max_len - 1)] == data[i + best_len.min(max_len - 1)] {
+                    let mut l = 0;
+                    while l < max_len && data[cand + l] == data[i + l] {
+                        l += 1;
+                    }
+                    if l > best_len {
+                        best_len = l;
+                        best_dist = i - cand;
+                        if l == max_len {
+                            break;
+                        }
+                    }
+                }
+                let next = prev[cand % WINDOW_SIZE];
+                if next == usize::MAX || next >= cand {
+                    break;
+                }
+                cand = next;
+                chain -= 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            bw.matched(best_len, best_dist);
+            for k in i..i + best_len {
+                insert(&mut head, &mut prev, k);
+            }
+            i += best_len;
+        } else {
+            bw.literal(data[i] as u32);
+            insert(&mut head, &mut prev, i);
+            i += 1;
+        }
+    }
+    bw.literal(256);
+    bw.flush();
+
+    let mut out = bw.out;
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
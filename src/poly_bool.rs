@@ -0,0 +1,624 @@
+use crate::basics::{is_end_poly, is_move_to, is_stop, is_vertex, FillingRule, PointD};
+use crate::conv_gpc::GpcOp;
+use crate::VertexSource;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+// Coordinates are snapped to a grid of 2^GRID_BITS steps across the
+// bounding box of both polygons. Vertices falling into the same grid
+// cell are merged, which makes coincident and touching edges share their
+// end points.
+const GRID_BITS: i32 = 36;
+
+type Key = (i64, i64);
+
+struct Seg {
+    p0: Key,
+    p1: Key,
+    cnt: [i32; 2],
+}
+
+// Edge of the planar graph. cnt is the number of times the edges
+// of each polygon run along it from u to v, minus the times they run
+// from v to u.
+struct Edge {
+    u: usize,
+    v: usize,
+    cnt: [i32; 2],
+}
+
+//==============================================================poly_bool
+// Boolean operation on two polygons given as lists of closed contours.
+// The contours may intersect themselves and each other, holes are
+// contours inside others; which areas are filled is decided by the
+// filling rule, as in the rasterizer.
+//
+// All edges are split at their mutual intersections, so they form a
+// planar graph. For each edge the winding numbers of both polygons are
+// computed on its two sides, and the edge belongs to the result if the
+// operation gives different answers for the sides. The result edges are
+// then chained into contours running counterclockwise around filled
+// areas and clockwise around holes (in a y-up coordinate system), so the
+// result can be rendered with either filling rule.
+pub fn poly_bool(
+    a: &[Vec<PointD>], b: &[Vec<PointD>], op: GpcOp, filling_rule: FillingRule,
+) -> Vec<Vec<PointD>> {
+    let mut x1 = f64::MAX;
+    let mut y1 = f64::MAX;
+    let mut x2 = f64::MIN;
+    let mut y2 = f64::MIN;
+    for p in a.iter().chain(b.iter()).flat_map(|c| c.iter()) {
+        x1 = x1.min(p.x);
+        y1 = y1.min(p.y);
+        x2 = x2.max(p.x);
+        y2 = y2.max(p.y);
+    }
+    if x1 > x2 {
+        return Vec::new();
+    }
+    let extent = (x2 - x1).max(y2 - y1);
+    let grid = if extent > 0. {
+        extent / (1i64 << GRID_BITS) as f64
+    } else {
+        1.
+    };
+    let snap = |x: f64, y: f64| -> Key {
+        (
+            ((x - x1) / grid).round() as i64,
+            ((y - y1) / grid).round() as i64,
+        )
+    };
+
+    let mut segs = Vec::new();
+    for (poly, contours) in [a, b].iter().enumerate() {
+        for c in contours.iter() {
+            for i in 0..c.len() {
+                let p0 = snap(c[i].x, c[i].y);
+                let j = if i + 1 < c.len() { i + 1 } else { 0 };
+                let p1 = snap(c[j].x, c[j].y);
+                if p0 != p1 {
+                    let mut cnt = [0, 0];
+                    cnt[poly] = 1;
+                    segs.push(Seg {
+                        p0: p0,
+                        p1: p1,
+                        cnt: cnt,
+                    });
+                }
+            }
+        }
+    }
+
+    let (pts, edges) = build_graph(segs);
+    let result = classify_edges(&pts, &edges, op, filling_rule);
+    chain_edges(&pts, &result)
+        .into_iter()
+        .map(|c| {
+            c.into_iter()
+                .map(|i| PointD {
+                    x: x1 + pts[i].0 * grid,
+                    y: y1 + pts[i].1 * grid,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+//==========================================================read_contours
+// Collects the contours of a path as input for poly_bool(). Repeated
+// vertices are skipped and contours with less than three vertices are
+// dropped.
+pub fn read_contours<VS: VertexSource + ?Sized>(vs: &mut VS, path_id: u32) -> Vec<Vec<PointD>> {
+    let mut contours: Vec<Vec<PointD>> = Vec::new();
+    let mut x = 0.;
+    let mut y = 0.;
+    let mut new_contour = true;
+    vs.rewind(path_id);
+    loop {
+        let cmd = vs.vertex(&mut x, &mut y);
+        if is_stop(cmd) {
+            break;
+        }
+        if is_vertex(cmd) {
+            if is_move_to(cmd) || new_contour {
+                contours.push(Vec::new());
+                new_contour = false;
+            }
+            let c = contours.last_mut().unwrap();
+            let p = PointD { x: x, y: y };
+            if c.last() != Some(&p) {
+                c.push(p);
+            }
+        } else if is_end_poly(cmd) {
+            new_contour = true;
+        }
+    }
+    for c in contours.iter_mut() {
+        if c.len() > 1 && c.first() == c.last() {
+            c.pop();
+        }
+    }
+    contours.retain(|c| c.len() > 2);
+    contours
+}
+
+#[inline]
+fn fk(k: Key) -> (f64, f64) {
+    (k.0 as f64, k.1 as f64)
+}
+
+#[inline]
+fn cross(ax: f64, ay: f64, bx: f64, by: f64) -> f64 {
+    ax * by - ay * bx
+}
+
+// Exact cross product of a-o and b-o on the grid
+#[inline]
+fn cross_i(o: Key, a: Key, b: Key) -> i128 {
+    (a.0 - o.0) as i128 * (b.1 - o.1) as i128 - (a.1 - o.1) as i128 * (b.0 - o.0) as i128
+}
+
+// Tells if p lies in the interior of the segment a-b or is closer than
+// one grid step to it
+fn on_seg(p: Key, a: Key, b: Key) -> bool {
+    if p == a || p == b {
+        return false;
+    }
+    let (rx, ry) = ((b.0 - a.0) as i128, (b.1 - a.1) as i128);
+    let l2 = rx * rx + ry * ry;
+    let t = (p.0 - a.0) as i128 * rx + (p.1 - a.1) as i128 * ry;
+    if t <= 0 || t >= l2 {
+        return false;
+    }
+    let c = cross_i(a, b, p) as f64;
+    c * c <= l2 as f64
+}
+
+// Finds the points where every segment has to be split: the crossings
+// with other segments and the end points of other segments touching it.
+// Candidate pairs come from a sweep over the x ranges.
+fn intersect_segs(segs: &[Seg]) -> Vec<Vec<Key>> {
+    let mut splits = vec![Vec::new(); segs.len()];
+    let xmin = |s: &Seg| s.p0.0.min(s.p1.0);
+    let xmax = |s: &Seg| s.p0.0.max(s.p1.0);
+    let mut order: Vec<usize> = (0..segs.len()).collect();
+    order.sort_by_key(|&i| xmin(&segs[i]));
+
+    let mut active: Vec<usize> = Vec::new();
+    for &i in order.iter() {
+        let s = &segs[i];
+        let x = xmin(s);
+        active.retain(|&j| xmax(&segs[j]) >= x);
+        let (sy1, sy2) = (s.p0.1.min(s.p1.1), s.p0.1.max(s.p1.1));
+        for &j in active.iter() {
+            let t = &segs[j];
+            if t.p0.1.max(t.p1.1) < sy1 || t.p0.1.min(t.p1.1) > sy2 {
+                continue;
+            }
+            intersect_pair(s, t, i, j, &mut splits);
+        }
+        active.push(i);
+    }
+    splits
+}
+
+fn intersect_pair(s: &Seg, t: &Seg, i: usize, j: usize, splits: &mut [Vec<Key>]) {
+    // Touching and overlapping
+    for &p in [t.p0, t.p1].iter() {
+        if on_seg(p, s.p0, s.p1) {
+            splits[i].push(p);
+        }
+    }
+    for &p in [s.p0, s.p1].iter() {
+        if on_seg(p, t.p0, t.p1) {
+            splits[j].push(p);
+        }
+    }
+
+    // Proper crossing
+    let d1 = cross_i(s.p0, s.p1, t.p0);
+    let d2 = cross_i(s.p0, s.p1, t.p1);
+    let d3 = cross_i(t.p0, t.p1, s.p0);
+    let d4 = cross_i(t.p0, t.p1, s.p1);
+    if ((d1 > 0 && d2 < 0) || (d1 < 0 && d2 > 0)) && ((d3 > 0 && d4 < 0) || (d3 < 0 && d4 > 0)) {
+        let k = d3 as f64 / (d3 - d4) as f64;
+        let (ax, ay) = fk(s.p0);
+        let (bx, by) = fk(s.p1);
+        let p = (
+            (ax + (bx - ax) * k).round() as i64,
+            (ay + (by - ay) * k).round() as i64,
+        );
+        splits[i].push(p);
+        splits[j].push(p);
+    }
+}
+
+// Splits the segments at the points found by intersect_segs(), moves the
+// end points of the pieces as given by the map and merges coincident
+// pieces
+fn split_segs(segs: &[Seg], splits: &[Vec<Key>], map: &HashMap<Key, Key>) -> Vec<Seg> {
+    let mut ids: HashMap<(Key, Key), usize> = HashMap::new();
+    let mut out: Vec<Seg> = Vec::new();
+    let mut chain: Vec<(f64, Key)> = Vec::new();
+    for (s, sp) in segs.iter().zip(splits.iter()) {
+        let (ax, ay) = fk(s.p0);
+        let (rx, ry) = (s.p1.0 as f64 - ax, s.p1.1 as f64 - ay);
+        chain.clear();
+        chain.push((0., s.p0));
+        for &p in sp.iter() {
+            chain.push(((p.0 as f64 - ax) * rx + (p.1 as f64 - ay) * ry, p));
+        }
+        chain.push((rx * rx + ry * ry, s.p1));
+        chain.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        for w in chain.windows(2) {
+            let (p, q) = (map[&w[0].1], map[&w[1].1]);
+            if p == q {
+                continue;
+            }
+            let (p0, p1, dir) = if p < q { (p, q, 1) } else { (q, p, -1) };
+            let e = *ids.entry((p0, p1)).or_insert_with(|| {
+                out.push(Seg {
+                    p0: p0,
+                    p1: p1,
+                    cnt: [0, 0],
+                });
+                out.len() - 1
+            });
+            out[e].cnt[0] += dir * s.cnt[0];
+            out[e].cnt[1] += dir * s.cnt[1];
+        }
+    }
+    out.retain(|s| s.cnt != [0, 0]);
+    out
+}
+
+// Maps points lying next to each other on the grid to the same place.
+// Crossings rounded to the grid often end up next to other end points,
+// and without merging them the splitting could go on creeping along such
+// clusters.
+fn merge_close(segs: &[Seg], splits: &[Vec<Key>]) -> HashMap<Key, Key> {
+    let mut keys: Vec<Key> = segs
+        .iter()
+        .flat_map(|s| [s.p0, s.p1])
+        .chain(splits.iter().flatten().cloned())
+        .collect();
+    keys.sort();
+    keys.dedup();
+    let mut map: HashMap<Key, Key> = HashMap::new();
+    for &k in keys.iter() {
+        let mut to = k;
+        'search: for dx in -1..=1 {
+            for dy in -1..=1 {
+                if let Some(&m) = map.get(&(k.0 + dx, k.1 + dy)) {
+                    to = m;
+                    break 'search;
+                }
+            }
+        }
+        map.insert(k, to);
+    }
+    map
+}
+
+// Splits the segments until none of them cross or touch each other and
+// turns them into the edges of a graph. Rounding the crossings to the grid
+// moves the pieces a little, so they may cross other segments they missed
+// before; the splitting is repeated until that stops. It takes two or
+// three passes as a rule; if it doesn't settle after MAX_PASSES, the
+// segments are snap rounded instead.
+fn build_graph(mut segs: Vec<Seg>) -> (Vec<(f64, f64)>, Vec<Edge>) {
+    const MAX_PASSES: usize = 10;
+    let mut passes = 0;
+    loop {
+        let splits = intersect_segs(&segs);
+        let map = merge_close(&segs, &splits);
+        let done = splits.iter().all(|s| s.is_empty()) && map.iter().all(|(k, m)| k == m);
+        if !done && passes == MAX_PASSES {
+            segs = snap_round(&segs, &splits);
+            break;
+        }
+        segs = split_segs(&segs, &splits, &map);
+        if done {
+            break;
+        }
+        passes += 1;
+    }
+
+    let mut ids: HashMap<Key, usize> = HashMap::new();
+    let mut pts: Vec<(f64, f64)> = Vec::new();
+    let mut id = |k: Key| -> usize {
+        *ids.entry(k).or_insert_with(|| {
+            pts.push(fk(k));
+            pts.len() - 1
+        })
+    };
+    let edges = segs
+        .iter()
+        .map(|s| Edge {
+            u: id(s.p0),
+            v: id(s.p1),
+            cnt: s.cnt,
+        })
+        .collect();
+    (pts, edges)
+}
+
+// Snap rounding: the cells of the grid around the end points and the
+// crossings are hot, and every segment is replaced by the path through
+// the hot cells it passes. The paths can run along each other and meet
+// at the hot cells, but they don't cross.
+fn snap_round(segs: &[Seg], splits: &[Vec<Key>]) -> Vec<Seg> {
+    let mut hot: Vec<Key> = segs
+        .iter()
+        .flat_map(|s| [s.p0, s.p1])
+        .chain(splits.iter().flatten().cloned())
+        .collect();
+    hot.sort();
+    hot.dedup();
+
+    let mut pieces = Vec::new();
+    let mut path: Vec<(f64, Key)> = Vec::new();
+    for s in segs.iter() {
+        let (ax, ay) = fk(s.p0);
+        let (rx, ry) = (s.p1.0 as f64 - ax, s.p1.1 as f64 - ay);
+        let (xa, xb) = (s.p0.0.min(s.p1.0), s.p0.0.max(s.p1.0));
+        let (ya, yb) = (s.p0.1.min(s.p1.1), s.p0.1.max(s.p1.1));
+        let first = hot.partition_point(|h| h.0 < xa);
+        path.clear();
+        for &h in hot[first..].iter().take_while(|h| h.0 <= xb) {
+            if h.1 < ya || h.1 > yb {
+                continue;
+            }
+            if let Some(t) = clip_cell(ax - h.0 as f64, ay - h.1 as f64, rx, ry) {
+                path.push((t, h));
+            }
+        }
+        path.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for w in path.windows(2) {
+            pieces.push(Seg {
+                p0: w[0].1,
+                p1: w[1].1,
+                cnt: s.cnt,
+            });
+        }
+    }
+    let map: HashMap<Key, Key> = pieces
+        .iter()
+        .flat_map(|s| [(s.p0, s.p0), (s.p1, s.p1)])
+        .collect();
+    split_segs(&pieces, &vec![Vec::new(); pieces.len()], &map)
+}
+
+// Where the segment from o by r passes the grid cell around the origin,
+// as the parameter of the middle of the part inside it
+fn clip_cell(ox: f64, oy: f64, rx: f64, ry: f64) -> Option<f64> {
+    let (mut t0, mut t1) = (0f64, 1f64);
+    for &(o, r) in [(ox, rx), (oy, ry)].iter() {
+        if r == 0. {
+            if o.abs() > 0.5 {
+                return None;
+            }
+        } else {
+            let (a, b) = ((-0.5 - o) / r, (0.5 - o) / r);
+            t0 = t0.max(a.min(b));
+            t1 = t1.min(a.max(b));
+        }
+    }
+    if t0 <= t1 {
+        Some((t0 + t1) * 0.5)
+    } else {
+        None
+    }
+}
+
+// Edge indices by the cells of a range of x (or y) values they span
+struct Buckets {
+    min: f64,
+    scale: f64,
+    cells: Vec<Vec<usize>>,
+}
+
+impl Buckets {
+    fn new(n: usize, min: f64, max: f64) -> Self {
+        Buckets {
+            min: min,
+            scale: n as f64 / (max - min).max(1.),
+            cells: vec![Vec::new(); n],
+        }
+    }
+
+    fn index(&self, v: f64) -> usize {
+        (((v - self.min) * self.scale) as usize).min(self.cells.len() - 1)
+    }
+
+    fn add(&mut self, lo: f64, hi: f64, e: usize) {
+        for i in self.index(lo)..=self.index(hi) {
+            self.cells[i].push(e);
+        }
+    }
+
+    fn cell(&self, v: f64) -> &[usize] {
+        &self.cells[self.index(v)]
+    }
+}
+
+#[inline]
+fn is_filled(w: i32, filling_rule: FillingRule) -> bool {
+    match filling_rule {
+        FillingRule::FillNonZero => w != 0,
+        FillingRule::FillEvenOdd => w & 1 != 0,
+    }
+}
+
+#[inline]
+fn apply_op(op: GpcOp, a: bool, b: bool) -> bool {
+    match op {
+        GpcOp::Or => a || b,
+        GpcOp::And => a && b,
+        GpcOp::Xor => a != b,
+        GpcOp::AMinusB => a && !b,
+        GpcOp::BMinusA => b && !a,
+    }
+}
+
+// Returns the edges of the result as pairs of vertices, directed so that
+// the filled area is on the left.
+//
+// The winding numbers are counted along a ray from the middle of the
+// edge: downwards for edges that aren't vertical, to the left for
+// vertical ones. The half-open span test counts a ray passing through a
+// vertex once.
+fn classify_edges(
+    pts: &[(f64, f64)], edges: &[Edge], op: GpcOp, filling_rule: FillingRule,
+) -> Vec<(usize, usize)> {
+    let mut result = Vec::new();
+    if edges.is_empty() {
+        return result;
+    }
+    let (mut x1, mut y1, mut x2, mut y2) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+    for p in pts.iter() {
+        x1 = x1.min(p.0);
+        y1 = y1.min(p.1);
+        x2 = x2.max(p.0);
+        y2 = y2.max(p.1);
+    }
+    let n = ((edges.len() as f64).sqrt() as usize).clamp(1, 4096);
+    let mut by_x = Buckets::new(n, x1, x2);
+    let mut by_y = Buckets::new(n, y1, y2);
+    for (i, e) in edges.iter().enumerate() {
+        let (p, q) = (pts[e.u], pts[e.v]);
+        if p.0 != q.0 {
+            by_x.add(p.0.min(q.0), p.0.max(q.0), i);
+        }
+        if p.1 != q.1 {
+            by_y.add(p.1.min(q.1), p.1.max(q.1), i);
+        }
+    }
+
+    for (i, e) in edges.iter().enumerate() {
+        let (p, q) = (pts[e.u], pts[e.v]);
+        let (mx, my) = ((p.0 + q.0) * 0.5, (p.1 + q.1) * 0.5);
+        let mut w = [0i32; 2];
+        let sign;
+        let ray_side_is_left;
+        if p.0 != q.0 {
+            for &j in by_x.cell(mx).iter() {
+                let f = &edges[j];
+                let (fp, fq) = (pts[f.u], pts[f.v]);
+                if j == i || mx < fp.0.min(fq.0) || mx >= fp.0.max(fq.0) {
+                    continue;
+                }
+                let y = fp.1 + (fq.1 - fp.1) * (mx - fp.0) / (fq.0 - fp.0);
+                if y < my {
+                    let s = if fq.0 > fp.0 { 1 } else { -1 };
+                    w[0] += s * f.cnt[0];
+                    w[1] += s * f.cnt[1];
+                }
+            }
+            // The side below the edge is the left one when it runs to -x
+            sign = if q.0 > p.0 { 1 } else { -1 };
+            ray_side_is_left = sign < 0;
+        } else {
+            for &j in by_y.cell(my).iter() {
+                let f = &edges[j];
+                let (fp, fq) = (pts[f.u], pts[f.v]);
+                if j == i || my < fp.1.min(fq.1) || my >= fp.1.max(fq.1) {
+                    continue;
+                }
+                let x = fp.0 + (fq.0 - fp.0) * (my - fp.1) / (fq.1 - fp.1);
+                if x < mx {
+                    let s = if fq.1 < fp.1 { 1 } else { -1 };
+                    w[0] += s * f.cnt[0];
+                    w[1] += s * f.cnt[1];
+                }
+            }
+            // The side at -x is the left one when the edge runs to +y
+            sign = if q.1 < p.1 { 1 } else { -1 };
+            ray_side_is_left = sign < 0;
+        }
+        let near = apply_op(
+            op,
+            is_filled(w[0], filling_rule),
+            is_filled(w[1], filling_rule),
+        );
+        let far = apply_op(
+            op,
+            is_filled(w[0] + sign * e.cnt[0], filling_rule),
+            is_filled(w[1] + sign * e.cnt[1], filling_rule),
+        );
+        if near != far {
+            let left = if ray_side_is_left { near } else { far };
+            result.push(if left { (e.u, e.v) } else { (e.v, e.u) });
+        }
+    }
+    result
+}
+
+// Chains the directed edges into closed contours. At vertices where more
+// contours meet, the walk takes the sharpest left turn, so it keeps to
+// the boundary of one filled area and touching areas come out as
+// separate contours.
+fn chain_edges(pts: &[(f64, f64)], edges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut out: Vec<Vec<usize>> = vec![Vec::new(); pts.len()];
+    for (i, e) in edges.iter().enumerate() {
+        out[e.0].push(i);
+    }
+    let angle = |from: usize, to: usize| (pts[to].1 - pts[from].1).atan2(pts[to].0 - pts[from].0);
+
+    let mut used = vec![false; edges.len()];
+    let mut contours = Vec::new();
+    for start in 0..edges.len() {
+        if used[start] {
+            continue;
+        }
+        let mut contour = Vec::new();
+        let mut e = start;
+        loop {
+            used[e] = true;
+            let (from, to) = edges[e];
+            contour.push(from);
+            let back = angle(to, from);
+            let mut next = None;
+            let mut best = f64::MAX;
+            for &n in out[to].iter() {
+                let mut a = back - angle(to, edges[n].1);
+                while a <= 0. {
+                    a += 2. * PI;
+                }
+                while a > 2. * PI {
+                    a -= 2. * PI;
+                }
+                if a < best {
+                    best = a;
+                    next = Some(n);
+                }
+            }
+            match next {
+                Some(n) if !used[n] => e = n,
+                _ => break,
+            }
+        }
+        remove_collinear(pts, &mut contour);
+        if contour.len() > 2 {
+            contours.push(contour);
+        }
+    }
+    contours
+}
+
+fn remove_collinear(pts: &[(f64, f64)], contour: &mut Vec<usize>) {
+    let mut i = 0;
+    while contour.len() > 2 && i < contour.len() {
+        let n = contour.len();
+        let p = pts[contour[(i + n - 1) % n]];
+        let c = pts[contour[i]];
+        let q = pts[contour[(i + 1) % n]];
+        let d = cross(c.0 - p.0, c.1 - p.1, q.0 - c.0, q.1 - c.1);
+        if d == 0. && (c.0 - p.0) * (q.0 - c.0) + (c.1 - p.1) * (q.1 - c.1) > 0. {
+            contour.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+}
@@ -0,0 +1,544 @@
+use std::fs;
+use std::io::{self, BufRead};
+
+use crate::basics::crc32;
+use crate::png::zlib::inflate_raw;
+
+//==============================================================RasterFont
+// A bitmap font of GlyphRasterBin with the glyphs looked up by code point.
+// The glyphs are kept as in the embedded fonts: the width of the glyph,
+// which is its advance, then height() rows from the top, each of
+// (width + 7) / 8 bytes with the leftmost pixel in the high bit.
+//
+// It is made from an embedded font or read from a BDF or PCF file, where
+// each glyph is put into a cell of its advance and the height of the
+// font, so pixels out of the cell are lost. The encodings of the files
+// are taken as code points, as they are for ISO10646 and ISO8859-1 fonts.
+#[derive(Clone)]
+pub struct RasterFont {
+    height: u32,
+    base_line: u32,
+    data: Vec<u8>,
+    index: Vec<(u32, usize)>,
+}
+
+// A glyph of a BDF or PCF file: its bounding box in pixels with the
+// origin on the base line, the advance and the rows from the top, each
+// of (w + 7) / 8 bytes with the leftmost pixel in the high bit
+struct BitmapGlyph {
+    code: u32,
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+    advance: i32,
+    rows: Vec<u8>,
+}
+
+impl RasterFont {
+    // Cells higher than 255 rows are taken for a broken font, as the
+    // embedded fonts keep the height in a byte
+    fn new(ascent: i32, descent: i32) -> io::Result<RasterFont> {
+        let height = match ascent.checked_add(descent) {
+            Some(h) if (0..=255).contains(&h) && descent <= h => h,
+            _ => return Err(bad_font("bad font ascent or descent")),
+        };
+        Ok(RasterFont {
+            height: height as u32,
+            base_line: descent.max(0) as u32,
+            data: Vec::new(),
+            index: Vec::new(),
+        })
+    }
+
+    // A font of one of the tables of embedded_raster_fonts: the height,
+    // the base line, the first code and the number of codes, each one
+    // byte, then the u16 little-endian offsets of the glyphs after them
+    pub fn from_embedded(font: &[u8]) -> io::Result<RasterFont> {
+        if font.len() < 4 {
+            return Err(bad_font("embedded font too short"));
+        }
+        let (height, start, num) = (font[0] as usize, font[2] as u32, font[3] as usize);
+        let glyphs = 4 + num * 2;
+        let mut f = RasterFont {
+            height: height as u32,
+            base_line: font[1] as u32,
+            data: Vec::new(),
+            index: Vec::new(),
+        };
+        for i in 0..num {
+            let pos = font
+                .get(4 + i * 2..6 + i * 2)
+                .map(|v| glyphs + u16::from_le_bytes([v[0], v[1]]) as usize);
+            let glyph = pos.and_then(|p| {
+                let w = *font.get(p)? as usize;
+                font.get(p..p + 1 + height * ((w + 7) >> 3))
+            });
+            match glyph {
+                Some(g) => {
+                    f.index.push((start + i as u32, f.data.len()));
+                    f.data.extend_from_slice(g);
+                }
+                None => return Err(bad_font("bad glyph offset in embedded font")),
+            }
+        }
+        Ok(f)
+    }
+
+    // Reads a BDF font (Glyph Bitmap Distribution Format 2.1)
+    pub fn read_bdf<R: BufRead>(rdr: &mut R) -> io::Result<RasterFont> {
+        let mut lines = rdr.lines();
+        let mut next = || -> io::Result<String> {
+            lines
+                .next()
+                .unwrap_or_else(|| Err(bad_font("unexpected end of BDF font")))
+        };
+        match next() {
+            Ok(line) if line.starts_with("STARTFONT") => {}
+            Err(e) if e.kind() != io::ErrorKind::InvalidData => return Err(e),
+            _ => return Err(bad_font("not a BDF font")),
+        }
+        let mut bbox = [0i32; 4];
+        let (mut ascent, mut descent) = (None, None);
+        let mut advance = None;
+        let mut glyphs = Vec::new();
+        loop {
+            let line = next()?;
+            let mut tok = line.split_whitespace();
+            match tok.next() {
+                Some("FONTBOUNDINGBOX") => bbox = bdf_numbers(&mut tok, &line)?,
+                Some("FONT_ASCENT") => ascent = Some(bdf_numbers::<1>(&mut tok, &line)?[0]),
+                Some("FONT_DESCENT") => descent = Some(bdf_numbers::<1>(&mut tok, &line)?[0]),
+                Some("DWIDTH") => advance = Some(bdf_numbers::<2>(&mut tok, &line)?[0]),
+                Some("STARTCHAR") => {
+                    if let Some(g) = read_bdf_glyph(&mut next, bbox, advance)? {
+                        glyphs.push(g);
+                    }
+                }
+                Some("ENDFONT") => break,
+                _ => {}
+            }
+        }
+        let ascent = ascent.unwrap_or(bbox[1].saturating_add(bbox[3]));
+        let descent = descent.unwrap_or(bbox[3].saturating_neg());
+        RasterFont::from_glyphs(ascent, descent, glyphs)
+    }
+
+    // A PCF font (the X11 Portable Compiled Format), which may be
+    // compressed with gzip
+    pub fn from_pcf(data: &[u8]) -> io::Result<RasterFont> {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            return RasterFont::from_pcf(&gunzip(data)?);
+        }
+        let pcf = Pcf::new(data)?;
+        let metrics = pcf.metrics()?;
+        let bitmaps = pcf.bitmaps(&metrics)?;
+        let (ascent, descent) = match pcf.font_ascent()? {
+            Some(v) => v,
+            None => metrics
+                .iter()
+                .fold((0, 0), |a, m| (a.0.max(m[3]), a.1.max(m[4]))),
+        };
+        let mut glyphs = Vec::new();
+        for (code, i) in pcf.encodings()? {
+            let (m, rows) = match (metrics.get(i), bitmaps.get(i)) {
+                (Some(m), Some(rows)) => (m, rows),
+                _ => return Err(bad_font("bad glyph index in PCF font")),
+            };
+            glyphs.push(BitmapGlyph {
+                code: code,
+                x: m[0],
+                y: -m[4],
+                w: m[1] - m[0],
+                h: m[3] + m[4],
+                advance: m[2],
+                rows: rows.clone(),
+            });
+        }
+        RasterFont::from_glyphs(ascent, descent, glyphs)
+    }
+
+    // Loads a BDF or PCF font file, telling them apart by their content
+    pub fn load(file: &str) -> io::Result<RasterFont> {
+        let mut data = fs::read(file)?;
+        if data.starts_with(&[0x1f, 0x8b]) {
+            data = gunzip(&data)?;
+        }
+        if data.starts_with(PCF_MAGIC) {
+            RasterFont::from_pcf(&data)
+        } else {
+            RasterFont::read_bdf(&mut &data[..])
+        }
+    }
+
+    fn from_glyphs(
+        ascent: i32, descent: i32, mut glyphs: Vec<BitmapGlyph>,
+    ) -> io::Result<RasterFont> {
+        glyphs.sort_by_key(|g| g.code);
+        glyphs.dedup_by_key(|g| g.code);
+        let mut f = RasterFont::new(ascent, descent)?;
+        for g in glyphs.iter() {
+            f.add_glyph(g, ascent);
+        }
+        Ok(f)
+    }
+
+    // Puts the glyph into its cell, ascent rows of which are above the
+    // base line
+    fn add_glyph(&mut self, g: &BitmapGlyph, ascent: i32) {
+        let width = g.advance.clamp(0, 255);
+        let byte_width = ((width + 7) >> 3) as usize;
+        let src_byte_width = (g.w as usize + 7) >> 3;
+        self.index.push((g.code, self.data.len()));
+        self.data.push(width as u8);
+        let start = self.data.len();
+        self.data
+            .resize(start + byte_width * self.height as usize, 0);
+        // In i64 as the offsets of a BDF glyph can be any i32; only the
+        // columns that land in the cell are visited
+        let x0 = (-(g.x as i64)).max(0);
+        let x1 = (width as i64 - g.x as i64).min(g.w as i64);
+        for j in 0..g.h {
+            let row = ascent as i64 - g.y as i64 - g.h as i64 + j as i64;
+            if row < 0 || row >= self.height as i64 {
+                continue;
+            }
+            for i in x0..x1 {
+                let bit =
+                    g.rows[j as usize * src_byte_width + (i >> 3) as usize] & (0x80 >> (i & 7));
+                let x = g.x as i64 + i;
+                if bit != 0 {
+                    self.data[start + row as usize * byte_width + (x >> 3) as usize] |=
+                        0x80 >> (x & 7);
+                }
+            }
+        }
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // The number of rows below the base line
+    pub fn base_line(&self) -> u32 {
+        self.base_line
+    }
+
+    pub fn num_glyphs(&self) -> usize {
+        self.index.len()
+    }
+
+    // The code points that have a glyph, in increasing order
+    pub fn codes(&self) -> impl Iterator<Item = u32> + '_ {
+        self.index.iter().map(|g| g.0)
+    }
+
+    // The glyph of the code point: its width and rows, see RasterFont
+    pub fn glyph(&self, code: u32) -> Option<&[u8]> {
+        let i = self.index.binary_search_by_key(&code, |g| g.0).ok()?;
+        let pos = self.index[i].1;
+        let w = self.data[pos] as usize;
+        Some(&self.data[pos..pos + 1 + self.height as usize * ((w + 7) >> 3)])
+    }
+
+    pub fn glyph_width(&self, code: u32) -> Option<u32> {
+        self.glyph(code).map(|g| g[0] as u32)
+    }
+}
+
+fn bad_font(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn bdf_numbers<'a, const N: usize>(
+    tok: &mut impl Iterator<Item = &'a str>, line: &str,
+) -> io::Result<[i32; N]> {
+    let mut v = [0; N];
+    for e in v.iter_mut() {
+        *e = tok
+            .next()
+            .and_then(|t| t.parse().ok())
+            .ok_or_else(|| bad_font(&format!("bad BDF line: {}", line)))?;
+    }
+    Ok(v)
+}
+
+// Reads a glyph up to ENDCHAR, None if it has no encoding
+fn read_bdf_glyph<F: FnMut() -> io::Result<String>>(
+    next: &mut F, bbox: [i32; 4], advance: Option<i32>,
+) -> io::Result<Option<BitmapGlyph>> {
+    let mut code = -1;
+    let mut bbx = bbox;
+    let mut advance = advance.unwrap_or(bbox[0]);
+    let mut rows = Vec::new();
+    loop {
+        let line = next()?;
+        let mut tok = line.split_whitespace();
+        match tok.next() {
+            Some("ENCODING") => code = bdf_numbers::<1>(&mut tok, &line)?[0],
+            Some("DWIDTH") => advance = bdf_numbers::<2>(&mut tok, &line)?[0],
+            Some("BBX") => bbx = bdf_numbers(&mut tok, &line)?,
+            Some("BITMAP") => {
+                let byte_width = (bbx[0].max(0) as usize + 7) >> 3;
+                for _ in 0..bbx[1].max(0) {
+                    let row = next()?;
+                    let row = row.trim();
+                    for k in 0..byte_width {
+                        let v = row
+                            .get(k * 2..k * 2 + 2)
+                            .and_then(|v| u8::from_str_radix(v, 16).ok())
+                            .ok_or_else(|| bad_font(&format!("bad BDF bitmap row: {}", row)))?;
+                        rows.push(v);
+                    }
+                }
+            }
+            Some("ENDCHAR") => break,
+            _ => {}
+        }
+    }
+    // The rows are read with the BBX seen at BITMAP, a later one has to
+    // agree with them
+    let (w, h) = (bbx[0].max(0), bbx[1].max(0));
+    if (h as usize).checked_mul((w as usize + 7) >> 3) != Some(rows.len()) {
+        return Err(bad_font("BDF bitmap doesn't match its BBX"));
+    }
+    if code < 0 {
+        return Ok(None);
+    }
+    Ok(Some(BitmapGlyph {
+        code: code as u32,
+        x: bbx[2],
+        y: bbx[3],
+        w: w,
+        h: h,
+        advance: advance,
+        rows: rows,
+    }))
+}
+
+// The data of a gzip file (RFC 1952)
+fn gunzip(data: &[u8]) -> io::Result<Vec<u8>> {
+    if data.len() < 18 || data[2] != 8 {
+        return Err(bad_font("bad gzip header"));
+    }
+    let flags = data[3];
+    let mut pos = 10;
+    if flags & 4 != 0 {
+        pos += 2 + u16::from_le_bytes([data[10], data[11]]) as usize;
+    }
+    for f in [8, 16] {
+        if flags & f != 0 {
+            pos += data
+                .get(pos..)
+                .and_then(|d| d.iter().position(|&c| c == 0))
+                .unwrap_or(0)
+                + 1;
+        }
+    }
+    if flags & 2 != 0 {
+        pos += 2;
+    }
+    let d = data.get(pos..).ok_or_else(|| bad_font("bad gzip header"))?;
+    let (out, len) = inflate_raw(d).map_err(|e| bad_font(&format!("gzip: {}", e)))?;
+    let crc = d
+        .get(len..len + 4)
+        .map(|v| u32::from_le_bytes([v[0], v[1], v[2], v[3]]));
+    if crc != Some(crc32(&[&out])) {
+        return Err(bad_font("gzip checksum mismatch"));
+    }
+    Ok(out)
+}
+
+//------------------------------------------------------------------------
+const PCF_MAGIC: &[u8] = b"\x01fcp";
+const PCF_ACCELERATORS: u32 = 1 << 1;
+const PCF_METRICS: u32 = 1 << 2;
+const PCF_BITMAPS: u32 = 1 << 3;
+const PCF_BDF_ENCODINGS: u32 = 1 << 5;
+const PCF_BDF_ACCELERATORS: u32 = 1 << 8;
+const PCF_COMPRESSED_METRICS: u32 = 0x100;
+
+// The tables of a PCF font. Each table starts with its format, always
+// little-endian, which gives the byte order of the rest of it.
+struct Pcf<'a> {
+    data: &'a [u8],
+    toc: Vec<[u32; 4]>,
+}
+
+// A table of a PCF font
+struct PcfTable<'a> {
+    data: &'a [u8],
+    format: u32,
+}
+
+impl<'a> PcfTable<'a> {
+    fn bytes<const N: usize>(&self, pos: usize) -> io::Result<[u8; N]> {
+        let mut v: [u8; N] = self
+            .data
+            .get(pos..pos + N)
+            .and_then(|v| v.try_into().ok())
+            .ok_or_else(|| bad_font("PCF table too short"))?;
+        if self.format & 4 == 0 {
+            v.reverse();
+        }
+        Ok(v)
+    }
+
+    fn i16(&self, pos: usize) -> io::Result<i32> {
+        Ok(i16::from_be_bytes(self.bytes(pos)?) as i32)
+    }
+
+    fn u16(&self, pos: usize) -> io::Result<usize> {
+        Ok(u16::from_be_bytes(self.bytes(pos)?) as usize)
+    }
+
+    fn i32(&self, pos: usize) -> io::Result<i32> {
+        Ok(i32::from_be_bytes(self.bytes(pos)?))
+    }
+
+    fn u32(&self, pos: usize) -> io::Result<usize> {
+        Ok(u32::from_be_bytes(self.bytes(pos)?) as usize)
+    }
+}
+
+impl<'a> Pcf<'a> {
+    fn new(data: &'a [u8]) -> io::Result<Pcf<'a>> {
+        if !data.starts_with(PCF_MAGIC) || data.len() < 8 {
+            return Err(bad_font("not a PCF font"));
+        }
+        let le = |pos: usize| {
+            data.get(pos..pos + 4)
+                .map(|v| u32::from_le_bytes([v[0], v[1], v[2], v[3]]))
+                .ok_or_else(|| bad_font("PCF font too short"))
+        };
+        let num = le(4)? as usize;
+        let mut toc = Vec::new();
+        for i in 0..num {
+            let p = 8 + i * 16;
+            toc.push([le(p)?, le(p + 4)?, le(p + 8)?, le(p + 12)?]);
+        }
+        Ok(Pcf {
+            data: data,
+            toc: toc,
+        })
+    }
+
+    fn table(&self, kind: u32) -> io::Result<Option<PcfTable<'a>>> {
+        let e = match self.toc.iter().find(|e| e[0] == kind) {
+            Some(e) => e,
+            None => return Ok(None),
+        };
+        let (size, offset) = (e[2] as usize, e[3] as usize);
+        let data = self
+            .data
+            .get(offset..offset + size)
+            .filter(|d| d.len() >= 4)
+            .ok_or_else(|| bad_font("PCF table out of the file"))?;
+        Ok(Some(PcfTable {
+            data: data,
+            format: u32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+        }))
+    }
+
+    fn required(&self, kind: u32, name: &str) -> io::Result<PcfTable<'a>> {
+        self.table(kind)?
+            .ok_or_else(|| bad_font(&format!("no {} table in PCF font", name)))
+    }
+
+    // The left and right bearings, advance, ascent and descent of each
+    // glyph
+    fn metrics(&self) -> io::Result<Vec<[i32; 5]>> {
+        let t = self.required(PCF_METRICS, "metrics")?;
+        let mut metrics = Vec::new();
+        if t.format & PCF_COMPRESSED_METRICS != 0 {
+            for i in 0..t.u16(4)? {
+                let m: [u8; 5] = t
+                    .data
+                    .get(6 + i * 5..11 + i * 5)
+                    .and_then(|v| v.try_into().ok())
+                    .ok_or_else(|| bad_font("PCF table too short"))?;
+                metrics.push(m.map(|v| v as i32 - 0x80));
+            }
+        } else {
+            for i in 0..t.u32(4)? {
+                let p = 8 + i * 12;
+                metrics.push([
+                    t.i16(p)?,
+                    t.i16(p + 2)?,
+                    t.i16(p + 4)?,
+                    t.i16(p + 6)?,
+                    t.i16(p + 8)?,
+                ]);
+            }
+        }
+        Ok(metrics)
+    }
+
+    // The rows of each glyph, as in BitmapGlyph
+    fn bitmaps(&self, metrics: &[[i32; 5]]) -> io::Result<Vec<Vec<u8>>> {
+        let t = self.required(PCF_BITMAPS, "bitmaps")?;
+        let num = t.u32(4)?;
+        let pad = 1 << (t.format & 3);
+        let unit = 1 << ((t.format >> 4) & 3);
+        let msb_bits = t.format & 8 != 0;
+        let swap = (t.format & 4 != 0) != msb_bits;
+        let start = 8 + num * 4 + 16;
+        let mut bitmaps = Vec::new();
+        for (i, m) in metrics.iter().enumerate().take(num) {
+            let (w, h) = ((m[1] - m[0]).max(0) as usize, (m[3] + m[4]).max(0) as usize);
+            let byte_width = w.div_ceil(8);
+            let stride = byte_width.div_ceil(pad) * pad;
+            let pos = start + t.u32(8 + i * 4)?;
+            let src = t
+                .data
+                .get(pos..pos + stride * h)
+                .ok_or_else(|| bad_font("PCF bitmap out of its table"))?;
+            let mut rows = Vec::with_capacity(byte_width * h);
+            for row in 0..h {
+                for k in 0..byte_width {
+                    // The byte k of the row once the scan units are in
+                    // the byte order of the bits
+                    let k = if swap && unit > 1 {
+                        k / unit * unit + unit - 1 - k % unit
+                    } else {
+                        k
+                    };
+                    let v = src[row * stride + k];
+                    rows.push(if msb_bits { v } else { v.reverse_bits() });
+                }
+            }
+            bitmaps.push(rows);
+        }
+        Ok(bitmaps)
+    }
+
+    // The code points and glyph indices
+    fn encodings(&self) -> io::Result<Vec<(u32, usize)>> {
+        let t = self.required(PCF_BDF_ENCODINGS, "encodings")?;
+        let (min2, max2) = (t.i16(4)?, t.i16(6)?);
+        let (min1, max1) = (t.i16(8)?, t.i16(10)?);
+        let mut codes = Vec::new();
+        let mut pos = 14;
+        for b1 in min1..=max1 {
+            for b2 in min2..=max2 {
+                let i = t.u16(pos)?;
+                if i != 0xffff {
+                    codes.push((((b1 << 8) | b2) as u32, i));
+                }
+                pos += 2;
+            }
+        }
+        Ok(codes)
+    }
+
+    fn font_ascent(&self) -> io::Result<Option<(i32, i32)>> {
+        let t = match self.table(PCF_BDF_ACCELERATORS)? {
+            Some(t) => t,
+            None => match self.table(PCF_ACCELERATORS)? {
+                Some(t) => t,
+                None => return Ok(None),
+            },
+        };
+        Ok(Some((t.i32(12)?, t.i32(16)?)))
+    }
+}
@@ -161,6 +161,8 @@ impl<'a, Pix: PixFmt> RendererBase<'a, Pix> {
         //let rdist = RectI::new(xdst, ydst, xdst + rsrc.x2 - rsrc.x1, ydst + rsrc.y2 - rsrc.y1);
 
         // Version with dx, dy (relative positioning)
+        let s = Pix::SUBPIXELS as i32;
+        let dx = dx.div_euclid(s);
         let mut rdst = RectI::new(rsrc.x1 + dx, rsrc.y1 + dy, rsrc.x2 + dx, rsrc.y2 + dy);
         let mut rc = self.clip_rect_area(
             &mut rdst,
@@ -192,8 +194,9 @@ impl<'a, Pix: PixFmt> RendererBase<'a, Pix> {
                             len -= x1src + len - rw.x2 - 1;
                         }
                         if len > 0 {
-                            self.ren
-                                .blend_from(src, x1dst, rdst.y1, x1src, rsrc.y1, len as u32, cover);
+                            self.ren.blend_from(
+                                src, x1dst * s, rdst.y1, x1src, rsrc.y1, len as u32, cover,
+                            );
                         }
                     }
                 }
@@ -206,8 +209,12 @@ impl<'a, Pix: PixFmt> RendererBase<'a, Pix> {
 
     pub fn clip_rect_area(&self, dst: &mut RectI, src: &mut RectI, wsrc: i32, hsrc: i32) -> RectI {
         let mut rc = RectI::new(0, 0, 0, 0);
+        // The clip box is in columns of the format, the images in whole
+        // pixels of it
+        let s = Pix::SUBPIXELS as i32;
         let mut cb = *self.clip_box();
-        cb.x2 += 1;
+        cb.x1 = (cb.x1 + s - 1).div_euclid(s);
+        cb.x2 = (cb.x2 + 1).div_euclid(s);
         cb.y2 += 1;
 
         if src.x1 < 0 {
@@ -675,6 +682,8 @@ impl<'a, Pix: PixFmt> RendererBase<'a, Pix> {
         //RectI rdst(xdst, ydst, xdst + rsrc.x2 - rsrc.x1, ydst + rsrc.y2 - rsrc.y1);
 
         // Version with dx, dy (relative positioning)
+        let s = Pix::SUBPIXELS as i32;
+        let dx = dx.div_euclid(s);
         let mut rdst = RectI::new(rsrc.x1 + dx, rsrc.y1 + dy, rsrc.x2 + dx, rsrc.y2 + dy);
 
         let mut rc = self.clip_rect_area(
@@ -693,7 +702,7 @@ impl<'a, Pix: PixFmt> RendererBase<'a, Pix> {
             }
             while rc.y2 > 0 {
                 self.ren
-                    .copy_from(src, rdst.x1, rdst.y1, rsrc.x1, rsrc.y1, rc.x2 as u32);
+                    .copy_from(src, rdst.x1 * s, rdst.y1, rsrc.x1, rsrc.y1, rc.x2 as u32);
                 rdst.y1 += incy;
                 rsrc.y1 += incy;
                 rc.y2 -= 1;
@@ -717,6 +726,8 @@ impl<'a, Pix: PixFmt> RendererBase<'a, Pix> {
         //let rdist = RectI::new(xdst, ydst, xdst + rsrc.x2 - rsrc.x1, ydst + rsrc.y2 - rsrc.y1);
 
         // Version with dx, dy (relative positioning)
+        let s = Pix::SUBPIXELS as i32;
+        let dx = dx.div_euclid(s);
         let mut rdst = RectI::new(rsrc.x1 + dx, rsrc.y1 + dy, rsrc.x2 + dx, rsrc.y2 + dy);
         let mut rc = self.clip_rect_area(
             &mut rdst,
@@ -749,7 +760,7 @@ impl<'a, Pix: PixFmt> RendererBase<'a, Pix> {
                         }
                         if len > 0 {
                             self.ren.blend_from_color(
-                                src, color, x1dst, rdst.y1, x1src, rsrc.y1, len as u32, cover,
+                                src, color, x1dst * s, rdst.y1, x1src, rsrc.y1, len as u32, cover,
                             );
                         }
                     }
@@ -777,6 +788,8 @@ impl<'a, Pix: PixFmt> RendererBase<'a, Pix> {
         //let rdist = RectI::new(xdst, ydst, xdst + rsrc.x2 - rsrc.x1, ydst + rsrc.y2 - rsrc.y1);
 
         // Version with dx, dy (relative positioning)
+        let s = Pix::SUBPIXELS as i32;
+        let dx = dx.div_euclid(s);
         let mut rdst = RectI::new(rsrc.x1 + dx, rsrc.y1 + dy, rsrc.x2 + dx, rsrc.y2 + dy);
         let mut rc = self.clip_rect_area(
             &mut rdst,
@@ -809,7 +822,8 @@ impl<'a, Pix: PixFmt> RendererBase<'a, Pix> {
                         }
                         if len > 0 {
                             self.ren.blend_from_lut(
-                                src, color_lut, x1dst, rdst.y1, x1src, rsrc.y1, len as u32, cover,
+                                src, color_lut, x1dst * s, rdst.y1, x1src, rsrc.y1, len as u32,
+                                cover,
                             );
                         }
                     }
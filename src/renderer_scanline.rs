@@ -25,7 +25,7 @@ use crate::{
 };
 
 //================================================render_scanline_aa_solid
-fn render_scanline_aa_solid<S: Scanline, R: Renderer<C = C>, C: Color>(
+pub fn render_scanline_aa_solid<S: Scanline, R: Renderer<C = C>, C: Color>(
     sl: &S, ren: &mut R, color: &C,
 ) {
     let span = sl.begin();
@@ -771,6 +771,7 @@ pub fn render_scanlines_compound_layered<
                                         }
                                         src_covers = unsafe { src_covers.offset(1) };
                                         dst_covers += 1;
+                                        cs_colors += 1;
                                     }
                                 }
                             }
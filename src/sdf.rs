@@ -0,0 +1,651 @@
+use crate::basics::{
+    is_curve3, is_curve4, is_end_poly, is_move_to, is_stop, is_vertex, FillingRule,
+};
+use crate::curves::{Curve3, Curve4};
+use crate::trans_affine::TransAffine;
+use crate::{Color, CurveBase, CurveType3, CurveType4, Interpolator, Order, PixFmt, SpanGenerator};
+use crate::{Transformer, VertexSource};
+
+// The channels of an edge in a multi-channel field
+const RED: u8 = 1;
+const GREEN: u8 = 2;
+const BLUE: u8 = 4;
+const WHITE: u8 = RED | GREEN | BLUE;
+const EDGE_COLORS: [u8; 3] = [GREEN | BLUE, RED | BLUE, RED | GREEN];
+
+// A line segment of a flattened contour in field pixels, directed so
+// that the inside is to its left
+#[derive(Clone, Copy)]
+struct Segment {
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    color: u8,
+    // The segment starts or ends an edge, beyond which the distance is
+    // measured to the extended segment
+    edge_start: bool,
+    edge_end: bool,
+}
+
+impl Segment {
+    // Distance to the point, the position along the segment, 0...1, and
+    // how perpendicular the nearest point is to the segment, for ties
+    fn distance(&self, x: f64, y: f64) -> (f64, f64, f64) {
+        let (dx, dy) = (self.x2 - self.x1, self.y2 - self.y1);
+        let len2 = dx * dx + dy * dy;
+        let t = ((x - self.x1) * dx + (y - self.y1) * dy) / len2;
+        if t > 0. && t < 1. {
+            let d = ((x - self.x1) * dy - (y - self.y1) * dx).abs() / len2.sqrt();
+            return (d, t, 1.);
+        }
+        let (ex, ey) = if t <= 0. {
+            (self.x1, self.y1)
+        } else {
+            (self.x2, self.y2)
+        };
+        let d = ((x - ex) * (x - ex) + (y - ey) * (y - ey)).sqrt();
+        let ortho = if d > 0. {
+            ((x - ex) * dy - (y - ey) * dx).abs() / (d * len2.sqrt())
+        } else {
+            1.
+        };
+        (d, t.max(0.).min(1.), ortho)
+    }
+
+    // Signed distance, positive on the inside, to the segment or, past
+    // the ends of an edge, to its extension
+    fn pseudo_distance(&self, x: f64, y: f64) -> f64 {
+        let (dx, dy) = (self.x2 - self.x1, self.y2 - self.y1);
+        let len = (dx * dx + dy * dy).sqrt();
+        let t = ((x - self.x1) * dx + (y - self.y1) * dy) / (len * len);
+        let side = (dx * (y - self.y1) - dy * (x - self.x1)) / len;
+        if (t >= 0. || self.edge_start) && (t <= 1. || self.edge_end) {
+            return side;
+        }
+        let (d, _, _) = self.distance(x, y);
+        if side < 0. {
+            -d
+        } else {
+            d
+        }
+    }
+}
+
+//============================================================SdfGenerator
+// Converts shapes given as vertex sources into signed distance fields:
+// every pixel of the field holds the distance from its center to the
+// outline, 0.5 on the outline, towards 1 inside and towards 0 outside,
+// reaching the ends at the range given in field pixels. Rendered with
+// SpanSdf, such a field gives sharp outlines at any scale.
+//
+// generate_msdf() builds a multi-channel field: the outline is split at
+// its corners into edges that get two of the three channels each, so
+// the median of the channels keeps the corners sharp where a single
+// channel rounds them off. The channels are written in the order of the
+// pixel format, which doesn't matter to the median.
+//
+// The shapes are filled by the filling rule, but the distances are taken
+// to all the contours, so overlapping contours leave seams in the field;
+// they're best merged before, e.g. with the polygon boolean operations.
+//
+//   let mut sdf = SdfGenerator::new();
+//   let mut path = fman.path_adaptor();
+//   path.init(gl.data.as_ptr(), gl.data_size as usize, 0., 0., 1.);
+//   sdf.add_path(&mut path, 0);
+//   let mtx = sdf.fit(64, 64, 4.);
+//   sdf.generate_sdf(&mut field.pixfmt(), &mtx, 4.);
+pub struct SdfGenerator {
+    // Move to, line to, curve3 and curve4 vertices of the shapes
+    vertices: Vec<(f64, f64, u32)>,
+    filling_rule: FillingRule,
+    approximation_scale: f64,
+}
+
+impl SdfGenerator {
+    pub fn new() -> SdfGenerator {
+        SdfGenerator {
+            vertices: Vec::new(),
+            filling_rule: FillingRule::FillNonZero,
+            approximation_scale: 4.,
+        }
+    }
+
+    pub fn remove_all(&mut self) {
+        self.vertices.clear();
+    }
+
+    pub fn filling_rule(&self) -> FillingRule {
+        self.filling_rule
+    }
+
+    pub fn set_filling_rule(&mut self, filling_rule: FillingRule) {
+        self.filling_rule = filling_rule;
+    }
+
+    // The curves are flattened in field pixels with this scale, 4 by
+    // default
+    pub fn approximation_scale(&self) -> f64 {
+        self.approximation_scale
+    }
+
+    pub fn set_approximation_scale(&mut self, s: f64) {
+        self.approximation_scale = s;
+    }
+
+    // Adds the path; the curves stay curves until a field is generated
+    pub fn add_path<VS: VertexSource>(&mut self, vs: &mut VS, path_id: u32) {
+        let (mut x, mut y) = (0., 0.);
+        vs.rewind(path_id);
+        loop {
+            let cmd = vs.vertex(&mut x, &mut y);
+            if is_stop(cmd) {
+                break;
+            }
+            if is_vertex(cmd) {
+                self.vertices.push((x, y, cmd));
+            } else if is_end_poly(cmd) {
+                // Every contour is closed
+                self.vertices.push((0., 0., cmd));
+            }
+        }
+    }
+
+    pub fn bounding_rect(&self) -> Option<(f64, f64, f64, f64)> {
+        let mut it = self.vertices.iter().filter(|v| is_vertex(v.2));
+        let &(x, y, _) = it.next()?;
+        Some(it.fold((x, y, x, y), |(x1, y1, x2, y2), &(x, y, _)| {
+            (x1.min(x), y1.min(y), x2.max(x), y2.max(y))
+        }))
+    }
+
+    // A transformation that centers the shapes in a field of the size,
+    // scaled to keep padding pixels free on all sides. Shapes have to
+    // fit with the range as padding to have all of their distances.
+    pub fn fit(&self, width: u32, height: u32, padding: f64) -> TransAffine {
+        let mut mtx = TransAffine::new_default();
+        if let Some((x1, y1, x2, y2)) = self.bounding_rect() {
+            let w = (width as f64 - padding * 2.).max(1.);
+            let h = (height as f64 - padding * 2.).max(1.);
+            let s = (w / (x2 - x1).max(1e-10)).min(h / (y2 - y1).max(1e-10));
+            mtx *= TransAffine::trans_affine_translation(-(x1 + x2) / 2., -(y1 + y2) / 2.);
+            mtx *= TransAffine::trans_affine_scaling_eq(s);
+            mtx *= TransAffine::trans_affine_translation(width as f64 / 2., height as f64 / 2.);
+        }
+        mtx
+    }
+
+    // Writes the single-channel field of the shapes transformed by mtx
+    // into all channels of the pixels
+    pub fn generate_sdf<Pix: PixFmt<T = u8>>(&self, pix: &mut Pix, mtx: &TransAffine, range: f64) {
+        self.generate(pix, mtx, range, false);
+    }
+
+    // Writes the multi-channel field of the shapes transformed by mtx,
+    // it needs three channels, a gray pixel format gets the single one
+    pub fn generate_msdf<Pix: PixFmt<T = u8>>(&self, pix: &mut Pix, mtx: &TransAffine, range: f64) {
+        self.generate(pix, mtx, range, Pix::PIXEL_WIDTH >= 3);
+    }
+
+    fn generate<Pix: PixFmt<T = u8>>(
+        &self, pix: &mut Pix, mtx: &TransAffine, range: f64, msdf: bool,
+    ) {
+        let segments = self.segments(mtx, msdf);
+        let range = range.max(1e-10);
+        let channels = if msdf {
+            vec![Pix::O::R, Pix::O::G, Pix::O::B]
+        } else {
+            (0..Pix::PIXEL_WIDTH as usize)
+                .filter(|&i| Pix::PIXEL_WIDTH < 4 || i != Pix::O::A)
+                .collect()
+        };
+        let value = |d: f64| ((0.5 + d / (2. * range)).max(0.).min(1.) * 255.).round() as u8;
+
+        let mut near: Vec<&Segment> = Vec::new();
+        let mut crossings = Vec::new();
+        let width = pix.width() as usize;
+        for y in 0..pix.height() as i32 {
+            let py = y as f64 + 0.5;
+            // Only the segments closer than the range matter, farther
+            // distances are clipped to it anyway
+            near.clear();
+            near.extend(
+                segments
+                    .iter()
+                    .filter(|s| s.y1.min(s.y2) - range < py && s.y1.max(s.y2) + range > py),
+            );
+            self.crossings(&segments, py, &mut crossings);
+
+            let row = pix.row_mut(y);
+            let mut c = 0;
+            let mut winding = 0;
+            for x in 0..width {
+                let px = x as f64 + 0.5;
+                while c < crossings.len() && crossings[c].0 < px {
+                    winding += crossings[c].1;
+                    c += 1;
+                }
+                let inside = match self.filling_rule {
+                    FillingRule::FillNonZero => winding != 0,
+                    FillingRule::FillEvenOdd => winding & 1 != 0,
+                };
+                let sign = if inside { 1. } else { -1. };
+
+                let mut dist = range;
+                for s in near.iter() {
+                    if s.x1.min(s.x2) - dist < px && s.x1.max(s.x2) + dist > px {
+                        dist = dist.min(s.distance(px, py).0);
+                    }
+                }
+                let sd = sign * dist;
+                let p = &mut row[x * Pix::PIXEL_WIDTH as usize..];
+                if !msdf {
+                    for &i in channels.iter() {
+                        p[i] = value(sd);
+                    }
+                    continue;
+                }
+
+                let mut md = [sign * range; 3];
+                for (ch, bit) in [RED, GREEN, BLUE].iter().enumerate() {
+                    // The closest segment of the channel, the most
+                    // perpendicular one of those meeting at a corner
+                    let mut best: Option<(&Segment, f64, f64)> = None;
+                    for s in near.iter().filter(|s| s.color & bit != 0) {
+                        let (d, _, ortho) = s.distance(px, py);
+                        let closer = match best {
+                            None => d < range,
+                            Some((_, bd, bo)) => d < bd - 1e-9 || (d < bd + 1e-9 && ortho > bo),
+                        };
+                        if closer {
+                            best = Some((s, d, ortho));
+                        }
+                    }
+                    if let Some((s, _, _)) = best {
+                        md[ch] = s.pseudo_distance(px, py).max(-range).min(range);
+                    }
+                }
+                // Where the median disagrees with the sign of the true
+                // distance the channels would give a wrong inside
+                let median = median(md[0], md[1], md[2]);
+                if (median > 0.) != inside {
+                    md = [sd; 3];
+                }
+                for (&i, &d) in channels.iter().zip(md.iter()) {
+                    p[i] = value(d);
+                }
+            }
+        }
+    }
+
+    // Where a horizontal line crosses the segments, sorted by x, with the
+    // winding direction
+    fn crossings(&self, segments: &[Segment], y: f64, out: &mut Vec<(f64, i32)>) {
+        out.clear();
+        for s in segments.iter() {
+            if (s.y1 <= y) != (s.y2 <= y) {
+                let x = s.x1 + (y - s.y1) * (s.x2 - s.x1) / (s.y2 - s.y1);
+                out.push((x, if s.y2 > s.y1 { 1 } else { -1 }));
+            }
+        }
+        out.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+
+    // The flattened contours in field pixels, turned to have the inside
+    // on the left, with the edge colors
+    fn segments(&self, mtx: &TransAffine, msdf: bool) -> Vec<Segment> {
+        let contours = self.contours(mtx);
+        let mut segments: Vec<Segment> = Vec::new();
+        for pts in contours.iter() {
+            for i in 0..pts.len() {
+                let (a, b) = (pts[i], pts[(i + 1) % pts.len()]);
+                segments.push(Segment {
+                    x1: a.0,
+                    y1: a.1,
+                    x2: b.0,
+                    y2: b.1,
+                    color: WHITE,
+                    edge_start: false,
+                    edge_end: false,
+                });
+            }
+        }
+
+        let mut first = 0;
+        let mut crossings = Vec::new();
+        let mut reversed = Vec::new();
+        for pts in contours.iter() {
+            let n = pts.len();
+            let contour = first..first + n;
+            first += n;
+            // A point just left of the longest segment tells if the
+            // contour has to be reversed
+            let s = segments[contour.clone()]
+                .iter()
+                .max_by(|a, b| seg_len(a).total_cmp(&seg_len(b)))
+                .copied()
+                .unwrap();
+            let len = seg_len(&s);
+            let eps = (len * 0.01).min(1e-3);
+            let mx = (s.x1 + s.x2) / 2. - (s.y2 - s.y1) / len * eps;
+            let my = (s.y1 + s.y2) / 2. + (s.x2 - s.x1) / len * eps;
+            self.crossings(&segments, my, &mut crossings);
+            let winding: i32 = crossings.iter().filter(|c| c.0 < mx).map(|c| c.1).sum();
+            let inside = match self.filling_rule {
+                FillingRule::FillNonZero => winding != 0,
+                FillingRule::FillEvenOdd => winding & 1 != 0,
+            };
+            reversed.push(!inside);
+            if !inside {
+                let seg = &mut segments[contour.clone()];
+                seg.reverse();
+                for s in seg.iter_mut() {
+                    std::mem::swap(&mut s.x1, &mut s.x2);
+                    std::mem::swap(&mut s.y1, &mut s.y2);
+                }
+            }
+        }
+        if msdf {
+            let mut first = 0;
+            for (pts, &rev) in contours.iter().zip(reversed.iter()) {
+                let corners: Vec<bool> = pts.iter().map(|p| p.2).collect();
+                color_edges(&mut segments[first..first + pts.len()], &corners, rev);
+                first += pts.len();
+            }
+        }
+        segments
+    }
+
+    // The closed contours as points (x, y, vertex of the path), without
+    // repeated points
+    fn contours(&self, mtx: &TransAffine) -> Vec<Vec<(f64, f64, bool)>> {
+        let mut contours = Vec::new();
+        let mut pts: Vec<(f64, f64, bool)> = Vec::new();
+        let mut c3 = Curve3::new();
+        let mut c4 = Curve4::new();
+        c3.set_approximation_scale(self.approximation_scale);
+        c4.set_approximation_scale(self.approximation_scale);
+
+        let close = |pts: &mut Vec<(f64, f64, bool)>, contours: &mut Vec<_>| {
+            while pts.len() > 1 && same(pts[0], pts[pts.len() - 1]) {
+                pts.pop();
+            }
+            if pts.len() > 2 {
+                contours.push(std::mem::replace(pts, Vec::new()));
+            }
+            pts.clear();
+        };
+        let push =
+            |pts: &mut Vec<(f64, f64, bool)>, x: f64, y: f64, orig: bool| match pts.last_mut() {
+                Some(p) if same(*p, (x, y, orig)) => p.2 |= orig,
+                _ => pts.push((x, y, orig)),
+            };
+
+        let mut i = 0;
+        while i < self.vertices.len() {
+            let (mut x, mut y, cmd) = self.vertices[i];
+            mtx.transform(&mut x, &mut y);
+            i += 1;
+            if is_move_to(cmd) {
+                close(&mut pts, &mut contours);
+                push(&mut pts, x, y, true);
+            } else if is_end_poly(cmd) {
+                close(&mut pts, &mut contours);
+            } else if is_curve3(cmd) || is_curve4(cmd) {
+                let n = if is_curve3(cmd) { 1 } else { 2 };
+                let mut p = [(x, y), (0., 0.), (0., 0.)];
+                for k in 1..=n {
+                    if let Some(&(mut vx, mut vy, _)) = self.vertices.get(i) {
+                        mtx.transform(&mut vx, &mut vy);
+                        p[k] = (vx, vy);
+                    }
+                    i += 1;
+                }
+                let (x0, y0) = pts.last().map_or(p[0], |p| (p.0, p.1));
+                let curve: &mut dyn VertexSource = if n == 1 {
+                    c3.init(x0, y0, p[0].0, p[0].1, p[1].0, p[1].1);
+                    &mut c3
+                } else {
+                    c4.init(x0, y0, p[0].0, p[0].1, p[1].0, p[1].1, p[2].0, p[2].1);
+                    &mut c4
+                };
+                let (mut cx, mut cy) = (0., 0.);
+                while !is_stop(curve.vertex(&mut cx, &mut cy)) {
+                    push(&mut pts, cx, cy, false);
+                }
+                // The end point of the curve is a vertex of the path
+                push(&mut pts, p[n].0, p[n].1, true);
+            } else {
+                push(&mut pts, x, y, true);
+            }
+        }
+        close(&mut pts, &mut contours);
+        contours
+    }
+}
+
+impl Default for SdfGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn same(a: (f64, f64, bool), b: (f64, f64, bool)) -> bool {
+    (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+}
+
+fn seg_len(s: &Segment) -> f64 {
+    ((s.x2 - s.x1) * (s.x2 - s.x1) + (s.y2 - s.y1) * (s.y2 - s.y1)).sqrt()
+}
+
+fn median(a: f64, b: f64, c: f64) -> f64 {
+    a.min(b).max(a.max(b).min(c))
+}
+
+// Splits a closed contour at its corners into edges and gives them
+// alternating colors. corners tells which points are vertices of the
+// path, the others come from flattened curves and are never corners.
+// The segments may have been reversed, then segment i starts at point
+// n - i of the contour.
+fn color_edges(seg: &mut [Segment], corners: &[bool], reversed: bool) {
+    let n = seg.len();
+    let mut starts = Vec::new();
+    for i in 0..n {
+        let prev = &seg[(i + n - 1) % n];
+        let cur = &seg[i];
+        let point = if reversed { (n - i) % n } else { i };
+        if !corners[point] {
+            continue;
+        }
+        let (l1, l2) = (seg_len(prev), seg_len(cur));
+        let (ax, ay) = ((prev.x2 - prev.x1) / l1, (prev.y2 - prev.y1) / l1);
+        let (bx, by) = ((cur.x2 - cur.x1) / l2, (cur.y2 - cur.y1) / l2);
+        // Turning by more than about 8 degrees
+        if ax * bx + ay * by <= 0. || (ax * by - ay * bx).abs() > 3f64.sin() {
+            starts.push(i);
+        }
+    }
+    if starts.is_empty() {
+        return;
+    }
+    // A single corner splits the contour in three to keep it sharp
+    if starts.len() == 1 {
+        if n < 3 {
+            return;
+        }
+        let s = starts[0];
+        starts = vec![s, (s + n / 3) % n, (s + n * 2 / 3) % n];
+    }
+    let m = starts.len();
+    for k in 0..m {
+        let mut color = EDGE_COLORS[k % 3];
+        if k == m - 1 && k % 3 == 0 {
+            color = EDGE_COLORS[1];
+        }
+        let (a, b) = (starts[k], starts[(k + 1) % m]);
+        let len = (b + n - a - 1) % n + 1;
+        for j in 0..len {
+            let s = &mut seg[(a + j) % n];
+            s.color = color;
+            s.edge_start = j == 0;
+            s.edge_end = j == len - 1;
+        }
+    }
+}
+
+//=================================================================SpanSdf
+// Renders a distance field made by SdfGenerator: samples it through an
+// interpolator, bilinear, and turns the distance into the coverage of
+// the color, with an outline and a glow around it if they're set. Pixel
+// formats with three channels or more are read as multi-channel fields.
+//
+// The interpolator maps screen to field pixels, e.g. SpanIpLinear with
+// the inverse of the transformation the field is drawn with; scale is
+// the size of a field pixel on the screen, mtx.scale() of that
+// transformation. The range is the one the field was generated with;
+// outlines and glows reach at most range * scale pixels out.
+pub struct SpanSdf<'a, Pix: PixFmt<T = u8>, I: Interpolator, C: Color> {
+    field: &'a Pix,
+    interpolator: &'a mut I,
+    range: f64,
+    scale: f64,
+    weight: f64,
+    color: C,
+    outline_color: C,
+    outline_width: f64,
+    glow_color: C,
+    glow_width: f64,
+}
+
+impl<'a, Pix: PixFmt<T = u8>, I: Interpolator, C: Color> SpanSdf<'a, Pix, I, C> {
+    pub fn new(field: &'a Pix, interpolator: &'a mut I, range: f64, color: C) -> Self {
+        SpanSdf {
+            field: field,
+            interpolator: interpolator,
+            range: range,
+            scale: 1.,
+            weight: 0.,
+            color: color,
+            outline_color: C::no_color(),
+            outline_width: 0.,
+            glow_color: C::no_color(),
+            glow_width: 0.,
+        }
+    }
+
+    pub fn interpolator_mut(&mut self) -> &mut I {
+        self.interpolator
+    }
+
+    pub fn range(&self) -> f64 {
+        self.range
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn set_scale(&mut self, scale: f64) {
+        self.scale = scale;
+    }
+
+    // Screen pixels the shape is made bolder by, or thinner if negative
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    pub fn set_weight(&mut self, weight: f64) {
+        self.weight = weight;
+    }
+
+    pub fn color(&self) -> C {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: C) {
+        self.color = color;
+    }
+
+    // An outline of width screen pixels around the shape, 0 for none
+    pub fn set_outline(&mut self, color: C, width: f64) {
+        self.outline_color = color;
+        self.outline_width = width;
+    }
+
+    // A glow fading out over width screen pixels around the shape and
+    // its outline, 0 for none
+    pub fn set_glow(&mut self, color: C, width: f64) {
+        self.glow_color = color;
+        self.glow_width = width;
+    }
+
+    // The field value at field pixel coordinates, bilinear between the
+    // pixel centers and clamped at the borders
+    fn sample(&self, x: f64, y: f64) -> f64 {
+        let (w, h) = (self.field.width() as i32, self.field.height() as i32);
+        if w == 0 || h == 0 {
+            return 0.;
+        }
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let cx = |x: i32| x.max(0).min(w - 1) as usize * Pix::PIXEL_WIDTH as usize;
+        let cy = |y: i32| y.max(0).min(h - 1);
+        let (xa, xb) = (cx(x0 as i32), cx(x0 as i32 + 1));
+        let (ra, rb) = (
+            self.field.row(cy(y0 as i32)),
+            self.field.row(cy(y0 as i32 + 1)),
+        );
+        let lerp = |i: usize| {
+            let top = ra[xa + i] as f64 * (1. - fx) + ra[xb + i] as f64 * fx;
+            let bottom = rb[xa + i] as f64 * (1. - fx) + rb[xb + i] as f64 * fx;
+            (top * (1. - fy) + bottom * fy) / 255.
+        };
+        if Pix::PIXEL_WIDTH >= 3 {
+            median(lerp(Pix::O::R), lerp(Pix::O::G), lerp(Pix::O::B))
+        } else {
+            lerp(0)
+        }
+    }
+}
+
+// The upper color over the lower one with coverage k
+fn over<C: Color>(lower: C, upper: &C, k: f64) -> C {
+    if lower.opacity() <= 0. {
+        let mut c = *upper;
+        c.set_opacity(upper.opacity() * k);
+        c
+    } else {
+        lower.gradient(upper, k)
+    }
+}
+
+impl<'a, Pix: PixFmt<T = u8>, I: Interpolator, C: Color> SpanGenerator for SpanSdf<'a, Pix, I, C> {
+    type C = C;
+
+    fn prepare(&mut self) {}
+
+    fn generate(&mut self, span: &mut [C], x: i32, y: i32, len: u32) {
+        let subpixel = (1 << I::SUBPIXEL_SHIFT) as f64;
+        self.interpolator.begin(x as f64 + 0.5, y as f64 + 0.5, len);
+        for s in span[..len as usize].iter_mut() {
+            let (mut fx, mut fy) = (0, 0);
+            self.interpolator.coordinates(&mut fx, &mut fy);
+            self.interpolator.next();
+            let v = self.sample(fx as f64 / subpixel, fy as f64 / subpixel);
+            let d = (v - 0.5) * 2. * self.range * self.scale + self.weight;
+
+            let clamp = |v: f64| v.max(0.).min(1.);
+            let mut c = C::no_color();
+            let edge = d + self.outline_width;
+            if self.glow_width > 0. {
+                let g = clamp(1. + edge / self.glow_width);
+                c = over(c, &self.glow_color, g * g);
+            }
+            if self.outline_width > 0. {
+                c = over(c, &self.outline_color, clamp(edge + 0.5));
+            }
+            *s = over(c, &self.color, clamp(d + 0.5));
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use crate::image_accessors::ImageAccessorClip;
+use crate::image_buffer::ImageBuffer;
+use crate::image_filters::{ImageFilterKind, ImageFilterLut};
+use crate::span_gradient::{GradientRadial, GradientX, SpanGradient};
+use crate::span_image_filter_rgba::*;
+use crate::span_interpolator_linear::SpanIpLinear;
+use crate::trans_affine::TransAffine;
+use crate::{Color, GradientFunc, PixFmt, PixFmtOwned, RasterStyle, RgbArgs, SpanGenerator};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GradientShape {
+    Linear,
+    Radial,
+}
+
+struct GradientFill<C: Color> {
+    shape: GradientShape,
+    colors: Vec<C>,
+    ip: SpanIpLinear<TransAffine>,
+    d1: f64,
+    d2: f64,
+}
+
+struct ImageFill<Pix: PixFmt> {
+    src: ImageAccessorClip<Pix>,
+    ip: SpanIpLinear<TransAffine>,
+    filter: ImageFilterKind,
+    lut: ImageFilterLut,
+    // The pixels src reads from if the fill owns the image
+    _data: Vec<u8>,
+}
+
+enum Fill<Pix: PixFmt> {
+    Solid,
+    Gradient(GradientFill<Pix::C>),
+    Image(ImageFill<Pix>),
+}
+
+struct Style<Pix: PixFmt> {
+    color: Pix::C,
+    fill: Fill<Pix>,
+}
+
+//=============================================================StyleTable
+// A RasterStyle for render_scanlines_compound() and
+// render_scanlines_compound_layered() over a list of fills, one per style
+// index: solid colors, linear and radial gradients and images. Image fills
+// read from pixel formats of the type Pix, which also gives the color type.
+//
+// The matrices of the gradients and images map them onto the screen.
+// A linear gradient runs along the x axis from d1 to d2, a radial one
+// around the origin; an image covers the area from (0,0) to (width,height)
+// and is transparent outside of it.
+//
+//   let mut styles = StyleTable::<PixBgra32>::new();
+//   let red = styles.add_solid(Rgba8::new_params(255, 0, 0, 255));
+//   let grad = styles.add_gradient(GradientShape::Linear, &mtx, 0., 100., &colors);
+//   render_scanlines_compound_layered(&mut rasc, &mut sl, &mut ren, &mut alloc, &mut styles);
+pub struct StyleTable<Pix: PixFmt>
+where
+    Pix::C: RgbArgs,
+{
+    styles: Vec<Style<Pix>>,
+    none: Pix::C,
+}
+
+impl<Pix: PixFmt> StyleTable<Pix>
+where
+    Pix::C: RgbArgs,
+{
+    pub fn new() -> Self {
+        StyleTable {
+            styles: Vec::new(),
+            none: Pix::C::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.styles.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.styles.clear();
+    }
+
+    // Each add_*() returns the style index of the new fill
+    pub fn add_solid(&mut self, c: Pix::C) -> u32 {
+        self.push(c, Fill::Solid)
+    }
+
+    // colors is the lookup table the distance from d1 to d2 is spread
+    // over, 256 entries are the usual choice
+    pub fn add_gradient(
+        &mut self, shape: GradientShape, mtx: &TransAffine, d1: f64, d2: f64, colors: &[Pix::C],
+    ) -> u32 {
+        let mut inv = *mtx;
+        inv.invert();
+        let fill = Fill::Gradient(GradientFill {
+            shape: shape,
+            colors: colors.to_vec(),
+            ip: SpanIpLinear::new(inv),
+            d1: d1,
+            d2: d2,
+        });
+        self.push(Pix::C::new(), fill)
+    }
+
+    pub fn add_image(&mut self, pix: Pix, mtx: &TransAffine, filter: ImageFilterKind) -> u32 {
+        self.push_image(pix, Vec::new(), mtx, filter)
+    }
+
+    // An image fill that takes over the image
+    pub fn add_image_buffer(
+        &mut self, mut img: ImageBuffer<Pix>, mtx: &TransAffine, filter: ImageFilterKind,
+    ) -> u32
+    where
+        Pix: PixFmtOwned,
+    {
+        // The pixel format points into the heap storage of the image, which
+        // stays in place in the fill and is dropped along with it
+        let pix = unsafe { img.pixfmt_detached() };
+        self.push_image(pix, img.into_vec(), mtx, filter)
+    }
+
+    fn push_image(
+        &mut self, pix: Pix, data: Vec<u8>, mtx: &TransAffine, filter: ImageFilterKind,
+    ) -> u32 {
+        let mut inv = *mtx;
+        inv.invert();
+        let fill = Fill::Image(ImageFill {
+            src: ImageAccessorClip::new(pix, &Pix::C::new()),
+            ip: SpanIpLinear::new(inv),
+            filter: filter,
+            lut: filter.lut(),
+            _data: data,
+        });
+        self.push(Pix::C::new(), fill)
+    }
+
+    // Replaces the fill of a style by a solid color
+    pub fn set_solid(&mut self, style: u32, c: Pix::C) {
+        let s = &mut self.styles[style as usize];
+        s.color = c;
+        s.fill = Fill::Solid;
+    }
+
+    fn push(&mut self, color: Pix::C, fill: Fill<Pix>) -> u32 {
+        self.styles.push(Style {
+            color: color,
+            fill: fill,
+        });
+        self.styles.len() as u32 - 1
+    }
+}
+
+impl<Pix: PixFmt> RasterStyle<Pix::C> for StyleTable<Pix>
+where
+    Pix::C: RgbArgs,
+{
+    fn is_solid(&self, style: u32) -> bool {
+        match self.styles.get(style as usize) {
+            Some(s) => matches!(s.fill, Fill::Solid),
+            None => true,
+        }
+    }
+
+    // Styles out of range are transparent
+    fn color(&self, style: u32) -> &Pix::C {
+        match self.styles.get(style as usize) {
+            Some(s) => &s.color,
+            None => &self.none,
+        }
+    }
+
+    fn generate_span(&mut self, span: &mut [Pix::C], x: i32, y: i32, len: u32, style: u32) {
+        let s = match self.styles.get_mut(style as usize) {
+            Some(s) => s,
+            None => return,
+        };
+        match &mut s.fill {
+            Fill::Solid => span[..len as usize].fill(s.color),
+            Fill::Gradient(g) => match g.shape {
+                GradientShape::Linear => gradient_span(g, GradientX, span, x, y, len),
+                GradientShape::Radial => gradient_span(g, GradientRadial, span, x, y, len),
+            },
+            Fill::Image(img) => image_span(img, span, x, y, len),
+        }
+    }
+}
+
+fn gradient_span<C: Color, GF: GradientFunc>(
+    g: &mut GradientFill<C>, mut gf: GF, span: &mut [C], x: i32, y: i32, len: u32,
+) {
+    let mut sg = SpanGradient::new(&mut g.ip, &mut gf, &mut g.colors, g.d1, g.d2);
+    sg.generate(span, x, y, len);
+}
+
+// The filter LUT moves into the span generator and back
+fn image_span<Pix: PixFmt>(img: &mut ImageFill<Pix>, span: &mut [Pix::C], x: i32, y: i32, len: u32)
+where
+    Pix::C: RgbArgs,
+{
+    let (src, ip) = (&mut img.src, &mut img.ip);
+    match img.filter {
+        ImageFilterKind::NoFilter => {
+            SpanImageFilterRgbaNn::new(src, ip).generate(span, x, y, len);
+        }
+        ImageFilterKind::Bilinear => {
+            SpanImageFilterRgbaBilinear::new(src, ip).generate(span, x, y, len);
+        }
+        _ => {
+            let lut = std::mem::replace(&mut img.lut, ImageFilterLut::new());
+            if lut.diameter() == 2 {
+                let mut sg = SpanImageFilterRgba2x2::new(src, ip, lut);
+                sg.generate(span, x, y, len);
+                img.lut = std::mem::replace(&mut sg.base_mut().filter, ImageFilterLut::new());
+            } else {
+                let mut sg = SpanImageFilterRgba::new(src, ip, lut);
+                sg.generate(span, x, y, len);
+                img.lut = std::mem::replace(&mut sg.base_mut().filter, ImageFilterLut::new());
+            }
+        }
+    }
+}
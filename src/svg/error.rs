@@ -0,0 +1,69 @@
+use std::fmt;
+
+//============================================================SvgErrorKind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SvgErrorKind {
+    UnexpectedEnd,
+    UnexpectedChar(char),
+    ExpectedMoveTo,
+    ExpectedNumber,
+    InvalidNumber,
+    InvalidFlag,
+    InvalidColor,
+    InvalidTransform,
+    InvalidValue,
+    MismatchedTag,
+    Io(std::io::ErrorKind),
+}
+
+impl fmt::Display for SvgErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SvgErrorKind::UnexpectedEnd => write!(f, "unexpected end of input"),
+            SvgErrorKind::UnexpectedChar(c) => write!(f, "unexpected character '{}'", c),
+            SvgErrorKind::ExpectedMoveTo => write!(f, "path data must start with a moveto"),
+            SvgErrorKind::ExpectedNumber => write!(f, "expected a number"),
+            SvgErrorKind::InvalidNumber => write!(f, "invalid number"),
+            SvgErrorKind::InvalidFlag => write!(f, "invalid arc flag, expected '0' or '1'"),
+            SvgErrorKind::InvalidColor => write!(f, "invalid color"),
+            SvgErrorKind::InvalidTransform => write!(f, "invalid transform"),
+            SvgErrorKind::InvalidValue => write!(f, "invalid attribute value"),
+            SvgErrorKind::MismatchedTag => write!(f, "mismatched end tag"),
+            SvgErrorKind::Io(e) => write!(f, "I/O error: {:?}", e),
+        }
+    }
+}
+
+//================================================================SvgError
+// Error produced by the SVG parsers. The position is the byte offset
+// into the parsed string at which the problem was detected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvgError {
+    kind: SvgErrorKind,
+    pos: usize,
+}
+
+impl SvgError {
+    pub fn new(kind: SvgErrorKind, pos: usize) -> Self {
+        SvgError {
+            kind: kind,
+            pos: pos,
+        }
+    }
+
+    pub fn kind(&self) -> &SvgErrorKind {
+        &self.kind
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+}
+
+impl fmt::Display for SvgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.kind, self.pos)
+    }
+}
+
+impl std::error::Error for SvgError {}
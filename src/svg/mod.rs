@@ -0,0 +1,9 @@
+pub mod error;
+pub mod parser;
+pub mod path_parser;
+pub mod path_renderer;
+
+pub use error::{SvgError, SvgErrorKind};
+pub use parser::{parse_color, parse_transform, Parser};
+pub use path_parser::{parse_path, parse_path_into, PathTokenizer};
+pub use path_renderer::{PathAttributes, PathRenderer};
@@ -0,0 +1,951 @@
+use crate::basics::deg2rad;
+use crate::color_rgba::Rgba8;
+use crate::math_stroke::{LineCap, LineJoin};
+use crate::svg::error::{SvgError, SvgErrorKind};
+use crate::svg::path_parser::PathTokenizer;
+use crate::svg::path_renderer::PathRenderer;
+use crate::trans_viewport::{AspectRatio, TransViewport};
+use crate::TransAffine;
+
+//==================================================================Parser
+// Parses a subset of SVG: <svg>, <g>, <path>, <rect>, <circle>,
+// <ellipse>, <line>, <polyline> and <polygon> with the fill, stroke,
+// opacity, fill-rule and transform attributes (also given via "style").
+// Paint servers, text, CSS and other elements are skipped together with
+// their content.
+pub struct Parser<'a> {
+    path: &'a mut PathRenderer,
+    title: String,
+    width: f64,
+    height: f64,
+    view_box: Option<[f64; 4]>,
+}
+
+#[derive(PartialEq, Eq)]
+enum Element {
+    Group,
+    Title,
+    Skip,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(path: &'a mut PathRenderer) -> Self {
+        Parser {
+            path: path,
+            title: String::new(),
+            width: 0.0,
+            height: 0.0,
+            view_box: None,
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        self.title.trim()
+    }
+
+    // Size of the document as given by the root element, or by its
+    // viewBox if width and height are missing.
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    pub fn view_box(&self) -> Option<[f64; 4]> {
+        self.view_box
+    }
+
+    pub fn parse(&mut self, fname: &str) -> Result<(), SvgError> {
+        match std::fs::read_to_string(fname) {
+            Ok(src) => self.parse_str(&src),
+            Err(e) => Err(SvgError::new(SvgErrorKind::Io(e.kind()), 0)),
+        }
+    }
+
+    pub fn parse_str(&mut self, src: &str) -> Result<(), SvgError> {
+        let mut reader = XmlReader::new(src);
+        let mut stack: Vec<(&str, Element)> = Vec::new();
+        let mut skip_depth = 0;
+        let mut root = true;
+
+        loop {
+            match reader.next()? {
+                XmlEvent::Start {
+                    name,
+                    attrs,
+                    empty,
+                    pos,
+                } => {
+                    if skip_depth > 0 {
+                        if !empty {
+                            skip_depth += 1;
+                        }
+                        continue;
+                    }
+                    let elem = self.start_element(name, &attrs, root, pos)?;
+                    root = false;
+                    if empty {
+                        self.end_element(elem);
+                    } else {
+                        if elem == Element::Skip {
+                            skip_depth = 1;
+                        }
+                        stack.push((name, elem));
+                    }
+                }
+                XmlEvent::End { name, pos } => {
+                    if skip_depth > 1 {
+                        skip_depth -= 1;
+                        continue;
+                    }
+                    match stack.pop() {
+                        Some((n, elem)) if n == name => {
+                            skip_depth = 0;
+                            self.end_element(elem);
+                        }
+                        _ => return Err(SvgError::new(SvgErrorKind::MismatchedTag, pos)),
+                    }
+                }
+                XmlEvent::Text(text) => {
+                    if skip_depth == 0 {
+                        if let Some((_, Element::Title)) = stack.last() {
+                            self.title.push_str(text);
+                        }
+                    }
+                }
+                XmlEvent::Eof => break,
+            }
+        }
+        if stack.len() > 0 {
+            return Err(SvgError::new(SvgErrorKind::UnexpectedEnd, src.len()));
+        }
+        Ok(())
+    }
+
+    fn start_element(
+        &mut self, name: &str, attrs: &[Attribute], root: bool, pos: usize,
+    ) -> Result<Element, SvgError> {
+        match name {
+            "svg" => {
+                self.path.push_attr();
+                if root {
+                    self.parse_svg_attr(attrs)?;
+                }
+                self.parse_attrs(attrs)?;
+                Ok(Element::Group)
+            }
+            "g" | "a" => {
+                self.path.push_attr();
+                self.parse_attrs(attrs)?;
+                Ok(Element::Group)
+            }
+            "title" => Ok(Element::Title),
+            "path" | "rect" | "circle" | "ellipse" | "line" | "polyline" | "polygon" => {
+                self.path.begin_path();
+                let res = self.parse_shape(name, attrs, pos);
+                self.path.end_path();
+                res?;
+                // Shapes cannot contain anything renderable
+                Ok(Element::Skip)
+            }
+            _ => Ok(Element::Skip),
+        }
+    }
+
+    fn end_element(&mut self, elem: Element) {
+        if elem == Element::Group {
+            self.path.pop_attr();
+        }
+    }
+
+    fn parse_svg_attr(&mut self, attrs: &[Attribute]) -> Result<(), SvgError> {
+        let mut aspect = (0.5, 0.5, AspectRatio::Meet);
+        let (mut width, mut height) = (None, None);
+        for a in attrs.iter() {
+            match a.name {
+                "width" => width = parse_length(&a.value, a.pos).ok(),
+                "height" => height = parse_length(&a.value, a.pos).ok(),
+                "viewBox" => {
+                    let v = parse_number_list(&a.value, a.pos)?;
+                    if v.len() != 4 {
+                        return Err(SvgError::new(SvgErrorKind::InvalidValue, a.pos));
+                    }
+                    self.view_box = Some([v[0], v[1], v[2], v[3]]);
+                }
+                "preserveAspectRatio" => aspect = parse_aspect_ratio(&a.value, a.pos)?,
+                _ => (),
+            }
+        }
+
+        match self.view_box {
+            Some(vb) => {
+                self.width = width.unwrap_or(vb[2]);
+                self.height = height.unwrap_or(vb[3]);
+                if vb[2] > 0.0 && vb[3] > 0.0 {
+                    let mut vp = TransViewport::new();
+                    vp.set_preserve_aspect_ratio(aspect.0, aspect.1, aspect.2);
+                    vp.set_world_viewport(vb[0], vb[1], vb[0] + vb[2], vb[1] + vb[3]);
+                    vp.set_device_viewport(0.0, 0.0, self.width, self.height);
+                    self.path.transform().premultiply(&vp.to_affine());
+                }
+            }
+            None => {
+                self.width = width.unwrap_or(0.0);
+                self.height = height.unwrap_or(0.0);
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_attrs(&mut self, attrs: &[Attribute]) -> Result<(), SvgError> {
+        for a in attrs.iter() {
+            self.parse_attr(a.name, &a.value, a.pos)?;
+        }
+        Ok(())
+    }
+
+    fn parse_shape(&mut self, name: &str, attrs: &[Attribute], pos: usize) -> Result<(), SvgError> {
+        let mut geom = [0.0; 6];
+        let mut points = None;
+        let mut d = None;
+        for a in attrs.iter() {
+            let idx = match (name, a.name) {
+                ("path", "d") => {
+                    d = Some(a);
+                    continue;
+                }
+                ("polyline", "points") | ("polygon", "points") => {
+                    points = Some(a);
+                    continue;
+                }
+                ("rect", "x") | ("circle", "cx") | ("ellipse", "cx") | ("line", "x1") => 0,
+                ("rect", "y") | ("circle", "cy") | ("ellipse", "cy") | ("line", "y1") => 1,
+                ("rect", "width") | ("circle", "r") | ("ellipse", "rx") | ("line", "x2") => 2,
+                ("rect", "height") | ("ellipse", "ry") | ("line", "y2") => 3,
+                ("rect", "rx") => 4,
+                ("rect", "ry") => 5,
+                _ => {
+                    self.parse_attr(a.name, &a.value, a.pos)?;
+                    continue;
+                }
+            };
+            geom[idx] = parse_length(&a.value, a.pos)?;
+            if idx == 4 && !has_attr(attrs, "ry") {
+                geom[5] = geom[4];
+            }
+            if idx == 5 && !has_attr(attrs, "rx") {
+                geom[4] = geom[5];
+            }
+        }
+
+        let [x, y, w, h, rx, ry] = geom;
+        let ps = self.path.storage_mut();
+        match name {
+            "path" => {
+                if let Some(a) = d {
+                    if let Err(e) = self.path.parse_path(&a.value) {
+                        return Err(SvgError::new(e.kind().clone(), a.pos + e.pos()));
+                    }
+                }
+            }
+            "rect" => {
+                if w > 0.0 && h > 0.0 {
+                    let rx = rx.max(0.0).min(w / 2.0);
+                    let ry = ry.max(0.0).min(h / 2.0);
+                    if rx > 0.0 && ry > 0.0 {
+                        ps.move_to(x + rx, y);
+                        ps.line_to(x + w - rx, y);
+                        ps.arc_to(rx, ry, 0.0, false, true, x + w, y + ry);
+                        ps.line_to(x + w, y + h - ry);
+                        ps.arc_to(rx, ry, 0.0, false, true, x + w - rx, y + h);
+                        ps.line_to(x + rx, y + h);
+                        ps.arc_to(rx, ry, 0.0, false, true, x, y + h - ry);
+                        ps.line_to(x, y + ry);
+                        ps.arc_to(rx, ry, 0.0, false, true, x + rx, y);
+                    } else {
+                        ps.move_to(x, y);
+                        ps.line_to(x + w, y);
+                        ps.line_to(x + w, y + h);
+                        ps.line_to(x, y + h);
+                    }
+                    ps.close_polygon(0);
+                }
+            }
+            "circle" | "ellipse" => {
+                let (rx, ry) = if name == "circle" { (w, w) } else { (w, h) };
+                if rx > 0.0 && ry > 0.0 {
+                    ps.move_to(x + rx, y);
+                    ps.arc_to(rx, ry, 0.0, false, true, x, y + ry);
+                    ps.arc_to(rx, ry, 0.0, false, true, x - rx, y);
+                    ps.arc_to(rx, ry, 0.0, false, true, x, y - ry);
+                    ps.arc_to(rx, ry, 0.0, false, true, x + rx, y);
+                    ps.close_polygon(0);
+                }
+            }
+            "line" => {
+                ps.move_to(x, y);
+                ps.line_to(w, h);
+            }
+            _ => {
+                // polyline, polygon
+                if let Some(a) = points {
+                    let v = parse_number_list(&a.value, a.pos)?;
+                    for (i, p) in v.chunks_exact(2).enumerate() {
+                        if i == 0 {
+                            ps.move_to(p[0], p[1]);
+                        } else {
+                            ps.line_to(p[0], p[1]);
+                        }
+                    }
+                    if name == "polygon" && v.len() >= 2 {
+                        ps.close_polygon(0);
+                    }
+                } else {
+                    return Err(SvgError::new(SvgErrorKind::InvalidValue, pos));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_attr(&mut self, name: &str, value: &str, pos: usize) -> Result<(), SvgError> {
+        let value = value.trim();
+        if value == "inherit" {
+            return Ok(());
+        }
+        match name {
+            "style" => self.parse_style(value, pos)?,
+            "fill" => match parse_paint(value, pos)? {
+                Some(c) => self.path.fill(c),
+                None => self.path.fill_none(),
+            },
+            "fill-opacity" => self.path.fill_opacity(parse_opacity(value, pos)?),
+            "fill-rule" => self.path.even_odd(value == "evenodd"),
+            "stroke" => match parse_paint(value, pos)? {
+                Some(c) => self.path.stroke(c),
+                None => self.path.stroke_none(),
+            },
+            "stroke-width" => self.path.stroke_width(parse_length(value, pos)?),
+            "stroke-opacity" => self.path.stroke_opacity(parse_opacity(value, pos)?),
+            "stroke-linecap" => match value {
+                "butt" => self.path.line_cap(LineCap::Butt),
+                "round" => self.path.line_cap(LineCap::Round),
+                "square" => self.path.line_cap(LineCap::Square),
+                _ => return Err(SvgError::new(SvgErrorKind::InvalidValue, pos)),
+            },
+            "stroke-linejoin" => match value {
+                "miter" => self.path.line_join(LineJoin::Miter),
+                "round" => self.path.line_join(LineJoin::Round),
+                "bevel" => self.path.line_join(LineJoin::Bevel),
+                _ => return Err(SvgError::new(SvgErrorKind::InvalidValue, pos)),
+            },
+            "stroke-miterlimit" => self.path.miter_limit(parse_number(value, pos)?),
+            "opacity" => self.path.opacity(parse_opacity(value, pos)?),
+            "transform" => {
+                let mtx = parse_transform(value, pos)?;
+                self.path.transform().premultiply(&mtx);
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn parse_style(&mut self, style: &str, pos: usize) -> Result<(), SvgError> {
+        let mut offset = 0;
+        for decl in style.split(';') {
+            if let Some(colon) = decl.find(':') {
+                let name = decl[..colon].trim();
+                let value = &decl[colon + 1..];
+                let value_pos = pos + offset + colon + 1 + (value.len() - value.trim_start().len());
+                self.parse_attr(name, value, value_pos)?;
+            }
+            offset += decl.len() + 1;
+        }
+        Ok(())
+    }
+}
+
+fn has_attr(attrs: &[Attribute], name: &str) -> bool {
+    attrs.iter().any(|a| a.name == name)
+}
+
+//------------------------------------------------------------parse_number
+fn parse_number(s: &str, pos: usize) -> Result<f64, SvgError> {
+    let mut tok = PathTokenizer::new(s);
+    let v = tok
+        .next_number()
+        .map_err(|e| SvgError::new(e.kind().clone(), pos + e.pos()))?;
+    if !tok.at_end() {
+        return Err(SvgError::new(SvgErrorKind::InvalidValue, pos + tok.pos()));
+    }
+    Ok(v)
+}
+
+fn parse_number_list(s: &str, pos: usize) -> Result<Vec<f64>, SvgError> {
+    let mut tok = PathTokenizer::new(s);
+    let mut v = Vec::new();
+    while !tok.at_end() {
+        v.push(
+            tok.next_number()
+                .map_err(|e| SvgError::new(e.kind().clone(), pos + e.pos()))?,
+        );
+    }
+    Ok(v)
+}
+
+// Lengths in user units, absolute units are converted at 96 dpi
+fn parse_length(s: &str, pos: usize) -> Result<f64, SvgError> {
+    let s = s.trim();
+    let units = [
+        ("px", 1.0),
+        ("pt", 96.0 / 72.0),
+        ("pc", 16.0),
+        ("mm", 96.0 / 25.4),
+        ("cm", 96.0 / 2.54),
+        ("in", 96.0),
+    ];
+    for (u, k) in units.iter() {
+        if s.ends_with(u) {
+            return Ok(parse_number(&s[..s.len() - u.len()], pos)? * k);
+        }
+    }
+    parse_number(s, pos)
+}
+
+fn parse_opacity(s: &str, pos: usize) -> Result<f64, SvgError> {
+    let s = s.trim();
+    let v = if s.ends_with('%') {
+        parse_number(&s[..s.len() - 1], pos)? / 100.0
+    } else {
+        parse_number(s, pos)?
+    };
+    Ok(v.max(0.0).min(1.0))
+}
+
+fn parse_aspect_ratio(s: &str, pos: usize) -> Result<(f64, f64, AspectRatio), SvgError> {
+    let mut words = s.split_whitespace();
+    let align = words.next().unwrap_or("xMidYMid");
+    let aspect = match words.next() {
+        Some("slice") => AspectRatio::Slice,
+        Some("meet") | None => AspectRatio::Meet,
+        _ => return Err(SvgError::new(SvgErrorKind::InvalidValue, pos)),
+    };
+    if align == "none" {
+        return Ok((0.5, 0.5, AspectRatio::Stretch));
+    }
+    let k = |a: &str| match a {
+        "Min" => Ok(0.0),
+        "Mid" => Ok(0.5),
+        "Max" => Ok(1.0),
+        _ => Err(SvgError::new(SvgErrorKind::InvalidValue, pos)),
+    };
+    match align.strip_prefix('x').and_then(|a| a.split_once('Y')) {
+        Some((x, y)) => Ok((k(x)?, k(y)?, aspect)),
+        None => Err(SvgError::new(SvgErrorKind::InvalidValue, pos)),
+    }
+}
+
+//-------------------------------------------------------------parse_paint
+// Returns None for "none". Paint servers ("url(#id)") are not supported
+// and treated as "none" as well.
+fn parse_paint(s: &str, pos: usize) -> Result<Option<Rgba8>, SvgError> {
+    if s == "none" || s.starts_with("url(") {
+        return Ok(None);
+    }
+    parse_color(s, pos).map(Some)
+}
+
+//-------------------------------------------------------------parse_color
+pub fn parse_color(s: &str, pos: usize) -> Result<Rgba8, SvgError> {
+    let s = s.trim();
+    let err = || SvgError::new(SvgErrorKind::InvalidColor, pos);
+
+    if let Some(hex) = s.strip_prefix('#') {
+        let v = u32::from_str_radix(hex, 16).map_err(|_| err())?;
+        return match hex.len() {
+            3 => Ok(Rgba8::new_params(
+                ((v >> 8) & 0xF) * 17,
+                ((v >> 4) & 0xF) * 17,
+                (v & 0xF) * 17,
+                255,
+            )),
+            6 => Ok(Rgba8::new_params(
+                (v >> 16) & 0xFF,
+                (v >> 8) & 0xFF,
+                v & 0xFF,
+                255,
+            )),
+            _ => Err(err()),
+        };
+    }
+
+    if let Some(args) = s.strip_prefix("rgb(") {
+        let args = args.strip_suffix(')').ok_or_else(err)?;
+        let mut c = [0u32; 3];
+        let mut n = 0;
+        for a in args.split(',') {
+            if n >= 3 {
+                return Err(err());
+            }
+            let a = a.trim();
+            let v = if a.ends_with('%') {
+                parse_number(&a[..a.len() - 1], pos)? * 255.0 / 100.0
+            } else {
+                parse_number(a, pos)?
+            };
+            c[n] = (v.max(0.0).min(255.0) + 0.5) as u32;
+            n += 1;
+        }
+        if n != 3 {
+            return Err(err());
+        }
+        return Ok(Rgba8::new_params(c[0], c[1], c[2], 255));
+    }
+
+    let name = s.to_ascii_lowercase();
+    match NAMED_COLORS.binary_search_by(|c| c.0.cmp(name.as_str())) {
+        Ok(i) => {
+            let (_, r, g, b, a) = NAMED_COLORS[i];
+            Ok(Rgba8::new_params(r, g, b, a))
+        }
+        Err(_) => Err(err()),
+    }
+}
+
+//---------------------------------------------------------parse_transform
+pub fn parse_transform(s: &str, pos: usize) -> Result<TransAffine, SvgError> {
+    let mut mtx = TransAffine::new_default();
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    loop {
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b',') {
+            i += 1;
+        }
+        if i >= bytes.len() {
+            break;
+        }
+        let start = i;
+        while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
+            i += 1;
+        }
+        let name = &s[start..i];
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i >= bytes.len() || bytes[i] != b'(' {
+            return Err(SvgError::new(SvgErrorKind::InvalidTransform, pos + start));
+        }
+        let args_start = i + 1;
+        let args_end = match s[args_start..].find(')') {
+            Some(e) => args_start + e,
+            None => return Err(SvgError::new(SvgErrorKind::UnexpectedEnd, pos + s.len())),
+        };
+        let a = parse_number_list(&s[args_start..args_end], pos + args_start)?;
+        let t = match (name, a.len()) {
+            ("matrix", 6) => TransAffine::new(a[0], a[1], a[2], a[3], a[4], a[5]),
+            ("translate", 1) => TransAffine::trans_affine_translation(a[0], 0.0),
+            ("translate", 2) => TransAffine::trans_affine_translation(a[0], a[1]),
+            ("rotate", 1) => TransAffine::trans_affine_rotation(deg2rad(a[0])),
+            ("rotate", 3) => {
+                let mut t = TransAffine::trans_affine_translation(-a[1], -a[2]);
+                t.multiply(&TransAffine::trans_affine_rotation(deg2rad(a[0])));
+                t.multiply(&TransAffine::trans_affine_translation(a[1], a[2]));
+                t
+            }
+            ("scale", 1) => TransAffine::trans_affine_scaling(a[0], a[0]),
+            ("scale", 2) => TransAffine::trans_affine_scaling(a[0], a[1]),
+            ("skewX", 1) => TransAffine::trans_affine_skewing(deg2rad(a[0]), 0.0),
+            ("skewY", 1) => TransAffine::trans_affine_skewing(0.0, deg2rad(a[0])),
+            _ => return Err(SvgError::new(SvgErrorKind::InvalidTransform, pos + start)),
+        };
+        // The rightmost transformation is applied first
+        mtx.premultiply(&t);
+        i = args_end + 1;
+    }
+    Ok(mtx)
+}
+
+//=================================================================XmlReader
+// Just enough of XML to read SVG files: elements, attributes, character
+// data and the predefined and numeric entities. Comments, processing
+// instructions and the document type declaration are skipped.
+struct Attribute<'s> {
+    name: &'s str,
+    value: String,
+    pos: usize,
+}
+
+enum XmlEvent<'s> {
+    Start {
+        name: &'s str,
+        attrs: Vec<Attribute<'s>>,
+        empty: bool,
+        pos: usize,
+    },
+    End {
+        name: &'s str,
+        pos: usize,
+    },
+    Text(&'s str),
+    Eof,
+}
+
+struct XmlReader<'s> {
+    src: &'s str,
+    pos: usize,
+}
+
+impl<'s> XmlReader<'s> {
+    fn new(src: &'s str) -> Self {
+        XmlReader { src: src, pos: 0 }
+    }
+
+    fn rest(&self) -> &'s str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn skip_past(&mut self, end: &str) -> Result<(), SvgError> {
+        match self.rest().find(end) {
+            Some(i) => {
+                self.pos += i + end.len();
+                Ok(())
+            }
+            None => Err(SvgError::new(SvgErrorKind::UnexpectedEnd, self.src.len())),
+        }
+    }
+
+    fn name(&mut self) -> Result<&'s str, SvgError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || c == '/' || c == '>' || c == '=')
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.unexpected());
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn unexpected(&self) -> SvgError {
+        match self.rest().chars().next() {
+            Some(c) => SvgError::new(SvgErrorKind::UnexpectedChar(c), self.pos),
+            None => SvgError::new(SvgErrorKind::UnexpectedEnd, self.pos),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SvgError> {
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn next(&mut self) -> Result<XmlEvent<'s>, SvgError> {
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Ok(XmlEvent::Eof);
+            }
+            if !rest.starts_with('<') {
+                let len = rest.find('<').unwrap_or(rest.len());
+                self.pos += len;
+                return Ok(XmlEvent::Text(&rest[..len]));
+            }
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.pos += 9;
+                let start = self.pos;
+                self.skip_past("]]>")?;
+                return Ok(XmlEvent::Text(&self.src[start..self.pos - 3]));
+            } else if rest.starts_with("<!") {
+                // <!DOCTYPE ...> with an optional internal subset
+                let mut depth = 0;
+                for (i, c) in rest.char_indices() {
+                    match c {
+                        '[' => depth += 1,
+                        ']' => depth -= 1,
+                        '>' if depth == 0 => {
+                            self.pos += i + 1;
+                            break;
+                        }
+                        _ => (),
+                    }
+                }
+                if self.rest().len() == rest.len() {
+                    return Err(SvgError::new(SvgErrorKind::UnexpectedEnd, self.src.len()));
+                }
+            } else if rest.starts_with("</") {
+                let pos = self.pos;
+                self.pos += 2;
+                let name = self.name()?;
+                self.skip_ws();
+                self.expect('>')?;
+                return Ok(XmlEvent::End {
+                    name: name,
+                    pos: pos,
+                });
+            } else {
+                return self.start_tag();
+            }
+        }
+    }
+
+    fn start_tag(&mut self) -> Result<XmlEvent<'s>, SvgError> {
+        let pos = self.pos;
+        self.pos += 1;
+        let name = self.name()?;
+        let mut attrs = Vec::new();
+        loop {
+            self.skip_ws();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Ok(XmlEvent::Start {
+                    name: name,
+                    attrs: attrs,
+                    empty: true,
+                    pos: pos,
+                });
+            }
+            if rest.starts_with('>') {
+                self.pos += 1;
+                return Ok(XmlEvent::Start {
+                    name: name,
+                    attrs: attrs,
+                    empty: false,
+                    pos: pos,
+                });
+            }
+            let attr_name = self.name()?;
+            self.skip_ws();
+            self.expect('=')?;
+            self.skip_ws();
+            let quote = match self.rest().chars().next() {
+                Some(q) if q == '"' || q == '\'' => q,
+                _ => return Err(self.unexpected()),
+            };
+            self.pos += 1;
+            let start = self.pos;
+            let len = match self.rest().find(quote) {
+                Some(len) => len,
+                None => return Err(SvgError::new(SvgErrorKind::UnexpectedEnd, self.src.len())),
+            };
+            self.pos += len + 1;
+            attrs.push(Attribute {
+                name: attr_name,
+                value: decode_entities(&self.src[start..start + len]),
+                pos: start,
+            });
+        }
+    }
+}
+
+fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+        let ent = &rest[1..end];
+        let c = match ent {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => {
+                if let Some(hex) = ent.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+                } else if let Some(dec) = ent.strip_prefix('#') {
+                    dec.parse::<u32>().ok().and_then(char::from_u32)
+                } else {
+                    None
+                }
+            }
+        };
+        match c {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+//------------------------------------------------------------NAMED_COLORS
+// Sorted by name for the binary search
+static NAMED_COLORS: [(&str, u32, u32, u32, u32); 148] = [
+    ("aliceblue", 240, 248, 255, 255),
+    ("antiquewhite", 250, 235, 215, 255),
+    ("aqua", 0, 255, 255, 255),
+    ("aquamarine", 127, 255, 212, 255),
+    ("azure", 240, 255, 255, 255),
+    ("beige", 245, 245, 220, 255),
+    ("bisque", 255, 228, 196, 255),
+    ("black", 0, 0, 0, 255),
+    ("blanchedalmond", 255, 235, 205, 255),
+    ("blue", 0, 0, 255, 255),
+    ("blueviolet", 138, 43, 226, 255),
+    ("brown", 165, 42, 42, 255),
+    ("burlywood", 222, 184, 135, 255),
+    ("cadetblue", 95, 158, 160, 255),
+    ("chartreuse", 127, 255, 0, 255),
+    ("chocolate", 210, 105, 30, 255),
+    ("coral", 255, 127, 80, 255),
+    ("cornflowerblue", 100, 149, 237, 255),
+    ("cornsilk", 255, 248, 220, 255),
+    ("crimson", 220, 20, 60, 255),
+    ("cyan", 0, 255, 255, 255),
+    ("darkblue", 0, 0, 139, 255),
+    ("darkcyan", 0, 139, 139, 255),
+    ("darkgoldenrod", 184, 134, 11, 255),
+    ("darkgray", 169, 169, 169, 255),
+    ("darkgreen", 0, 100, 0, 255),
+    ("darkgrey", 169, 169, 169, 255),
+    ("darkkhaki", 189, 183, 107, 255),
+    ("darkmagenta", 139, 0, 139, 255),
+    ("darkolivegreen", 85, 107, 47, 255),
+    ("darkorange", 255, 140, 0, 255),
+    ("darkorchid", 153, 50, 204, 255),
+    ("darkred", 139, 0, 0, 255),
+    ("darksalmon", 233, 150, 122, 255),
+    ("darkseagreen", 143, 188, 143, 255),
+    ("darkslateblue", 72, 61, 139, 255),
+    ("darkslategray", 47, 79, 79, 255),
+    ("darkslategrey", 47, 79, 79, 255),
+    ("darkturquoise", 0, 206, 209, 255),
+    ("darkviolet", 148, 0, 211, 255),
+    ("deeppink", 255, 20, 147, 255),
+    ("deepskyblue", 0, 191, 255, 255),
+    ("dimgray", 105, 105, 105, 255),
+    ("dimgrey", 105, 105, 105, 255),
+    ("dodgerblue", 30, 144, 255, 255),
+    ("firebrick", 178, 34, 34, 255),
+    ("floralwhite", 255, 250, 240, 255),
+    ("forestgreen", 34, 139, 34, 255),
+    ("fuchsia", 255, 0, 255, 255),
+    ("gainsboro", 220, 220, 220, 255),
+    ("ghostwhite", 248, 248, 255, 255),
+    ("gold", 255, 215, 0, 255),
+    ("goldenrod", 218, 165, 32, 255),
+    ("gray", 128, 128, 128, 255),
+    ("green", 0, 128, 0, 255),
+    ("greenyellow", 173, 255, 47, 255),
+    ("grey", 128, 128, 128, 255),
+    ("honeydew", 240, 255, 240, 255),
+    ("hotpink", 255, 105, 180, 255),
+    ("indianred", 205, 92, 92, 255),
+    ("indigo", 75, 0, 130, 255),
+    ("ivory", 255, 255, 240, 255),
+    ("khaki", 240, 230, 140, 255),
+    ("lavender", 230, 230, 250, 255),
+    ("lavenderblush", 255, 240, 245, 255),
+    ("lawngreen", 124, 252, 0, 255),
+    ("lemonchiffon", 255, 250, 205, 255),
+    ("lightblue", 173, 216, 230, 255),
+    ("lightcoral", 240, 128, 128, 255),
+    ("lightcyan", 224, 255, 255, 255),
+    ("lightgoldenrodyellow", 250, 250, 210, 255),
+    ("lightgray", 211, 211, 211, 255),
+    ("lightgreen", 144, 238, 144, 255),
+    ("lightgrey", 211, 211, 211, 255),
+    ("lightpink", 255, 182, 193, 255),
+    ("lightsalmon", 255, 160, 122, 255),
+    ("lightseagreen", 32, 178, 170, 255),
+    ("lightskyblue", 135, 206, 250, 255),
+    ("lightslategray", 119, 136, 153, 255),
+    ("lightslategrey", 119, 136, 153, 255),
+    ("lightsteelblue", 176, 196, 222, 255),
+    ("lightyellow", 255, 255, 224, 255),
+    ("lime", 0, 255, 0, 255),
+    ("limegreen", 50, 205, 50, 255),
+    ("linen", 250, 240, 230, 255),
+    ("magenta", 255, 0, 255, 255),
+    ("maroon", 128, 0, 0, 255),
+    ("mediumaquamarine", 102, 205, 170, 255),
+    ("mediumblue", 0, 0, 205, 255),
+    ("mediumorchid", 186, 85, 211, 255),
+    ("mediumpurple", 147, 112, 219, 255),
+    ("mediumseagreen", 60, 179, 113, 255),
+    ("mediumslateblue", 123, 104, 238, 255),
+    ("mediumspringgreen", 0, 250, 154, 255),
+    ("mediumturquoise", 72, 209, 204, 255),
+    ("mediumvioletred", 199, 21, 133, 255),
+    ("midnightblue", 25, 25, 112, 255),
+    ("mintcream", 245, 255, 250, 255),
+    ("mistyrose", 255, 228, 225, 255),
+    ("moccasin", 255, 228, 181, 255),
+    ("navajowhite", 255, 222, 173, 255),
+    ("navy", 0, 0, 128, 255),
+    ("oldlace", 253, 245, 230, 255),
+    ("olive", 128, 128, 0, 255),
+    ("olivedrab", 107, 142, 35, 255),
+    ("orange", 255, 165, 0, 255),
+    ("orangered", 255, 69, 0, 255),
+    ("orchid", 218, 112, 214, 255),
+    ("palegoldenrod", 238, 232, 170, 255),
+    ("palegreen", 152, 251, 152, 255),
+    ("paleturquoise", 175, 238, 238, 255),
+    ("palevioletred", 219, 112, 147, 255),
+    ("papayawhip", 255, 239, 213, 255),
+    ("peachpuff", 255, 218, 185, 255),
+    ("peru", 205, 133, 63, 255),
+    ("pink", 255, 192, 203, 255),
+    ("plum", 221, 160, 221, 255),
+    ("powderblue", 176, 224, 230, 255),
+    ("purple", 128, 0, 128, 255),
+    ("red", 255, 0, 0, 255),
+    ("rosybrown", 188, 143, 143, 255),
+    ("royalblue", 65, 105, 225, 255),
+    ("saddlebrown", 139, 69, 19, 255),
+    ("salmon", 250, 128, 114, 255),
+    ("sandybrown", 244, 164, 96, 255),
+    ("seagreen", 46, 139, 87, 255),
+    ("seashell", 255, 245, 238, 255),
+    ("sienna", 160, 82, 45, 255),
+    ("silver", 192, 192, 192, 255),
+    ("skyblue", 135, 206, 235, 255),
+    ("slateblue", 106, 90, 205, 255),
+    ("slategray", 112, 128, 144, 255),
+    ("slategrey", 112, 128, 144, 255),
+    ("snow", 255, 250, 250, 255),
+    ("springgreen", 0, 255, 127, 255),
+    ("steelblue", 70, 130, 180, 255),
+    ("tan", 210, 180, 140, 255),
+    ("teal", 0, 128, 128, 255),
+    ("thistle", 216, 191, 216, 255),
+    ("tomato", 255, 99, 71, 255),
+    ("transparent", 0, 0, 0, 0),
+    ("turquoise", 64, 224, 208, 255),
+    ("violet", 238, 130, 238, 255),
+    ("wheat", 245, 222, 179, 255),
+    ("white", 255, 255, 255, 255),
+    ("whitesmoke", 245, 245, 245, 255),
+    ("yellow", 255, 255, 0, 255),
+    ("yellowgreen", 154, 205, 50, 255),
+];
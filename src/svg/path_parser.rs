@@ -0,0 +1,345 @@
+use crate::basics::deg2rad;
+use crate::svg::error::{SvgError, SvgErrorKind};
+use crate::PathStorage;
+
+//===========================================================PathTokenizer
+// SVG path tokenizer. Splits the contents of the "d" attribute into
+// commands, numbers and arc flags. Numbers following a command without
+// a new command letter repeat the last command, an implicit repeat of
+// "M"/"m" becomes "L"/"l" as required by the SVG specification.
+pub struct PathTokenizer<'a> {
+    src: &'a str,
+    pos: usize,
+    last_cmd: u8,
+}
+
+impl<'a> PathTokenizer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        PathTokenizer {
+            src: src,
+            pos: 0,
+            last_cmd: 0,
+        }
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn last_command(&self) -> u8 {
+        self.last_cmd
+    }
+
+    pub fn at_end(&mut self) -> bool {
+        self.skip_separators();
+        self.pos >= self.src.len()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    fn error(&self, kind: SvgErrorKind, pos: usize) -> SvgError {
+        SvgError::new(kind, pos)
+    }
+
+    fn unexpected_char(&self, pos: usize) -> SvgError {
+        match self.src[pos..].chars().next() {
+            Some(c) => self.error(SvgErrorKind::UnexpectedChar(c), pos),
+            None => self.error(SvgErrorKind::UnexpectedEnd, pos),
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while let Some(c) = self.peek() {
+            if !is_separator(c) {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    // Returns the next command, either an explicit command letter or
+    // the implicit repetition of the previous one. None at the end of
+    // the path data.
+    pub fn next_command(&mut self) -> Result<Option<u8>, SvgError> {
+        self.skip_separators();
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        if is_command(c) {
+            self.pos += 1;
+            self.last_cmd = c;
+            return Ok(Some(c));
+        }
+        if !is_number_start(c) {
+            return Err(self.unexpected_char(self.pos));
+        }
+        match self.last_cmd {
+            0 => Err(self.error(SvgErrorKind::ExpectedMoveTo, self.pos)),
+            b'Z' | b'z' => Err(self.unexpected_char(self.pos)),
+            b'M' => {
+                self.last_cmd = b'L';
+                Ok(Some(b'L'))
+            }
+            b'm' => {
+                self.last_cmd = b'l';
+                Ok(Some(b'l'))
+            }
+            cmd => Ok(Some(cmd)),
+        }
+    }
+
+    pub fn next_number(&mut self) -> Result<f64, SvgError> {
+        self.skip_separators();
+        let bytes = self.src.as_bytes();
+        let start = self.pos;
+        let mut i = start;
+
+        if i >= bytes.len() {
+            return Err(self.error(SvgErrorKind::UnexpectedEnd, i));
+        }
+        if bytes[i] == b'+' || bytes[i] == b'-' {
+            i += 1;
+        }
+        let mut digits = 0;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+            digits += 1;
+        }
+        if i < bytes.len() && bytes[i] == b'.' {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+                digits += 1;
+            }
+        }
+        if digits == 0 {
+            if i >= bytes.len() {
+                return Err(self.error(SvgErrorKind::UnexpectedEnd, i));
+            }
+            return Err(self.error(SvgErrorKind::ExpectedNumber, start));
+        }
+        if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+            let e = i;
+            i += 1;
+            if i < bytes.len() && (bytes[i] == b'+' || bytes[i] == b'-') {
+                i += 1;
+            }
+            let exp_start = i;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            if i == exp_start {
+                return Err(self.error(SvgErrorKind::InvalidNumber, e));
+            }
+        }
+
+        match self.src[start..i].parse::<f64>() {
+            Ok(v) if v.is_finite() => {
+                self.pos = i;
+                Ok(v)
+            }
+            _ => Err(self.error(SvgErrorKind::InvalidNumber, start)),
+        }
+    }
+
+    // Arc flags are single characters and may be written without
+    // separators, e.g. "a10 10 0 01 20 20".
+    pub fn next_flag(&mut self) -> Result<bool, SvgError> {
+        self.skip_separators();
+        match self.peek() {
+            Some(b'0') => {
+                self.pos += 1;
+                Ok(false)
+            }
+            Some(b'1') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(_) => Err(self.error(SvgErrorKind::InvalidFlag, self.pos)),
+            None => Err(self.error(SvgErrorKind::UnexpectedEnd, self.pos)),
+        }
+    }
+}
+
+#[inline]
+fn is_separator(c: u8) -> bool {
+    c == b' ' || c == b',' || c == b'\t' || c == b'\n' || c == b'\r'
+}
+
+#[inline]
+fn is_command(c: u8) -> bool {
+    match c {
+        b'M' | b'm' | b'L' | b'l' | b'H' | b'h' | b'V' | b'v' | b'C' | b'c' | b'S' | b's'
+        | b'Q' | b'q' | b'T' | b't' | b'A' | b'a' | b'Z' | b'z' => true,
+        _ => false,
+    }
+}
+
+#[inline]
+fn is_number_start(c: u8) -> bool {
+    c.is_ascii_digit() || c == b'.' || c == b'+' || c == b'-'
+}
+
+//--------------------------------------------------------------parse_path
+// Parses SVG path data into a new PathStorage.
+pub fn parse_path(d: &str) -> Result<PathStorage, SvgError> {
+    let mut path = PathStorage::new();
+    parse_path_into(&mut path, d)?;
+    Ok(path)
+}
+
+//---------------------------------------------------------parse_path_into
+// Appends SVG path data to an existing path. The current point and the
+// reflected control points are tracked here rather than taken from the
+// storage, since after "Z" SVG continues from the start of the closed
+// subpath and smooth curves only reflect curves of the same kind.
+pub fn parse_path_into(path: &mut PathStorage, d: &str) -> Result<(), SvgError> {
+    let mut tok = PathTokenizer::new(d);
+
+    let (mut cx, mut cy) = (0.0, 0.0); // Current point
+    let (mut sx, mut sy) = (0.0, 0.0); // Start of the current subpath
+    let mut ctrl3: Option<(f64, f64)> = None; // Last control point of Q/T
+    let mut ctrl4: Option<(f64, f64)> = None; // Last control point of C/S
+    let mut need_move = false;
+    let mut first = true;
+
+    loop {
+        let cmd = match tok.next_command()? {
+            Some(cmd) => cmd,
+            None => break,
+        };
+        if first && cmd != b'M' && cmd != b'm' {
+            // Only an explicit command letter can get here
+            return Err(SvgError::new(SvgErrorKind::ExpectedMoveTo, tok.pos() - 1));
+        }
+        first = false;
+
+        let rel = cmd.is_ascii_lowercase();
+        let (ox, oy) = if rel { (cx, cy) } else { (0.0, 0.0) };
+
+        match cmd {
+            b'M' | b'm' | b'Z' | b'z' => (),
+            _ if need_move => path.move_to(sx, sy),
+            _ => (),
+        }
+        if cmd != b'Z' && cmd != b'z' {
+            need_move = false;
+        }
+
+        match cmd.to_ascii_uppercase() {
+            b'M' => {
+                let x = tok.next_number()? + ox;
+                let y = tok.next_number()? + oy;
+                path.move_to(x, y);
+                cx = x;
+                cy = y;
+                sx = x;
+                sy = y;
+                ctrl3 = None;
+                ctrl4 = None;
+            }
+            b'L' => {
+                let x = tok.next_number()? + ox;
+                let y = tok.next_number()? + oy;
+                path.line_to(x, y);
+                cx = x;
+                cy = y;
+                ctrl3 = None;
+                ctrl4 = None;
+            }
+            b'H' => {
+                let x = tok.next_number()? + ox;
+                path.line_to(x, cy);
+                cx = x;
+                ctrl3 = None;
+                ctrl4 = None;
+            }
+            b'V' => {
+                let y = tok.next_number()? + oy;
+                path.line_to(cx, y);
+                cy = y;
+                ctrl3 = None;
+                ctrl4 = None;
+            }
+            b'C' => {
+                let x1 = tok.next_number()? + ox;
+                let y1 = tok.next_number()? + oy;
+                let x2 = tok.next_number()? + ox;
+                let y2 = tok.next_number()? + oy;
+                let x = tok.next_number()? + ox;
+                let y = tok.next_number()? + oy;
+                path.curve4_ctrl(x1, y1, x2, y2, x, y);
+                cx = x;
+                cy = y;
+                ctrl3 = None;
+                ctrl4 = Some((x2, y2));
+            }
+            b'S' => {
+                let x2 = tok.next_number()? + ox;
+                let y2 = tok.next_number()? + oy;
+                let x = tok.next_number()? + ox;
+                let y = tok.next_number()? + oy;
+                let (x1, y1) = match ctrl4 {
+                    Some((px, py)) => (cx + cx - px, cy + cy - py),
+                    None => (cx, cy),
+                };
+                path.curve4_ctrl(x1, y1, x2, y2, x, y);
+                cx = x;
+                cy = y;
+                ctrl3 = None;
+                ctrl4 = Some((x2, y2));
+            }
+            b'Q' => {
+                let x1 = tok.next_number()? + ox;
+                let y1 = tok.next_number()? + oy;
+                let x = tok.next_number()? + ox;
+                let y = tok.next_number()? + oy;
+                path.curve3_ctrl(x1, y1, x, y);
+                cx = x;
+                cy = y;
+                ctrl3 = Some((x1, y1));
+                ctrl4 = None;
+            }
+            b'T' => {
+                let x = tok.next_number()? + ox;
+                let y = tok.next_number()? + oy;
+                let (x1, y1) = match ctrl3 {
+                    Some((px, py)) => (cx + cx - px, cy + cy - py),
+                    None => (cx, cy),
+                };
+                path.curve3_ctrl(x1, y1, x, y);
+                cx = x;
+                cy = y;
+                ctrl3 = Some((x1, y1));
+                ctrl4 = None;
+            }
+            b'A' => {
+                let rx = tok.next_number()?;
+                let ry = tok.next_number()?;
+                let angle = tok.next_number()?;
+                let large_arc_flag = tok.next_flag()?;
+                let sweep_flag = tok.next_flag()?;
+                let x = tok.next_number()? + ox;
+                let y = tok.next_number()? + oy;
+                path.arc_to(rx, ry, deg2rad(angle), large_arc_flag, sweep_flag, x, y);
+                cx = x;
+                cy = y;
+                ctrl3 = None;
+                ctrl4 = None;
+            }
+            _ => {
+                // Z/z
+                path.close_polygon(0);
+                cx = sx;
+                cy = sy;
+                ctrl3 = None;
+                ctrl4 = None;
+                need_move = true;
+            }
+        }
+    }
+    Ok(())
+}
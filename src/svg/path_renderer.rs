@@ -0,0 +1,373 @@
+use crate::basics::{FillingRule, RectD};
+use crate::bounding_rect::bounding_rect_single;
+use crate::color_rgba::{Rgba, Rgba8};
+use crate::math_stroke::{InnerJoin, LineCap, LineJoin};
+use crate::renderer_scanline::render_scanlines_aa_solid;
+use crate::svg::error::SvgError;
+use crate::svg::path_parser::parse_path_into;
+use crate::{
+    Color, ConvCurve, ConvStroke, ConvTransform, PathStorage, RasClip, RasterScanLine,
+    RasterizerScanlineAa, Renderer, Scanline, TransAffine,
+};
+
+//==========================================================PathAttributes
+#[derive(Clone, Copy)]
+pub struct PathAttributes {
+    pub index: u32,
+    pub fill_color: Rgba8,
+    pub stroke_color: Rgba8,
+    pub fill_flag: bool,
+    pub stroke_flag: bool,
+    pub even_odd_flag: bool,
+    pub line_join: LineJoin,
+    pub line_cap: LineCap,
+    pub miter_limit: f64,
+    pub stroke_width: f64,
+    pub fill_opacity: f64,
+    pub stroke_opacity: f64,
+    pub opacity: f64,
+    pub transform: TransAffine,
+}
+
+impl PathAttributes {
+    pub fn new() -> Self {
+        PathAttributes {
+            index: 0,
+            fill_color: Rgba8::new_params(0, 0, 0, 255),
+            stroke_color: Rgba8::new_params(0, 0, 0, 255),
+            fill_flag: true,
+            stroke_flag: false,
+            even_odd_flag: false,
+            line_join: LineJoin::Miter,
+            line_cap: LineCap::Butt,
+            miter_limit: 4.0,
+            stroke_width: 1.0,
+            fill_opacity: 1.0,
+            stroke_opacity: 1.0,
+            opacity: 1.0,
+            transform: TransAffine::new_default(),
+        }
+    }
+
+    pub fn new_with_index(attr: &PathAttributes, idx: u32) -> Self {
+        let mut a = *attr;
+        a.index = idx;
+        a
+    }
+}
+
+//============================================================PathRenderer
+// Keeps all the paths of an SVG document in a single PathStorage and
+// a list of attributes, one per path, referring to the storage by the
+// path index. While the document is being built the attributes of the
+// nested elements are kept on a stack: every element pushes a copy of
+// its parent's attributes and modifies it.
+//
+// Note that "opacity" is applied to the fill and stroke colors of every
+// path of a group rather than to the group composited as a whole.
+pub struct PathRenderer {
+    storage: PathStorage,
+    attr_storage: Vec<PathAttributes>,
+    attr_stack: Vec<PathAttributes>,
+}
+
+impl PathRenderer {
+    pub fn new() -> Self {
+        PathRenderer {
+            storage: PathStorage::new(),
+            attr_storage: Vec::new(),
+            attr_stack: Vec::new(),
+        }
+    }
+
+    pub fn remove_all(&mut self) {
+        self.storage.remove_all();
+        self.attr_storage.clear();
+        self.attr_stack.clear();
+    }
+
+    pub fn storage(&self) -> &PathStorage {
+        &self.storage
+    }
+
+    pub fn storage_mut(&mut self) -> &mut PathStorage {
+        &mut self.storage
+    }
+
+    pub fn attributes(&self) -> &[PathAttributes] {
+        &self.attr_storage
+    }
+
+    // Use these functions as follows:
+    // begin_path() when the XML tag <path> comes ("start_element" handler)
+    // parse_path() on "d=" tag attribute
+    // end_path() when parsing of the entire tag is done.
+    pub fn begin_path(&mut self) {
+        self.push_attr();
+        let idx = self.storage.start_new_path();
+        let attr = PathAttributes::new_with_index(self.cur_attr(), idx);
+        self.attr_storage.push(attr);
+    }
+
+    pub fn end_path(&mut self) {
+        if let Some(last) = self.attr_storage.last() {
+            let idx = last.index;
+            let attr = PathAttributes::new_with_index(self.cur_attr(), idx);
+            let len = self.attr_storage.len();
+            self.attr_storage[len - 1] = attr;
+            self.pop_attr();
+        }
+    }
+
+    pub fn parse_path(&mut self, d: &str) -> Result<(), SvgError> {
+        parse_path_into(&mut self.storage, d)
+    }
+
+    // The following functions are essentially a "reflection" of
+    // the respective SVG path commands.
+    pub fn move_to(&mut self, x: f64, y: f64, rel: bool) {
+        if rel {
+            self.storage.move_rel(x, y);
+        } else {
+            self.storage.move_to(x, y);
+        }
+    }
+
+    pub fn line_to(&mut self, x: f64, y: f64, rel: bool) {
+        if rel {
+            self.storage.line_rel(x, y);
+        } else {
+            self.storage.line_to(x, y);
+        }
+    }
+
+    pub fn hline_to(&mut self, x: f64, rel: bool) {
+        if rel {
+            self.storage.hline_rel(x);
+        } else {
+            self.storage.hline_to(x);
+        }
+    }
+
+    pub fn vline_to(&mut self, y: f64, rel: bool) {
+        if rel {
+            self.storage.vline_rel(y);
+        } else {
+            self.storage.vline_to(y);
+        }
+    }
+
+    pub fn arc_to(
+        &mut self, rx: f64, ry: f64, angle: f64, large_arc_flag: bool, sweep_flag: bool, x: f64,
+        y: f64, rel: bool,
+    ) {
+        if rel {
+            self.storage
+                .arc_rel(rx, ry, angle, large_arc_flag, sweep_flag, x, y);
+        } else {
+            self.storage
+                .arc_to(rx, ry, angle, large_arc_flag, sweep_flag, x, y);
+        }
+    }
+
+    pub fn close_subpath(&mut self) {
+        self.storage.end_poly(crate::basics::PathFlag::Close as u32);
+    }
+
+    // Attribute stack
+    pub fn push_attr(&mut self) {
+        let attr = match self.attr_stack.last() {
+            Some(a) => *a,
+            None => PathAttributes::new(),
+        };
+        self.attr_stack.push(attr);
+    }
+
+    pub fn pop_attr(&mut self) {
+        if self.attr_stack.pop().is_none() {
+            panic!("pop_attr: attribute stack is empty");
+        }
+    }
+
+    pub fn cur_attr(&self) -> &PathAttributes {
+        self.attr_stack
+            .last()
+            .expect("cur_attr: attribute stack is empty")
+    }
+
+    pub fn cur_attr_mut(&mut self) -> &mut PathAttributes {
+        self.attr_stack
+            .last_mut()
+            .expect("cur_attr: attribute stack is empty")
+    }
+
+    pub fn fill(&mut self, f: Rgba8) {
+        let attr = self.cur_attr_mut();
+        attr.fill_color = f;
+        attr.fill_flag = true;
+    }
+
+    pub fn stroke(&mut self, s: Rgba8) {
+        let attr = self.cur_attr_mut();
+        attr.stroke_color = s;
+        attr.stroke_flag = true;
+    }
+
+    pub fn even_odd(&mut self, flag: bool) {
+        self.cur_attr_mut().even_odd_flag = flag;
+    }
+
+    pub fn stroke_width(&mut self, w: f64) {
+        self.cur_attr_mut().stroke_width = w;
+    }
+
+    pub fn fill_none(&mut self) {
+        self.cur_attr_mut().fill_flag = false;
+    }
+
+    pub fn stroke_none(&mut self) {
+        self.cur_attr_mut().stroke_flag = false;
+    }
+
+    pub fn fill_opacity(&mut self, op: f64) {
+        self.cur_attr_mut().fill_opacity = op;
+    }
+
+    pub fn stroke_opacity(&mut self, op: f64) {
+        self.cur_attr_mut().stroke_opacity = op;
+    }
+
+    // Unlike fill and stroke opacity, the opacity of nested elements
+    // accumulates.
+    pub fn opacity(&mut self, op: f64) {
+        self.cur_attr_mut().opacity *= op;
+    }
+
+    pub fn line_join(&mut self, join: LineJoin) {
+        self.cur_attr_mut().line_join = join;
+    }
+
+    pub fn line_cap(&mut self, cap: LineCap) {
+        self.cur_attr_mut().line_cap = cap;
+    }
+
+    pub fn miter_limit(&mut self, ml: f64) {
+        self.cur_attr_mut().miter_limit = ml;
+    }
+
+    pub fn transform(&mut self) -> &mut TransAffine {
+        &mut self.cur_attr_mut().transform
+    }
+
+    // Make all polygons CCW-oriented
+    pub fn arrange_orientations(&mut self) {
+        self.storage
+            .arrange_orientations_all_paths(crate::basics::PathFlag::Ccw);
+    }
+
+    pub fn bounding_rect(
+        &mut self, x1: &mut f64, y1: &mut f64, x2: &mut f64, y2: &mut f64,
+    ) -> bool {
+        let mut rect: Option<RectD> = None;
+        for attr in self.attr_storage.iter() {
+            let mut curved: ConvCurve<_> = ConvCurve::new_borrowed(&mut self.storage);
+            let mut trans = ConvTransform::new_borrowed(&mut curved, attr.transform);
+            let (mut bx1, mut by1, mut bx2, mut by2) = (0.0, 0.0, 0.0, 0.0);
+            if bounding_rect_single(
+                &mut trans, attr.index, &mut bx1, &mut by1, &mut bx2, &mut by2,
+            ) {
+                rect = Some(match rect {
+                    Some(r) => {
+                        RectD::new(r.x1.min(bx1), r.y1.min(by1), r.x2.max(bx2), r.y2.max(by2))
+                    }
+                    None => RectD::new(bx1, by1, bx2, by2),
+                });
+            }
+        }
+        match rect {
+            Some(r) => {
+                *x1 = r.x1;
+                *y1 = r.y1;
+                *x2 = r.x2;
+                *y2 = r.y2;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Renders all the paths, applying mtx on top of the transformations
+    // of the document.
+    pub fn render<Clip: RasClip, Sl: Scanline, Ren: Renderer>(
+        &mut self, ras: &mut RasterizerScanlineAa<Clip>, sl: &mut Sl, ren: &mut Ren,
+        mtx: &TransAffine, opacity: f64,
+    ) {
+        let cb = *ren.bounding_clip_box();
+        ras.clip_box(
+            cb.x1 as f64,
+            cb.y1 as f64,
+            cb.x2 as f64 + 1.0,
+            cb.y2 as f64 + 1.0,
+        );
+
+        for attr in self.attr_storage.iter() {
+            let mut transform = attr.transform;
+            transform.multiply(mtx);
+            let scl = transform.scale();
+
+            let mut curved: ConvCurve<_> = ConvCurve::new_borrowed(&mut self.storage);
+            curved.set_approximation_scale(scl);
+            curved.set_angle_tolerance(0.0);
+
+            if attr.fill_flag {
+                ras.reset();
+                ras.set_filling_rule(if attr.even_odd_flag {
+                    FillingRule::FillEvenOdd
+                } else {
+                    FillingRule::FillNonZero
+                });
+                let mut trans = ConvTransform::new_borrowed(&mut curved, transform);
+                ras.add_path(&mut trans, attr.index);
+                let color = color_with_opacity::<Ren::C>(
+                    &attr.fill_color,
+                    attr.fill_opacity * attr.opacity * opacity,
+                );
+                render_scanlines_aa_solid(ras, sl, ren, &color);
+            }
+
+            if attr.stroke_flag {
+                // If the *visual* line width is considerable we
+                // turn on processing of curve cusps.
+                if attr.stroke_width * scl > 1.0 {
+                    curved.set_angle_tolerance(0.2);
+                }
+                let mut stroke = ConvStroke::<_>::new_borrowed(&mut curved);
+                stroke.set_width(attr.stroke_width);
+                stroke.set_line_join(attr.line_join);
+                stroke.set_line_cap(attr.line_cap);
+                stroke.set_miter_limit(attr.miter_limit);
+                stroke.set_inner_join(InnerJoin::Round);
+                stroke.set_approximation_scale(scl);
+
+                ras.reset();
+                ras.set_filling_rule(FillingRule::FillNonZero);
+                let mut trans = ConvTransform::new_borrowed(&mut stroke, transform);
+                ras.add_path(&mut trans, attr.index);
+                let color = color_with_opacity::<Ren::C>(
+                    &attr.stroke_color,
+                    attr.stroke_opacity * attr.opacity * opacity,
+                );
+                render_scanlines_aa_solid(ras, sl, ren, &color);
+            }
+        }
+    }
+}
+
+fn color_with_opacity<C: Color>(c: &Rgba8, opacity: f64) -> C {
+    C::new_from_rgba(&Rgba::new_params(
+        c.r as f64 / 255.0,
+        c.g as f64 / 255.0,
+        c.b as f64 / 255.0,
+        c.a as f64 / 255.0 * opacity.max(0.0).min(1.0),
+    ))
+}
@@ -0,0 +1,285 @@
+use crate::font_cache_manager::FontCacheManager;
+use crate::FontEngine;
+
+//==============================================================TextAlign
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+//============================================================LayoutGlyph
+// A character and the pen position of its origin on the base line, as
+// the adaptors of FontCacheManager take it
+#[derive(Clone, Copy, Debug)]
+pub struct LayoutGlyph {
+    pub code: u32,
+    pub x: f64,
+    pub y: f64,
+}
+
+//=============================================================LayoutLine
+// One line of the layout: the glyphs first..first + len, the pen position
+// of its start on the base line and its width without trailing spaces
+#[derive(Clone, Copy, Debug)]
+pub struct LayoutLine {
+    pub first: usize,
+    pub len: usize,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+}
+
+// A glyph before it's placed on a line
+struct Item {
+    code: u32,
+    advance: f64,
+    kerning: f64,
+    space: bool,
+}
+
+//=============================================================TextLayout
+// Places the characters of a UTF-8 string with the glyphs of a font
+// engine: kerning, lines broken at '\n', word wrapping to a width and
+// alignment within it. Lines are line_spacing times ascender minus
+// descender apart and go down the screen if the engine flips y, up
+// otherwise. The text is laid out horizontally, so the engine shouldn't
+// be rotated; transform the glyph outlines instead.
+//
+// The result is a list of glyphs with their positions, grouped into
+// lines, to be rendered with the adaptors of the font cache:
+//
+//   let mut layout = TextLayout::new();
+//   layout.set_width(400.);
+//   layout.set_align(TextAlign::Justify);
+//   layout.layout(&mut fman, text, 10., 10.);
+//   for g in layout.glyphs() {
+//       let glyph = fman.glyph(g.code);
+//       // init the adaptor for (*glyph).data_type at g.x, g.y and render
+//   }
+pub struct TextLayout {
+    width: f64,
+    align: TextAlign,
+    line_spacing: f64,
+    kerning: bool,
+    glyphs: Vec<LayoutGlyph>,
+    lines: Vec<LayoutLine>,
+    line_height: f64,
+}
+
+impl TextLayout {
+    pub fn new() -> Self {
+        TextLayout {
+            width: 0.,
+            align: TextAlign::Left,
+            line_spacing: 1.,
+            kerning: true,
+            glyphs: Vec::new(),
+            lines: Vec::new(),
+            line_height: 0.,
+        }
+    }
+
+    // Width to wrap the lines to, 0 for no wrapping. Without it the
+    // alignment is relative to the longest line.
+    pub fn set_width(&mut self, w: f64) {
+        self.width = w;
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn set_align(&mut self, align: TextAlign) {
+        self.align = align;
+    }
+
+    pub fn align(&self) -> TextAlign {
+        self.align
+    }
+
+    pub fn set_line_spacing(&mut self, s: f64) {
+        self.line_spacing = s;
+    }
+
+    pub fn line_spacing(&self) -> f64 {
+        self.line_spacing
+    }
+
+    pub fn set_kerning(&mut self, k: bool) {
+        self.kerning = k;
+    }
+
+    pub fn kerning(&self) -> bool {
+        self.kerning
+    }
+
+    pub fn glyphs(&self) -> &[LayoutGlyph] {
+        &self.glyphs
+    }
+
+    pub fn lines(&self) -> &[LayoutLine] {
+        &self.lines
+    }
+
+    // The glyphs of a line
+    pub fn line_glyphs(&self, i: usize) -> &[LayoutGlyph] {
+        let l = &self.lines[i];
+        &self.glyphs[l.first..l.first + l.len]
+    }
+
+    // Width of the longest line
+    pub fn text_width(&self) -> f64 {
+        self.lines.iter().fold(0., |w, l| l.width.max(w))
+    }
+
+    // Distance from the top of the first line to the bottom of the last one
+    pub fn text_height(&self) -> f64 {
+        self.lines.len() as f64 * self.line_height
+    }
+
+    // Lays the text out with the current font of the cache, the top left
+    // corner of the first line at (x, y). Characters without a glyph are
+    // left out, tabs are taken as spaces.
+    pub fn layout<F: FontEngine>(
+        &mut self, fman: &mut FontCacheManager<F>, text: &str, x: f64, y: f64,
+    ) {
+        self.glyphs.clear();
+        self.lines.clear();
+        let (ascender, descender, flip_y) = {
+            let e = fman.engine();
+            (e.ascender(), e.descender(), e.flip_y())
+        };
+        self.line_height = (ascender - descender) * self.line_spacing;
+        let dir = if flip_y { 1. } else { -1. };
+
+        // Lines as ranges of items, ending a paragraph or not
+        let mut items = Vec::new();
+        let mut ranges = Vec::new();
+        for para in text.split('\n') {
+            let para = para.strip_suffix('\r').unwrap_or(para);
+            let start = items.len();
+            fman.reset_last_glyph();
+            for c in para.chars() {
+                let code = if c == '\t' { ' ' as u32 } else { c as u32 };
+                let glyph = fman.glyph(code);
+                if glyph.is_null() {
+                    continue;
+                }
+                let (mut kx, mut ky) = (0., 0.);
+                if self.kerning && items.len() > start {
+                    fman.add_kerning(&mut kx, &mut ky);
+                }
+                items.push(Item {
+                    code: code,
+                    advance: unsafe { (*glyph).advance_x },
+                    kerning: kx,
+                    space: c.is_whitespace(),
+                });
+            }
+            self.wrap(&items, start, &mut ranges);
+        }
+
+        let box_width = if self.width > 0. {
+            self.width
+        } else {
+            ranges
+                .iter()
+                .fold(0., |w, &(s, e, _)| line_width(&items, s, e).max(w))
+        };
+        let mut base_y = y + dir * ascender;
+        for &(start, end, last) in ranges.iter() {
+            let width = line_width(&items, start, end);
+            let spaces = items[start..end].iter().filter(|it| it.space).count();
+            let mut extra = 0.;
+            let mut pen_x = x;
+            match self.align {
+                TextAlign::Left => {}
+                TextAlign::Center => pen_x += (box_width - width) * 0.5,
+                TextAlign::Right => pen_x += box_width - width,
+                TextAlign::Justify => {
+                    if !last && spaces > 0 && box_width > width {
+                        extra = (box_width - width) / spaces as f64;
+                    }
+                }
+            }
+            self.lines.push(LayoutLine {
+                first: self.glyphs.len(),
+                len: end - start,
+                x: pen_x,
+                y: base_y,
+                width: width + extra * spaces as f64,
+            });
+            for i in start..end {
+                let it = &items[i];
+                if i > start {
+                    pen_x += it.kerning;
+                }
+                self.glyphs.push(LayoutGlyph {
+                    code: it.code,
+                    x: pen_x,
+                    y: base_y,
+                });
+                pen_x += it.advance;
+                if it.space {
+                    pen_x += extra;
+                }
+            }
+            base_y += dir * self.line_height;
+        }
+    }
+
+    // Breaks the items of a paragraph from start on into lines, at spaces
+    // if possible. The spaces around the breaks are dropped.
+    fn wrap(&self, items: &[Item], start: usize, ranges: &mut Vec<(usize, usize, bool)>) {
+        let n = items.len();
+        if start == n {
+            ranges.push((start, start, true));
+            return;
+        }
+        let mut first = start;
+        while first < n {
+            let mut pen = 0.;
+            let mut brk = None;
+            let mut i = first;
+            while i < n {
+                let k = if i > first { items[i].kerning } else { 0. };
+                let right = pen + k + items[i].advance;
+                if self.width > 0. && right > self.width && !items[i].space && i > first {
+                    break;
+                }
+                pen = right;
+                if items[i].space {
+                    brk = Some(i);
+                }
+                i += 1;
+            }
+            let (mut end, mut next) = match brk {
+                _ if i == n => (n, n),
+                Some(b) if b > first => (b, b + 1),
+                _ => (i, i),
+            };
+            while end > first && items[end - 1].space {
+                end -= 1;
+            }
+            while next < n && items[next].space {
+                next += 1;
+            }
+            ranges.push((first, end, next == n));
+            first = next;
+        }
+    }
+}
+
+fn line_width(items: &[Item], start: usize, end: usize) -> f64 {
+    let mut w = 0.;
+    for i in start..end {
+        if i > start {
+            w += items[i].kerning;
+        }
+        w += items[i].advance;
+    }
+    w
+}
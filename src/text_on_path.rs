@@ -0,0 +1,348 @@
+use crate::basics::{is_stop, is_vertex};
+use crate::conv_curve::ConvCurve;
+use crate::conv_segmentator::ConvSegmentator;
+use crate::font_cache_manager::{FontCacheManager, GlyphDataType};
+use crate::gsv_text::GsvText;
+use crate::path_storage::PathStorage;
+use crate::text_layout::TextAlign;
+use crate::trans_affine::TransAffine;
+use crate::trans_single_path::TransSinglePath;
+use crate::{FontEngine, GlyphPathAdaptor, Transformer, VertexSource};
+
+//=======================================================GlyphOrientation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GlyphOrientation {
+    // The outlines are bent along the path, as by TransSinglePath
+    Bend,
+    // Each glyph is rotated as a whole to the tangent at its center
+    Tangent,
+    // Each glyph stays upright, its center moved onto the path
+    Upright,
+}
+
+//==============================================================PathGlyph
+// A glyph placed on the path: the position its origin is moved to and
+// the angle it's rotated by. Upright glyphs have angle 0, so bitmap
+// glyphs can be rendered at (x, y) with the adaptors of the font cache.
+#[derive(Clone, Copy, Debug)]
+pub struct PathGlyph {
+    pub code: u32,
+    pub x: f64,
+    pub y: f64,
+    pub angle: f64,
+}
+
+// A glyph before it's placed
+struct Item {
+    code: u32,
+    advance: f64,
+    kerning: f64,
+}
+
+//=============================================================TextOnPath
+// Places a string along a path, with the glyphs of a font engine or of
+// GsvText, and keeps the transformed outlines as a vertex source.
+//
+// The text is aligned within the part of the path from the start offset
+// to its end; Justify widens the letter spacing to fill it. Glyphs whose
+// center is off the path are left out, so a negative start offset
+// scrolls the text in. The base line offset moves the glyphs off the
+// path along its normal, in the y direction of the glyphs: with flipped
+// glyphs a positive offset puts the text below a left to right path.
+//
+//   let mut top = TextOnPath::new();
+//   top.set_path(&mut curve, 0);
+//   top.set_align(TextAlign::Center);
+//   top.layout(&mut fman, "Anti-Grain Geometry");
+//   canvas.fill_path(&mut top, 0, &color);
+//
+// GsvText glyphs are strokes, so the result of layout_gsv() is to be
+// stroked rather than filled. The outlines are flattened, finely enough
+// for approximation_scale times the size they're laid out at.
+pub struct TextOnPath {
+    start: f64,
+    align: TextAlign,
+    letter_spacing: f64,
+    baseline_offset: f64,
+    orientation: GlyphOrientation,
+    kerning: bool,
+    approximation_scale: f64,
+    tpath: TransSinglePath,
+    glyphs: Vec<PathGlyph>,
+    path: PathStorage,
+}
+
+impl TextOnPath {
+    pub fn new() -> Self {
+        TextOnPath {
+            start: 0.,
+            align: TextAlign::Left,
+            letter_spacing: 0.,
+            baseline_offset: 0.,
+            orientation: GlyphOrientation::Bend,
+            kerning: true,
+            approximation_scale: 1.,
+            tpath: TransSinglePath::new(),
+            glyphs: Vec::new(),
+            path: PathStorage::new(),
+        }
+    }
+
+    // The path to put the text on, replacing the previous one
+    pub fn set_path<VS: VertexSource>(&mut self, vs: &mut VS, path_id: u32) {
+        self.tpath.reset();
+        self.tpath.add_path(vs, path_id);
+    }
+
+    // To set a base length or x scale preservation
+    pub fn trans_path_mut(&mut self) -> &mut TransSinglePath {
+        &mut self.tpath
+    }
+
+    pub fn trans_path(&self) -> &TransSinglePath {
+        &self.tpath
+    }
+
+    pub fn path_length(&self) -> f64 {
+        self.tpath.total_length()
+    }
+
+    pub fn set_start(&mut self, s: f64) {
+        self.start = s;
+    }
+
+    pub fn start(&self) -> f64 {
+        self.start
+    }
+
+    pub fn set_align(&mut self, align: TextAlign) {
+        self.align = align;
+    }
+
+    pub fn align(&self) -> TextAlign {
+        self.align
+    }
+
+    pub fn set_letter_spacing(&mut self, s: f64) {
+        self.letter_spacing = s;
+    }
+
+    pub fn letter_spacing(&self) -> f64 {
+        self.letter_spacing
+    }
+
+    pub fn set_baseline_offset(&mut self, d: f64) {
+        self.baseline_offset = d;
+    }
+
+    pub fn baseline_offset(&self) -> f64 {
+        self.baseline_offset
+    }
+
+    pub fn set_orientation(&mut self, o: GlyphOrientation) {
+        self.orientation = o;
+    }
+
+    pub fn orientation(&self) -> GlyphOrientation {
+        self.orientation
+    }
+
+    pub fn set_kerning(&mut self, k: bool) {
+        self.kerning = k;
+    }
+
+    pub fn kerning(&self) -> bool {
+        self.kerning
+    }
+
+    pub fn set_approximation_scale(&mut self, s: f64) {
+        self.approximation_scale = s;
+    }
+
+    pub fn approximation_scale(&self) -> f64 {
+        self.approximation_scale
+    }
+
+    // The glyphs placed by the last layout
+    pub fn glyphs(&self) -> &[PathGlyph] {
+        &self.glyphs
+    }
+
+    // Places the text with the current font of the cache. Only outline
+    // glyphs get into the vertex source, the others are just placed.
+    pub fn layout<F: FontEngine>(&mut self, fman: &mut FontCacheManager<F>, text: &str) {
+        let mut items = Vec::new();
+        fman.reset_last_glyph();
+        for c in text.chars() {
+            let glyph = fman.glyph(c as u32);
+            if glyph.is_null() {
+                continue;
+            }
+            let (mut kx, mut ky) = (0., 0.);
+            if self.kerning && !items.is_empty() {
+                fman.add_kerning(&mut kx, &mut ky);
+            }
+            items.push(Item {
+                code: c as u32,
+                advance: unsafe { (*glyph).advance_x },
+                kerning: kx,
+            });
+        }
+
+        let pos = self.place(&items);
+        for (it, &s) in items.iter().zip(pos.iter()) {
+            let gl = unsafe { &*fman.glyph(it.code) };
+            let mut adp = fman.path_adaptor();
+            adp.init(
+                gl.data.as_ptr(),
+                gl.data_size as usize,
+                s,
+                self.baseline_offset,
+                1.,
+            );
+            let mut curve: ConvCurve<_> = ConvCurve::new_owned(adp);
+            curve.set_approximation_scale(self.approximation_scale);
+            let outline = gl.data_type == GlyphDataType::Outline;
+            self.add_glyph(&mut curve, it, s, outline);
+        }
+    }
+
+    // Places the text with the fonts and size of gsv, one glyph per
+    // character. Its start point and text are changed.
+    pub fn layout_gsv(&mut self, gsv: &mut GsvText, text: &str) {
+        let mut items = Vec::new();
+        for c in text.chars() {
+            gsv.set_text(&c.to_string());
+            items.push(Item {
+                code: c as u32,
+                advance: gsv.text_advance(),
+                kerning: 0.,
+            });
+        }
+
+        let pos = self.place(&items);
+        for (it, &s) in items.iter().zip(pos.iter()) {
+            gsv.set_text(&char::from_u32(it.code).unwrap().to_string());
+            gsv.set_start_point(s, self.baseline_offset);
+            self.add_glyph(gsv, it, s, true);
+        }
+    }
+
+    // Pen positions of the items along the path
+    fn place(&mut self, items: &[Item]) -> Vec<f64> {
+        self.glyphs.clear();
+        self.path.remove_all();
+        let n = items.len();
+        if n == 0 {
+            return Vec::new();
+        }
+        let mut width = self.letter_spacing * (n - 1) as f64;
+        for (i, it) in items.iter().enumerate() {
+            if i > 0 {
+                width += it.kerning;
+            }
+            width += it.advance;
+        }
+
+        let room = self.tpath.total_length() - self.start;
+        let mut spacing = self.letter_spacing;
+        let mut s = self.start;
+        match self.align {
+            TextAlign::Left => {}
+            TextAlign::Center => s += (room - width) * 0.5,
+            TextAlign::Right => s += room - width,
+            TextAlign::Justify => {
+                if n > 1 && room > width {
+                    spacing += (room - width) / (n - 1) as f64;
+                }
+            }
+        }
+
+        let mut pos = Vec::with_capacity(n);
+        for (i, it) in items.iter().enumerate() {
+            if i > 0 {
+                s += it.kerning + spacing;
+            }
+            pos.push(s);
+            s += it.advance;
+        }
+        pos
+    }
+
+    // Transforms the glyph at pen position s onto the path and adds its
+    // outline if draw is set
+    fn add_glyph<VS: VertexSource>(&mut self, vs: &mut VS, it: &Item, s: f64, draw: bool) {
+        let len = self.tpath.total_length();
+        let cx = s + it.advance * 0.5;
+        if cx < 0. || cx > len {
+            return;
+        }
+
+        // The path point and tangent at the center of the glyph
+        let (mut px, mut py) = (cx, 0.);
+        self.tpath.transform(&mut px, &mut py);
+        let (mut x1, mut y1) = ((cx - 0.5).max(0.), 0.);
+        let (mut x2, mut y2) = ((cx + 0.5).min(len), 0.);
+        self.tpath.transform(&mut x1, &mut y1);
+        self.tpath.transform(&mut x2, &mut y2);
+        let angle = (y2 - y1).atan2(x2 - x1);
+
+        let mut mtx = TransAffine::trans_affine_translation(-cx, 0.);
+        if self.orientation != GlyphOrientation::Upright {
+            mtx *= TransAffine::trans_affine_rotation(angle);
+        }
+        mtx *= TransAffine::trans_affine_translation(px, py);
+
+        let (mut x, mut y) = (s, self.baseline_offset);
+        if self.orientation == GlyphOrientation::Bend {
+            self.tpath.transform(&mut x, &mut y);
+        } else {
+            mtx.transform(&mut x, &mut y);
+        }
+        self.glyphs.push(PathGlyph {
+            code: it.code,
+            x: x,
+            y: y,
+            angle: if self.orientation == GlyphOrientation::Upright {
+                0.
+            } else {
+                angle
+            },
+        });
+
+        if draw {
+            if self.orientation == GlyphOrientation::Bend {
+                let mut segm = ConvSegmentator::new_borrowed(vs);
+                segm.set_approximation_scale(self.approximation_scale);
+                append_path(&mut self.path, &mut segm, &self.tpath);
+            } else {
+                append_path(&mut self.path, vs, &mtx);
+            }
+        }
+    }
+}
+
+impl VertexSource for TextOnPath {
+    fn rewind(&mut self, path_id: u32) {
+        self.path.rewind(path_id);
+    }
+
+    fn vertex(&mut self, x: &mut f64, y: &mut f64) -> u32 {
+        self.path.vertex(x, y)
+    }
+}
+
+fn append_path<VS: VertexSource, T: Transformer>(path: &mut PathStorage, vs: &mut VS, trans: &T) {
+    let (mut x, mut y) = (0., 0.);
+    vs.rewind(0);
+    loop {
+        let cmd = vs.vertex(&mut x, &mut y);
+        if is_stop(cmd) {
+            break;
+        }
+        if is_vertex(cmd) {
+            trans.transform(&mut x, &mut y);
+        }
+        path.add_vertex(x, y, cmd);
+    }
+}
@@ -0,0 +1,1187 @@
+use crate::path_storage::PathStorage;
+use crate::trans_affine::TransAffine;
+use crate::Transformer;
+use std::fs::File;
+use std::io::{self, Read};
+
+//=============================================================TrueTypeFace
+// A TrueType or OpenType face parsed from the font data without
+// FreeType: the metrics from head, hhea and hmtx, the character map,
+// kerning pairs from the kern table and glyph outlines from glyf/loca
+// or, in OpenType fonts with PostScript outlines, from CFF.
+//
+// Everything is in font units with y going up; the outlines come as
+// quadratic (glyf) or cubic (CFF) curves in a PathStorage. There's no
+// hinting or variations; GPOS kerning is read by gpos.rs for FreeType
+// faces only. Font collections (.ttc) are read by the index of the face.
+pub struct TrueTypeFace {
+    data: Vec<u8>,
+    num_faces: u32,
+    units_per_em: u16,
+    loca_format: i16,
+    num_glyphs: u16,
+    ascender: i16,
+    descender: i16,
+    line_gap: i16,
+    num_hmetrics: u16,
+    hmtx: Table,
+    loca: Table,
+    glyf: Table,
+    cmap: Option<CharMap>,
+    kern: Vec<(u32, i16)>,
+    cff: Option<Cff>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Table {
+    offset: usize,
+    len: usize,
+}
+
+// The character map subtable in use
+#[derive(Clone, Copy)]
+struct CharMap {
+    offset: usize,
+    format: u16,
+    symbol: bool,
+}
+
+// A CFF INDEX: count items, the offsets of off_size bytes at offsets
+// and the data, which offsets count from one
+#[derive(Clone, Copy, Default)]
+struct Index {
+    count: usize,
+    off_size: usize,
+    offsets: usize,
+    data: usize,
+    end: usize,
+}
+
+struct Cff {
+    char_strings: Index,
+    global_subrs: Index,
+    // One for each font DICT of a CID font, or the only one
+    local_subrs: Vec<Index>,
+    fd_select: Option<usize>,
+}
+
+impl TrueTypeFace {
+    pub fn load(fname: &str, face_index: u32) -> io::Result<TrueTypeFace> {
+        let mut data = Vec::new();
+        File::open(fname)?.read_to_end(&mut data)?;
+        TrueTypeFace::from_data(data, face_index)
+    }
+
+    pub fn from_data(data: Vec<u8>, face_index: u32) -> io::Result<TrueTypeFace> {
+        let d = &data[..];
+        let (start, num_faces) = if rd_u32(d, 0) == Some(0x74746366) {
+            // 'ttcf'
+            let n = rd_u32(d, 8).ok_or_else(|| bad_data("truncated font collection"))?;
+            if face_index >= n {
+                return Err(bad_data("no such face in the collection"));
+            }
+            let off = rd_u32(d, 12 + 4 * face_index as usize)
+                .ok_or_else(|| bad_data("truncated font collection"))?;
+            (off as usize, n)
+        } else {
+            if face_index > 0 {
+                return Err(bad_data("no such face in the font"));
+            }
+            (0, 1)
+        };
+        match rd_u32(d, start) {
+            Some(0x00010000) | Some(0x74727565) | Some(0x4F54544F) => {}
+            _ => return Err(bad_data("not a TrueType or OpenType font")),
+        }
+
+        let num_tables = rd_u16(d, start + 4).ok_or_else(|| bad_data("truncated font"))?;
+        let find = |tag: &[u8; 4]| -> io::Result<Option<Table>> {
+            for i in 0..num_tables as usize {
+                let rec = start + 12 + i * 16;
+                let t = d
+                    .get(rec..rec + 4)
+                    .ok_or_else(|| bad_data("truncated table directory"))?;
+                if t == tag {
+                    let offset = rd_u32(d, rec + 8).unwrap_or(0) as usize;
+                    let len = rd_u32(d, rec + 12).unwrap_or(0) as usize;
+                    if offset.checked_add(len).map_or(true, |e| e > d.len()) {
+                        return Err(bad_data("table out of the font data"));
+                    }
+                    return Ok(Some(Table {
+                        offset: offset,
+                        len: len,
+                    }));
+                }
+            }
+            Ok(None)
+        };
+        let required = |t: Option<Table>, name: &str| -> io::Result<Table> {
+            t.ok_or_else(|| bad_data(&format!("no {} table", name)))
+        };
+        let head = required(find(b"head")?, "head")?;
+        let hhea = required(find(b"hhea")?, "hhea")?;
+        let maxp = required(find(b"maxp")?, "maxp")?;
+        let hmtx = required(find(b"hmtx")?, "hmtx")?;
+        let cmap = find(b"cmap")?;
+        let kern = find(b"kern")?;
+        let os2 = find(b"OS/2")?;
+        let loca = find(b"loca")?;
+        let glyf = find(b"glyf")?;
+        let cff = find(b"CFF ")?;
+
+        let field = |t: Table, off: usize| -> io::Result<u16> {
+            if off + 2 > t.len {
+                return Err(bad_data("truncated table"));
+            }
+            Ok(rd_u16(d, t.offset + off).unwrap())
+        };
+        let mut face = TrueTypeFace {
+            data: Vec::new(),
+            num_faces: num_faces,
+            units_per_em: field(head, 18)?,
+            loca_format: field(head, 50)? as i16,
+            num_glyphs: field(maxp, 4)?,
+            ascender: field(hhea, 4)? as i16,
+            descender: field(hhea, 6)? as i16,
+            line_gap: field(hhea, 8)? as i16,
+            num_hmetrics: field(hhea, 34)?,
+            hmtx: hmtx,
+            loca: loca.unwrap_or_default(),
+            glyf: glyf.unwrap_or_default(),
+            cmap: None,
+            kern: Vec::new(),
+            cff: None,
+        };
+        if face.units_per_em == 0 {
+            return Err(bad_data("zero units per em"));
+        }
+        if face.num_hmetrics == 0 || face.num_hmetrics as usize * 4 > hmtx.len {
+            return Err(bad_data("bad horizontal metrics"));
+        }
+        if face.ascender == 0 && face.descender == 0 {
+            if let Some(os2) = os2 {
+                face.ascender = field(os2, 68)? as i16;
+                face.descender = field(os2, 70)? as i16;
+                face.line_gap = field(os2, 72)? as i16;
+            }
+        }
+        if let Some(cff) = cff {
+            face.cff = Some(parse_cff(d, cff).ok_or_else(|| bad_data("bad CFF table"))?);
+        } else if loca.is_none() || glyf.is_none() {
+            return Err(bad_data("no glyph outlines"));
+        }
+        if let Some(cmap) = cmap {
+            face.cmap = choose_cmap(d, cmap);
+        }
+        if let Some(kern) = kern {
+            face.kern = parse_kern(d, kern);
+        }
+        face.data = data;
+        Ok(face)
+    }
+
+    // Number of faces in the file the face comes from
+    pub fn num_faces(&self) -> u32 {
+        self.num_faces
+    }
+
+    pub fn units_per_em(&self) -> u16 {
+        self.units_per_em
+    }
+
+    pub fn ascender(&self) -> i16 {
+        self.ascender
+    }
+
+    pub fn descender(&self) -> i16 {
+        self.descender
+    }
+
+    pub fn line_gap(&self) -> i16 {
+        self.line_gap
+    }
+
+    pub fn num_glyphs(&self) -> u16 {
+        self.num_glyphs
+    }
+
+    pub fn has_kerning(&self) -> bool {
+        !self.kern.is_empty()
+    }
+
+    // Whether the outlines are cubic CFF ones
+    pub fn is_cff(&self) -> bool {
+        self.cff.is_some()
+    }
+
+    // The glyph of a Unicode character, 0 if there's none
+    pub fn glyph_index(&self, code: u32) -> u32 {
+        let cm = match self.cmap {
+            Some(cm) => cm,
+            None => return 0,
+        };
+        let mut g = cmap_lookup(&self.data, cm, code).unwrap_or(0);
+        if g == 0 && cm.symbol && code < 0x100 {
+            g = cmap_lookup(&self.data, cm, code + 0xF000).unwrap_or(0);
+        }
+        // Broken character maps may point past the glyphs
+        if g < self.num_glyphs as u32 {
+            g
+        } else {
+            0
+        }
+    }
+
+    pub fn advance_width(&self, glyph: u32) -> u16 {
+        let i = (glyph as usize).min(self.num_hmetrics as usize - 1);
+        rd_u16(&self.data, self.hmtx.offset + i * 4).unwrap_or(0)
+    }
+
+    pub fn left_side_bearing(&self, glyph: u32) -> i16 {
+        let d = &self.data;
+        let n = self.num_hmetrics as usize;
+        let g = glyph as usize;
+        let pos = if g < n {
+            self.hmtx.offset + g * 4 + 2
+        } else {
+            self.hmtx.offset + n * 4 + (g - n) * 2
+        };
+        if pos + 2 > self.hmtx.offset + self.hmtx.len {
+            return 0;
+        }
+        rd_u16(d, pos).unwrap_or(0) as i16
+    }
+
+    // Kerning between two glyphs in font units
+    pub fn kerning(&self, left: u32, right: u32) -> i32 {
+        let key = (left << 16) | (right & 0xFFFF);
+        match self.kern.binary_search_by_key(&key, |&(k, _)| k) {
+            Ok(i) => self.kern[i].1 as i32,
+            Err(_) => 0,
+        }
+    }
+
+    // Adds the outline of a glyph to the path, closed contours in font
+    // units. Returns false if the glyph data is broken; empty glyphs like
+    // the space add nothing and succeed.
+    pub fn outline(&self, glyph: u32, path: &mut PathStorage) -> bool {
+        if glyph >= self.num_glyphs as u32 {
+            return false;
+        }
+        if let Some(cff) = &self.cff {
+            return cff_outline(&self.data, cff, glyph, path).is_some();
+        }
+        let mut pts = Vec::new();
+        let mut ends = Vec::new();
+        if self
+            .glyph_points(glyph, 0, &TransAffine::new_default(), &mut pts, &mut ends)
+            .is_none()
+        {
+            return false;
+        }
+        let mut first = 0;
+        for &end in ends.iter() {
+            if end >= first && end < pts.len() {
+                add_contour(&pts[first..=end], path);
+            }
+            first = end + 1;
+        }
+        true
+    }
+
+    // Byte range of a glyph in glyf
+    fn glyph_range(&self, glyph: u32) -> Option<(usize, usize)> {
+        let d = &self.data;
+        let g = glyph as usize;
+        let (a, b) = if self.loca_format == 0 {
+            let p = self.loca.offset + g * 2;
+            (rd_u16(d, p)? as usize * 2, rd_u16(d, p + 2)? as usize * 2)
+        } else {
+            let p = self.loca.offset + g * 4;
+            (rd_u32(d, p)? as usize, rd_u32(d, p + 4)? as usize)
+        };
+        if a > b || b > self.glyf.len {
+            return None;
+        }
+        Some((self.glyf.offset + a, self.glyf.offset + b))
+    }
+
+    // Collects the points of a glyph, transformed by mtx, and the indices
+    // of the last points of its contours, following composite glyphs
+    fn glyph_points(
+        &self, glyph: u32, depth: u32, mtx: &TransAffine, pts: &mut Vec<(f64, f64, bool)>,
+        ends: &mut Vec<usize>,
+    ) -> Option<()> {
+        if depth > 8 {
+            return None;
+        }
+        let d = &self.data;
+        let (start, end) = self.glyph_range(glyph)?;
+        if start == end {
+            return Some(());
+        }
+        let d = d.get(..end)?;
+        let num_contours = rd_u16(d, start)? as i16;
+        let mut p = start + 10;
+
+        if num_contours >= 0 {
+            let base = pts.len();
+            let n = num_contours as usize;
+            let mut last = 0;
+            for i in 0..n {
+                last = rd_u16(d, p + i * 2)? as usize;
+                ends.push(base + last);
+            }
+            if n == 0 {
+                return Some(());
+            }
+            let num_points = last + 1;
+            p += n * 2;
+            p += 2 + rd_u16(d, p)? as usize;
+
+            let mut flags = Vec::with_capacity(num_points);
+            while flags.len() < num_points {
+                let f = rd_u8(d, p)?;
+                p += 1;
+                flags.push(f);
+                if f & 8 != 0 {
+                    let r = rd_u8(d, p)?;
+                    p += 1;
+                    for _ in 0..r {
+                        flags.push(f);
+                    }
+                }
+            }
+            flags.truncate(num_points);
+
+            let mut xs = Vec::with_capacity(num_points);
+            let mut v = 0i32;
+            for &f in flags.iter() {
+                if f & 2 != 0 {
+                    let dv = rd_u8(d, p)? as i32;
+                    p += 1;
+                    v += if f & 16 != 0 { dv } else { -dv };
+                } else if f & 16 == 0 {
+                    v += rd_u16(d, p)? as i16 as i32;
+                    p += 2;
+                }
+                xs.push(v);
+            }
+            v = 0;
+            for (i, &f) in flags.iter().enumerate() {
+                if f & 4 != 0 {
+                    let dv = rd_u8(d, p)? as i32;
+                    p += 1;
+                    v += if f & 32 != 0 { dv } else { -dv };
+                } else if f & 32 == 0 {
+                    v += rd_u16(d, p)? as i16 as i32;
+                    p += 2;
+                }
+                let (mut x, mut y) = (xs[i] as f64, v as f64);
+                mtx.transform(&mut x, &mut y);
+                pts.push((x, y, f & 1 != 0));
+            }
+            return Some(());
+        }
+
+        // Composite glyph
+        loop {
+            let flags = rd_u16(d, p)?;
+            let component = rd_u16(d, p + 2)? as u32;
+            p += 4;
+            let (arg1, arg2) = if flags & 1 != 0 {
+                p += 4;
+                if flags & 2 != 0 {
+                    (
+                        rd_u16(d, p - 4)? as i16 as i32,
+                        rd_u16(d, p - 2)? as i16 as i32,
+                    )
+                } else {
+                    (rd_u16(d, p - 4)? as i32, rd_u16(d, p - 2)? as i32)
+                }
+            } else {
+                p += 2;
+                if flags & 2 != 0 {
+                    (rd_u8(d, p - 2)? as i8 as i32, rd_u8(d, p - 1)? as i8 as i32)
+                } else {
+                    (rd_u8(d, p - 2)? as i32, rd_u8(d, p - 1)? as i32)
+                }
+            };
+            let (mut a, mut b, mut c, mut dd) = (1., 0., 0., 1.);
+            if flags & 8 != 0 {
+                a = rd_f2dot14(d, p)?;
+                dd = a;
+                p += 2;
+            } else if flags & 0x40 != 0 {
+                a = rd_f2dot14(d, p)?;
+                dd = rd_f2dot14(d, p + 2)?;
+                p += 4;
+            } else if flags & 0x80 != 0 {
+                a = rd_f2dot14(d, p)?;
+                b = rd_f2dot14(d, p + 2)?;
+                c = rd_f2dot14(d, p + 4)?;
+                dd = rd_f2dot14(d, p + 6)?;
+                p += 8;
+            }
+
+            if flags & 2 != 0 {
+                let (mut dx, mut dy) = (arg1 as f64, arg2 as f64);
+                if flags & 0x800 != 0 {
+                    let (x, y) = (dx, dy);
+                    dx = a * x + c * y;
+                    dy = b * x + dd * y;
+                }
+                let mut m = TransAffine::new(a, b, c, dd, dx, dy);
+                m *= *mtx;
+                self.glyph_points(component, depth + 1, &m, pts, ends)?;
+            } else {
+                // The component is moved to put its point arg2 on the
+                // point arg1 of the glyph so far
+                let mut m = TransAffine::new(a, b, c, dd, 0., 0.);
+                m *= *mtx;
+                let base = pts.len();
+                self.glyph_points(component, depth + 1, &m, pts, ends)?;
+                let p1 = *pts.get(arg1 as usize)?;
+                let p2 = *pts.get(base + arg2 as usize)?;
+                let (dx, dy) = (p1.0 - p2.0, p1.1 - p2.1);
+                for pt in pts[base..].iter_mut() {
+                    pt.0 += dx;
+                    pt.1 += dy;
+                }
+            }
+            if flags & 0x20 == 0 {
+                break;
+            }
+        }
+        Some(())
+    }
+}
+
+// Adds a closed quadratic contour, with the on curve points between two
+// off curve ones implied
+fn add_contour(pts: &[(f64, f64, bool)], path: &mut PathStorage) {
+    let n = pts.len();
+    let first = pts[0];
+    let last = pts[n - 1];
+    let mid = |a: (f64, f64, bool), b: (f64, f64, bool)| ((a.0 + b.0) * 0.5, (a.1 + b.1) * 0.5);
+    let (start, rest) = if first.2 {
+        ((first.0, first.1), &pts[1..])
+    } else if last.2 {
+        ((last.0, last.1), &pts[..n - 1])
+    } else {
+        (mid(first, last), pts)
+    };
+    path.move_to(start.0, start.1);
+    let mut ctrl: Option<(f64, f64)> = None;
+    for &p in rest.iter() {
+        if p.2 {
+            match ctrl {
+                Some(c) => path.curve3_ctrl(c.0, c.1, p.0, p.1),
+                None => path.line_to(p.0, p.1),
+            }
+            ctrl = None;
+        } else {
+            if let Some(c) = ctrl {
+                let m = mid((c.0, c.1, false), p);
+                path.curve3_ctrl(c.0, c.1, m.0, m.1);
+            }
+            ctrl = Some((p.0, p.1));
+        }
+    }
+    if let Some(c) = ctrl {
+        path.curve3_ctrl(c.0, c.1, start.0, start.1);
+    }
+    path.close_polygon(0);
+}
+
+// Picks the Unicode subtable if there's one, the symbol or Mac Roman
+// one otherwise
+fn choose_cmap(d: &[u8], t: Table) -> Option<CharMap> {
+    let n = rd_u16(d, t.offset + 2)? as usize;
+    let mut best: Option<(u32, CharMap)> = None;
+    for i in 0..n {
+        let rec = t.offset + 4 + i * 8;
+        let platform = rd_u16(d, rec)?;
+        let encoding = rd_u16(d, rec + 2)?;
+        let offset = t.offset + rd_u32(d, rec + 4)? as usize;
+        let format = rd_u16(d, offset)?;
+        if format != 0 && format != 4 && format != 6 && format != 12 {
+            continue;
+        }
+        let mut score = match (platform, encoding) {
+            (3, 10) | (0, 4) | (0, 6) => 8,
+            (0, _) | (3, 1) => 6,
+            (3, 0) => 4,
+            (1, 0) => 2,
+            _ => continue,
+        };
+        if format == 12 {
+            score += 1;
+        }
+        if best.map_or(true, |(s, _)| score > s) {
+            best = Some((
+                score,
+                CharMap {
+                    offset: offset,
+                    format: format,
+                    symbol: platform == 3 && encoding == 0,
+                },
+            ));
+        }
+    }
+    best.map(|(_, cm)| cm)
+}
+
+fn cmap_lookup(d: &[u8], cm: CharMap, code: u32) -> Option<u32> {
+    let t = cm.offset;
+    match cm.format {
+        0 => {
+            if code < 256 {
+                Some(rd_u8(d, t + 6 + code as usize)? as u32)
+            } else {
+                None
+            }
+        }
+        4 => {
+            if code > 0xFFFF {
+                return None;
+            }
+            let seg_x2 = rd_u16(d, t + 6)? as usize;
+            let ends = t + 14;
+            let starts = ends + seg_x2 + 2;
+            let deltas = starts + seg_x2;
+            let ranges = deltas + seg_x2;
+            // The first segment ending at or after the code
+            let (mut lo, mut hi) = (0, seg_x2 / 2);
+            while lo < hi {
+                let mid = (lo + hi) / 2;
+                if (rd_u16(d, ends + mid * 2)? as u32) < code {
+                    lo = mid + 1;
+                } else {
+                    hi = mid;
+                }
+            }
+            if lo == seg_x2 / 2 {
+                return None;
+            }
+            let i = lo * 2;
+            let start = rd_u16(d, starts + i)? as u32;
+            if code < start {
+                return None;
+            }
+            let delta = rd_u16(d, deltas + i)? as u32;
+            let range = rd_u16(d, ranges + i)? as usize;
+            if range == 0 {
+                return Some((code + delta) & 0xFFFF);
+            }
+            let g = rd_u16(d, ranges + i + range + (code - start) as usize * 2)? as u32;
+            if g == 0 {
+                Some(0)
+            } else {
+                Some((g + delta) & 0xFFFF)
+            }
+        }
+        6 => {
+            let first = rd_u16(d, t + 6)? as u32;
+            let count = rd_u16(d, t + 8)? as u32;
+            if code < first || code >= first + count {
+                return None;
+            }
+            Some(rd_u16(d, t + 10 + (code - first) as usize * 2)? as u32)
+        }
+        12 => {
+            let n = rd_u32(d, t + 12)? as usize;
+            let (mut lo, mut hi) = (0, n);
+            while lo < hi {
+                let mid = (lo + hi) / 2;
+                let g = t + 16 + mid * 12;
+                let start = rd_u32(d, g)?;
+                let end = rd_u32(d, g + 4)?;
+                if code < start {
+                    hi = mid;
+                } else if code > end {
+                    lo = mid + 1;
+                } else {
+                    return rd_u32(d, g + 8)?.checked_add(code - start);
+                }
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+// The horizontal format 0 pairs of both the Microsoft and the Apple kern
+// table, sorted by left << 16 | right
+fn parse_kern(d: &[u8], t: Table) -> Vec<(u32, i16)> {
+    let mut pairs: Vec<(u32, i16)> = Vec::new();
+    let apple = rd_u32(d, t.offset) == Some(0x00010000);
+    let (n, mut p) = if apple {
+        (rd_u32(d, t.offset + 4).unwrap_or(0) as usize, t.offset + 8)
+    } else {
+        (rd_u16(d, t.offset + 2).unwrap_or(0) as usize, t.offset + 4)
+    };
+    let end = t.offset + t.len;
+    for _ in 0..n {
+        let (len, coverage, header) = if apple {
+            match (rd_u32(d, p), rd_u16(d, p + 4)) {
+                (Some(l), Some(c)) => (l as usize, c, 8),
+                _ => break,
+            }
+        } else {
+            match (rd_u16(d, p + 2), rd_u16(d, p + 4)) {
+                (Some(l), Some(c)) => (l as usize, c, 6),
+                _ => break,
+            }
+        };
+        let (format, horizontal, minimum, cross) = if apple {
+            (
+                coverage & 0xFF,
+                coverage & 0x8000 == 0,
+                false,
+                coverage & 0x4000 != 0,
+            )
+        } else {
+            (
+                coverage >> 8,
+                coverage & 1 != 0,
+                coverage & 2 != 0,
+                coverage & 4 != 0,
+            )
+        };
+        if format == 0 && horizontal && !minimum && !cross {
+            let sub = p + header;
+            let count = rd_u16(d, sub).unwrap_or(0) as usize;
+            for i in 0..count {
+                let e = sub + 8 + i * 6;
+                if e + 6 > end {
+                    break;
+                }
+                let key = rd_u32(d, e).unwrap();
+                let v = rd_u16(d, e + 4).unwrap() as i16;
+                pairs.push((key, v));
+            }
+        }
+        if len == 0 {
+            break;
+        }
+        p += len;
+    }
+    pairs.sort_by_key(|&(k, _)| k);
+    pairs.dedup_by_key(|&mut (k, _)| k);
+    pairs
+}
+
+fn read_index(d: &[u8], pos: usize) -> Option<Index> {
+    let count = rd_u16(d, pos)? as usize;
+    if count == 0 {
+        return Some(Index {
+            end: pos + 2,
+            ..Index::default()
+        });
+    }
+    let off_size = rd_u8(d, pos + 2)? as usize;
+    if off_size == 0 || off_size > 4 {
+        return None;
+    }
+    let mut idx = Index {
+        count: count,
+        off_size: off_size,
+        offsets: pos + 3,
+        data: pos + 3 + (count + 1) * off_size - 1,
+        end: 0,
+    };
+    idx.end = idx.data.checked_add(index_offset(d, &idx, count)?)?;
+    if idx.end > d.len() {
+        return None;
+    }
+    Some(idx)
+}
+
+fn index_offset(d: &[u8], idx: &Index, i: usize) -> Option<usize> {
+    let p = idx.offsets + i * idx.off_size;
+    let mut v = 0;
+    for b in d.get(p..p + idx.off_size)? {
+        v = (v << 8) | *b as usize;
+    }
+    Some(v)
+}
+
+fn index_item(d: &[u8], idx: &Index, i: usize) -> Option<(usize, usize)> {
+    if i >= idx.count {
+        return None;
+    }
+    let a = idx.data.checked_add(index_offset(d, idx, i)?)?;
+    let b = idx.data.checked_add(index_offset(d, idx, i + 1)?)?;
+    if a > b || b > idx.end {
+        return None;
+    }
+    Some((a, b))
+}
+
+// The operators of a DICT with their operands, the two byte ones as
+// 1200 + the second byte
+fn parse_dict(d: &[u8], start: usize, end: usize) -> Option<Vec<(u16, Vec<f64>)>> {
+    let mut ops = Vec::new();
+    let mut args = Vec::new();
+    let mut p = start;
+    while p < end {
+        let b0 = rd_u8(d, p)?;
+        p += 1;
+        match b0 {
+            0..=21 => {
+                let op = if b0 == 12 {
+                    p += 1;
+                    1200 + rd_u8(d, p - 1)? as u16
+                } else {
+                    b0 as u16
+                };
+                ops.push((op, std::mem::take(&mut args)));
+            }
+            28 => {
+                args.push(rd_u16(d, p)? as i16 as f64);
+                p += 2;
+            }
+            29 => {
+                args.push(rd_u32(d, p)? as i32 as f64);
+                p += 4;
+            }
+            30 => {
+                let mut s = String::new();
+                'real: loop {
+                    let b = rd_u8(d, p)?;
+                    p += 1;
+                    for nib in [b >> 4, b & 15].iter() {
+                        match nib {
+                            0..=9 => s.push((b'0' + nib) as char),
+                            10 => s.push('.'),
+                            11 => s.push('E'),
+                            12 => s.push_str("E-"),
+                            14 => s.push('-'),
+                            15 => break 'real,
+                            _ => {}
+                        }
+                    }
+                }
+                args.push(s.parse().unwrap_or(0.));
+            }
+            32..=246 => args.push(b0 as f64 - 139.),
+            247..=250 => {
+                args.push((b0 as f64 - 247.) * 256. + rd_u8(d, p)? as f64 + 108.);
+                p += 1;
+            }
+            251..=254 => {
+                args.push(-(b0 as f64 - 251.) * 256. - rd_u8(d, p)? as f64 - 108.);
+                p += 1;
+            }
+            _ => return None,
+        }
+    }
+    Some(ops)
+}
+
+fn dict_get(dict: &[(u16, Vec<f64>)], op: u16) -> Option<&[f64]> {
+    dict.iter().find(|(o, _)| *o == op).map(|(_, a)| &a[..])
+}
+
+// The position of a DICT operand that is an offset from pos or a size,
+// None if it is negative or the sum overflows
+fn dict_offset(pos: usize, v: f64) -> Option<usize> {
+    if !(v >= 0.) {
+        return None;
+    }
+    pos.checked_add(v as usize)
+}
+
+// The local subroutines of the font DICT at start..end
+fn private_subrs(d: &[u8], base: usize, start: usize, end: usize) -> Option<Index> {
+    let dict = parse_dict(d, start, end)?;
+    let private = match dict_get(&dict, 18) {
+        Some(a) if a.len() >= 2 => a,
+        _ => return Some(Index::default()),
+    };
+    let p_start = dict_offset(base, private[1])?;
+    let p_end = dict_offset(p_start, private[0])?;
+    let pdict = parse_dict(d, p_start, p_end)?;
+    match dict_get(&pdict, 19) {
+        Some(a) if !a.is_empty() => read_index(d, dict_offset(p_start, a[0])?),
+        _ => Some(Index::default()),
+    }
+}
+
+fn parse_cff(d: &[u8], t: Table) -> Option<Cff> {
+    let base = t.offset;
+    let d = d.get(..t.offset + t.len)?;
+    let hdr_size = rd_u8(d, base + 2)? as usize;
+    let names = read_index(d, base + hdr_size)?;
+    let top_dicts = read_index(d, names.end)?;
+    let strings = read_index(d, top_dicts.end)?;
+    let global_subrs = read_index(d, strings.end)?;
+    let (ts, te) = index_item(d, &top_dicts, 0)?;
+    let top = parse_dict(d, ts, te)?;
+
+    let cs = dict_get(&top, 17)?;
+    let char_strings = read_index(d, dict_offset(base, *cs.first()?)?)?;
+    let mut local_subrs = Vec::new();
+    let mut fd_select = None;
+    match (dict_get(&top, 1236), dict_get(&top, 1237)) {
+        (Some(fda), Some(fds)) if !fda.is_empty() && !fds.is_empty() => {
+            let fd_array = read_index(d, dict_offset(base, fda[0])?)?;
+            for i in 0..fd_array.count {
+                let (s, e) = index_item(d, &fd_array, i)?;
+                local_subrs.push(private_subrs(d, base, s, e)?);
+            }
+            fd_select = Some(dict_offset(base, fds[0])?);
+        }
+        _ => local_subrs.push(private_subrs(d, base, ts, te)?),
+    }
+    Some(Cff {
+        char_strings: char_strings,
+        global_subrs: global_subrs,
+        local_subrs: local_subrs,
+        fd_select: fd_select,
+    })
+}
+
+fn fd_index(d: &[u8], pos: usize, glyph: u32) -> Option<usize> {
+    match rd_u8(d, pos)? {
+        0 => Some(rd_u8(d, pos + 1 + glyph as usize)? as usize),
+        3 => {
+            let n = rd_u16(d, pos + 1)? as usize;
+            for i in 0..n {
+                let r = pos + 3 + i * 3;
+                let next = rd_u16(d, r + 3)? as u32;
+                if glyph >= rd_u16(d, r)? as u32 && glyph < next {
+                    return Some(rd_u8(d, r + 2)? as usize);
+                }
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+fn subr_bias(idx: &Index) -> i32 {
+    if idx.count < 1240 {
+        107
+    } else if idx.count < 33900 {
+        1131
+    } else {
+        32768
+    }
+}
+
+fn cff_outline(d: &[u8], cff: &Cff, glyph: u32, path: &mut PathStorage) -> Option<()> {
+    let (s, e) = index_item(d, &cff.char_strings, glyph as usize)?;
+    let fd = match cff.fd_select {
+        Some(pos) => fd_index(d, pos, glyph)?,
+        None => 0,
+    };
+    let mut cs = CharString {
+        d: d,
+        global_subrs: &cff.global_subrs,
+        local_subrs: cff.local_subrs.get(fd)?,
+        stack: Vec::new(),
+        x: 0.,
+        y: 0.,
+        num_stems: 0,
+        have_width: false,
+        open: false,
+        path: path,
+    };
+    cs.run(s, e, 0)?;
+    if cs.open {
+        cs.path.close_polygon(0);
+    }
+    Some(())
+}
+
+// Type 2 charstring interpreter, enough to get the outline: hints are
+// skipped, the width is dropped and seac accents aren't supported
+struct CharString<'a> {
+    d: &'a [u8],
+    global_subrs: &'a Index,
+    local_subrs: &'a Index,
+    stack: Vec<f64>,
+    x: f64,
+    y: f64,
+    num_stems: usize,
+    have_width: bool,
+    open: bool,
+    path: &'a mut PathStorage,
+}
+
+impl<'a> CharString<'a> {
+    // Drops the width in front of the operands of the first stack
+    // clearing operator if there's one more than it takes
+    fn take_width(&mut self, extra: bool) {
+        if !self.have_width && extra && !self.stack.is_empty() {
+            self.stack.remove(0);
+        }
+        self.have_width = true;
+    }
+
+    fn move_to(&mut self, dx: f64, dy: f64) {
+        if self.open {
+            self.path.close_polygon(0);
+        }
+        self.x += dx;
+        self.y += dy;
+        self.path.move_to(self.x, self.y);
+        self.open = true;
+    }
+
+    fn line_to(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+        self.path.line_to(self.x, self.y);
+    }
+
+    fn curve_to(&mut self, dx1: f64, dy1: f64, dx2: f64, dy2: f64, dx3: f64, dy3: f64) {
+        let (x1, y1) = (self.x + dx1, self.y + dy1);
+        let (x2, y2) = (x1 + dx2, y1 + dy2);
+        self.x = x2 + dx3;
+        self.y = y2 + dy3;
+        self.path.curve4_ctrl(x1, y1, x2, y2, self.x, self.y);
+    }
+
+    // Returns Some(true) at endchar
+    fn run(&mut self, start: usize, end: usize, depth: u32) -> Option<bool> {
+        if depth > 10 {
+            return None;
+        }
+        let d = self.d;
+        let mut p = start;
+        while p < end {
+            let b0 = rd_u8(d, p)?;
+            p += 1;
+            match b0 {
+                28 => {
+                    self.stack.push(rd_u16(d, p)? as i16 as f64);
+                    p += 2;
+                }
+                32..=246 => self.stack.push(b0 as f64 - 139.),
+                247..=250 => {
+                    self.stack
+                        .push((b0 as f64 - 247.) * 256. + rd_u8(d, p)? as f64 + 108.);
+                    p += 1;
+                }
+                251..=254 => {
+                    self.stack
+                        .push(-(b0 as f64 - 251.) * 256. - rd_u8(d, p)? as f64 - 108.);
+                    p += 1;
+                }
+                255 => {
+                    self.stack.push(rd_u32(d, p)? as i32 as f64 / 65536.);
+                    p += 4;
+                }
+                1 | 3 | 18 | 23 => {
+                    // hstem, vstem, hstemhm, vstemhm
+                    self.take_width(self.stack.len() % 2 == 1);
+                    self.num_stems += self.stack.len() / 2;
+                    self.stack.clear();
+                }
+                19 | 20 => {
+                    // hintmask, cntrmask, with implied vstems before
+                    self.take_width(self.stack.len() % 2 == 1);
+                    self.num_stems += self.stack.len() / 2;
+                    self.stack.clear();
+                    p += (self.num_stems + 7) / 8;
+                }
+                21 => {
+                    // rmoveto
+                    self.take_width(self.stack.len() > 2);
+                    let (dx, dy) = (*self.stack.get(0)?, *self.stack.get(1)?);
+                    self.move_to(dx, dy);
+                    self.stack.clear();
+                }
+                22 => {
+                    // hmoveto
+                    self.take_width(self.stack.len() > 1);
+                    let dx = *self.stack.get(0)?;
+                    self.move_to(dx, 0.);
+                    self.stack.clear();
+                }
+                4 => {
+                    // vmoveto
+                    self.take_width(self.stack.len() > 1);
+                    let dy = *self.stack.get(0)?;
+                    self.move_to(0., dy);
+                    self.stack.clear();
+                }
+                5 => {
+                    // rlineto
+                    let s = std::mem::take(&mut self.stack);
+                    for a in s.chunks_exact(2) {
+                        self.line_to(a[0], a[1]);
+                    }
+                }
+                6 | 7 => {
+                    // hlineto, vlineto
+                    let s = std::mem::take(&mut self.stack);
+                    for (i, &v) in s.iter().enumerate() {
+                        if (i % 2 == 0) == (b0 == 6) {
+                            self.line_to(v, 0.);
+                        } else {
+                            self.line_to(0., v);
+                        }
+                    }
+                }
+                8 => {
+                    // rrcurveto
+                    let s = std::mem::take(&mut self.stack);
+                    for a in s.chunks_exact(6) {
+                        self.curve_to(a[0], a[1], a[2], a[3], a[4], a[5]);
+                    }
+                }
+                24 => {
+                    // rcurveline
+                    let s = std::mem::take(&mut self.stack);
+                    if s.len() < 8 {
+                        return None;
+                    }
+                    let n = (s.len() - 2) / 6 * 6;
+                    for a in s[..n].chunks_exact(6) {
+                        self.curve_to(a[0], a[1], a[2], a[3], a[4], a[5]);
+                    }
+                    self.line_to(s[n], s[n + 1]);
+                }
+                25 => {
+                    // rlinecurve
+                    let s = std::mem::take(&mut self.stack);
+                    if s.len() < 8 {
+                        return None;
+                    }
+                    let n = (s.len() - 6) / 2 * 2;
+                    for a in s[..n].chunks_exact(2) {
+                        self.line_to(a[0], a[1]);
+                    }
+                    let a = &s[n..];
+                    self.curve_to(a[0], a[1], a[2], a[3], a[4], a[5]);
+                }
+                26 | 27 => {
+                    // vvcurveto, hhcurveto
+                    let s = std::mem::take(&mut self.stack);
+                    let (mut first, rest) = if s.len() % 2 == 1 {
+                        (s[0], &s[1..])
+                    } else {
+                        (0., &s[..])
+                    };
+                    for a in rest.chunks_exact(4) {
+                        if b0 == 26 {
+                            self.curve_to(first, a[0], a[1], a[2], 0., a[3]);
+                        } else {
+                            self.curve_to(a[0], first, a[1], a[2], a[3], 0.);
+                        }
+                        first = 0.;
+                    }
+                }
+                30 | 31 => {
+                    // vhcurveto, hvcurveto
+                    let s = std::mem::take(&mut self.stack);
+                    let mut horizontal = b0 == 31;
+                    let mut i = 0;
+                    while s.len() - i >= 4 {
+                        let last = if s.len() - i == 5 { s[i + 4] } else { 0. };
+                        if horizontal {
+                            self.curve_to(s[i], 0., s[i + 1], s[i + 2], last, s[i + 3]);
+                        } else {
+                            self.curve_to(0., s[i], s[i + 1], s[i + 2], s[i + 3], last);
+                        }
+                        horizontal = !horizontal;
+                        i += 4;
+                    }
+                }
+                10 | 29 => {
+                    // callsubr, callgsubr
+                    let subrs = if b0 == 10 {
+                        self.local_subrs
+                    } else {
+                        self.global_subrs
+                    };
+                    let i = (self.stack.pop()? as i32).checked_add(subr_bias(subrs))?;
+                    if i < 0 {
+                        return None;
+                    }
+                    let (s, e) = index_item(d, subrs, i as usize)?;
+                    if self.run(s, e, depth + 1)? {
+                        return Some(true);
+                    }
+                }
+                11 => return Some(false),
+                14 => {
+                    // endchar
+                    let n = self.stack.len();
+                    self.take_width(n == 1 || n == 5);
+                    self.stack.clear();
+                    return Some(true);
+                }
+                12 => {
+                    let b1 = rd_u8(d, p)?;
+                    p += 1;
+                    let s = std::mem::take(&mut self.stack);
+                    match b1 {
+                        35 if s.len() >= 12 => {
+                            // flex
+                            self.curve_to(s[0], s[1], s[2], s[3], s[4], s[5]);
+                            self.curve_to(s[6], s[7], s[8], s[9], s[10], s[11]);
+                        }
+                        34 if s.len() >= 7 => {
+                            // hflex
+                            self.curve_to(s[0], 0., s[1], s[2], s[3], 0.);
+                            self.curve_to(s[4], 0., s[5], -s[2], s[6], 0.);
+                        }
+                        36 if s.len() >= 9 => {
+                            // hflex1
+                            let y0 = self.y;
+                            self.curve_to(s[0], s[1], s[2], s[3], s[4], 0.);
+                            let dy = y0 - self.y - s[7];
+                            self.curve_to(s[5], 0., s[6], s[7], s[8], dy);
+                        }
+                        37 if s.len() >= 11 => {
+                            // flex1
+                            let (x0, y0) = (self.x, self.y);
+                            let dx: f64 = s[..10].iter().step_by(2).sum();
+                            let dy: f64 = s[1..10].iter().step_by(2).sum();
+                            self.curve_to(s[0], s[1], s[2], s[3], s[4], s[5]);
+                            let (x1, y1) = (self.x + s[6], self.y + s[7]);
+                            let (x2, y2) = (x1 + s[8], y1 + s[9]);
+                            let (x3, y3) = if dx.abs() > dy.abs() {
+                                (x2 + s[10], y0)
+                            } else {
+                                (x0, y2 + s[10])
+                            };
+                            self.path.curve4_ctrl(x1, y1, x2, y2, x3, y3);
+                            self.x = x3;
+                            self.y = y3;
+                        }
+                        _ => {}
+                    }
+                }
+                _ => self.stack.clear(),
+            }
+        }
+        Some(false)
+    }
+}
+
+pub(crate) fn rd_u8(d: &[u8], p: usize) -> Option<u8> {
+    d.get(p).copied()
+}
+
+pub(crate) fn rd_u16(d: &[u8], p: usize) -> Option<u16> {
+    d.get(p..p + 2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+pub(crate) fn rd_u32(d: &[u8], p: usize) -> Option<u32> {
+    d.get(p..p + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+pub(crate) fn rd_f2dot14(d: &[u8], p: usize) -> Option<f64> {
+    Some(rd_u16(d, p)? as i16 as f64 / 16384.)
+}
+
+fn bad_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
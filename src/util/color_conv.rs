@@ -54,6 +54,7 @@ impl<const BPP: u32> ConverterSame<BPP> {
     }
 }
 
+pub type Gray8ToGray8 = ConverterSame<1> ;
 pub type Bgr24ToBgr24 = ConverterSame<3> ;
 pub type Rgb24ToRgb24 = ConverterSame<3> ;
 
@@ -350,3 +350,59 @@ impl<const R: usize, const B: usize> ConverterRgb24ToGray8<R, B> {
 
 pub type Rgb24ToGray8 = ConverterRgb24ToGray8<0, 2>; //----color_conv_rgb24_to_gray8
 pub type Bgr24ToGray8 = ConverterRgb24ToGray8<2, 0>; //----color_conv_bgr24_to_gray8
+
+//---------------------------------------------color_conv_rgba32_gray8
+pub struct ConverterRgba32ToGray8<const R: usize, const G: usize, const B: usize>;
+impl<const R: usize, const G: usize, const B: usize> ConverterRgba32ToGray8<R, G, B> {
+    pub fn convert(dst: &mut [u8], src: &[u8], width: u32) {
+        let mut j = 0;
+        for i in 0..width as usize {
+            dst[i] = ((src[j + R] as u32 * 77 + src[j + G] as u32 * 150 + src[j + B] as u32 * 29)
+                >> 8) as u8;
+            j += 4;
+        }
+    }
+}
+
+pub type Rgba32ToGray8 = ConverterRgba32ToGray8<0, 1, 2>; //----color_conv_rgba32_to_gray8
+pub type Argb32ToGray8 = ConverterRgba32ToGray8<1, 2, 3>; //----color_conv_argb32_to_gray8
+pub type Abgr32ToGray8 = ConverterRgba32ToGray8<3, 2, 1>; //----color_conv_abgr32_to_gray8
+pub type Bgra32ToGray8 = ConverterRgba32ToGray8<2, 1, 0>; //----color_conv_bgra32_to_gray8
+
+//----------------------------------------------color_conv_gray8_rgb24
+pub struct ConverterGray8ToRgb24;
+impl ConverterGray8ToRgb24 {
+    pub fn convert(dst: &mut [u8], src: &[u8], width: u32) {
+        let mut i = 0;
+        for &v in src[..width as usize].iter() {
+            dst[i + 0] = v;
+            dst[i + 1] = v;
+            dst[i + 2] = v;
+            i += 3;
+        }
+    }
+}
+
+pub type Gray8ToRgb24 = ConverterGray8ToRgb24; //----color_conv_gray8_to_rgb24
+pub type Gray8ToBgr24 = ConverterGray8ToRgb24; //----color_conv_gray8_to_bgr24
+
+//---------------------------------------------color_conv_gray8_rgba32
+pub struct ConverterGray8ToRgba32<const A: usize>;
+impl<const A: usize> ConverterGray8ToRgba32<A> {
+    pub fn convert(dst: &mut [u8], src: &[u8], width: u32) {
+        let mut i = 0;
+        for &v in src[..width as usize].iter() {
+            dst[i + 0] = v;
+            dst[i + 1] = v;
+            dst[i + 2] = v;
+            dst[i + 3] = v;
+            dst[i + A] = 255;
+            i += 4;
+        }
+    }
+}
+
+pub type Gray8ToRgba32 = ConverterGray8ToRgba32<3>; //----color_conv_gray8_to_rgba32
+pub type Gray8ToArgb32 = ConverterGray8ToRgba32<0>; //----color_conv_gray8_to_argb32
+pub type Gray8ToAbgr32 = ConverterGray8ToRgba32<0>; //----color_conv_gray8_to_abgr32
+pub type Gray8ToBgra32 = ConverterGray8ToRgba32<3>; //----color_conv_gray8_to_bgra32